@@ -3,14 +3,21 @@
 
 pub use self::primer_contrato::{
     PrimerContratoRef,
+    Psp22Error,
+    EventoMarketplace,
     Usuario,
     Rol,
     EstadoCompra,
+    PLAZO_DEVOLUCION,
     OrdenCompra,
     Categoria,
     Producto,
     Comprador,
     Vendedor,
+    CalificacionPonderada,
+    Recibo,
+    Id,
+    Psp34Error,
 };
 
 #[ink::contract]
@@ -18,7 +25,11 @@ mod primer_contrato {
     use ink::storage::Mapping;
     use ink::storage::StorageVec;
     use ink::prelude::vec::Vec;
+    use ink::prelude::boxed::Box;
     use ink::prelude::string::{String, ToString};
+
+    /// Clave de los fondos a retirar: (usuario, token PSP22 o None para moneda nativa). 
+    type CuentaFondos = (AccountId, Option<AccountId>);
 /////////////////////////// SISTEMA ///////////////////////////
     /// Struct que hace de "sistema". Encargado de persistir los datos. 
     /// Los usuarios se almacenan en un Mapping. La clave es el AccountId, y su contenido los datos del usuario.
@@ -27,6 +38,37 @@ mod primer_contrato {
     /// Las ordenes de compra se almacenan en un StorageVec. Cuyo contenido es una tupla con el id de la orden y los datos de la misma. (id, orden)   
     /// La dimensión lógica de "historial_productos" utilizada para definir la id de los productos que se agregan al sistema. 
     /// Las ids de los usuarios registrados (para poder recorrer el mapping de usuarios)
+    /// El owner es quien desplegó el contrato, y es el único que puede configurar el sistema.
+    /// El contrato de reportes (opcional) es el contrato al que se le notifican los cambios de estado de las ordenes y las calificaciones.
+    /// El contrato de recibos (opcional) es el contrato PSP34 que acuña un recibo al comprador de cada orden recibida. 
+    /// Las suscripciones se almacenan en el orden en que se crearon; las activas guardan los ids de las que todavía tienen períodos por ejecutar 
+    /// (sin un orden en particular), y sus posiciones indican, por id, dónde está cada una entre las activas. 
+    /// La comisión (en puntos básicos, 100 = 1%) es el porcentaje que el sistema retiene de cada pago liberado al vendedor. 
+    /// La tesorería acumula las comisiones cobradas en moneda nativa, hasta que el owner las retira. 
+    /// La tesorería de tokens acumula las comisiones cobradas en cada token PSP22. Donde la clave es la dirección del token. 
+    /// Los fondos a retirar son las ofertas superadas (o no adjudicadas) de las subastas y las ofertas de precio rechazadas o retiradas. La clave es (usuario, token), con token None para moneda nativa. 
+    /// Las devoluciones aceptadas guardan, por orden, el timestamp en que el vendedor aceptó la devolución (desde allí corre el plazo para confirmarla). 
+    /// Las ofertas de precio se almacenan en un StorageVec, donde la posición es el id de la oferta. 
+    /// Los cupones de descuento se almacenan por (vendedor, código), ya que cada vendedor define los códigos de sus publicaciones. 
+    /// El plazo de calificación (en milisegundos) es el tiempo, desde que la orden se recibe, durante el cual las partes pueden enviar su calificación. 
+    /// El monto mínimo y la antigüedad mínima (en milisegundos) son los requisitos que el owner exige a una orden y a la cuenta que califica para poder calificar. 
+    /// Las cuentas relacionadas son los pares de cuentas que el owner marcó como vinculadas (por ejemplo, fondeadas desde el mismo origen). Se guardan en ambos sentidos. 
+    /// Las ordenes entre cuentas cuentan las compras recibidas por cada par (comprador, vendedor), para detectar contrapartes repetidas. 
+    /// Los verificadores son las cuentas habilitadas por el owner para otorgar y revocar verificaciones de vendedores. 
+    /// Las verificaciones se almacenan por vendedor, con a lo sumo una verificación por cada verificador. 
+    /// Los moderadores son las cuentas habilitadas por el owner para suspender usuarios y dar de baja publicaciones. 
+    /// Las sanciones vigentes se almacenan por objetivo (usuario suspendido o publicación dada de baja). 
+    /// Las apelaciones se almacenan en un StorageVec, donde la posición es el id de la apelación. Se conservan todas, como registro. 
+    /// Las denuncias de los usuarios se almacenan en un StorageVec, donde la posición es el id de la denuncia. 
+    /// Los denunciantes son, por objetivo, las cuentas distintas con denuncias abiertas sobre él. 
+    /// Los objetivos en revisión son los que superaron el umbral de denunciantes, y quedan ocultos hasta que un moderador los revisa. 
+    /// La última denuncia guarda, por cuenta, el timestamp de su última denuncia (para limitar la frecuencia). 
+    /// Las categorias se almacenan en un StorageVec, donde la posición es el id de la categoría. Las administra el owner. 
+    /// Las variantes (talle, color, etc.) se almacenan por producto, cada una con su propio stock. El id de una variante es su posición en el Vec. 
+    /// Las listas de deseos se almacenan por comprador, con el precio y la disponibilidad de cada publicación al momento de guardarla. 
+    /// Los vendedores seguidos se almacenan por comprador, y los seguidores cuentan, por vendedor, cuántos compradores lo siguen. 
+    /// Los mensajes entre comprador y vendedor se almacenan por orden, en el orden en que se enviaron. No se editan ni se borran. 
+    /// Los puntos de fidelidad son el saldo de puntos de cada usuario, ganados con las compras y ventas recibidas y canjeables como descuento. 
     #[ink(storage)]
     pub struct PrimerContrato {
         usuarios: Mapping<AccountId, Usuario>,
@@ -35,1961 +77,6430 @@ mod primer_contrato {
         historial_ordenes_de_compra: StorageVec<(u32, OrdenCompra)>,   
         dimension_logica_productos: u32, 
         vector_ids_usuarios: StorageVec<AccountId>,
+        owner: AccountId,
+        contrato_reportes: Option<AccountId>,
+        comision_bps: u16,
+        tesoreria: Balance,
+        tesoreria_tokens: Mapping<AccountId, Balance>,
+        fondos_a_retirar: Mapping<CuentaFondos, Balance>,
+        devoluciones_aceptadas: Mapping<u32, Timestamp>,
+        ofertas: StorageVec<Oferta>,
+        cupones: Mapping<(AccountId, String), Cupon>,
+        plazo_calificacion: Timestamp,
+        monto_minimo_calificacion: u32,
+        antiguedad_minima_calificacion: Timestamp,
+        cuentas_relacionadas: Mapping<(AccountId, AccountId), bool>,
+        ordenes_entre_cuentas: Mapping<(AccountId, AccountId), u32>,
+        verificadores: Mapping<AccountId, bool>,
+        verificaciones: Mapping<AccountId, Vec<Verificacion>>,
+        moderadores: Mapping<AccountId, bool>,
+        sanciones: Mapping<ObjetivoModeracion, Sancion>,
+        apelaciones: StorageVec<Apelacion>,
+        denuncias: StorageVec<Denuncia>,
+        denunciantes: Mapping<ObjetivoModeracion, Vec<AccountId>>,
+        en_revision: Mapping<ObjetivoModeracion, bool>,
+        ultima_denuncia: Mapping<AccountId, Timestamp>,
+        categorias: StorageVec<Categoria>,
+        variantes: Mapping<u32, Vec<Variante>>,
+        listas_de_deseos: Mapping<AccountId, Vec<Deseo>>,
+        vendedores_seguidos: Mapping<AccountId, Vec<AccountId>>,
+        seguidores: Mapping<AccountId, u32>,
+        mensajes: Mapping<u32, Vec<Mensaje>>,
+        puntos_fidelidad: Mapping<AccountId, u32>,
+        contrato_recibos: Option<AccountId>,
+        suscripciones: StorageVec<Suscripcion>,
+        suscripciones_activas: StorageVec<u32>,
+        posiciones_suscripciones_activas: Mapping<u32, u32>,
     }
     impl PrimerContrato {
 
         #[ink(constructor)]
         /// Contructor del sistema. Inicializa todo en default/new, y la dimensión logica de los productos en cero. 
+        /// Quien despliega el contrato queda registrado como owner. 
+        /// Las categorias se inicializan con CATEGORIAS_INICIALES (ids 0 en adelante, sin categoría padre). 
         pub fn new() -> Self {
-            Self {
+            let mut contrato = Self {
                 usuarios: Mapping::default(),
                 historial_publicaciones: StorageVec::new(),
                 historial_productos: Mapping::default(),
                 historial_ordenes_de_compra:  StorageVec::new(),
                 dimension_logica_productos: 0,
                 vector_ids_usuarios: StorageVec::new(),
+                owner: Self::env().caller(),
+                contrato_reportes: None,
+                comision_bps: 0,
+                tesoreria: 0,
+                tesoreria_tokens: Mapping::default(),
+                fondos_a_retirar: Mapping::default(),
+                devoluciones_aceptadas: Mapping::default(),
+                ofertas: StorageVec::new(),
+                cupones: Mapping::default(),
+                plazo_calificacion: PLAZO_CALIFICACION_POR_DEFECTO,
+                monto_minimo_calificacion: 0,
+                antiguedad_minima_calificacion: 0,
+                cuentas_relacionadas: Mapping::default(),
+                ordenes_entre_cuentas: Mapping::default(),
+                verificadores: Mapping::default(),
+                verificaciones: Mapping::default(),
+                moderadores: Mapping::default(),
+                sanciones: Mapping::default(),
+                apelaciones: StorageVec::new(),
+                denuncias: StorageVec::new(),
+                denunciantes: Mapping::default(),
+                en_revision: Mapping::default(),
+                ultima_denuncia: Mapping::default(),
+                categorias: StorageVec::new(),
+                variantes: Mapping::default(),
+                listas_de_deseos: Mapping::default(),
+                vendedores_seguidos: Mapping::default(),
+                seguidores: Mapping::default(),
+                mensajes: Mapping::default(),
+                puntos_fidelidad: Mapping::default(),
+                contrato_recibos: None,
+                suscripciones: StorageVec::new(),
+                suscripciones_activas: StorageVec::new(),
+                posiciones_suscripciones_activas: Mapping::default(),
+            };
+            for (id, nombre) in (0u32..).zip(CATEGORIAS_INICIALES) {
+                contrato.categorias.push(&Categoria{ id, nombre: nombre.to_string(), padre: None });
             }
+            contrato
         }
 
         #[ink(message)]
-        /// La función agregar_usuario_sistema se encarga de registrar un usuario en mi sistema. 
+        /// La función "configurar_comision" permite al owner definir la comisión, en puntos básicos (100 = 1%), que se descuenta de cada pago liberado al vendedor. 
         /// 
-        /// Errores posibles: cuando el usuario ya está registrado. 
+        /// Errores posibles: el usuario no es el owner del sistema; la comisión es mayor a 10000 (100%). 
         #[cfg(not(test))]
-        pub fn agregar_usuario_sistema(&mut self, nombre: String, apellido: String, direccion: String, email: String, rol: Rol) -> Result <(), String>{
+        pub fn configurar_comision(&mut self, comision_bps: u16) -> Result<(), String>{
             let account_id = self.env().caller();
-            self.priv_agregar_usuario_sistema(account_id, nombre, apellido, direccion, email, rol)
+            self.priv_configurar_comision(account_id, comision_bps)
         }
-        fn priv_agregar_usuario_sistema(&mut self, account_id: AccountId, nombre: String, apellido: String, direccion: String, email: String, rol: Rol) -> Result <(), String>{
-            if self.usuarios.get(account_id).is_some(){
-                Err("El usuario ya esta registrado.".to_string())
-            } else {
-                let usuario = Usuario::nuevo(account_id, nombre, apellido, direccion, email, rol);
-                self.usuarios.insert(account_id, &usuario); 
-                self.vector_ids_usuarios.push(&account_id);
-                Ok(())
+        fn priv_configurar_comision(&mut self, account_id: AccountId, comision_bps: u16) -> Result<(), String>{
+            self.es_owner(account_id)?;
+            if comision_bps > 10000 {
+                return Err("La comision no puede superar los 10000 puntos basicos.".to_string())
             }
+            self.comision_bps = comision_bps;
+            Ok(())
         }
-        
+
         #[ink(message)]
-        /// La función "modificar_rol" permite al usuario cambiar su rol al recibido por parametro. 
+        /// La función "configurar_plazo_calificacion" permite al owner definir el plazo (en milisegundos desde la recepción de la orden) 
+        /// durante el cual las partes pueden enviar el compromiso de su calificación. 
+        /// 
+        /// Errores posibles: el usuario no es el owner del sistema; el plazo es 0. 
         #[cfg(not(test))]
-        pub fn modificar_rol(&mut self, nuevo_rol: Rol) -> Result<(), String>{
+        pub fn configurar_plazo_calificacion(&mut self, plazo: Timestamp) -> Result<(), String>{
             let account_id = self.env().caller();
-            self.priv_modificar_rol(account_id, nuevo_rol)
+            self.priv_configurar_plazo_calificacion(account_id, plazo)
         }
-        fn priv_modificar_rol(&mut self, account_id: AccountId, nuevo_rol: Rol) -> Result<(), String>{
-            let mut usuario = self.buscar_usuario(account_id)?;
-            usuario.modificar_rol(nuevo_rol)?;
-            self.usuarios.insert(account_id, &usuario);
+        fn priv_configurar_plazo_calificacion(&mut self, account_id: AccountId, plazo: Timestamp) -> Result<(), String>{
+            self.es_owner(account_id)?;
+            if plazo == 0 {
+                return Err("El plazo de calificacion no es valido.".to_string())
+            }
+            self.plazo_calificacion = plazo;
             Ok(())
         }
 
-        /// La función cargar_producto se encarga de registrar un producto en mi sistema.
+        #[ink(message)]
+        /// La función "crear_categoria" permite al owner agregar una categoría, opcionalmente como subcategoría de otra. 
+        /// Devuelve el id de la nueva categoría. Las categorías no se eliminan, ya que los productos cargados las referencian. 
         /// 
-        /// Errores posibles: el precio recibido por parametro es 0; 
-        /// el stock recibido por parametro es 0; 
-        /// si el usuario tiene rol Comp.
+        /// Errores posibles: el usuario no es el owner del sistema; el nombre está vacío o ya existe; la categoría padre no existe. 
+        #[cfg(not(test))]
+        pub fn crear_categoria(&mut self, nombre: String, padre: Option<u32>) -> Result<u32, String>{
+            let account_id = self.env().caller();
+            self.priv_crear_categoria(account_id, nombre, padre)
+        }
+        fn priv_crear_categoria(&mut self, account_id: AccountId, nombre: String, padre: Option<u32>) -> Result<u32, String>{
+            self.es_owner(account_id)?;
+            let id = self.categorias.len();
+            self.validar_categoria(id, &nombre, padre)?;
+            self.categorias.push(&Categoria{ id, nombre, padre });
+            Ok(id)
+        }
+
         #[ink(message)]
+        /// La función "modificar_categoria" permite al owner cambiar el nombre y la categoría padre de una categoría existente. 
+        /// 
+        /// Errores posibles: el usuario no es el owner del sistema; la categoría no existe; el nombre está vacío o ya existe; 
+        /// la categoría padre no existe, o es la misma categoría o una de sus subcategorías. 
         #[cfg(not(test))]
-        pub fn cargar_producto(&mut self, nombre: String, descripcion: String, precio: u32, categoria: Categoria, stock: u32) -> Result<(), String>{
+        pub fn modificar_categoria(&mut self, id_categoria: u32, nombre: String, padre: Option<u32>) -> Result<(), String>{
             let account_id = self.env().caller();
-            self.priv_cargar_producto(account_id, nombre, descripcion, precio, categoria, stock)
+            self.priv_modificar_categoria(account_id, id_categoria, nombre, padre)
         }
-        fn priv_cargar_producto(&mut self, account_id: AccountId, nombre: String, descripcion: String, precio: u32, categoria: Categoria, stock: u32) -> Result<(), String>{
-            if precio == 0 { //<---- Desde. Correccion punto 12. 12/08
-                return Err("Precio no valido".to_string())
-            }
-                if stock == 0 {
-                return Err("Stock no valido".to_string())
-            } //<---- Hasta. 
-                let mut usuario = self.buscar_usuario(account_id)?;
-            if (usuario.rol == Rol::Vend) | (usuario.rol == Rol::Ambos){
-                self.dimension_logica_productos = self.dimension_logica_productos.checked_add(1).ok_or("Error al sumar.")?;
-                    self.historial_productos.insert(self.dimension_logica_productos, &(Producto::cargar_producto(self.dimension_logica_productos, nombre, descripcion, precio, categoria), stock));
-                usuario.cargar_producto(self.dimension_logica_productos)?;
-                self.usuarios.insert(account_id, &usuario);
-                Ok(())
-            }
-            else {
-                Err("El usuario no tiene permisos para cargar productos. No es vendedor.".to_string())
+        fn priv_modificar_categoria(&mut self, account_id: AccountId, id_categoria: u32, nombre: String, padre: Option<u32>) -> Result<(), String>{
+            self.es_owner(account_id)?;
+            self.buscar_categoria(id_categoria)?;
+            self.validar_categoria(id_categoria, &nombre, padre)?;
+            if let Some(id_padre) = padre {
+                if Categoria::es_subcategoria(&self.priv_get_categorias(), id_padre, id_categoria) {
+                    return Err("La categoria padre no puede ser una subcategoria de la categoria.".to_string())
+                }
             }
+            self.categorias.set(id_categoria, &Categoria{ id: id_categoria, nombre, padre });
+            Ok(())
         }
 
-        /// La función "visualizar_productos_propios" devuelve un Vector de tuplas donde cada tupla tiene,
-        /// en la posición 0, el ID del producto, y en la posición 1 el stock.
         #[ink(message)]
+        /// La función "configurar_requisitos_calificacion" permite al owner definir el monto mínimo que debe tener una orden, 
+        /// y la antigüedad mínima (en milisegundos) que debe tener la cuenta que califica, para que la calificación se acepte. 
+        /// 
+        /// Errores posibles: el usuario no es el owner del sistema. 
         #[cfg(not(test))]
-        pub fn visualizar_productos_propios(&self) -> Result<Vec<(u32, u32)>, String>{
+        pub fn configurar_requisitos_calificacion(&mut self, monto_minimo: u32, antiguedad_minima: Timestamp) -> Result<(), String>{
             let account_id = self.env().caller();
-            self.priv_visualizar_productos_propios(account_id)
+            self.priv_configurar_requisitos_calificacion(account_id, monto_minimo, antiguedad_minima)
         }
-        fn priv_visualizar_productos_propios(&self, account_id: AccountId) -> Result<Vec<(u32, u32)>, String>{
-            let mut usuario = self.buscar_usuario(account_id)?;
-            usuario.es_vendedor_ambos()?;
-            let lista_de_productos = usuario.lista_de_productos()?;
-            let mut productos = self.cargar_productos_propios(account_id, lista_de_productos);
-            Ok(productos)
+        fn priv_configurar_requisitos_calificacion(&mut self, account_id: AccountId, monto_minimo: u32, antiguedad_minima: Timestamp) -> Result<(), String>{
+            self.es_owner(account_id)?;
+            self.monto_minimo_calificacion = monto_minimo;
+            self.antiguedad_minima_calificacion = antiguedad_minima;
+            Ok(())
         }
 
-        /// La función "crear_publicacion" se encarga de crear la publicación y luego registrarla en mi sistema. 
-        /// Recibe un Vector de tuplas donde la posición 0 es el ID del producto, y la posición 1 es la cantidad a publicar de ese producto.
-        /// 
-        /// Errores posibles: cuando la cantidad de un producto a publicar es 0. 
         #[ink(message)]
+        /// La función "marcar_cuentas_relacionadas" permite al owner marcar (o desmarcar) dos cuentas como relacionadas. 
+        /// Las calificaciones entre cuentas relacionadas siguen registrándose, pero pesan menos en la reputación ponderada. 
+        /// 
+        /// Errores posibles: el usuario no es el owner del sistema; ambas cuentas son la misma. 
         #[cfg(not(test))]
-        pub fn crear_publicacion(&mut self, productos_a_publicar: Vec<(u32, u32)>) -> Result<(), String> {
+        pub fn marcar_cuentas_relacionadas(&mut self, cuenta_a: AccountId, cuenta_b: AccountId, relacionadas: bool) -> Result<(), String>{
             let account_id = self.env().caller();
-            self.priv_crear_publicacion(account_id, productos_a_publicar)
+            self.priv_marcar_cuentas_relacionadas(account_id, cuenta_a, cuenta_b, relacionadas)
         }
-        fn priv_crear_publicacion(&mut self, account_id: AccountId, productos_a_publicar: Vec<(u32, u32)>) -> Result<(), String> {
-            let mut usuario = self.buscar_usuario(account_id)?;
-            for (id, cantidad) in productos_a_publicar.clone(){
-                if cantidad == 0 {
-                    return Err("Un producto tiene cantidades no validas.".to_string())
-                }
-                usuario.verificar_propiedad_producto(id)? // <------ Correccion Punto 12. 12/08
+        fn priv_marcar_cuentas_relacionadas(&mut self, account_id: AccountId, cuenta_a: AccountId, cuenta_b: AccountId, relacionadas: bool) -> Result<(), String>{
+            self.es_owner(account_id)?;
+            if cuenta_a == cuenta_b {
+                return Err("Las cuentas deben ser distintas.".to_string())
+            }
+            if relacionadas {
+                self.cuentas_relacionadas.insert((cuenta_a, cuenta_b), &true);
+                self.cuentas_relacionadas.insert((cuenta_b, cuenta_a), &true);
+            }
+            else {
+                self.cuentas_relacionadas.remove((cuenta_a, cuenta_b));
+                self.cuentas_relacionadas.remove((cuenta_b, cuenta_a));
             }
-            self.hay_stock_suficiente(productos_a_publicar.clone())?;
-            let id_publicacion = self.historial_publicaciones.len();
-            let precio_final = self.calcular_precio_final(productos_a_publicar.clone())?;
-            let publicacion = usuario.crear_publicacion(productos_a_publicar.clone(), precio_final, id_publicacion, account_id)?;
-            self.descontar_stock(productos_a_publicar)?;
-            self.historial_publicaciones.push(&(id_publicacion, publicacion));
-            self.usuarios.insert(account_id, &usuario);
             Ok(())
         }
-        
-        /// La función "visualizar_productos_de_publicación" se encarga de retornar todos los datos de una publicación (ID) recibida por parámetro. Devolviendo un tipo de dato Publicacion.  
-        /// 
-        /// Errores posibles: cuando el ID recibido por parametro no se halla en mi sistema (historial_publicaciones).
+
         #[ink(message)]
-        pub fn visualizar_productos_de_publicacion(&self, id_publicacion: u32) -> Result<Publicacion, String> {
-            self.priv_visualizar_productos_de_publicacion(id_publicacion)
+        /// La función "registrar_verificador" permite al owner habilitar (o deshabilitar) una cuenta como verificadora de vendedores. 
+        /// Al deshabilitar un verificador, las verificaciones que otorgó dejan de considerarse vigentes. 
+        /// 
+        /// Errores posibles: el usuario no es el owner del sistema. 
+        #[cfg(not(test))]
+        pub fn registrar_verificador(&mut self, verificador: AccountId, habilitado: bool) -> Result<(), String>{
+            let account_id = self.env().caller();
+            self.priv_registrar_verificador(account_id, verificador, habilitado)
         }
-        fn priv_visualizar_productos_de_publicacion(&self, id_publicacion: u32) -> Result<Publicacion, String> {
-            for i in 0..self.historial_publicaciones.len() {
-                if let Some((id, publicacion)) = self.historial_publicaciones.get(i) {
-                    if id == id_publicacion { 
-                        return Ok(publicacion.clone());
-                    }
-                }
+        fn priv_registrar_verificador(&mut self, account_id: AccountId, verificador: AccountId, habilitado: bool) -> Result<(), String>{
+            self.es_owner(account_id)?;
+            if habilitado {
+                self.verificadores.insert(verificador, &true);
             }
-            Err("No se encontro la publicacion.".to_string())
+            else {
+                self.verificadores.remove(verificador);
+            }
+            Ok(())
         }
-         
-        /// La función "crear_orden_de_compra" se encarga de crear una orden de compra de una publicación (ID) recibida por parametro.
-        /// 
-        /// Errores posibles: cuando la publicación ya no está disponible (boolean de Publicacion = false);
-        /// Cuando el usuario que quiere comprar una publicación, y es también el vendedor de la misma; 
-        /// Cuando el usuario que creó la publicación y luego cambia de rol a Comp. 
+
         #[ink(message)]
+        /// La función "otorgar_verificacion" permite a un verificador verificar a un vendedor hasta el vencimiento indicado. 
+        /// La referencia es el hash de la documentación (fuera de la cadena) que respalda la verificación. 
+        /// Si el verificador ya había verificado al vendedor, la verificación anterior se reemplaza. 
+        /// 
+        /// Errores posibles: el usuario no es verificador; el vendedor no existe o no es vendedor; el vencimiento ya pasó. 
         #[cfg(not(test))]
-        pub fn crear_orden_de_compra(&mut self, id_publicacion: u32) -> Result<(), String>{
+        pub fn otorgar_verificacion(&mut self, id_vendedor: AccountId, vencimiento: Timestamp, referencia: [u8; 32]) -> Result<(), String>{
             let account_id = self.env().caller();
-            self.priv_crear_orden_de_compra(account_id, id_publicacion)
+            let ahora = self.env().block_timestamp();
+            self.priv_otorgar_verificacion(account_id, id_vendedor, vencimiento, referencia, ahora)
         }
-        fn priv_crear_orden_de_compra(&mut self, account_id: AccountId, id_publicacion: u32) -> Result<(), String>{
-            let mut usuario = self.buscar_usuario(account_id)?;
-            let mut publicacion = self.visualizar_productos_de_publicacion(id_publicacion)?;
-            if publicacion.disponible{ //Agregado el 14/08
-                let vendedor_de_la_orden = self.usuarios.get(publicacion.id_vendedor).unwrap();
-                if account_id == vendedor_de_la_orden.id_usuario{
-                    return Err("El usuario no puede comprar sus propias publicaciones.".to_string())
-                }
-                if vendedor_de_la_orden.rol == Rol::Comp {
-                    return Err("La publicacion ya no se encuentra disponible.".to_string())
-                }
-                let id_orden = self.historial_ordenes_de_compra.len();
-                let orden_de_compra = usuario.crear_orden_de_compra(id_orden, publicacion.clone(), account_id)?;
-                self.historial_ordenes_de_compra.push(&(id_orden, orden_de_compra));
-                self.usuarios.insert(account_id, &usuario);
-                if self.puede_restockear(publicacion.clone()){ //Agregado el 14/08
-                    self.descontar_stock(publicacion.productos)?;
-                }
-                else {
-                    publicacion.disponible = !publicacion.disponible;
-                    let pos = self.devolver_posicion_publicacion(id_publicacion)?;
-                    self.historial_publicaciones.set(pos, &(id_publicacion, publicacion));
-                }
-                Ok(())  
+        fn priv_otorgar_verificacion(&mut self, account_id: AccountId, id_vendedor: AccountId, vencimiento: Timestamp, referencia: [u8; 32], ahora: Timestamp) -> Result<(), String>{
+            self.es_verificador(account_id)?;
+            self.buscar_usuario(id_vendedor)?.es_vendedor_ambos()?;
+            if vencimiento <= ahora {
+                return Err("El vencimiento de la verificacion ya paso.".to_string())
             }
-            else { //Agregado el 14/08
-                Err("La publicacion ya no tiene stock".to_string())
+            let mut verificaciones = self.verificaciones.get(id_vendedor).unwrap_or_default();
+            verificaciones.retain(|verificacion| verificacion.verificador != account_id);
+            verificaciones.push(Verificacion{ verificador: account_id, otorgada_en: ahora, vencimiento, referencia });
+            self.verificaciones.insert(id_vendedor, &verificaciones);
+            Ok(())
+        }
+
+        #[ink(message)]
+        /// La función "revocar_verificacion" permite a un verificador revocar la verificación que otorgó a un vendedor. 
+        /// 
+        /// Errores posibles: el usuario no es verificador; el verificador no había verificado al vendedor. 
+        #[cfg(not(test))]
+        pub fn revocar_verificacion(&mut self, id_vendedor: AccountId) -> Result<(), String>{
+            let account_id = self.env().caller();
+            self.priv_revocar_verificacion(account_id, id_vendedor)
+        }
+        fn priv_revocar_verificacion(&mut self, account_id: AccountId, id_vendedor: AccountId) -> Result<(), String>{
+            self.es_verificador(account_id)?;
+            let mut verificaciones = self.verificaciones.get(id_vendedor).unwrap_or_default();
+            let cantidad = verificaciones.len();
+            verificaciones.retain(|verificacion| verificacion.verificador != account_id);
+            if verificaciones.len() == cantidad {
+                return Err("El vendedor no tiene una verificacion de este verificador.".to_string())
             }
+            self.verificaciones.insert(id_vendedor, &verificaciones);
+            Ok(())
         }
-        
-        /// La función "cancelar_compra" se encarga de cancelar una compra.
+
         #[ink(message)]
+        /// La función "registrar_moderador" permite al owner habilitar (o deshabilitar) una cuenta como moderadora. 
+        /// 
+        /// Errores posibles: el usuario no es el owner del sistema. 
         #[cfg(not(test))]
-        pub fn cancelar_comprar(&mut self, id_orden: u32) -> Result<(), String>{
+        pub fn registrar_moderador(&mut self, moderador: AccountId, habilitado: bool) -> Result<(), String>{
             let account_id = self.env().caller();
-            self.priv_cancelar_compra(account_id, id_orden)
+            self.priv_registrar_moderador(account_id, moderador, habilitado)
         }
-        fn priv_cancelar_compra(&mut self, account_id: AccountId, id_orden: u32) -> Result<(), String>{
-            let mut usuario = self.buscar_usuario(account_id)?;
-            let mut datos_de_la_orden = self.buscar_orden(id_orden)?;
-            self.se_puede_cancelar(datos_de_la_orden.estado.clone())?;
-            let id_vendedor = datos_de_la_orden.info_publicacion.3;
-            let id_comprador = datos_de_la_orden.id_comprador;
-            let id_publicacion = datos_de_la_orden.info_publicacion.0; 
-            let rol = usuario.comprobar_rol(id_vendedor, id_comprador)?;
-            if rol == Rol::Comp{
-                datos_de_la_orden.cancelar_compra_comprador()?;
+        fn priv_registrar_moderador(&mut self, account_id: AccountId, moderador: AccountId, habilitado: bool) -> Result<(), String>{
+            self.es_owner(account_id)?;
+            if habilitado {
+                self.moderadores.insert(moderador, &true);
             }
             else {
-                datos_de_la_orden.cancelar_compra_vendedor()?;
-                self.devolver_productos(id_publicacion)?;
+                self.moderadores.remove(moderador);
             }
-            self.actualizar_ordenes(datos_de_la_orden, id_orden)?;
-            return Ok(());
+            Ok(())
         }
 
-        /// Funcion para enviar una compra.
-        /// 
-        /// Errores posibles: cuando el estado de la compra no es pendiente; 
-        /// cuando el ID de la orden recibida por parametro no se halla en mi sistema (historial_ordenes_compra).
         #[ink(message)]
+        /// La función "suspender_usuario" permite a un moderador suspender a un usuario, indicando el motivo. 
+        /// El usuario suspendido no puede cargar productos, publicar ni comprar, pero puede completar las ordenes que ya tenía en curso. 
+        /// 
+        /// Errores posibles: el usuario no es moderador; el usuario a suspender no existe o ya está suspendido. 
         #[cfg(not(test))]
-        pub fn enviar_compra(&mut self, id_orden: u32) -> Result<(), String>{
+        pub fn suspender_usuario(&mut self, id_usuario: AccountId, motivo: MotivoModeracion) -> Result<(), String>{
             let account_id = self.env().caller();
-            self.priv_enviar_compra(account_id, id_orden)
+            let ahora = self.env().block_timestamp();
+            self.priv_suspender_usuario(account_id, id_usuario, motivo, ahora)
         }
-        fn priv_enviar_compra(&mut self, account_id: AccountId, id_orden: u32) -> Result<(), String>{
-            let mut usuario = self.buscar_usuario(account_id)?;
-            for i in 0..self.historial_ordenes_de_compra.len() {
-                if let Some((id, mut orden_de_compra)) = self.historial_ordenes_de_compra.get(i){
-                    if id == id_orden {
-                        if orden_de_compra.estado != EstadoCompra::Pendiente{
-                            return Err("El producto no puede ser enviado.".to_string());
-                        }
-                        let id_publicacion = orden_de_compra.info_publicacion.0;
-                        usuario.enviar_compra(id_publicacion)?;
-                        orden_de_compra.estado = EstadoCompra::Enviado;
-                        let _ = self.historial_ordenes_de_compra.set(i, &(id, orden_de_compra));
-                        return Ok(());
-                    }
-                }
-            }
-            Err("No existe la orden buscada.".to_string())
+        fn priv_suspender_usuario(&mut self, account_id: AccountId, id_usuario: AccountId, motivo: MotivoModeracion, ahora: Timestamp) -> Result<(), String>{
+            self.buscar_usuario(id_usuario)?;
+            self.sancionar(account_id, ObjetivoModeracion::Usuario(id_usuario), motivo, ahora)
         }
-        
-        /// Funcion para recibir una compra.
+
+        #[ink(message)]
+        /// La función "dar_de_baja_publicacion" permite a un moderador dar de baja una publicación, indicando el motivo. 
+        /// La publicación deja de aparecer en las búsquedas y no se pueden crear nuevas ordenes sobre ella. 
         /// 
-        /// Errores posibles: cuando el estado de la orden de compra no es enviado;
-        /// cuando el ID de la orden recibida por parametro no se halla en mi sistema (historial_ordenes_compra).
+        /// Errores posibles: el usuario no es moderador; la publicación no existe o ya fue dada de baja. 
+        #[cfg(not(test))]
+        pub fn dar_de_baja_publicacion(&mut self, id_publicacion: u32, motivo: MotivoModeracion) -> Result<(), String>{
+            let account_id = self.env().caller();
+            let ahora = self.env().block_timestamp();
+            self.priv_dar_de_baja_publicacion(account_id, id_publicacion, motivo, ahora)
+        }
+        fn priv_dar_de_baja_publicacion(&mut self, account_id: AccountId, id_publicacion: u32, motivo: MotivoModeracion, ahora: Timestamp) -> Result<(), String>{
+            self.buscar_publicacion(id_publicacion)?;
+            self.sancionar(account_id, ObjetivoModeracion::Publicacion(id_publicacion), motivo, ahora)
+        }
+
         #[ink(message)]
+        /// La función "apelar" permite al afectado por una sanción (el usuario suspendido, o el vendedor de la publicación dada de baja) 
+        /// apelarla con un argumento. Devuelve el id de la apelación. 
+        /// 
+        /// Errores posibles: el objetivo no tiene una sanción vigente; el usuario no es el afectado; ya hay una apelación pendiente para el objetivo. 
         #[cfg(not(test))]
-        pub fn recibir_compra(&mut self, id_orden: u32) -> Result<(), String>{
+        pub fn apelar(&mut self, objetivo: ObjetivoModeracion, argumento: String) -> Result<u32, String>{
             let account_id = self.env().caller();
-            self.priv_recibir_compra(account_id, id_orden)
+            let ahora = self.env().block_timestamp();
+            self.priv_apelar(account_id, objetivo, argumento, ahora)
         }
-        fn priv_recibir_compra(&mut self, account_id: AccountId, id_orden: u32) -> Result<(), String>{ 
-            let mut usuario = self.buscar_usuario(account_id)?;
-            for i in 0..self.historial_ordenes_de_compra.len() {
-                if let Some((id, mut orden_de_compra)) = self.historial_ordenes_de_compra.get(i){
-                    if id == id_orden {
-                        if orden_de_compra.estado != EstadoCompra::Enviado{
-                            return Err("El producto todavia no fue enviado.".to_string());
-                        }
-                        usuario.recibir_compra(id_orden)?;
-                        orden_de_compra.estado = EstadoCompra::Recibido;
-                        let _ = self.historial_ordenes_de_compra.set(i, &(id, orden_de_compra));
-                        return Ok(())
-                    }
-                }
+        fn priv_apelar(&mut self, account_id: AccountId, objetivo: ObjetivoModeracion, argumento: String, ahora: Timestamp) -> Result<u32, String>{
+            if !self.sanciones.contains(&objetivo){
+                return Err("El objetivo no tiene una sancion vigente.".to_string())
             }
-            Err("No existe la orden buscada.".to_string())
+            let afectado = match objetivo {
+                ObjetivoModeracion::Usuario(id_usuario) => id_usuario,
+                ObjetivoModeracion::Publicacion(id_publicacion) => self.buscar_publicacion(id_publicacion)?.id_vendedor,
+            };
+            if account_id != afectado {
+                return Err("Solo el afectado puede apelar la sancion.".to_string())
+            }
+            let hay_pendiente = self.apelaciones_de(&objetivo).iter().any(|apelacion| apelacion.estado == EstadoApelacion::Pendiente);
+            if hay_pendiente {
+                return Err("Ya hay una apelacion pendiente.".to_string())
+            }
+            let id = self.apelaciones.len();
+            self.apelaciones.push(&Apelacion{ id, objetivo, apelante: account_id, argumento, fecha: ahora, estado: EstadoApelacion::Pendiente, resuelta_por: None });
+            Ok(id)
         }
 
-        /// Función que se encarga de calificar a un usuario.
-        /// 
-        /// Errores posibles: cuando la calificación recibida por parametro se encuentra fuera de rango (rango = [1..5]).
         #[ink(message)]
+        /// La función "resolver_apelacion" permite a un moderador aceptar o rechazar una apelación pendiente. 
+        /// Si se acepta, la sanción se levanta (el usuario deja de estar suspendido, o la publicación vuelve a estar visible). 
+        /// 
+        /// Errores posibles: el usuario no es moderador; la apelación no existe o ya fue resuelta. 
         #[cfg(not(test))]
-        pub fn calificar (&mut self, id_orden:u32, calificacion: u8) -> Result<(), String>{
+        pub fn resolver_apelacion(&mut self, id_apelacion: u32, aceptar: bool) -> Result<(), String>{
             let account_id = self.env().caller();
-            self.priv_calificar(id_orden, calificacion, account_id)
+            self.priv_resolver_apelacion(account_id, id_apelacion, aceptar)
         }
-        fn priv_calificar(&mut self, id_orden:u32, calificacion: u8, account_id: AccountId) -> Result<(), String>{
-            if (calificacion < 1) | (calificacion > 5){ //Revisa que la calificacion este en rango
-                return Err("El valor de la calificacion no es valido (1..5).".to_string())
+        fn priv_resolver_apelacion(&mut self, account_id: AccountId, id_apelacion: u32, aceptar: bool) -> Result<(), String>{
+            self.es_moderador(account_id)?;
+            let mut apelacion = self.apelaciones.get(id_apelacion).ok_or("No se encontro la apelacion.")?;
+            if apelacion.estado != EstadoApelacion::Pendiente {
+                return Err("La apelacion ya fue resuelta.".to_string())
             }
-            let mut usuario = self.buscar_usuario(account_id)?;
-            let mut orden_de_compra = self.buscar_orden(id_orden)?;
-            let id_comprador = orden_de_compra.id_comprador; //guarda la id del comprador y del vendedor
-            let id_vendedor = orden_de_compra.info_publicacion.3;
-            self.comprobar_estado_recibido(orden_de_compra.clone())?;
-            self.calificar_segun_rol(calificacion, orden_de_compra, id_vendedor, id_comprador, usuario) 
+            if aceptar {
+                apelacion.estado = EstadoApelacion::Aceptada;
+                self.sanciones.remove(&apelacion.objetivo);
+            }
+            else {
+                apelacion.estado = EstadoApelacion::Rechazada;
+            }
+            apelacion.resuelta_por = Some(account_id);
+            self.apelaciones.set(id_apelacion, &apelacion);
+            Ok(())
         }
 
-        /// La función "calcular_precio_final" se encarga de calcular el precio final de una publicación.
-        /// Recibe los productos con sus cantidades de una publicación y retorna el precio final. 
+        #[ink(message)]
+        /// La función "denunciar" permite a un usuario registrado denunciar a un usuario o una publicación, indicando el motivo. 
+        /// Cada cuenta puede denunciar una vez cada INTERVALO_ENTRE_DENUNCIAS, y una sola vez a cada objetivo mientras sus denuncias estén abiertas. 
+        /// Cuando el objetivo alcanza UMBRAL_DENUNCIANTES denunciantes distintos, queda oculto hasta que un moderador lo revisa. 
+        /// Devuelve el id de la denuncia. 
         /// 
-        /// Errores posibles: overflow en la suma/multiplicación. 
-        fn calcular_precio_final(&self, productos_publicados: Vec<(u32, u32)>) -> Result<u32, String>{
-            let mut total: u32 = 0;
-            for (id, cantidad) in productos_publicados{
-                if let Some((producto, _stock)) = self.historial_productos.get(id){
-                    total = total.checked_add(producto.precio.checked_mul(cantidad)
-                    .ok_or("Overflow al multiplicar precio por cantidad.")?)
-                    .ok_or("Overflow al acumular el total.")?;
+        /// Errores posibles: el usuario o el objetivo no existen; el usuario denunció hace menos de INTERVALO_ENTRE_DENUNCIAS; 
+        /// el usuario ya denunció al objetivo. 
+        #[cfg(not(test))]
+        pub fn denunciar(&mut self, objetivo: ObjetivoModeracion, motivo: MotivoModeracion) -> Result<u32, String>{
+            let account_id = self.env().caller();
+            let ahora = self.env().block_timestamp();
+            self.priv_denunciar(account_id, objetivo, motivo, ahora)
+        }
+        fn priv_denunciar(&mut self, account_id: AccountId, objetivo: ObjetivoModeracion, motivo: MotivoModeracion, ahora: Timestamp) -> Result<u32, String>{
+            self.buscar_usuario(account_id)?;
+            match objetivo {
+                ObjetivoModeracion::Usuario(id_usuario) => { self.buscar_usuario(id_usuario)?; },
+                ObjetivoModeracion::Publicacion(id_publicacion) => { self.buscar_publicacion(id_publicacion)?; },
+            }
+            if let Some(ultima) = self.ultima_denuncia.get(account_id) {
+                if ahora < ultima.checked_add(INTERVALO_ENTRE_DENUNCIAS).ok_or("Error al sumar.")? {
+                    return Err("Debe esperar para volver a denunciar.".to_string())
                 }
             }
-            Ok(total)
+            let mut denunciantes = self.denunciantes.get(&objetivo).unwrap_or_default();
+            if denunciantes.contains(&account_id) {
+                return Err("El usuario ya denuncio este objetivo.".to_string())
+            }
+            denunciantes.push(account_id);
+            if denunciantes.len() >= UMBRAL_DENUNCIANTES {
+                self.en_revision.insert(&objetivo, &true);
+            }
+            self.denunciantes.insert(&objetivo, &denunciantes);
+            self.ultima_denuncia.insert(account_id, &ahora);
+            let id = self.denuncias.len();
+            self.denuncias.push(&Denuncia{ id, objetivo, denunciante: account_id, motivo, fecha: ahora, abierta: true });
+            Ok(id)
         }
 
-      
-        /// La función "hay_stock_suficiente" se encarga de comprobar que el stock de mis productos almacenados sean suficientes y distintos de cero.
-        /// Recibe un Vector de tuplas donde la posición cero es el ID del producto y la posición uno la cantidad de ese producto a publicar. 
+        #[ink(message)]
+        /// La función "resolver_denuncias" permite a un moderador cerrar todas las denuncias abiertas de un objetivo. 
+        /// Si se indica un motivo, además sanciona al objetivo (como "suspender_usuario" o "dar_de_baja_publicacion"). 
+        /// En cualquier caso el objetivo deja de estar en revisión. 
         /// 
-        /// Errores posibles: si el stock es menor a la cantidad a publicar o el stock de mi producto es cero;
-        /// si el producto a publicar no se encuentra en mi sistema (historial_productos). 
-        fn hay_stock_suficiente(&self, productos_cantidades: Vec<(u32, u32)>) -> Result<(), String>{
-            for (id, cantidad) in productos_cantidades{
-                if let Some((_producto, stock)) = self.historial_productos.get(id){
-                    if (stock < cantidad) | (stock == 0){
-                        return Err("No hay stock suficiente.".to_string())
+        /// Errores posibles: el usuario no es moderador; el objetivo no tiene denuncias abiertas; el objetivo ya tiene una sanción vigente. 
+        #[cfg(not(test))]
+        pub fn resolver_denuncias(&mut self, objetivo: ObjetivoModeracion, sancion: Option<MotivoModeracion>) -> Result<(), String>{
+            let account_id = self.env().caller();
+            let ahora = self.env().block_timestamp();
+            self.priv_resolver_denuncias(account_id, objetivo, sancion, ahora)
+        }
+        fn priv_resolver_denuncias(&mut self, account_id: AccountId, objetivo: ObjetivoModeracion, sancion: Option<MotivoModeracion>, ahora: Timestamp) -> Result<(), String>{
+            self.es_moderador(account_id)?;
+            if !self.denunciantes.contains(&objetivo){
+                return Err("El objetivo no tiene denuncias abiertas.".to_string())
+            }
+            if let Some(motivo) = sancion {
+                self.sancionar(account_id, objetivo.clone(), motivo, ahora)?;
+            }
+            for i in 0..self.denuncias.len() {
+                if let Some(mut denuncia) = self.denuncias.get(i) {
+                    if denuncia.abierta & (denuncia.objetivo == objetivo) {
+                        denuncia.abierta = false;
+                        self.denuncias.set(i, &denuncia);
                     }
                 }
-                else{
-                    return Err("No se encontro el producto.".to_string())
-                }
             }
+            self.denunciantes.remove(&objetivo);
+            self.en_revision.remove(&objetivo);
             Ok(())
         }
-        
-        /// Función para descontar el stock de un producto. Recibe los productos (ID) con las cantidades a descontar. 
+
+        #[ink(message)]
+        /// La función "denuncias_abiertas" devuelve a un moderador las denuncias abiertas, agrupadas por objetivo, 
+        /// junto a si el objetivo está oculto en revisión. (objetivo, en revisión, denuncias). 
         /// 
-        /// Errores posibles: cuando ocurre overflow/underflow al realizar la resta. 
-        fn descontar_stock(&mut self, productos_cantidades: Vec<(u32, u32)>) -> Result<(), String>{
-            for (id, cantidad) in productos_cantidades{
-                if let Some ((producto, mut stock)) = self.historial_productos.get(id){
-                    stock = stock.checked_sub(cantidad).ok_or("Error al restar stock")?;
-                    self.historial_productos.insert(id, &(producto, stock));
+        /// Errores posibles: el usuario no es moderador. 
+        #[cfg(not(test))]
+        pub fn denuncias_abiertas(&self) -> Result<Vec<(ObjetivoModeracion, bool, Vec<Denuncia>)>, String>{
+            let account_id = self.env().caller();
+            self.priv_denuncias_abiertas(account_id)
+        }
+        fn priv_denuncias_abiertas(&self, account_id: AccountId) -> Result<Vec<(ObjetivoModeracion, bool, Vec<Denuncia>)>, String>{
+            self.es_moderador(account_id)?;
+            let mut agrupadas: Vec<(ObjetivoModeracion, bool, Vec<Denuncia>)> = Vec::new();
+            for i in 0..self.denuncias.len() {
+                if let Some(denuncia) = self.denuncias.get(i) {
+                    if !denuncia.abierta {
+                        continue;
+                    }
+                    match agrupadas.iter_mut().find(|(objetivo, _, _)| *objetivo == denuncia.objetivo) {
+                        Some((_, _, denuncias)) => denuncias.push(denuncia),
+                        None => {
+                            let en_revision = self.en_revision.contains(&denuncia.objetivo);
+                            agrupadas.push((denuncia.objetivo.clone(), en_revision, Vec::from([denuncia])));
+                        },
+                    }
                 }
             }
-            Ok(())
+            Ok(agrupadas)
         }
 
-        /// La función se encarga de cargar los productos (ID, stock) en un Vector. 
-        /// Recibe un Vector con IDs de productos y retorna un Vector de tuplas con ID del producto, stock del producto.
-        fn cargar_productos_propios(&self, account_id: AccountId, lista_de_productos: Vec<u32>) -> Vec<(u32, u32)>{
-            let mut productos = Vec::new();
-            for id_producto in lista_de_productos {
-                let stock = self.historial_productos.get(id_producto).unwrap().1;
-                let producto = (id_producto, stock);
-                productos.push(producto);
+        #[ink(message)]
+        /// La función "guardar_en_lista_de_deseos" permite a un comprador guardar una publicación en su lista de deseos. 
+        /// Se guardan el precio vigente y la disponibilidad del momento, para luego informar bajas de precio o reposiciones (ver "novedades_lista_de_deseos"). 
+        /// Si la publicación ya estaba guardada, se actualizan esos datos. 
+        /// 
+        /// Errores posibles: el usuario no es comprador; la publicación no existe o es propia; la lista de deseos está llena. 
+        #[cfg(not(test))]
+        pub fn guardar_en_lista_de_deseos(&mut self, id_publicacion: u32) -> Result<(), String>{
+            let account_id = self.env().caller();
+            let ahora = self.env().block_timestamp();
+            self.priv_guardar_en_lista_de_deseos(account_id, id_publicacion, ahora)
+        }
+        fn priv_guardar_en_lista_de_deseos(&mut self, account_id: AccountId, id_publicacion: u32, ahora: Timestamp) -> Result<(), String>{
+            self.buscar_usuario(account_id)?.es_comprador_ambos()?;
+            let publicacion = self.buscar_publicacion(id_publicacion)?;
+            if publicacion.id_vendedor == account_id {
+                return Err("El usuario no puede guardar sus propias publicaciones.".to_string())
             }
-            productos
+            let deseo = Deseo{ id_publicacion, precio_guardado: publicacion.precio_vigente(ahora), disponible_al_guardar: self.esta_disponible(&publicacion) };
+            let mut lista = self.listas_de_deseos.get(account_id).unwrap_or_default();
+            match lista.iter_mut().find(|guardado| guardado.id_publicacion == id_publicacion) {
+                Some(guardado) => *guardado = deseo,
+                None => {
+                    if lista.len() >= MAXIMO_LISTA_DE_DESEOS {
+                        return Err("La lista de deseos esta llena.".to_string())
+                    }
+                    lista.push(deseo);
+                },
+            }
+            self.listas_de_deseos.insert(account_id, &lista);
+            Ok(())
         }
 
-        /// La función "puede_restockear" se encarga de comprobar si el stock de cada producto es mayor o igual al de una publicacion recibida por parametro.
+        #[ink(message)]
+        /// La función "quitar_de_lista_de_deseos" quita una publicación de la lista de deseos del usuario. 
         /// 
-        /// Retornos posibles: falso en caso de que el stock sea insuficiente para reponer;
-        /// verdadero cuando es posible realizar la reposición. 
-        fn puede_restockear(&self, publicacion: Publicacion) -> bool{
-            for (id, cantidad) in publicacion.productos{
-                let stock = self.historial_productos.get(id).unwrap().1;
-                if stock < cantidad{
-                    return false;
-                }
-            }
-            return true;
+        /// Errores posibles: la publicación no está en la lista de deseos. 
+        #[cfg(not(test))]
+        pub fn quitar_de_lista_de_deseos(&mut self, id_publicacion: u32) -> Result<(), String>{
+            let account_id = self.env().caller();
+            self.priv_quitar_de_lista_de_deseos(account_id, id_publicacion)
+        }
+        fn priv_quitar_de_lista_de_deseos(&mut self, account_id: AccountId, id_publicacion: u32) -> Result<(), String>{
+            let mut lista = self.listas_de_deseos.get(account_id).unwrap_or_default();
+            let pos = lista.iter().position(|deseo| deseo.id_publicacion == id_publicacion).ok_or("La publicacion no esta en la lista de deseos.")?;
+            lista.remove(pos);
+            self.listas_de_deseos.insert(account_id, &lista);
+            Ok(())
         }
 
-        /// La función se encarga de devolver la posición en la que se encuentra una publicación en base a un ID recibido por parametro. 
-        /// 
-        /// Errores posibles: cuando la publicación no se encuentra en mi sistema (historial_publicaciones).
-        fn devolver_posicion_publicacion(&self, id_publicacion: u32) -> Result<u32, String>{
-            for i in 0..self.historial_publicaciones.len() {
-                if let Some((id, publicacion)) = self.historial_publicaciones.get(i) {
-                    if id == id_publicacion { 
-                        return Ok(i);
+        #[ink(message)]
+        /// La función "lista_de_deseos" devuelve las publicaciones guardadas por el usuario, con el precio y la disponibilidad al momento de guardarlas. 
+        #[cfg(not(test))]
+        pub fn lista_de_deseos(&self) -> Vec<Deseo>{
+            let account_id = self.env().caller();
+            self.listas_de_deseos.get(account_id).unwrap_or_default()
+        }
+
+        #[ink(message)]
+        /// La función "novedades_lista_de_deseos" devuelve las publicaciones de la lista de deseos del usuario que están disponibles y, 
+        /// desde que se guardaron, bajaron de precio o volvieron a estar disponibles. 
+        /// Las publicaciones que ya no existen se omiten. Para dejar de ver una novedad, se puede volver a guardar la publicación. 
+        #[cfg(not(test))]
+        pub fn novedades_lista_de_deseos(&self) -> Vec<NovedadDeseo>{
+            let account_id = self.env().caller();
+            let ahora = self.env().block_timestamp();
+            self.priv_novedades_lista_de_deseos(account_id, ahora)
+        }
+        fn priv_novedades_lista_de_deseos(&self, account_id: AccountId, ahora: Timestamp) -> Vec<NovedadDeseo>{
+            let mut novedades = Vec::new();
+            for deseo in self.listas_de_deseos.get(account_id).unwrap_or_default() {
+                if let Ok(publicacion) = self.buscar_publicacion(deseo.id_publicacion) {
+                    if !self.esta_disponible(&publicacion) {
+                        continue;
+                    }
+                    let precio_actual = publicacion.precio_vigente(ahora);
+                    let volvio_a_estar_disponible = !deseo.disponible_al_guardar;
+                    if (precio_actual < deseo.precio_guardado) | volvio_a_estar_disponible {
+                        novedades.push(NovedadDeseo{ id_publicacion: deseo.id_publicacion, precio_guardado: deseo.precio_guardado, precio_actual, volvio_a_estar_disponible });
                     }
                 }
             }
-            return Err("No se encontro publicacion".to_string());
+            novedades
         }
 
-        /// La función se encarga de comprobar si es posible cancelar una compra. 
+        #[ink(message)]
+        /// La función "seguir_vendedor" permite a un comprador seguir a un vendedor. 
+        /// Cada vez que un vendedor con seguidores crea una publicación se emite el evento PublicacionDeVendedorSeguido. 
         /// 
-        /// Errores posibles: cuando la compra no tiene estado Pendiente. 
-        fn se_puede_cancelar(&self, estado_de_la_orden: EstadoCompra)-> Result<(), String>{
-            if estado_de_la_orden != EstadoCompra::Pendiente{
-                return Err("Ya no se puede cancelar la compra.".to_string())
-            }
-            else {
-                return Ok(())
-            }
+        /// Errores posibles: el usuario no es comprador; el vendedor no existe o no es vendedor; el usuario intenta seguirse a sí mismo; 
+        /// ya sigue al vendedor; alcanzó la cantidad máxima de vendedores seguidos. 
+        #[cfg(not(test))]
+        pub fn seguir_vendedor(&mut self, id_vendedor: AccountId) -> Result<(), String>{
+            let account_id = self.env().caller();
+            self.priv_seguir_vendedor(account_id, id_vendedor)
         }
-
-        /// La función se encarga de reponer los productos en la publicación o en el stock del producto que corresponda.
-        fn devolver_productos(&mut self, id_publicacion: u32,) -> Result<(), String>{
-            let mut publicacion = self.buscar_publicacion(id_publicacion)?;
-            if publicacion.disponible {
-                return self.aumentar_stock_productos(publicacion.productos);
+        fn priv_seguir_vendedor(&mut self, account_id: AccountId, id_vendedor: AccountId) -> Result<(), String>{
+            self.buscar_usuario(account_id)?.es_comprador_ambos()?;
+            self.buscar_usuario(id_vendedor)?.es_vendedor_ambos()?;
+            if id_vendedor == account_id {
+                return Err("El usuario no puede seguirse a si mismo.".to_string())
             }
-            else {
-                publicacion.disponible = true;
-                return self.actualizar_publicaciones(publicacion, id_publicacion);
+            let mut seguidos = self.vendedores_seguidos.get(account_id).unwrap_or_default();
+            if seguidos.contains(&id_vendedor) {
+                return Err("El usuario ya sigue a ese vendedor.".to_string())
             }
-        }
-
-        /// La función se encarga de aumentar el stock de cada producto recibido por parametro. 
-        /// Recibe un Vector de tuplas donde la posición cero es el ID del producto y la posición uno es la cantidad a aumentar.
-        /// 
-        /// Errores posibles: overflow en la suma;
-        /// un ID del Vector recibido por parametro no se encuentra en mi sistema (historial_productos). 
-        fn aumentar_stock_productos(&mut self, productos_cantidades: Vec<(u32, u32)>) -> Result<(), String>{
-            for (id, cantidad) in productos_cantidades{
-                if let Some ((producto, mut stock)) = self.historial_productos.get(id){
-                    stock = stock.checked_add(cantidad).ok_or("Error al sumar stock")?;
-                    self.historial_productos.insert(id, &(producto, stock)); //sobreescribe el vector
-                }
-                else {
-                    return Err("No se encontro el producto.".to_string())
-                }
+            if seguidos.len() >= MAXIMO_VENDEDORES_SEGUIDOS {
+                return Err("El usuario alcanzo la cantidad maxima de vendedores seguidos.".to_string())
             }
+            let seguidores = self.seguidores.get(id_vendedor).unwrap_or(0).checked_add(1).ok_or("Error al sumar.")?;
+            seguidos.push(id_vendedor);
+            self.vendedores_seguidos.insert(account_id, &seguidos);
+            self.seguidores.insert(id_vendedor, &seguidores);
             Ok(())
         }
 
-        /// La función se encarga de pisar un valor del StorageVec "historial_publicaciones" en base a un ID de una publicación recibida por parametro.
-        fn actualizar_publicaciones(&mut self, publicacion: Publicacion, id_publicacion: u32) -> Result<(), String>{
-            let pos = self.devolver_posicion_publicacion(id_publicacion)?;
-            self.historial_publicaciones.set(pos, &(id_publicacion, publicacion));
-            return Ok(());
+        #[ink(message)]
+        /// La función "dejar_de_seguir_vendedor" permite a un comprador dejar de seguir a un vendedor. 
+        /// 
+        /// Errores posibles: el usuario no sigue al vendedor. 
+        #[cfg(not(test))]
+        pub fn dejar_de_seguir_vendedor(&mut self, id_vendedor: AccountId) -> Result<(), String>{
+            let account_id = self.env().caller();
+            self.priv_dejar_de_seguir_vendedor(account_id, id_vendedor)
         }
-
-        /// La función se encarga de pisar un valor del StorageVec "historial_ordenes_de_compra" en base a un ID de una orden recibida por parametro.
-        fn actualizar_ordenes(&mut self, orden: OrdenCompra, id_orden: u32) -> Result<(), String>{
-            let pos = self.devolver_posicion_orden_de_compra(id_orden)?;
-            self.historial_ordenes_de_compra.set(pos, &(id_orden, orden));
-            return Ok(());
+        fn priv_dejar_de_seguir_vendedor(&mut self, account_id: AccountId, id_vendedor: AccountId) -> Result<(), String>{
+            let mut seguidos = self.vendedores_seguidos.get(account_id).unwrap_or_default();
+            let pos = seguidos.iter().position(|seguido| *seguido == id_vendedor).ok_or("El usuario no sigue a ese vendedor.")?;
+            let seguidores = self.seguidores.get(id_vendedor).unwrap_or(0).checked_sub(1).ok_or("Error al restar.")?;
+            seguidos.remove(pos);
+            self.vendedores_seguidos.insert(account_id, &seguidos);
+            self.seguidores.insert(id_vendedor, &seguidores);
+            Ok(())
         }
 
-        /// La función se encarga de devolver una OrdenCompra en base a un ID recibido por parametro.
-        /// 
-        /// Errores posibles: cuando no se encuentra el ID de la orden en mi sistema (historial_ordenes_de_compra). 
-        fn buscar_orden(&self, id_orden: u32) -> Result<OrdenCompra, String>{
-            for i in 0..self.historial_ordenes_de_compra.len(){
-                if let Some((id, ref orden)) = self.historial_ordenes_de_compra.get(i){
-                    if id == id_orden{
-                        return Ok(orden.clone());
-                    }
-                }
-            }
-            return Err("No se encontro la orden.".to_string());
+        #[ink(message)]
+        /// La función "vendedores_seguidos" devuelve los vendedores que sigue el usuario. 
+        #[cfg(not(test))]
+        pub fn vendedores_seguidos(&self) -> Vec<AccountId>{
+            let account_id = self.env().caller();
+            self.vendedores_seguidos.get(account_id).unwrap_or_default()
         }
 
-        /// La función se encarga de devolver una Publicación en base a un ID recibido por parametro. 
+        #[ink(message)]
+        /// La función "retirar_comisiones" transfiere al owner todas las comisiones acumuladas en la tesorería, y devuelve el monto retirado. 
         /// 
-        /// Errores posibles: cuando no se encuentra el ID de la publicación en mi sistema (historial_publicaciones).
-        fn buscar_publicacion(&self, id_publicacion: u32) -> Result<Publicacion, String>{
-            for i in 0..self.historial_publicaciones.len(){
-                if let Some((id, ref publicacion)) = self.historial_publicaciones.get(i){
-                    if id == id_publicacion{
-                        return Ok(publicacion.clone());
-                    }
-                }
+        /// Errores posibles: el usuario no es el owner del sistema; la tesorería está vacía; falla la transferencia. 
+        #[cfg(not(test))]
+        pub fn retirar_comisiones(&mut self) -> Result<Balance, String>{
+            let account_id = self.env().caller();
+            self.priv_retirar_comisiones(account_id)
+        }
+        fn priv_retirar_comisiones(&mut self, account_id: AccountId) -> Result<Balance, String>{
+            self.es_owner(account_id)?;
+            let monto = self.tesoreria;
+            if monto == 0 {
+                return Err("No hay comisiones para retirar.".to_string())
             }
-            return Err("No se encontro la publicacion.".to_string());
+            self.tesoreria = 0;
+            self.transferir(None, account_id, monto)?;
+            Ok(monto)
         }
 
-        /// La función se encarga de devolver la posición de una orden de compra en base a el ID recibido por parametro.
+        #[ink(message)]
+        /// La función "retirar_comisiones_token" transfiere al owner todas las comisiones acumuladas en el token PSP22 recibido por parametro, y devuelve el monto retirado. 
         /// 
-        /// Errores posibles: cuando la orden con el ID recibido por parametro no se encuentra en mi sistema (historial_ordenes_de_compra).
-        fn devolver_posicion_orden_de_compra(&self, id_orden: u32) -> Result<u32, String>{
-            for i in 0..self.historial_ordenes_de_compra.len() {
-                if let Some((id, orden)) = self.historial_ordenes_de_compra.get(i) {
-                    if id == id_orden { 
-                        return Ok(i);
-                    }
-                }
+        /// Errores posibles: el usuario no es el owner del sistema; no hay comisiones de ese token; falla la transferencia. 
+        #[cfg(not(test))]
+        pub fn retirar_comisiones_token(&mut self, token: AccountId) -> Result<Balance, String>{
+            let account_id = self.env().caller();
+            self.priv_retirar_comisiones_token(account_id, token)
+        }
+        fn priv_retirar_comisiones_token(&mut self, account_id: AccountId, token: AccountId) -> Result<Balance, String>{
+            self.es_owner(account_id)?;
+            let monto = self.tesoreria_tokens.get(token).unwrap_or(0);
+            if monto == 0 {
+                return Err("No hay comisiones para retirar.".to_string())
             }
-            return Err("No se encontro la orden de compra.".to_string());
+            self.tesoreria_tokens.remove(token);
+            self.transferir(Some(token), account_id, monto)?;
+            Ok(monto)
         }
 
-        /// La función se encarga comprobar el estado de la compra. 
+        #[ink(message)]
+        /// La función "registrar_contrato_reportes" permite al owner configurar el contrato de reportes al que se le notifican
+        /// los cambios de estado de las ordenes y las calificaciones. Recibiendo None se deja de notificar.
         /// 
-        /// Errores posibles: cuando el estado de la compra es distinto de Recibido. 
-        fn comprobar_estado_recibido(&self, orden: OrdenCompra) -> Result<(), String>{
-            if orden.estado == EstadoCompra::Recibido{
-                Ok(())
-            }
-            else{
-                Err("La compra aun no fue recibida.".to_string())
-            }
+        /// Errores posibles: el usuario no es el owner del sistema. 
+        #[cfg(not(test))]
+        pub fn registrar_contrato_reportes(&mut self, direccion: Option<AccountId>) -> Result<(), String>{
+            let account_id = self.env().caller();
+            self.priv_registrar_contrato_reportes(account_id, direccion)
         }
-
-        /// La función se encarga de calificar a un usuario según el rol que tuvo en la compra. 
-        fn calificar_segun_rol(&mut self, calificacion: u8, mut orden_de_compra: OrdenCompra, id_vendedor: AccountId, id_comprador: AccountId, usuario: Usuario) -> Result<(), String>{
-            let rol_del_usuario_en_compra = usuario.comprobar_rol(id_vendedor, id_comprador)?;
-            let _ = self.ya_califico(rol_del_usuario_en_compra.clone(), orden_de_compra.clone())?;
-            if rol_del_usuario_en_compra == Rol::Comp{
-                self.calificar_vendedor(id_vendedor, calificacion)?;
-                orden_de_compra.calificaciones.0 = true;
-                orden_de_compra.puntuacion_del_comprador = Some(calificacion);
-            }
-            else {
-                self.calificar_comprador(id_comprador, calificacion)?;
-                orden_de_compra.calificaciones.1 = true;
-            }
-            let id_orden = orden_de_compra.id;
-            self.actualizar_ordenes(orden_de_compra, id_orden)
+        fn priv_registrar_contrato_reportes(&mut self, account_id: AccountId, direccion: Option<AccountId>) -> Result<(), String>{
+            self.es_owner(account_id)?;
+            self.contrato_reportes = direccion;
+            Ok(())
         }
 
-        /// La función se encarga de de comprobar si un usuario ya calificó una compra que realizó. 
+        #[ink(message)]
+        /// La función "registrar_contrato_recibos" permite al owner configurar el contrato PSP34 que acuña los recibos de compra. 
+        /// Recibiendo None se dejan de emitir recibos. 
         /// 
-        /// Errores posibles: cuando el usuario ya calificó la compra anteriormente. 
-        fn ya_califico(&self, rol: Rol, orden: OrdenCompra) -> Result<(), String>{
-            if ((rol == Rol::Comp) & (orden.calificaciones.0)) | ((rol == Rol::Vend) & (orden.calificaciones.1)){
-                return Err("El usuario ya califico.".to_string())
-            }
+        /// Errores posibles: el usuario no es el owner del sistema. 
+        #[cfg(not(test))]
+        pub fn registrar_contrato_recibos(&mut self, direccion: Option<AccountId>) -> Result<(), String>{
+            let account_id = self.env().caller();
+            self.priv_registrar_contrato_recibos(account_id, direccion)
+        }
+        fn priv_registrar_contrato_recibos(&mut self, account_id: AccountId, direccion: Option<AccountId>) -> Result<(), String>{
+            self.es_owner(account_id)?;
+            self.contrato_recibos = direccion;
             Ok(())
         }
 
-        /// La función se encarga de registrar la puntuación recibida por parametro a un usuario con rol Vend.
+        #[ink(message)]
+        /// La función agregar_usuario_sistema se encarga de registrar un usuario en mi sistema. 
         /// 
-        /// Errores posibles: cuando el usuario no tiene los datos correspondientes a un vendedor cargados. 
-        fn calificar_vendedor(&mut self, id_vendedor: AccountId, calificacion: u8) -> Result<(), String>{
-            let mut vendedor = self.buscar_usuario(id_vendedor)?;
-            if let Some(ref mut datos_vendedor) = vendedor.datos_vendedor{
-                datos_vendedor.reputacion_como_vendedor.push(calificacion);
-                self.actualizar_usuarios(vendedor);
+        /// Errores posibles: cuando el usuario ya está registrado. 
+        #[cfg(not(test))]
+        pub fn agregar_usuario_sistema(&mut self, nombre: String, apellido: String, direccion: String, email: String, rol: Rol) -> Result <(), String>{
+            let account_id = self.env().caller();
+            self.priv_agregar_usuario_sistema(account_id, nombre, apellido, direccion, email, rol)
+        }
+        fn priv_agregar_usuario_sistema(&mut self, account_id: AccountId, nombre: String, apellido: String, direccion: String, email: String, rol: Rol) -> Result <(), String>{
+            if self.usuarios.get(account_id).is_some(){
+                Err("El usuario ya esta registrado.".to_string())
+            } else {
+                let usuario = Usuario::nuevo(account_id, nombre, apellido, direccion, email, rol, self.env().block_timestamp());
+                self.usuarios.insert(account_id, &usuario); 
+                self.vector_ids_usuarios.push(&account_id);
                 Ok(())
             }
-            else{
-                Err("El vendedor no tiene datos cargados.".to_string())
-            }
+        }
+        
+        #[ink(message)]
+        /// La función "modificar_rol" permite al usuario cambiar su rol al recibido por parametro. 
+        #[cfg(not(test))]
+        pub fn modificar_rol(&mut self, nuevo_rol: Rol) -> Result<(), String>{
+            let account_id = self.env().caller();
+            self.priv_modificar_rol(account_id, nuevo_rol)
+        }
+        fn priv_modificar_rol(&mut self, account_id: AccountId, nuevo_rol: Rol) -> Result<(), String>{
+            let mut usuario = self.buscar_usuario(account_id)?;
+            usuario.modificar_rol(nuevo_rol)?;
+            self.usuarios.insert(account_id, &usuario);
+            Ok(())
         }
 
-        /// La función se encarga de registrar la puntuación recibida por parametro a un usuario con rol Comp. 
+        /// La función cargar_producto se encarga de registrar un producto en mi sistema.
         /// 
-        /// Errores posibles: cuando el usuario no tiene los datos correspondientes a un comprador cargados. 
-        fn calificar_comprador(&mut self, id_comprador: AccountId, calificacion: u8) -> Result<(), String>{
-            let mut comprador = self.buscar_usuario(id_comprador)?;
-            if let Some(ref mut datos_comprador) = comprador.datos_comprador{
-                datos_comprador.reputacion_como_comprador.push(calificacion);
-                self.actualizar_usuarios(comprador);
+        /// categoria es el id de una de las categorías del sistema. 
+        /// 
+        /// Errores posibles: el usuario está suspendido; el precio recibido por parametro es 0; 
+        /// el stock recibido por parametro es 0; la categoría no existe; 
+        /// si el usuario tiene rol Comp.
+        #[ink(message)]
+        #[cfg(not(test))]
+        pub fn cargar_producto(&mut self, nombre: String, descripcion: String, precio: u32, categoria: u32, stock: u32) -> Result<(), String>{
+            let account_id = self.env().caller();
+            self.priv_cargar_producto(account_id, nombre, descripcion, precio, categoria, stock)
+        }
+        fn priv_cargar_producto(&mut self, account_id: AccountId, nombre: String, descripcion: String, precio: u32, categoria: u32, stock: u32) -> Result<(), String>{
+            self.no_esta_suspendido(account_id)?;
+            self.buscar_categoria(categoria)?;
+            if precio == 0 { //<---- Desde. Correccion punto 12. 12/08
+                return Err("Precio no valido".to_string())
+            }
+                if stock == 0 {
+                return Err("Stock no valido".to_string())
+            } //<---- Hasta. 
+                let mut usuario = self.buscar_usuario(account_id)?;
+            if (usuario.rol == Rol::Vend) | (usuario.rol == Rol::Ambos){
+                self.dimension_logica_productos = self.dimension_logica_productos.checked_add(1).ok_or("Error al sumar.")?;
+                    self.historial_productos.insert(self.dimension_logica_productos, &(Producto::cargar_producto(self.dimension_logica_productos, nombre, descripcion, precio, categoria), stock));
+                usuario.cargar_producto(self.dimension_logica_productos)?;
+                self.usuarios.insert(account_id, &usuario);
                 Ok(())
             }
-            else{
-                Err("El comprador no tiene datos cargados.".to_string())
+            else {
+                Err("El usuario no tiene permisos para cargar productos. No es vendedor.".to_string())
             }
         }
 
-        /// La función se encarga de pisar un valor de mi Mapping "usuarios".  
-        fn actualizar_usuarios(&mut self, usuario: Usuario){
-            self.usuarios.insert(usuario.id_usuario, &usuario);
+        /// La función "visualizar_productos_propios" devuelve un Vector de tuplas donde cada tupla tiene,
+        /// en la posición 0, el ID del producto, y en la posición 1 el stock.
+        #[ink(message)]
+        #[cfg(not(test))]
+        pub fn visualizar_productos_propios(&self) -> Result<Vec<(u32, u32)>, String>{
+            let account_id = self.env().caller();
+            self.priv_visualizar_productos_propios(account_id)
+        }
+        fn priv_visualizar_productos_propios(&self, account_id: AccountId) -> Result<Vec<(u32, u32)>, String>{
+            let mut usuario = self.buscar_usuario(account_id)?;
+            usuario.es_vendedor_ambos()?;
+            let lista_de_productos = usuario.lista_de_productos()?;
+            let mut productos = self.cargar_productos_propios(account_id, lista_de_productos);
+            Ok(productos)
         }
 
-        /// La función se encarga de devolver el Usuario correspondiente al ID recibido por parametro. 
+        /// La función "agregar_variante" permite al vendedor de un producto definir una variante del mismo (por ejemplo talle y color), con su propio stock. 
+        /// atributos son los pares (atributo, valor) que describen a la variante. ej: [("talle", "M"), ("color", "azul")]. 
+        /// delta_precio es la diferencia (positiva o negativa) entre el precio de la variante y el del producto. 
+        /// Retorna el id de la variante dentro del producto. 
         /// 
-        /// Errores posibles: no se halla el ID en mi sistema (usuarios). 
-        fn buscar_usuario(&self, id_usuario: AccountId) -> Result<Usuario, String>{
-            if let Some(ref usuario) = self.usuarios.get(id_usuario){
-                Ok(usuario.clone())
+        /// Errores posibles: el usuario está suspendido; el usuario no posee el producto; la variante no tiene atributos; 
+        /// el stock es 0; el precio resultante de la variante no es mayor a 0. 
+        #[ink(message)]
+        #[cfg(not(test))]
+        pub fn agregar_variante(&mut self, id_producto: u32, atributos: Vec<(String, String)>, delta_precio: i32, stock: u32) -> Result<u32, String>{
+            let account_id = self.env().caller();
+            self.priv_agregar_variante(account_id, id_producto, atributos, delta_precio, stock)
+        }
+        fn priv_agregar_variante(&mut self, account_id: AccountId, id_producto: u32, atributos: Vec<(String, String)>, delta_precio: i32, stock: u32) -> Result<u32, String>{
+            self.no_esta_suspendido(account_id)?;
+            self.buscar_usuario(account_id)?.verificar_propiedad_producto(id_producto)?;
+            if atributos.is_empty() {
+                return Err("La variante debe tener al menos un atributo.".to_string())
             }
-            else {
-                return Err("No se encontro el usuario.".to_string())
+            if stock == 0 {
+                return Err("Stock no valido".to_string())
             }
+            let (producto, _stock) = self.historial_productos.get(id_producto).ok_or("No se encontro el producto.")?;
+            let mut variantes = self.variantes.get(id_producto).unwrap_or_default();
+            let id = u32::try_from(variantes.len()).map_err(|_| "Error al sumar.")?;
+            let variante = Variante{ id, atributos, delta_precio, stock };
+            variante.precio(producto.precio)?;
+            variantes.push(variante);
+            self.variantes.insert(id_producto, &variantes);
+            Ok(id)
         }
 
-        /// Funcion que retorna el id del usuario en una posicion dada del vector vector_ids_usuarios
-        ///
-        /// Errores posibles: El id no se encuentra cargado en el sistema
-        fn buscar_id_usuario(&self, pos: u32) -> Result<AccountId, String>{
-            if let Some(id) = self.vector_ids_usuarios.get(pos){
-                return Ok(id)
-            } else{
-                return Err("No se encontro el id.".to_string())
+        /// La función "reponer_stock_variante" permite al vendedor de un producto sumar unidades al stock de una de sus variantes. 
+        /// 
+        /// Errores posibles: el usuario no posee el producto; la variante no existe; la cantidad es 0; overflow en la suma. 
+        #[ink(message)]
+        #[cfg(not(test))]
+        pub fn reponer_stock_variante(&mut self, id_producto: u32, id_variante: u32, cantidad: u32) -> Result<(), String>{
+            let account_id = self.env().caller();
+            self.priv_reponer_stock_variante(account_id, id_producto, id_variante, cantidad)
+        }
+        fn priv_reponer_stock_variante(&mut self, account_id: AccountId, id_producto: u32, id_variante: u32, cantidad: u32) -> Result<(), String>{
+            self.buscar_usuario(account_id)?.verificar_propiedad_producto(id_producto)?;
+            if cantidad == 0 {
+                return Err("Stock no valido".to_string())
             }
+            self.aumentar_stock_productos(vec![(id_producto, cantidad)], &[(id_producto, id_variante)])
         }
 
-        //Getters para el segundo contrato
-        
+        /// La función "visualizar_variantes" devuelve las variantes de un producto, con sus atributos, diferencia de precio y stock. 
+        /// 
+        /// Errores posibles: el producto no existe. 
         #[ink(message)]
-        /// Funcion que devuelve un vector con los productos en sistema
-        pub fn get_productos(&self) -> Vec<Producto>{
-            self.priv_get_productos()
-        }
-        fn priv_get_productos(&self) -> Vec<Producto>{
-            let mut vec_productos = Vec::new();
-
-            for i in 0..=self.dimension_logica_productos {
-                if let Some((producto, _stock)) = self.historial_productos.get(i) {
-                    vec_productos.push(producto);
-                }
+        pub fn visualizar_variantes(&self, id_producto: u32) -> Result<Vec<Variante>, String>{
+            if !self.historial_productos.contains(id_producto) {
+                return Err("No se encontro el producto.".to_string())
             }
-            vec_productos
+            Ok(self.variantes.get(id_producto).unwrap_or_default())
         }
 
+        /// La función "actualizar_medios" permite al vendedor de un producto reemplazar sus referencias a contenido fuera de la cadena 
+        /// (CIDs de IPFS o hashes de imágenes y fichas técnicas). Una lista vacía quita todas las referencias. 
+        /// 
+        /// Errores posibles: el usuario no posee el producto; se supera la cantidad máxima de referencias; 
+        /// una referencia está vacía o supera el largo máximo. 
         #[ink(message)]
-        /// Funcion que devuelve un vector con los usuarios en sistema
-        pub fn get_usuarios(&self) -> Result<Vec<Usuario>, String>{
-            self.priv_get_usuarios()
+        #[cfg(not(test))]
+        pub fn actualizar_medios(&mut self, id_producto: u32, medios: Vec<String>) -> Result<(), String>{
+            let account_id = self.env().caller();
+            self.priv_actualizar_medios(account_id, id_producto, medios)
         }
-        fn priv_get_usuarios(&self) -> Result<Vec<Usuario>, String>{
-            let mut vec_usuarios= Vec::new();
-            
-            for i in 0..self.vector_ids_usuarios.len(){
-                vec_usuarios.push(self.buscar_usuario(self.buscar_id_usuario(i)?)?);
-            }
-            return Ok(vec_usuarios);
+        fn priv_actualizar_medios(&mut self, account_id: AccountId, id_producto: u32, medios: Vec<String>) -> Result<(), String>{
+            self.buscar_usuario(account_id)?.verificar_propiedad_producto(id_producto)?;
+            Producto::validar_medios(&medios)?;
+            let (mut producto, stock) = self.historial_productos.get(id_producto).ok_or("No se encontro el producto.")?;
+            producto.medios = medios;
+            self.historial_productos.insert(id_producto, &(producto, stock));
+            Ok(())
         }
 
+        /// La función "visualizar_producto" devuelve los datos de un producto (incluidas sus referencias a contenido) y su stock. 
+        /// 
+        /// Errores posibles: el producto no existe. 
         #[ink(message)]
-        ///Funcion que devuelve un vector con las ordenes de compra en sistema.
-        ///
-        /// Errores posibles: No hay ordenes de compra en el sistema.
-        pub fn get_ordenes(&self) -> Result<Vec<OrdenCompra>, String> {
-            self.priv_get_ordenes()
+        pub fn visualizar_producto(&self, id_producto: u32) -> Result<(Producto, u32), String>{
+            self.historial_productos.get(id_producto).ok_or("No se encontro el producto.".to_string())
         }
-        fn priv_get_ordenes(&self) -> Result<Vec<OrdenCompra>, String> {
-            let mut vec_ordenes = Vec::new();
 
-            for i in 0..self.historial_ordenes_de_compra.len() {
-                if let Some((_, orden)) = self.historial_ordenes_de_compra.get(i){
-                    vec_ordenes.push(orden)
-                }
-            }
-            if vec_ordenes.is_empty(){
-                return Err("No hay ordenes de compra cargadas.".to_string());
-            }
-            else {
-                return Ok(vec_ordenes);
-            }
+        /// Devuelve la variante de un producto. 
+        /// 
+        /// Errores posibles: la variante no existe. 
+        fn buscar_variante(&self, id_producto: u32, id_variante: u32) -> Result<Variante, String>{
+            self.variantes.get(id_producto).unwrap_or_default().into_iter()
+                .find(|variante| variante.id == id_variante)
+                .ok_or("La variante no existe.".to_string())
         }
 
-    }
-       
-
-/////////////////////////// USUARIO ///////////////////////////
-
-    #[ink::scale_derive(Encode, Decode, TypeInfo)]
-    #[cfg_attr(feature = "std",derive(ink::storage::traits::StorageLayout))]
-    /// Struct que almacena la información de un usuario. 
-    /// id_usuario almacena el id.
-    /// nombre almacena su nombre.
-    /// apellido almacena su apellido.
-    /// dirección almacena su dirección.
-    /// email almacena su email.
-    /// rol almacena el rol que tiene el usuario. Éste puede ser: Comp (comprador), Vend (vendedor), Ambos. 
-    /// datos_comprador almacena toda la información correspondiente al rol comprador. El Option será Some cuando éste posea el rol Comp u Ambos. Si en algún momento deja de serlo, el Option seguirá en Some con toda la información.  
-    /// datos_vendedor almacena toda la información correspondiente al rol vendedor. El Option será Some cuando éste posea el rol Vend u Ambos. Si en algún momento deja de serlo, el Option seguirá en Some con toda la información.  
-    #[derive(Clone)]
-    pub struct Usuario{
-        pub id_usuario: AccountId,
-        pub nombre: String,
-        pub apellido: String,
-        pub direccion: String,
-        pub email: String,
-        pub rol: Rol,
-        pub datos_comprador: Option<Comprador>,
-        pub datos_vendedor: Option<Vendedor>,
-    }
-    impl Usuario {
+        /// La función "crear_publicacion" se encarga de crear la publicación y luego registrarla en mi sistema. 
+        /// Recibe un Vector de tuplas donde la posición 0 es el ID del producto, y la posición 1 es la cantidad a publicar de ese producto.
+        /// 
+        /// token_de_pago es la dirección del contrato PSP22 con el que se debe pagar la publicación. Si es None, se paga con la moneda nativa. 
+        /// 
+        /// Errores posibles: cuando la cantidad de un producto a publicar es 0. 
+        #[ink(message)]
+        #[cfg(not(test))]
+        pub fn crear_publicacion(&mut self, productos_a_publicar: Vec<(u32, u32)>, token_de_pago: Option<AccountId>) -> Result<(), String> {
+            let account_id = self.env().caller();
+            self.priv_crear_publicacion(account_id, productos_a_publicar, token_de_pago)
+        }
+        fn priv_crear_publicacion(&mut self, account_id: AccountId, productos_a_publicar: Vec<(u32, u32)>, token_de_pago: Option<AccountId>) -> Result<(), String> {
+            self.registrar_publicacion(account_id, productos_a_publicar, Vec::new(), token_de_pago, None)
+        }
 
-        fn es_vendedor_ambos(&self) -> Result<(), String>{
-            if self.rol == Rol::Comp{
-                return(Err("El usuario es comprador.".to_string()))
-            }
-            Ok(())
+        /// La función "crear_publicacion_con_variantes" crea una publicación en la que algunos productos se venden en una variante específica. 
+        /// variantes es un Vector de tuplas (ID del producto, ID de la variante). Las unidades de esos productos se descuentan del stock de la variante elegida; 
+        /// los productos sin variante indicada se descuentan del stock del producto. Cada producto puede tener a lo sumo una variante elegida. 
+        /// 
+        /// Errores posibles: los mismos que "crear_publicacion"; una variante no existe, no corresponde a un producto publicado o se repite. 
+        #[ink(message)]
+        #[cfg(not(test))]
+        pub fn crear_publicacion_con_variantes(&mut self, productos_a_publicar: Vec<(u32, u32)>, variantes: Vec<(u32, u32)>, token_de_pago: Option<AccountId>) -> Result<(), String> {
+            let account_id = self.env().caller();
+            self.priv_crear_publicacion_con_variantes(account_id, productos_a_publicar, variantes, token_de_pago)
+        }
+        fn priv_crear_publicacion_con_variantes(&mut self, account_id: AccountId, productos_a_publicar: Vec<(u32, u32)>, variantes: Vec<(u32, u32)>, token_de_pago: Option<AccountId>) -> Result<(), String> {
+            self.registrar_publicacion(account_id, productos_a_publicar, variantes, token_de_pago, None)
         }
 
-        fn es_comprador_ambos(&self) -> Result<(), String>{
-            if self.rol == Rol::Vend{
-                return(Err("El usuario es vendedor.".to_string()))
+        /// La función "crear_subasta" crea una publicación que se vende al mejor postor en lugar de a precio fijo. 
+        /// Las unidades publicadas quedan reservadas hasta que la subasta se finaliza. 
+        /// precio_reserva es el precio mínimo para que la subasta se adjudique, y fin el timestamp a partir del cual ya no se aceptan ofertas. 
+        /// 
+        /// Errores posibles: los mismos que "crear_publicacion"; el fin de la subasta no es posterior al momento actual. 
+        #[ink(message)]
+        #[cfg(not(test))]
+        pub fn crear_subasta(&mut self, productos_a_publicar: Vec<(u32, u32)>, precio_reserva: u32, fin: Timestamp, token_de_pago: Option<AccountId>) -> Result<(), String> {
+            let account_id = self.env().caller();
+            let ahora = self.env().block_timestamp();
+            self.priv_crear_subasta(account_id, productos_a_publicar, precio_reserva, fin, token_de_pago, ahora)
+        }
+        fn priv_crear_subasta(&mut self, account_id: AccountId, productos_a_publicar: Vec<(u32, u32)>, precio_reserva: u32, fin: Timestamp, token_de_pago: Option<AccountId>, ahora: Timestamp) -> Result<(), String> {
+            if fin <= ahora {
+                return Err("El fin de la subasta debe ser posterior al momento actual.".to_string())
             }
-            Ok(())
+            self.registrar_publicacion(account_id, productos_a_publicar, Vec::new(), token_de_pago, Some(Subasta::nueva(precio_reserva, fin)))
         }
 
-        fn lista_de_productos(&self) -> Result<Vec<u32>, String>{
-            if let Some(ref datos_vendedor) = self.datos_vendedor{
-                if datos_vendedor.productos.is_empty(){
-                    return Err("El vendedor no tiene productos.".to_string())
+        /// La función se encarga de validar y registrar una publicación (de precio fijo o subasta) en mi sistema, descontando el stock publicado. 
+        /// 
+        /// variantes son las variantes elegidas de los productos publicados (ver "crear_publicacion_con_variantes"). 
+        /// 
+        /// Si el vendedor tiene seguidores, se emite el evento PublicacionDeVendedorSeguido. 
+        /// 
+        /// Errores posibles: el usuario está suspendido; cuando la cantidad de un producto a publicar es 0; el producto no pertenece al usuario; 
+        /// una variante no es válida; no hay stock suficiente. 
+        fn registrar_publicacion(&mut self, account_id: AccountId, productos_a_publicar: Vec<(u32, u32)>, variantes: Vec<(u32, u32)>, token_de_pago: Option<AccountId>, subasta: Option<Subasta>) -> Result<(), String> {
+            self.no_esta_suspendido(account_id)?;
+            let mut usuario = self.buscar_usuario(account_id)?;
+            for (id, cantidad) in productos_a_publicar.clone(){
+                if cantidad == 0 {
+                    return Err("Un producto tiene cantidades no validas.".to_string())
                 }
-                Ok(datos_vendedor.productos.clone())
+                usuario.verificar_propiedad_producto(id)? // <------ Correccion Punto 12. 12/08
             }
-            else{
-                return Err("El vendedor no tiene datos.".to_string())
+            for (pos, &(id_producto, id_variante)) in variantes.iter().enumerate(){
+                if !productos_a_publicar.iter().any(|&(id, _cantidad)| id == id_producto){
+                    return Err("Una variante no corresponde a un producto publicado.".to_string())
+                }
+                if variantes[..pos].iter().any(|&(id, _variante)| id == id_producto){
+                    return Err("Un producto tiene mas de una variante elegida.".to_string())
+                }
+                self.buscar_variante(id_producto, id_variante)?;
+            }
+            self.hay_stock_suficiente(productos_a_publicar.clone(), &variantes)?;
+            let id_publicacion = self.historial_publicaciones.len();
+            let precio_final = self.calcular_precio_final(productos_a_publicar.clone(), &variantes)?;
+            let mut publicacion = usuario.crear_publicacion(productos_a_publicar.clone(), precio_final, id_publicacion, account_id, token_de_pago)?;
+            publicacion.subasta = subasta;
+            self.descontar_stock(productos_a_publicar, &variantes)?;
+            publicacion.variantes = variantes;
+            self.historial_publicaciones.push(&(id_publicacion, publicacion));
+            self.usuarios.insert(account_id, &usuario);
+            if self.seguidores.get(account_id).unwrap_or(0) > 0 {
+                self.env().emit_event(PublicacionDeVendedorSeguido{ vendedor: account_id, id_publicacion });
             }
+            Ok(())
         }
-
-        fn verificar_propiedad_producto(&self, id_producto: u32) -> Result<(), String>{
-            if let Some(ref datos_vendedor) = self.datos_vendedor{
-                if datos_vendedor.productos.contains(&id_producto){
-                    return Ok(())
+        
+        /// La función "visualizar_productos_de_publicación" se encarga de retornar todos los datos de una publicación (ID) recibida por parámetro. Devolviendo un tipo de dato Publicacion.  
+        /// 
+        /// Errores posibles: cuando el ID recibido por parametro no se halla en mi sistema (historial_publicaciones).
+        #[ink(message)]
+        pub fn visualizar_productos_de_publicacion(&self, id_publicacion: u32) -> Result<Publicacion, String> {
+            self.priv_visualizar_productos_de_publicacion(id_publicacion)
+        }
+        fn priv_visualizar_productos_de_publicacion(&self, id_publicacion: u32) -> Result<Publicacion, String> {
+            for i in 0..self.historial_publicaciones.len() {
+                if let Some((id, publicacion)) = self.historial_publicaciones.get(i) {
+                    if id == id_publicacion { 
+                        return Ok(publicacion.clone());
+                    }
                 }
-                return Err("El vendedor no posee ese producto.".to_string())
             }
-            else{
-                return Err("El vendedor no tiene datos.".to_string())
+            Err("No se encontro la publicacion.".to_string())
+        }
+         
+        /// La función "crear_cupon" permite a un vendedor crear un cupón de descuento aplicable a cualquiera de sus publicaciones. 
+        /// El descuento puede ser un porcentaje (1 a 100) o un monto fijo, y el cupón puede usarse una cantidad limitada de veces hasta su vencimiento. 
+        /// 
+        /// Errores posibles: el usuario no es vendedor; el código ya existe; el descuento o la cantidad de usos no son válidos; el vencimiento no es posterior al momento actual. 
+        #[ink(message)]
+        #[cfg(not(test))]
+        pub fn crear_cupon(&mut self, codigo: String, descuento: Descuento, usos: u32, vencimiento: Timestamp) -> Result<(), String>{
+            let account_id = self.env().caller();
+            let ahora = self.env().block_timestamp();
+            self.priv_crear_cupon(account_id, codigo, descuento, usos, vencimiento, ahora)
+        }
+        fn priv_crear_cupon(&mut self, account_id: AccountId, codigo: String, descuento: Descuento, usos: u32, vencimiento: Timestamp, ahora: Timestamp) -> Result<(), String>{
+            let usuario = self.buscar_usuario(account_id)?;
+            usuario.es_vendedor_ambos()?;
+            if self.cupones.contains((account_id, codigo.clone())) {
+                return Err("El cupon ya existe.".to_string())
+            }
+            descuento.validar()?;
+            if usos == 0 {
+                return Err("La cantidad de usos del cupon no es valida.".to_string())
             }
+            if vencimiento <= ahora {
+                return Err("El vencimiento del cupon debe ser posterior al momento actual.".to_string())
+            }
+            self.cupones.insert((account_id, codigo), &Cupon{ descuento, usos_restantes: usos, vencimiento });
+            Ok(())
         }
 
-        fn cargar_producto(&mut self, id_producto: u32) -> Result<(), String>{
-            if let Some(ref mut datos_vendedor) = self.datos_vendedor{
-                datos_vendedor.cargar_producto(id_producto);
-                return Ok(())
+        /// La función "programar_promocion" permite al vendedor fijar un precio de oferta para una de sus publicaciones de precio fijo, 
+        /// vigente entre inicio (inclusive) y fin (exclusive). Reemplaza la promoción anterior, si la había. 
+        /// 
+        /// Errores posibles: la publicación no existe, no pertenece al usuario o es una subasta; 
+        /// el precio no es menor al precio final de la publicación; el inicio no es anterior al fin. 
+        #[ink(message)]
+        #[cfg(not(test))]
+        pub fn programar_promocion(&mut self, id_publicacion: u32, precio: u32, inicio: Timestamp, fin: Timestamp) -> Result<(), String>{
+            let account_id = self.env().caller();
+            self.priv_programar_promocion(account_id, id_publicacion, precio, inicio, fin)
+        }
+        fn priv_programar_promocion(&mut self, account_id: AccountId, id_publicacion: u32, precio: u32, inicio: Timestamp, fin: Timestamp) -> Result<(), String>{
+            let mut publicacion = self.buscar_publicacion(id_publicacion)?;
+            if publicacion.id_vendedor != account_id {
+                return Err("La publicacion no pertenece al usuario.".to_string())
             }
-            else {
-                return Err("El vendedor no tiene datos.".to_string())
+            if publicacion.subasta.is_some() {
+                return Err("Las subastas no admiten promociones.".to_string())
             }
-        }
-        
-        pub fn nuevo(id: AccountId,nombre: String,apellido: String,direccion: String,email: String,rol: Rol) -> Usuario {
-            Usuario {
-                id_usuario: id,
-                nombre,
-                apellido,
-                direccion,
-                email,
-                rol: rol.clone(),
-                datos_comprador: match rol {
-                    Rol::Comp | Rol::Ambos => Some(Comprador { 
-                        ordenes_de_compra: Vec::new(),
-                        reputacion_como_comprador: Vec::new(),
-                    }),
-                    _ => None,
-                },
-                datos_vendedor: match rol {
-                    Rol::Vend | Rol::Ambos => Some(Vendedor {
-                        productos: Vec::new(),
-                        publicaciones: Vec::new(),
-                        reputacion_como_vendedor: Vec::new(),
-                    }),
-                    _ => None,
-                },
+            if (precio == 0) | (precio >= publicacion.precio_final) {
+                return Err("El precio de la promocion debe ser menor al precio de la publicacion.".to_string())
             }
+            if inicio >= fin {
+                return Err("El inicio de la promocion debe ser anterior a su fin.".to_string())
+            }
+            publicacion.promocion = Some(Promocion{ precio, inicio, fin });
+            self.actualizar_publicaciones(publicacion, id_publicacion)
         }
 
-        fn crear_publicacion(&mut self, productos_a_publicar: Vec<(u32, u32)>, precio_final: u32, id_publicacion: u32, id_vendedor: AccountId) -> Result<Publicacion, String>{  //productos_a_publicar = Vec<(id, cantidad)>
-            if self.rol == Rol::Comp {
-                Err("El usuario no es vendedor.".to_string())
+        /// La función "crear_orden_de_compra" se encarga de crear una orden de compra de una publicación (ID) recibida por parametro.
+        /// El precio a pagar es el de la promoción vigente (si la hay), con el descuento del cupón del vendedor aplicado (si se envía un código), 
+        /// menos los puntos de fidelidad canjeados (cada punto descuenta VALOR_DEL_PUNTO). Los puntos solo se canjean en publicaciones en moneda nativa, 
+        /// y su descuento lo absorbe el vendedor: la venta se liquida al precio pagado. 
+        /// La orden registra el precio original de la publicación, el precio pagado y los puntos canjeados. 
+        /// 
+        /// Errores posibles: cuando la publicación ya no está disponible (boolean de Publicacion = false);
+        /// Cuando el usuario que quiere comprar una publicación, y es también el vendedor de la misma; 
+        /// Cuando el usuario que creó la publicación y luego cambia de rol a Comp; 
+        /// cuando el monto transferido no coincide con el precio final de la publicación; 
+        /// cuando la publicación se paga con un token PSP22 y el comprador no autorizó (approve) al contrato a cobrar el precio final; 
+        /// cuando el cupón no existe, está vencido, no tiene usos restantes o su descuento supera el precio; 
+        /// cuando el usuario o el vendedor están suspendidos, o la publicación fue dada de baja; 
+        /// cuando la publicación o su vendedor están en revisión por denuncias; 
+        /// cuando el usuario no tiene los puntos a canjear o su descuento no deja un precio mayor a 0; 
+        /// cuando se canjean puntos en una publicación en token PSP22. 
+        /// El monto queda retenido en el contrato hasta que el comprador recibe la compra, o se reintegra si la compra se cancela. 
+        #[ink(message, payable)]
+        #[cfg(not(test))]
+        pub fn crear_orden_de_compra(&mut self, id_publicacion: u32, cupon: Option<String>, puntos: u32) -> Result<(), String>{
+            let account_id = self.env().caller();
+            let ahora = self.env().block_timestamp();
+            self.verificar_pago(id_publicacion, self.env().transferred_value(), &cupon, puntos, ahora)?;
+            self.priv_crear_orden_de_compra(account_id, id_publicacion, cupon, puntos, ahora)
+        }
+        fn priv_crear_orden_de_compra(&mut self, account_id: AccountId, id_publicacion: u32, cupon: Option<String>, puntos: u32, ahora: Timestamp) -> Result<(), String>{
+            let saldo = self.puntos_fidelidad.get(account_id).unwrap_or(0).checked_sub(puntos).ok_or("El usuario no tiene puntos suficientes.")?;
+            let precio = self.calcular_precio_de_compra(id_publicacion, &cupon, puntos, ahora)?;
+            self.crear_orden(account_id, id_publicacion, precio, true, puntos)?;
+            self.puntos_fidelidad.insert(account_id, &saldo);
+            if let Some(codigo) = cupon {
+                let id_vendedor = self.buscar_publicacion(id_publicacion)?.id_vendedor;
+                self.consumir_cupon(id_vendedor, codigo)?;
             }
-            else {
-                Ok(self.datos_vendedor.as_mut().expect("Error con los datos del vendedor.").crear_publicacion(productos_a_publicar, precio_final, id_publicacion, id_vendedor))
-            } 
+            Ok(())
         }
 
-        fn modificar_rol(&mut self, nuevo_rol: Rol) -> Result<(), String>{
-            if nuevo_rol == self.rol {
-                Err("El usuario ya posee ese rol.".to_string())
+        /// La función se encarga de crear una orden de compra de una publicación al precio recibido por parametro. 
+        /// Es el camino común de "crear_orden_de_compra" (al precio de la publicación) y de las ofertas aceptadas (al precio negociado). 
+        /// cobrar indica si el pago en token PSP22 debe cobrarse ahora; es falso cuando el monto ya fue retenido (ofertas). 
+        /// puntos_canjeados son los puntos de fidelidad que el comprador usó como descuento, y quedan registrados en la orden. 
+        /// 
+        /// Errores posibles: los mismos que "crear_orden_de_compra". 
+        fn crear_orden(&mut self, account_id: AccountId, id_publicacion: u32, precio_final: u32, cobrar: bool, puntos_canjeados: u32) -> Result<(), String>{
+            let mut usuario = self.buscar_usuario(account_id)?;
+            let mut publicacion = self.visualizar_productos_de_publicacion(id_publicacion)?;
+            if publicacion.subasta.is_some(){
+                return Err("La publicacion es una subasta. Solo se puede ofertar.".to_string())
             }
-            else {
-                self.rol = nuevo_rol.clone();
-                match nuevo_rol{
-                    Rol::Comp => {
-                        if self.datos_comprador.is_none(){
-                            let ordenes_de_compra = Vec::new();
-                            let reputacion_como_comprador = Vec::new();
-                            self.datos_comprador = Some(Comprador{
-                                ordenes_de_compra,
-                                reputacion_como_comprador,
-                            });
-                        }
-                    }
-
-                    Rol::Vend => {
-                        if self.datos_vendedor.is_none(){ 
-                            let productos = Vec::new();
-                            let publicaciones = Vec::new();
-                            let reputacion_como_vendedor = Vec::new();
-                            self.datos_vendedor = Some(Vendedor{
-                                productos,
-                                publicaciones,
-                                reputacion_como_vendedor,
-                            });
-                        }
-                    }
-
-                    Rol::Ambos => {
-                        if self.datos_comprador.is_none(){
-                            let ordenes_de_compra = Vec::new();
-                            let reputacion_como_comprador = Vec::new();
-                            self.datos_comprador = Some(Comprador{
-                                ordenes_de_compra,
-                                reputacion_como_comprador,
-                            });
-                        }
-                        if self.datos_vendedor.is_none(){
-                            let productos = Vec::new();
-                            let publicaciones = Vec::new();
-                            let reputacion_como_vendedor = Vec::new();
-                            self.datos_vendedor = Some(Vendedor{
-                                productos,
-                                publicaciones,
-                                reputacion_como_vendedor,
-                            });
-                        }
-                    }
+            self.validar_moderacion_de_compra(account_id, &publicacion)?;
+            if publicacion.disponible{ //Agregado el 14/08
+                let vendedor_de_la_orden = self.usuarios.get(publicacion.id_vendedor).unwrap();
+                if account_id == vendedor_de_la_orden.id_usuario{
+                    return Err("El usuario no puede comprar sus propias publicaciones.".to_string())
+                }
+                if vendedor_de_la_orden.rol == Rol::Comp {
+                    return Err("La publicacion ya no se encuentra disponible.".to_string())
+                }
+                let id_orden = self.historial_ordenes_de_compra.len();
+                let mut publicacion_vendida = publicacion.clone();
+                publicacion_vendida.precio_final = precio_final;
+                let mut orden_de_compra = usuario.crear_orden_de_compra(id_orden, publicacion_vendida, account_id)?;
+                orden_de_compra.precio_original = publicacion.precio_final;
+                orden_de_compra.puntos_canjeados = puntos_canjeados;
+                if cobrar {
+                    self.cobrar_pago_token(account_id, publicacion.token_de_pago, Balance::from(precio_final))?;
+                }
+                self.historial_ordenes_de_compra.push(&(id_orden, orden_de_compra.clone()));
+                self.usuarios.insert(account_id, &usuario);
+                if self.puede_restockear(publicacion.clone()){ //Agregado el 14/08
+                    self.descontar_stock(publicacion.productos, &publicacion.variantes)?;
+                }
+                else {
+                    publicacion.disponible = !publicacion.disponible;
+                    let pos = self.devolver_posicion_publicacion(id_publicacion)?;
+                    self.historial_publicaciones.set(pos, &(id_publicacion, publicacion));
                 }
+                self.notificar_orden_actualizada(orden_de_compra, None);
                 Ok(())
             }
+            else { //Agregado el 14/08
+                Err("La publicacion ya no tiene stock".to_string())
+            }
         }
         
-        fn crear_orden_de_compra(&mut self, id_orden: u32, publicacion: Publicacion, id_comprador: AccountId) -> Result<OrdenCompra, String>{
-            if self.rol == Rol::Vend{
-                Err("El usuario no esta autorizado para realizar una compra. ERROR: No posee el rol comprador.".to_string())
+        /// La función "ofertar" registra una oferta en una subasta. El monto ofertado queda retenido en el contrato 
+        /// (en moneda nativa transferida junto al mensaje, o en el token PSP22 de la publicación mediante transfer_from). 
+        /// La oferta que queda superada se acredita a su ofertante, que puede recuperarla con "retirar_fondos". 
+        /// 
+        /// Errores posibles: la publicación no es una subasta o ya finalizó; el usuario no es comprador o es el vendedor; 
+        /// el monto no supera a la mejor oferta; el pago no coincide con el monto ofertado. 
+        #[ink(message, payable)]
+        #[cfg(not(test))]
+        pub fn ofertar(&mut self, id_publicacion: u32, monto: u32) -> Result<(), String>{
+            let account_id = self.env().caller();
+            let ahora = self.env().block_timestamp();
+            let publicacion = self.buscar_publicacion(id_publicacion)?;
+            let pago_esperado = if publicacion.token_de_pago.is_some() { 0 } else { Balance::from(monto) };
+            if self.env().transferred_value() != pago_esperado {
+                return Err("El pago no coincide con el monto ofertado.".to_string())
             }
-            else{
-                Ok(self.datos_comprador.as_mut().expect("No hay datos del comprador.").crear_orden_de_compra(id_orden, publicacion, id_comprador))
+            self.priv_ofertar(account_id, id_publicacion, monto, ahora)
+        }
+        fn priv_ofertar(&mut self, account_id: AccountId, id_publicacion: u32, monto: u32, ahora: Timestamp) -> Result<(), String>{
+            let usuario = self.buscar_usuario(account_id)?;
+            usuario.es_comprador_ambos()?;
+            let mut publicacion = self.buscar_publicacion(id_publicacion)?;
+            self.validar_moderacion_de_compra(account_id, &publicacion)?;
+            if publicacion.id_vendedor == account_id {
+                return Err("El usuario no puede ofertar en sus propias publicaciones.".to_string())
             }
+            let mut subasta = publicacion.subasta.clone().ok_or("La publicacion no es una subasta.")?;
+            subasta.validar_oferta(monto, ahora)?;
+            self.cobrar_pago_token(account_id, publicacion.token_de_pago, Balance::from(monto))?;
+            if let Some((ofertante_anterior, monto_anterior)) = subasta.mejor_oferta {
+                self.acreditar_fondos(ofertante_anterior, publicacion.token_de_pago, Balance::from(monto_anterior))?;
+            }
+            subasta.mejor_oferta = Some((account_id, monto));
+            publicacion.subasta = Some(subasta);
+            self.actualizar_publicaciones(publicacion, id_publicacion)
         }
 
-        fn enviar_compra(&self, id_publicacion: u32) -> Result<(), String>{
-            if self.rol == Rol::Comp{
-                Err("El usuario no posee el rol de vendedor.".to_string())
+        /// La función "finalizar_subasta" cierra una subasta cuyo tiempo terminó. Puede ser llamada por cualquier usuario. 
+        /// Si la mejor oferta alcanza el precio de reserva, se crea la orden de compra para el ganador al precio ofertado (el pago ya está retenido). 
+        /// Si no, o si el ganador ya no puede recibir la orden (por ejemplo, porque pasó a ser solo vendedor), 
+        /// las unidades reservadas vuelven al stock de los productos y la mejor oferta se acredita a su ofertante. 
+        /// 
+        /// Errores posibles: la publicación no es una subasta; la subasta todavía no terminó o ya fue finalizada. 
+        #[ink(message)]
+        #[cfg(not(test))]
+        pub fn finalizar_subasta(&mut self, id_publicacion: u32) -> Result<(), String>{
+            let ahora = self.env().block_timestamp();
+            self.priv_finalizar_subasta(id_publicacion, ahora)
+        }
+        fn priv_finalizar_subasta(&mut self, id_publicacion: u32, ahora: Timestamp) -> Result<(), String>{
+            let mut publicacion = self.buscar_publicacion(id_publicacion)?;
+            let mut subasta = publicacion.subasta.clone().ok_or("La publicacion no es una subasta.")?;
+            if subasta.finalizada {
+                return Err("La subasta ya fue finalizada.".to_string())
             }
-            else{
-                self.datos_vendedor.as_ref().expect("No hay datos del vendedor.").enviar_compra(id_publicacion)
+            if ahora < subasta.fin {
+                return Err("La subasta todavia no termino.".to_string())
+            }
+            subasta.finalizada = true;
+            publicacion.disponible = false;
+            publicacion.subasta = Some(subasta.clone());
+            self.actualizar_publicaciones(publicacion.clone(), id_publicacion)?;
+
+            if let Some((ofertante, monto)) = subasta.mejor_oferta {
+                if monto >= subasta.precio_reserva {
+                    publicacion.precio_final = monto;
+                    if self.registrar_orden_de_compra(ofertante, publicacion.clone()).is_ok() {
+                        return Ok(())
+                    }
+                }
+                self.acreditar_fondos(ofertante, publicacion.token_de_pago, Balance::from(monto))?;
             }
+            self.aumentar_stock_productos(publicacion.productos, &publicacion.variantes)
         }
 
-        fn recibir_compra(&self, id_orden: u32) -> Result<(), String>{
-            if self.rol == Rol::Vend{
-                Err("El usuario no posee el rol de comprador.".to_string())
+        /// La función "hacer_oferta" permite a un comprador ofrecer un precio distinto al de una publicación de precio fijo. 
+        /// El monto ofertado queda retenido en el contrato (moneda nativa transferida junto al mensaje, o token PSP22 mediante transfer_from). 
+        /// La oferta vence a las DURACION_OFERTA milisegundos de realizada. 
+        /// 
+        /// Errores posibles: la publicación no existe, es una subasta o no está disponible; el usuario no es comprador o es el vendedor; 
+        /// el precio es 0; el pago no coincide con el precio ofertado. 
+        #[ink(message, payable)]
+        #[cfg(not(test))]
+        pub fn hacer_oferta(&mut self, id_publicacion: u32, precio: u32) -> Result<u32, String>{
+            let account_id = self.env().caller();
+            let ahora = self.env().block_timestamp();
+            let publicacion = self.buscar_publicacion(id_publicacion)?;
+            let pago_esperado = if publicacion.token_de_pago.is_some() { 0 } else { Balance::from(precio) };
+            if self.env().transferred_value() != pago_esperado {
+                return Err("El pago no coincide con el precio ofertado.".to_string())
             }
-            else{
-                self.datos_comprador.as_ref().expect("No hay datos del comprador.").recibir_compra(id_orden)
+            self.priv_hacer_oferta(account_id, id_publicacion, precio, ahora)
+        }
+        fn priv_hacer_oferta(&mut self, account_id: AccountId, id_publicacion: u32, precio: u32, ahora: Timestamp) -> Result<u32, String>{
+            let usuario = self.buscar_usuario(account_id)?;
+            usuario.es_comprador_ambos()?;
+            let publicacion = self.buscar_publicacion(id_publicacion)?;
+            self.validar_moderacion_de_compra(account_id, &publicacion)?;
+            if publicacion.subasta.is_some() {
+                return Err("La publicacion es una subasta. Las ofertas se realizan con ofertar.".to_string())
+            }
+            if !publicacion.disponible {
+                return Err("La publicacion ya no tiene stock".to_string())
+            }
+            if publicacion.id_vendedor == account_id {
+                return Err("El usuario no puede ofertar en sus propias publicaciones.".to_string())
             }
+            if precio == 0 {
+                return Err("El precio ofertado no es valido.".to_string())
+            }
+            self.cobrar_pago_token(account_id, publicacion.token_de_pago, Balance::from(precio))?;
+            let id_oferta = self.ofertas.len();
+            let vencimiento = ahora.checked_add(DURACION_OFERTA).ok_or("Error al sumar.")?;
+            self.ofertas.push(&Oferta::nueva(id_oferta, id_publicacion, account_id, precio, publicacion.token_de_pago, vencimiento));
+            Ok(id_oferta)
         }
 
-        fn comprobar_rol(&self, id_vendedor: AccountId, id_comprador: AccountId) -> Result<Rol, String>{
-            match self.rol{
-                Rol::Vend => {
-                    if let Some(ref _datos_vendedor) = self.datos_vendedor{
-                        if self.es_el_vendedor(id_vendedor){
-                            Ok(Rol::Vend)
-                        }
-                        else {
-                            Err("El vendedor no posee esta publicacion.".to_string())
-                        }
-                    }
-                    else {
-                        Err("El vendedor no tiene datos cargados.".to_string())
-                    }
-                },
-
-                Rol::Comp => {
-                    if let Some(ref _datos_comprador) = self.datos_comprador{
-                        if self.es_el_comprador(id_comprador){
-                            Ok(Rol::Comp)
-                        }
-                        else{
-                            Err("El usuario no posee esa orden de compra.".to_string())
-                        }
-                    }
-                    else {
-                        Err("El comprador no tiene datos cargados.".to_string())
-                    }
-                },
-
-                Rol::Ambos => {
-                    if self.es_el_vendedor(id_vendedor) {
-                        Ok(Rol::Vend)
-                    }
-                    else if self.es_el_comprador(id_comprador) {
-                        Ok(Rol::Comp)
-                    }
-                    else {
-                       Err("El usuario no participa de la compra.".to_string())
-                    }
-                },
+        /// La función "aceptar_oferta" permite al vendedor aceptar una oferta (o al comprador aceptar la contraoferta del vendedor). 
+        /// Se crea la orden de compra al precio acordado por el mismo camino que "crear_orden_de_compra". 
+        /// Si el comprador acepta una contraoferta mayor a su oferta, debe pagar la diferencia; si es menor, la diferencia se le acredita en sus fondos a retirar. 
+        /// 
+        /// Errores posibles: la oferta no existe, venció o ya fue resuelta; el usuario no es quien debe responder la oferta; 
+        /// el pago de la diferencia no es correcto; la publicación ya no está disponible. 
+        #[ink(message, payable)]
+        #[cfg(not(test))]
+        pub fn aceptar_oferta(&mut self, id_oferta: u32) -> Result<(), String>{
+            let account_id = self.env().caller();
+            let ahora = self.env().block_timestamp();
+            let oferta = self.buscar_oferta(id_oferta)?;
+            let diferencia = oferta.diferencia_a_pagar();
+            let pago_esperado = if oferta.token_de_pago.is_some() { 0 } else { Balance::from(diferencia) };
+            if (account_id == oferta.comprador) & (self.env().transferred_value() != pago_esperado) {
+                return Err("El pago no coincide con la diferencia de la contraoferta.".to_string())
             }
+            self.priv_aceptar_oferta(account_id, id_oferta, ahora)
         }
-
-        fn es_el_vendedor(&self, id_vendedor: AccountId) -> bool{
-            id_vendedor == self.id_usuario
+        fn priv_aceptar_oferta(&mut self, account_id: AccountId, id_oferta: u32, ahora: Timestamp) -> Result<(), String>{
+            let mut oferta = self.buscar_oferta(id_oferta)?;
+            let publicacion = self.buscar_publicacion(oferta.id_publicacion)?;
+            oferta.validar_respuesta(account_id, publicacion.id_vendedor, ahora)?;
+            let precio_acordado = oferta.precio_vigente();
+            if let Some(contraoferta) = oferta.contraoferta {
+                if contraoferta > oferta.precio {
+                    self.cobrar_pago_token(oferta.comprador, oferta.token_de_pago, Balance::from(oferta.diferencia_a_pagar()))?;
+                }
+                else {
+                    let sobrante = oferta.precio.checked_sub(contraoferta).ok_or("Error al restar.")?;
+                    self.acreditar_fondos(oferta.comprador, oferta.token_de_pago, Balance::from(sobrante))?;
+                }
+            }
+            self.crear_orden(oferta.comprador, oferta.id_publicacion, precio_acordado, false, 0)?;
+            oferta.estado = EstadoOferta::Aceptada;
+            self.ofertas.set(id_oferta, &oferta);
+            Ok(())
         }
 
-        fn es_el_comprador(&self, id_comprador: AccountId) -> bool{
-            id_comprador == self.id_usuario
+        /// La función "rechazar_oferta" permite al vendedor rechazar una oferta. El monto retenido se acredita al comprador en sus fondos a retirar. 
+        /// 
+        /// Errores posibles: la oferta no existe o ya fue resuelta; el usuario no es el vendedor de la publicación. 
+        #[ink(message)]
+        #[cfg(not(test))]
+        pub fn rechazar_oferta(&mut self, id_oferta: u32) -> Result<(), String>{
+            let account_id = self.env().caller();
+            self.priv_rechazar_oferta(account_id, id_oferta)
         }
-    }
-
-
-
-/////////////////////////// COMPRADOR ///////////////////////////
-
-    #[ink::scale_derive(Encode, Decode, TypeInfo)]
-    #[cfg_attr(feature = "std",derive(ink::storage::traits::StorageLayout))]
-    /// Struct donde se encuentran los datos de aquel usuario con rol Comprador. 
-    /// ordenes_de_compra, es un Vec que almacena los IDs de cada orden realizada por el comprador. 
-    /// reputacion_como_comprador, es un Vec que almacena las califaciones recibidas por vendedores. 
-    #[derive(Clone)]
-    pub struct Comprador{
-        pub ordenes_de_compra: Vec<u32>,
-        pub reputacion_como_comprador: Vec<u8>, 
-    }
-    impl Comprador{
-
-        fn crear_orden_de_compra(&mut self, id_orden: u32, publicacion: Publicacion, id_comprador: AccountId) -> OrdenCompra{
-            self.ordenes_de_compra.push(id_orden);
-            OrdenCompra::crear_orden_de_compra(id_orden, publicacion, id_comprador)
+        fn priv_rechazar_oferta(&mut self, account_id: AccountId, id_oferta: u32) -> Result<(), String>{
+            let oferta = self.buscar_oferta(id_oferta)?;
+            let publicacion = self.buscar_publicacion(oferta.id_publicacion)?;
+            if account_id != publicacion.id_vendedor {
+                return Err("Solo el vendedor puede rechazar la oferta.".to_string())
+            }
+            self.cerrar_oferta(oferta, EstadoOferta::Rechazada)
         }
 
-        fn recibir_compra(&self, id_orden: u32) -> Result<(), String>{
-            if self.ordenes_de_compra.contains(&id_orden){
-                Ok(())
+        /// La función "contraofertar" permite al vendedor proponer otro precio para una oferta pendiente. 
+        /// El comprador puede aceptar la contraoferta con "aceptar_oferta" o retirar su oferta. El vencimiento se renueva. 
+        /// 
+        /// Errores posibles: la oferta no existe, venció o ya fue resuelta; el usuario no es el vendedor; el precio es 0. 
+        #[ink(message)]
+        #[cfg(not(test))]
+        pub fn contraofertar(&mut self, id_oferta: u32, precio: u32) -> Result<(), String>{
+            let account_id = self.env().caller();
+            let ahora = self.env().block_timestamp();
+            self.priv_contraofertar(account_id, id_oferta, precio, ahora)
+        }
+        fn priv_contraofertar(&mut self, account_id: AccountId, id_oferta: u32, precio: u32, ahora: Timestamp) -> Result<(), String>{
+            let mut oferta = self.buscar_oferta(id_oferta)?;
+            let publicacion = self.buscar_publicacion(oferta.id_publicacion)?;
+            if oferta.estado != EstadoOferta::Pendiente {
+                return Err("Solo se puede contraofertar una oferta pendiente.".to_string())
             }
-            else {
-                Err("No se encontro la orden de compra.".to_string())
+            oferta.validar_respuesta(account_id, publicacion.id_vendedor, ahora)?;
+            if precio == 0 {
+                return Err("El precio ofertado no es valido.".to_string())
             }
+            oferta.contraoferta = Some(precio);
+            oferta.estado = EstadoOferta::Contraofertada;
+            oferta.vencimiento = ahora.checked_add(DURACION_OFERTA).ok_or("Error al sumar.")?;
+            self.ofertas.set(id_oferta, &oferta);
+            Ok(())
         }
 
-    }
-
-
-/////////////////////////// VENDEDOR ///////////////////////////
-
-    #[ink::scale_derive(Encode, Decode, TypeInfo)]
-    #[cfg_attr(feature = "std",derive(ink::storage::traits::StorageLayout))]
-    /// Struct que contiene la información del usuario con rol vendedor. 
-    /// productos, es un Vec con las ids de los productos de su propiedad. 
-    /// reputacion_como_vendedor, es un Vec que almacena las califaciones recibidas por compradores. 
-    #[derive(Clone)]
-    pub struct Vendedor{
-        pub productos: Vec<u32>,
-        pub publicaciones: Vec<u32>,
-        pub reputacion_como_vendedor: Vec<u8>, 
-    }
-    impl Vendedor{
+        /// La función "retirar_oferta" permite al comprador retirar una oferta que todavía no fue aceptada (esté vencida o no). 
+        /// El monto retenido se le acredita en sus fondos a retirar. 
+        /// 
+        /// Errores posibles: la oferta no existe o ya fue resuelta; el usuario no es quien realizó la oferta. 
+        #[ink(message)]
+        #[cfg(not(test))]
+        pub fn retirar_oferta(&mut self, id_oferta: u32) -> Result<(), String>{
+            let account_id = self.env().caller();
+            self.priv_retirar_oferta(account_id, id_oferta)
+        }
+        fn priv_retirar_oferta(&mut self, account_id: AccountId, id_oferta: u32) -> Result<(), String>{
+            let oferta = self.buscar_oferta(id_oferta)?;
+            if account_id != oferta.comprador {
+                return Err("Solo el comprador puede retirar la oferta.".to_string())
+            }
+            self.cerrar_oferta(oferta, EstadoOferta::Retirada)
+        }
 
-        fn crear_publicacion(&mut self, productos_a_publicar: Vec<(u32, u32)>, precio_final: u32, id_publicacion: u32, id_vendedor: AccountId) -> Publicacion { 
-            let publicacion = Publicacion::crear_publicacion(productos_a_publicar, precio_final, id_publicacion, id_vendedor);
-            self.publicaciones.push(id_publicacion);
-            publicacion
+        /// La función "retirar_fondos" transfiere al usuario las ofertas superadas o no adjudicadas que tiene acreditadas, y devuelve el monto retirado. 
+        /// token es el token PSP22 de las subastas en las que ofertó, o None para moneda nativa. 
+        /// 
+        /// Errores posibles: el usuario no tiene fondos acreditados en ese token; falla la transferencia. 
+        #[ink(message)]
+        #[cfg(not(test))]
+        pub fn retirar_fondos(&mut self, token: Option<AccountId>) -> Result<Balance, String>{
+            let account_id = self.env().caller();
+            self.priv_retirar_fondos(account_id, token)
+        }
+        fn priv_retirar_fondos(&mut self, account_id: AccountId, token: Option<AccountId>) -> Result<Balance, String>{
+            let monto = self.fondos_a_retirar.get((account_id, token)).unwrap_or(0);
+            if monto == 0 {
+                return Err("No hay fondos para retirar.".to_string())
+            }
+            self.fondos_a_retirar.remove((account_id, token));
+            self.transferir(token, account_id, monto)?;
+            Ok(monto)
         }
 
-        fn enviar_compra(&self, id_publicacion: u32) -> Result<(), String>{
-            if self.publicaciones.contains(&id_publicacion){
-                Ok(())
+        /// La función "habilitar_suscripcion" permite al vendedor ofrecer una publicación de precio fijo por suscripción, 
+        /// repitiendo la compra cada periodo milisegundos. Recibiendo None no se aceptan nuevas suscripciones (las existentes se siguen ejecutando). 
+        /// 
+        /// Errores posibles: la publicación no existe, no pertenece al usuario o es una subasta; el período es menor a PERIODO_MINIMO_SUSCRIPCION. 
+        #[ink(message)]
+        #[cfg(not(test))]
+        pub fn habilitar_suscripcion(&mut self, id_publicacion: u32, periodo: Option<Timestamp>) -> Result<(), String>{
+            let account_id = self.env().caller();
+            self.priv_habilitar_suscripcion(account_id, id_publicacion, periodo)
+        }
+        fn priv_habilitar_suscripcion(&mut self, account_id: AccountId, id_publicacion: u32, periodo: Option<Timestamp>) -> Result<(), String>{
+            let mut publicacion = self.buscar_publicacion(id_publicacion)?;
+            if publicacion.id_vendedor != account_id {
+                return Err("La publicacion no pertenece al usuario.".to_string())
             }
-            else {
-                Err("La publicacion buscada no pertenece a este vendedor.".to_string())
+            if publicacion.subasta.is_some() {
+                return Err("Las subastas no admiten suscripciones.".to_string())
+            }
+            if periodo.is_some_and(|periodo| periodo < PERIODO_MINIMO_SUSCRIPCION) {
+                return Err("El periodo de la suscripcion no es valido.".to_string())
             }
+            publicacion.periodo_suscripcion = periodo;
+            self.actualizar_publicaciones(publicacion, id_publicacion)
         }
 
-        fn cargar_producto(& mut self, id_producto: u32){
-            self.productos.push(id_producto);
+        /// La función "suscribirse" permite a un comprador autorizar por adelantado "periodos" compras de una publicación que admite suscripciones. 
+        /// El precio de cada período es el precio vigente al suscribirse, y el total de los períodos queda retenido en el contrato 
+        /// (moneda nativa transferida junto al mensaje, o token PSP22 mediante transfer_from). 
+        /// La primera orden se crea en la próxima ejecución de "ejecutar_suscripciones", y las siguientes cada período. Devuelve el id de la suscripción. 
+        /// 
+        /// Errores posibles: la publicación no existe, no está disponible o no admite suscripciones; el usuario no es comprador, está suspendido o es el vendedor; 
+        /// la cantidad de períodos es 0 o supera MAXIMO_PERIODOS_SUSCRIPCION; el pago no coincide con el total de los períodos. 
+        #[ink(message, payable)]
+        #[cfg(not(test))]
+        pub fn suscribirse(&mut self, id_publicacion: u32, periodos: u32) -> Result<u32, String>{
+            let account_id = self.env().caller();
+            let ahora = self.env().block_timestamp();
+            let publicacion = self.buscar_publicacion(id_publicacion)?;
+            let total = publicacion.precio_vigente(ahora).checked_mul(periodos).ok_or("Error al multiplicar.")?;
+            let pago_esperado = if publicacion.token_de_pago.is_some() { 0 } else { Balance::from(total) };
+            if self.env().transferred_value() != pago_esperado {
+                return Err("El pago no coincide con el total de la suscripcion.".to_string())
+            }
+            self.priv_suscribirse(account_id, id_publicacion, periodos, ahora)
+        }
+        fn priv_suscribirse(&mut self, account_id: AccountId, id_publicacion: u32, periodos: u32, ahora: Timestamp) -> Result<u32, String>{
+            self.no_esta_suspendido(account_id)?;
+            let usuario = self.buscar_usuario(account_id)?;
+            usuario.es_comprador_ambos()?;
+            let publicacion = self.buscar_publicacion(id_publicacion)?;
+            let periodo = publicacion.periodo_suscripcion.ok_or("La publicacion no admite suscripciones.")?;
+            if !self.esta_disponible(&publicacion) {
+                return Err("La publicacion ya no tiene stock".to_string())
+            }
+            if publicacion.id_vendedor == account_id {
+                return Err("El usuario no puede suscribirse a sus propias publicaciones.".to_string())
+            }
+            if (periodos == 0) | (periodos > MAXIMO_PERIODOS_SUSCRIPCION) {
+                return Err("La cantidad de periodos no es valida.".to_string())
+            }
+            let precio = publicacion.precio_vigente(ahora);
+            let total = precio.checked_mul(periodos).ok_or("Error al multiplicar.")?;
+            self.cobrar_pago_token(account_id, publicacion.token_de_pago, Balance::from(total))?;
+            let id_suscripcion = self.suscripciones.len();
+            self.suscripciones.push(&Suscripcion{
+                id: id_suscripcion,
+                id_publicacion,
+                comprador: account_id,
+                precio,
+                token_de_pago: publicacion.token_de_pago,
+                periodo,
+                periodos_restantes: periodos,
+                proxima_ejecucion: ahora,
+                estado: EstadoSuscripcion::Activa,
+            });
+            self.posiciones_suscripciones_activas.insert(id_suscripcion, &self.suscripciones_activas.len());
+            self.suscripciones_activas.push(&id_suscripcion);
+            Ok(id_suscripcion)
         }
-    }
-
 
+        /// La función "ejecutar_suscripciones" crea las órdenes de compra de las suscripciones activas cuyo período ya venció. Puede ser llamada por cualquier usuario. 
+        /// Cada orden se crea por el mismo camino que "crear_orden_de_compra", al precio de la suscripción y con el pago ya retenido, descontando el stock. 
+        /// Para acotar el costo de cada llamada, se revisan a lo sumo "maximo" suscripciones activas a partir de la posición "desde"; 
+        /// el resultado indica la posición desde la que continuar (None si se llegó al final). 
+        /// Se crea a lo sumo una orden por suscripción en cada llamada. Si la orden no puede crearse (la publicación no está disponible, 
+        /// fue sancionada o está en revisión, o el comprador fue suspendido o dejó de ser comprador), la suscripción se informa como pendiente 
+        /// y el período queda para la próxima ejecución. 
+        /// 
+        /// Errores posibles: overflow en las operaciones; falla la creación de una orden que pasó las validaciones. 
+        #[ink(message)]
+        #[cfg(not(test))]
+        pub fn ejecutar_suscripciones(&mut self, desde: u32, maximo: u32) -> Result<EjecucionSuscripciones, String>{
+            let ahora = self.env().block_timestamp();
+            self.priv_ejecutar_suscripciones(desde, maximo, ahora)
+        }
+        fn priv_ejecutar_suscripciones(&mut self, desde: u32, maximo: u32, ahora: Timestamp) -> Result<EjecucionSuscripciones, String>{
+            let mut ejecucion = EjecucionSuscripciones{ creadas: 0, pendientes: Vec::new(), siguiente: None };
+            let mut posicion = desde;
+            let mut revisadas: u32 = 0;
+            while (posicion < self.suscripciones_activas.len()) & (revisadas < maximo) {
+                revisadas = revisadas.checked_add(1).ok_or("Error al sumar.")?;
+                let id_suscripcion = self.suscripciones_activas.get(posicion).ok_or("No se encontro la suscripcion.")?;
+                let mut suscripcion = self.buscar_suscripcion(id_suscripcion)?;
+                if suscripcion.proxima_ejecucion > ahora {
+                    posicion = posicion.checked_add(1).ok_or("Error al sumar.")?;
+                    continue;
+                }
+                if !self.se_puede_ejecutar(&suscripcion) {
+                    ejecucion.pendientes.push(id_suscripcion);
+                    posicion = posicion.checked_add(1).ok_or("Error al sumar.")?;
+                    continue;
+                }
+                self.crear_orden(suscripcion.comprador, suscripcion.id_publicacion, suscripcion.precio, false, 0)?;
+                suscripcion.periodos_restantes = suscripcion.periodos_restantes.checked_sub(1).ok_or("Error al restar.")?;
+                suscripcion.proxima_ejecucion = suscripcion.proxima_ejecucion.checked_add(suscripcion.periodo).ok_or("Error al sumar.")?;
+                if suscripcion.periodos_restantes == 0 {
+                    suscripcion.estado = EstadoSuscripcion::Finalizada;
+                    self.quitar_suscripcion_activa(id_suscripcion)?; //La última activa pasa a ocupar esta posición
+                }
+                else {
+                    posicion = posicion.checked_add(1).ok_or("Error al sumar.")?;
+                }
+                self.suscripciones.set(id_suscripcion, &suscripcion);
+                ejecucion.creadas = ejecucion.creadas.checked_add(1).ok_or("Error al sumar.")?;
+            }
+            if posicion < self.suscripciones_activas.len() {
+                ejecucion.siguiente = Some(posicion);
+            }
+            Ok(ejecucion)
+        }
 
-/////////////////////////// ROL ///////////////////////////
-
-    #[derive(Debug, Clone, PartialEq)]
-    #[ink::scale_derive(Encode, Decode, TypeInfo)]                            
-    #[cfg_attr(feature = "std",derive(ink::storage::traits::StorageLayout))]
-    /// Enum utulizado para definir el rol de un usuario. 
-    /// Ambos (comprador y vendedor).
-    /// Comp (comprador).
-    /// Vend (vendedor).
-    pub enum Rol {
-        Ambos,
-        Comp, 
-        Vend,
-    }
+        /// La función se encarga de quitar una suscripción de las activas, moviendo la última activa a su posición. 
+        /// 
+        /// Errores posibles: la suscripción no está entre las activas. 
+        fn quitar_suscripcion_activa(&mut self, id_suscripcion: u32) -> Result<(), String>{
+            let posicion = self.posiciones_suscripciones_activas.get(id_suscripcion).ok_or("La suscripcion ya no esta activa.")?;
+            let ultima = self.suscripciones_activas.pop().ok_or("La suscripcion ya no esta activa.")?;
+            if ultima != id_suscripcion {
+                self.suscripciones_activas.set(posicion, &ultima);
+                self.posiciones_suscripciones_activas.insert(ultima, &posicion);
+            }
+            self.posiciones_suscripciones_activas.remove(id_suscripcion);
+            Ok(())
+        }
 
+        /// La función "cancelar_suscripcion" permite al comprador cancelar los períodos que todavía no se ejecutaron. 
+        /// El monto retenido de esos períodos se le acredita en sus fondos a retirar. Las órdenes ya creadas no se modifican. 
+        /// 
+        /// Errores posibles: la suscripción no existe o ya no está activa; el usuario no es el comprador; overflow en las operaciones. 
+        #[ink(message)]
+        #[cfg(not(test))]
+        pub fn cancelar_suscripcion(&mut self, id_suscripcion: u32) -> Result<(), String>{
+            let account_id = self.env().caller();
+            self.priv_cancelar_suscripcion(account_id, id_suscripcion)
+        }
+        fn priv_cancelar_suscripcion(&mut self, account_id: AccountId, id_suscripcion: u32) -> Result<(), String>{
+            let mut suscripcion = self.buscar_suscripcion(id_suscripcion)?;
+            if account_id != suscripcion.comprador {
+                return Err("Solo el comprador puede cancelar la suscripcion.".to_string())
+            }
+            if suscripcion.estado != EstadoSuscripcion::Activa {
+                return Err("La suscripcion ya no esta activa.".to_string())
+            }
+            let reintegro = suscripcion.precio.checked_mul(suscripcion.periodos_restantes).ok_or("Error al multiplicar.")?;
+            self.acreditar_fondos(account_id, suscripcion.token_de_pago, Balance::from(reintegro))?;
+            suscripcion.periodos_restantes = 0;
+            suscripcion.estado = EstadoSuscripcion::Cancelada;
+            self.suscripciones.set(id_suscripcion, &suscripcion);
+            self.quitar_suscripcion_activa(id_suscripcion)
+        }
 
+        #[ink(message)]
+        /// Funcion que devuelve una suscripción por su id. 
+        /// 
+        /// Errores posibles: la suscripción no existe. 
+        pub fn visualizar_suscripcion(&self, id_suscripcion: u32) -> Result<Suscripcion, String>{
+            self.buscar_suscripcion(id_suscripcion)
+        }
 
+        /// La función "cancelar_compra" se encarga de cancelar una compra.
+        #[ink(message)]
+        #[cfg(not(test))]
+        pub fn cancelar_comprar(&mut self, id_orden: u32) -> Result<(), String>{
+            let account_id = self.env().caller();
+            self.priv_cancelar_compra(account_id, id_orden)
+        }
+        fn priv_cancelar_compra(&mut self, account_id: AccountId, id_orden: u32) -> Result<(), String>{
+            let mut usuario = self.buscar_usuario(account_id)?;
+            let mut datos_de_la_orden = self.buscar_orden(id_orden)?;
+            self.se_puede_cancelar(&datos_de_la_orden)?;
+            let id_vendedor = datos_de_la_orden.info_publicacion.3;
+            let id_comprador = datos_de_la_orden.id_comprador;
+            let id_publicacion = datos_de_la_orden.info_publicacion.0; 
+            let rol = usuario.comprobar_rol(id_vendedor, id_comprador)?;
+            if rol == Rol::Comp{
+                datos_de_la_orden.cancelar_compra_comprador()?;
+            }
+            else {
+                datos_de_la_orden.cancelar_compra_vendedor()?;
+                self.devolver_productos(id_publicacion)?;
+                self.transferir(datos_de_la_orden.token_de_pago, id_comprador, Balance::from(datos_de_la_orden.info_publicacion.2))?;
+                self.acreditar_puntos(id_comprador, datos_de_la_orden.puntos_canjeados)?;
+            }
+            self.actualizar_ordenes(datos_de_la_orden.clone(), id_orden)?;
+            if datos_de_la_orden.cancelacion == (true, true){
+                self.notificar_orden_actualizada(datos_de_la_orden, Some(EstadoCompra::Pendiente));
+            }
+            return Ok(());
+        }
 
-/////////////////////////// PUBLICACION ///////////////////////////
+        /// Funcion para enviar una compra.
+        /// 
+        /// Errores posibles: cuando el estado de la compra no es pendiente; 
+        /// cuando el ID de la orden recibida por parametro no se halla en mi sistema (historial_ordenes_compra).
+        #[ink(message)]
+        #[cfg(not(test))]
+        pub fn enviar_compra(&mut self, id_orden: u32) -> Result<(), String>{
+            let account_id = self.env().caller();
+            self.priv_enviar_compra(account_id, id_orden)
+        }
+        fn priv_enviar_compra(&mut self, account_id: AccountId, id_orden: u32) -> Result<(), String>{
+            let mut usuario = self.buscar_usuario(account_id)?;
+            for i in 0..self.historial_ordenes_de_compra.len() {
+                if let Some((id, mut orden_de_compra)) = self.historial_ordenes_de_compra.get(i){
+                    if id == id_orden {
+                        if orden_de_compra.estado != EstadoCompra::Pendiente{
+                            return Err("El producto no puede ser enviado.".to_string());
+                        }
+                        let id_publicacion = orden_de_compra.info_publicacion.0;
+                        usuario.enviar_compra(id_publicacion)?;
+                        orden_de_compra.actualizar_lineas(EstadoLinea::Pendiente, EstadoLinea::Enviada);
+                        orden_de_compra.estado = EstadoCompra::Enviado;
+                        let _ = self.historial_ordenes_de_compra.set(i, &(id, orden_de_compra.clone()));
+                        self.notificar_orden_actualizada(orden_de_compra, Some(EstadoCompra::Pendiente));
+                        return Ok(());
+                    }
+                }
+            }
+            Err("No existe la orden buscada.".to_string())
+        }
+        
+        /// Funcion para recibir una compra.
+        /// 
+        /// Errores posibles: cuando el estado de la orden de compra no es enviado;
+        /// cuando el ID de la orden recibida por parametro no se halla en mi sistema (historial_ordenes_compra).
+        #[ink(message)]
+        #[cfg(not(test))]
+        pub fn recibir_compra(&mut self, id_orden: u32) -> Result<(), String>{
+            let account_id = self.env().caller();
+            let ahora = self.env().block_timestamp();
+            self.priv_recibir_compra(account_id, id_orden, ahora)
+        }
+        fn priv_recibir_compra(&mut self, account_id: AccountId, id_orden: u32, ahora: Timestamp) -> Result<(), String>{ 
+            let mut usuario = self.buscar_usuario(account_id)?;
+            for i in 0..self.historial_ordenes_de_compra.len() {
+                if let Some((id, mut orden_de_compra)) = self.historial_ordenes_de_compra.get(i){
+                    if id == id_orden {
+                        if orden_de_compra.estado != EstadoCompra::Enviado{
+                            return Err("El producto todavia no fue enviado.".to_string());
+                        }
+                        usuario.recibir_compra(id_orden)?;
+                        orden_de_compra.actualizar_lineas(EstadoLinea::Enviada, EstadoLinea::Recibida);
+                        self.completar_recepcion(&mut orden_de_compra, ahora)?;
+                        let _ = self.historial_ordenes_de_compra.set(i, &(id, orden_de_compra.clone()));
+                        self.notificar_orden_actualizada(orden_de_compra, Some(EstadoCompra::Enviado));
+                        return Ok(())
+                    }
+                }
+            }
+            Err("No existe la orden buscada.".to_string())
+        }
 
-    #[derive(Clone, Debug)]
-    #[ink::scale_derive(Encode, Decode, TypeInfo)]
-    #[cfg_attr(feature = "std",derive(ink::storage::traits::StorageLayout))]
-    /// Struct que contiene la información de una publicacion. 
-    /// id, guarda la id de la publicación. 
-    /// productos, es un Vec que contiene tuplas cuyos campos son, el id de cada producto publicado y la cantidad de unidades publicadas de ese mismo producto. (id producto, cantidad del producto)
-    /// precio_final, es la suma de, el precio de cada producto multiplicado por la cantidad de unidades del mismo. 
-    /// id_vendedor, es el id del vendedor que realizó la publicación. 
-    pub struct Publicacion{
-        id: u32,
-        productos: Vec<(u32, u32)>,
-        precio_final: u32,
-        id_vendedor:AccountId,
-        disponible: bool,
-    }
+        /// La función "enviar_linea" permite al vendedor enviar por separado una línea (producto) de una orden, con su código de seguimiento. 
+        /// Cuando todas las líneas de la orden fueron enviadas, la orden pasa a estado Enviado. 
+        /// 
+        /// Errores posibles: la orden no existe o no está pendiente; el usuario no es el vendedor; 
+        /// la orden no tiene una línea con ese producto; la línea ya fue enviada. 
+        #[ink(message)]
+        #[cfg(not(test))]
+        pub fn enviar_linea(&mut self, id_orden: u32, id_producto: u32, seguimiento: String) -> Result<(), String>{
+            let account_id = self.env().caller();
+            self.priv_enviar_linea(account_id, id_orden, id_producto, seguimiento)
+        }
+        fn priv_enviar_linea(&mut self, account_id: AccountId, id_orden: u32, id_producto: u32, seguimiento: String) -> Result<(), String>{
+            let usuario = self.buscar_usuario(account_id)?;
+            let mut orden = self.buscar_orden(id_orden)?;
+            if orden.estado != EstadoCompra::Pendiente{
+                return Err("El producto no puede ser enviado.".to_string());
+            }
+            usuario.enviar_compra(orden.info_publicacion.0)?;
+            let linea = orden.buscar_linea(id_producto)?;
+            if linea.estado != EstadoLinea::Pendiente {
+                return Err("La linea ya fue enviada.".to_string())
+            }
+            linea.estado = EstadoLinea::Enviada;
+            linea.seguimiento = Some(seguimiento);
+            if orden.lineas.iter().all(|linea| linea.estado != EstadoLinea::Pendiente) {
+                orden.estado = EstadoCompra::Enviado;
+                return self.actualizar_orden(orden, Some(EstadoCompra::Pendiente))
+            }
+            self.actualizar_ordenes(orden, id_orden)
+        }
 
-    impl Publicacion {
-        fn crear_publicacion(productos_a_publicar: Vec<(u32, u32)>, precio_final: u32, id_publicacion: u32, id_vendedor: AccountId) -> Publicacion{
-            Publicacion{
-                id: id_publicacion,
-                productos: productos_a_publicar,
-                precio_final,
-                id_vendedor,
-                disponible: true,
+        /// La función "recibir_linea" permite al comprador confirmar la recepción de una línea (producto) enviada de una orden. 
+        /// Cuando todas las líneas de la orden fueron recibidas, la orden pasa a estado Recibido y se libera el pago al vendedor. 
+        /// 
+        /// Errores posibles: la orden no existe; el usuario no es el comprador; 
+        /// la orden no tiene una línea con ese producto; la línea todavía no fue enviada o ya fue recibida. 
+        #[ink(message)]
+        #[cfg(not(test))]
+        pub fn recibir_linea(&mut self, id_orden: u32, id_producto: u32) -> Result<(), String>{
+            let account_id = self.env().caller();
+            let ahora = self.env().block_timestamp();
+            self.priv_recibir_linea(account_id, id_orden, id_producto, ahora)
+        }
+        fn priv_recibir_linea(&mut self, account_id: AccountId, id_orden: u32, id_producto: u32, ahora: Timestamp) -> Result<(), String>{
+            let usuario = self.buscar_usuario(account_id)?;
+            usuario.recibir_compra(id_orden)?;
+            let mut orden = self.buscar_orden(id_orden)?;
+            let linea = orden.buscar_linea(id_producto)?;
+            if linea.estado != EstadoLinea::Enviada {
+                return Err("La linea no esta en viaje.".to_string())
+            }
+            linea.estado = EstadoLinea::Recibida;
+            if orden.lineas.iter().all(|linea| linea.estado == EstadoLinea::Recibida) {
+                self.completar_recepcion(&mut orden, ahora)?;
+                return self.actualizar_orden(orden, Some(EstadoCompra::Enviado))
             }
+            self.actualizar_ordenes(orden, id_orden)
         }
-    }
 
+        /// La función "solicitar_devolucion" permite al comprador pedir la devolución de una orden recibida, indicando el motivo. 
+        /// Solo puede pedirse una vez por orden y dentro de los PLAZO_DEVOLUCION milisegundos posteriores a la recepción. 
+        /// 
+        /// Errores posibles: la orden no existe; el usuario no es el comprador; la orden no está recibida; 
+        /// la devolución ya fue solicitada; el plazo de devolución venció. 
+        #[ink(message)]
+        #[cfg(not(test))]
+        pub fn solicitar_devolucion(&mut self, id_orden: u32, motivo: String) -> Result<(), String>{
+            let account_id = self.env().caller();
+            let ahora = self.env().block_timestamp();
+            self.priv_solicitar_devolucion(account_id, id_orden, motivo, ahora)
+        }
+        fn priv_solicitar_devolucion(&mut self, account_id: AccountId, id_orden: u32, motivo: String, ahora: Timestamp) -> Result<(), String>{
+            let mut orden = self.buscar_orden(id_orden)?;
+            if orden.id_comprador != account_id {
+                return Err("Solo el comprador puede solicitar la devolucion.".to_string())
+            }
+            self.comprobar_estado_recibido(orden.clone())?;
+            if orden.motivo_devolucion.is_some() {
+                return Err("La devolucion ya fue solicitada.".to_string())
+            }
+            let recibido_en = orden.recibido_en.ok_or("La compra aun no fue recibida.")?;
+            if ahora >= recibido_en.checked_add(PLAZO_DEVOLUCION).ok_or("Error al sumar.")? {
+                return Err("El plazo de devolucion ya vencio.".to_string())
+            }
+            orden.motivo_devolucion = Some(motivo);
+            orden.estado = EstadoCompra::DevolucionSolicitada;
+            self.actualizar_orden(orden, Some(EstadoCompra::Recibido))
+        }
 
-/////////////////////////// PRODUCTO ///////////////////////////
+        /// La función "aceptar_devolucion" permite al vendedor aceptar la devolución solicitada. La orden pasa a estado EnDevolucion 
+        /// y el vendedor deposita en el contrato lo que recibió por la venta (precio menos comisión), 
+        /// en moneda nativa transferida junto al mensaje o en el token PSP22 de la orden mediante transfer_from. 
+        /// La comisión cobrada se descuenta de la tesorería, para que el comprador recupere el total pagado. 
+        /// El reintegro se paga al comprador cuando el vendedor confirma que recibió los productos devueltos, 
+        /// o cuando el comprador lo reclama porque el vendedor no confirmó dentro de PLAZO_CONFIRMACION_DEVOLUCION. 
+        /// 
+        /// Errores posibles: la orden no existe; el usuario no es el vendedor; no hay una devolución solicitada; el depósito no es correcto; 
+        /// la tesorería ya no tiene la comisión de la orden. 
+        #[ink(message, payable)]
+        #[cfg(not(test))]
+        pub fn aceptar_devolucion(&mut self, id_orden: u32) -> Result<(), String>{
+            let account_id = self.env().caller();
+            let ahora = self.env().block_timestamp();
+            let orden = self.buscar_orden(id_orden)?;
+            let deposito_esperado = if orden.token_de_pago.is_some() { 0 } else { orden.monto_a_reintegrar()? };
+            if self.env().transferred_value() != deposito_esperado {
+                return Err("El deposito no coincide con el monto a reintegrar.".to_string())
+            }
+            self.priv_aceptar_devolucion(account_id, id_orden, ahora)
+        }
+        fn priv_aceptar_devolucion(&mut self, account_id: AccountId, id_orden: u32, ahora: Timestamp) -> Result<(), String>{
+            let mut orden = self.buscar_orden(id_orden)?;
+            self.validar_devolucion(&orden, account_id, EstadoCompra::DevolucionSolicitada)?;
+            self.descontar_comision_de_tesoreria(&orden)?;
+            self.cobrar_pago_token(account_id, orden.token_de_pago, orden.monto_a_reintegrar()?)?;
+            self.devoluciones_aceptadas.insert(id_orden, &ahora);
+            orden.estado = EstadoCompra::EnDevolucion;
+            self.actualizar_orden(orden, Some(EstadoCompra::DevolucionSolicitada))
+        }
 
-    #[derive(Clone)]
-    #[ink::scale_derive(Encode, Decode, TypeInfo)]
-    #[cfg_attr(feature = "std",derive(ink::storage::traits::StorageLayout))]
-    /// Struct que contiene la información de un producto.
-    /// id, almacena el id del producto. Utilizado como clave en el "historial_productos" del sistema. 
-    /// nombre, almacena el nombre del producto.
-    /// descripcion, almacena la descripción de un producto.
-    /// precio, almacena el precio del producto.
-    /// categoria, almacena la categoria. 
-    pub struct Producto{
-        pub id: u32,
-        pub nombre: String,
-        pub descripcion: String,
-        pub precio: u32,
-        pub categoria:Categoria,
-    }
-    impl Producto{
+        /// La función "rechazar_devolucion" permite al vendedor rechazar la devolución solicitada. La orden vuelve a estado Recibido 
+        /// y no puede volver a solicitarse la devolución. (El marketplace no tiene un sistema de disputas al cual escalar el rechazo.) 
+        /// 
+        /// Errores posibles: la orden no existe; el usuario no es el vendedor; no hay una devolución solicitada. 
+        #[ink(message)]
+        #[cfg(not(test))]
+        pub fn rechazar_devolucion(&mut self, id_orden: u32) -> Result<(), String>{
+            let account_id = self.env().caller();
+            self.priv_rechazar_devolucion(account_id, id_orden)
+        }
+        fn priv_rechazar_devolucion(&mut self, account_id: AccountId, id_orden: u32) -> Result<(), String>{
+            let mut orden = self.buscar_orden(id_orden)?;
+            self.validar_devolucion(&orden, account_id, EstadoCompra::DevolucionSolicitada)?;
+            orden.estado = EstadoCompra::Recibido;
+            self.actualizar_orden(orden, Some(EstadoCompra::DevolucionSolicitada))
+        }
 
+        /// La función "confirmar_devolucion" permite al vendedor confirmar que recibió los productos devueltos. 
+        /// Se reintegra al comprador el total pagado, las unidades vuelven al stock de cada producto y la orden pasa a estado Devuelto. 
+        /// 
+        /// Errores posibles: la orden no existe; el usuario no es el vendedor; la devolución no fue aceptada; falla la transferencia. 
+        #[ink(message)]
+        #[cfg(not(test))]
+        pub fn confirmar_devolucion(&mut self, id_orden: u32) -> Result<(), String>{
+            let account_id = self.env().caller();
+            self.priv_confirmar_devolucion(account_id, id_orden)
+        }
+        fn priv_confirmar_devolucion(&mut self, account_id: AccountId, id_orden: u32) -> Result<(), String>{
+            let orden = self.buscar_orden(id_orden)?;
+            self.validar_devolucion(&orden, account_id, EstadoCompra::EnDevolucion)?;
+            self.aumentar_stock_productos(orden.info_publicacion.1.clone(), &orden.variantes)?;
+            self.completar_devolucion(orden)
+        }
 
-        fn cargar_producto(id: u32, nombre: String, descripcion: String, precio: u32, categoria: Categoria) -> Producto{
-            Producto{
-                id,
-                nombre,
-                descripcion,
-                precio,
-                categoria,
+        /// La función "reclamar_devolucion" permite al comprador cobrar el depósito de una devolución aceptada cuando el vendedor 
+        /// no confirmó la recepción de los productos dentro de los PLAZO_CONFIRMACION_DEVOLUCION milisegundos posteriores a aceptarla. 
+        /// Se reintegra al comprador el total pagado y la orden pasa a estado Devuelto. Como el vendedor no confirmó haber recibido 
+        /// los productos, las unidades no vuelven al stock. 
+        /// 
+        /// Errores posibles: la orden no existe; el usuario no es el comprador; la devolución no fue aceptada; 
+        /// el plazo de confirmación todavía no venció; falla la transferencia. 
+        #[ink(message)]
+        #[cfg(not(test))]
+        pub fn reclamar_devolucion(&mut self, id_orden: u32) -> Result<(), String>{
+            let account_id = self.env().caller();
+            let ahora = self.env().block_timestamp();
+            self.priv_reclamar_devolucion(account_id, id_orden, ahora)
+        }
+        fn priv_reclamar_devolucion(&mut self, account_id: AccountId, id_orden: u32, ahora: Timestamp) -> Result<(), String>{
+            let orden = self.buscar_orden(id_orden)?;
+            if orden.id_comprador != account_id {
+                return Err("Solo el comprador puede reclamar la devolucion.".to_string())
+            }
+            if orden.estado != EstadoCompra::EnDevolucion {
+                return Err("La orden no esta en el estado requerido para esta accion.".to_string())
             }
+            let aceptada_en = self.devoluciones_aceptadas.get(id_orden).ok_or("La devolucion no fue aceptada.")?;
+            if ahora < aceptada_en.checked_add(PLAZO_CONFIRMACION_DEVOLUCION).ok_or("Error al sumar.")? {
+                return Err("El vendedor todavia esta a tiempo de confirmar la devolucion.".to_string())
+            }
+            self.completar_devolucion(orden)
         }
-    }
-
-/////////////////////////// ORDEN DE COMPRA ///////////////////////////
 
-    #[derive(Clone, Debug)]
-    #[ink::scale_derive(Encode, Decode, TypeInfo)]
-    #[cfg_attr(feature = "std",derive(ink::storage::traits::StorageLayout))]
-    /// Struct que contiene la información de una orden de compra.
-    /// id, almacena la id de la orden de compra. 
-    /// estado, almacena el estado de la compra. Éste puede ser: Pendiente, Enviado, Recibido, Cancelada. 
-    /// cancelacion, es una tupla que almacena el pedido de cancelación, tanto del vendedor como del comprador. (vendedor, comprador)
-    /// info_publicacion, tupla que almacena los datos de la publicación. (ID de la publicacion, Vec<(IDs de los productos, cantidades de ese producto)>, precio final de la publicacion, ID del Vendedor).
-    /// id_comprador, almacena el id del comprador de la orden de compra. 
-    /// calificaciones, es una tupla que indica si el vendor y/o comprador realizó la calificación a su contraparte. (vendedor, comprador)
-    /// puntuacion_del_comprador, almacena la calificacion dada por el comprador (una vez recibido)
-    pub struct OrdenCompra{
-        pub id: u32,
-        pub estado: EstadoCompra,
-        pub cancelacion: (bool, bool), 
-        pub info_publicacion: (u32, Vec<(u32, u32)>, u32, AccountId),
-        pub id_comprador:AccountId,
-        pub calificaciones: (bool, bool),
-        pub puntuacion_del_comprador: Option<u8>,
-    }
-    impl OrdenCompra{
-        
-        fn crear_orden_de_compra(id_orden: u32, publicacion: Publicacion, id_comprador: AccountId) -> OrdenCompra{
-            let id_publicacion = publicacion.id;
-            let productos = publicacion.productos;
-            let precio_final = publicacion.precio_final;
-            let id_vendedor = publicacion.id_vendedor;
-            let info_publicacion = (id_publicacion, productos, precio_final, id_vendedor);
-            let calificaciones = (false, false);
+        /// La función se encarga de cerrar una devolución aceptada: reintegra al comprador el total pagado 
+        /// (el depósito del vendedor más la comisión descontada de la tesorería al aceptarla), 
+        /// ajusta los puntos de fidelidad de ambas partes y deja la orden en estado Devuelto. 
+        /// 
+        /// Errores posibles: falla la transferencia; overflow en las operaciones. 
+        fn completar_devolucion(&mut self, mut orden: OrdenCompra) -> Result<(), String>{
+            self.transferir(orden.token_de_pago, orden.id_comprador, Balance::from(orden.info_publicacion.2))?;
+            self.devoluciones_aceptadas.remove(orden.id);
+            let puntos_ganados = orden.puntos_ganados();
+            self.quitar_puntos(orden.id_comprador, puntos_ganados);
+            self.quitar_puntos(orden.info_publicacion.3, puntos_ganados);
+            self.acreditar_puntos(orden.id_comprador, orden.puntos_canjeados)?;
+            orden.estado = EstadoCompra::Devuelto;
+            self.actualizar_orden(orden, Some(EstadoCompra::EnDevolucion))
+        }
 
-            OrdenCompra{
-                id: id_orden,
-                estado: EstadoCompra::Pendiente,
-                cancelacion: (false, false),
-                info_publicacion,
-                id_comprador, 
-                calificaciones,
-                puntuacion_del_comprador: None,
-            }
+        /// La función "enviar_mensaje" agrega un mensaje al registro de una orden, para que comprador y vendedor coordinen la entrega. 
+        /// El contenido puede ser texto o un contenido cifrado por las partes fuera de la cadena. Retorna la posición del mensaje en el registro. 
+        /// Los mensajes quedan con su autor y fecha, y no pueden modificarse, por lo que sirven como evidencia ante un reclamo. 
+        /// 
+        /// Errores posibles: la orden no existe; el usuario no es el comprador ni el vendedor de la orden; 
+        /// el contenido está vacío o supera el largo máximo; la orden alcanzó la cantidad máxima de mensajes. 
+        #[ink(message)]
+        #[cfg(not(test))]
+        pub fn enviar_mensaje(&mut self, id_orden: u32, contenido: ContenidoMensaje) -> Result<u32, String>{
+            let account_id = self.env().caller();
+            let ahora = self.env().block_timestamp();
+            self.priv_enviar_mensaje(account_id, id_orden, contenido, ahora)
         }
-        
-        fn cancelar_compra_comprador(&mut self) -> Result<(), String>{
-            if self.cancelacion.1 {
-                return Err("La compra ya fue cancelada por el comprador anteriormente.".to_string());
+        fn priv_enviar_mensaje(&mut self, account_id: AccountId, id_orden: u32, contenido: ContenidoMensaje, ahora: Timestamp) -> Result<u32, String>{
+            let orden = self.buscar_orden(id_orden)?;
+            if (account_id != orden.id_comprador) & (account_id != orden.info_publicacion.3) {
+                return Err("El usuario no participa de la orden.".to_string())
             }
-            else{
-                self.cancelacion.1 = true;
-                return Ok(());
+            contenido.validar()?;
+            let mut mensajes = self.mensajes.get(id_orden).unwrap_or_default();
+            if mensajes.len() >= MAXIMO_MENSAJES_POR_ORDEN {
+                return Err("La orden alcanzo la cantidad maxima de mensajes.".to_string())
             }
+            let posicion = u32::try_from(mensajes.len()).map_err(|_| "Error al sumar.")?;
+            mensajes.push(Mensaje{ autor: account_id, fecha: ahora, contenido });
+            self.mensajes.insert(id_orden, &mensajes);
+            Ok(posicion)
         }
-        
-        fn cancelar_compra_vendedor(&mut self) -> Result<(), String>{
-            if self.cancelacion.0 {
-                return Err("La compra ya fue cancelada.".to_string());
-            }
-            else {
-                if self.cancelacion.1 {
-                    self.cancelacion.0 = true;
-                    return Ok(());
+
+        /// La función "mensajes_de_orden" devuelve una página del registro de mensajes de una orden: hasta "cantidad" mensajes a partir de la posición "desde", 
+        /// junto a la cantidad total de mensajes de la orden. 
+        /// Pueden leerlo el comprador y el vendedor de la orden, y los moderadores (para revisar reclamos). 
+        /// 
+        /// Errores posibles: la orden no existe; el usuario no participa de la orden ni es moderador. 
+        #[ink(message)]
+        #[cfg(not(test))]
+        pub fn mensajes_de_orden(&self, id_orden: u32, desde: u32, cantidad: u32) -> Result<(Vec<Mensaje>, u32), String>{
+            let account_id = self.env().caller();
+            self.priv_mensajes_de_orden(account_id, id_orden, desde, cantidad)
+        }
+        fn priv_mensajes_de_orden(&self, account_id: AccountId, id_orden: u32, desde: u32, cantidad: u32) -> Result<(Vec<Mensaje>, u32), String>{
+            let orden = self.buscar_orden(id_orden)?;
+            if (account_id != orden.id_comprador) & (account_id != orden.info_publicacion.3) & self.es_moderador(account_id).is_err() {
+                return Err("El usuario no participa de la orden.".to_string())
+            }
+            let mensajes = self.mensajes.get(id_orden).unwrap_or_default();
+            let total = u32::try_from(mensajes.len()).map_err(|_| "Error al sumar.")?;
+            let pagina = mensajes.into_iter().skip(desde as usize).take(cantidad as usize).collect();
+            Ok((pagina, total))
+        }
+
+        /// La función "calcular_compromiso" devuelve el compromiso de una calificación: el hash Blake2x256 de (calificacion, sal). 
+        /// La sal es un valor secreto elegido por quien califica, que impide adivinar la calificación a partir del compromiso. 
+        #[ink(message)]
+        pub fn calcular_compromiso(&self, calificacion: u8, sal: [u8; 32]) -> [u8; 32]{
+            let mut compromiso = [0u8; 32];
+            ink::env::hash_encoded::<ink::env::hash::Blake2x256, _>(&(calificacion, sal), &mut compromiso);
+            compromiso
+        }
+
+        /// La función "comprometer_calificacion" registra, sin revelarla, la calificación de una de las partes de una orden recibida. 
+        /// El compromiso se calcula con "calcular_compromiso", y la calificación se revela luego con "revelar_calificacion". 
+        /// Solo se aceptan compromisos dentro del plazo de calificación contado desde la recepción de la orden. 
+        /// 
+        /// Además, la orden debe alcanzar el monto mínimo y la cuenta que califica debe tener la antigüedad mínima configurados por el owner. 
+        /// 
+        /// Errores posibles: la orden no existe o no fue recibida; el usuario no es parte de la orden; 
+        /// el usuario ya calificó o ya envió su compromiso; el plazo de calificación venció; 
+        /// la orden no alcanza el monto mínimo; la cuenta no tiene la antigüedad mínima. 
+        #[ink(message)]
+        #[cfg(not(test))]
+        pub fn comprometer_calificacion(&mut self, id_orden: u32, compromiso: [u8; 32]) -> Result<(), String>{
+            let account_id = self.env().caller();
+            let ahora = self.env().block_timestamp();
+            self.priv_comprometer_calificacion(account_id, id_orden, compromiso, ahora)
+        }
+        fn priv_comprometer_calificacion(&mut self, account_id: AccountId, id_orden: u32, compromiso: [u8; 32], ahora: Timestamp) -> Result<(), String>{
+            let usuario = self.buscar_usuario(account_id)?;
+            let mut orden = self.buscar_orden(id_orden)?;
+            self.comprobar_estado_recibido(orden.clone())?;
+            let rol = usuario.comprobar_rol(orden.info_publicacion.3, orden.id_comprador)?;
+            self.ya_califico(rol.clone(), orden.clone())?;
+            if ahora >= self.limite_calificacion(&orden)? {
+                return Err("El plazo de calificacion ya vencio.".to_string())
+            }
+            self.cumple_requisitos_calificacion(&usuario, &orden, ahora)?;
+            if rol == Rol::Comp {
+                orden.compromisos.0 = Some(compromiso);
+            }
+            else {
+                orden.compromisos.1 = Some(compromiso);
+            }
+            self.actualizar_ordenes(orden, id_orden)
+        }
+
+        /// La función "revelar_calificacion" revela la calificación comprometida y la aplica a la contraparte. 
+        /// Solo puede revelarse cuando ambas partes enviaron su compromiso (o ya revelaron), o cuando venció el plazo de calificación, 
+        /// de forma que nadie conozca la calificación de la contraparte antes de comprometer la suya. 
+        /// Los compromisos deben revelarse antes de que pasen PLAZO_REVELACION milisegundos del fin del plazo de calificación; 
+        /// después quedan anulados (ver "anular_compromisos_vencidos"). 
+        /// 
+        /// Errores posibles: la orden no existe; el usuario no es parte de la orden o no envió compromiso; 
+        /// la contraparte todavía no se comprometió y el plazo no venció; venció el plazo para revelar; 
+        /// la calificación y la sal no coinciden con el compromiso; la calificación está fuera de rango (1..5). 
+        #[ink(message)]
+        #[cfg(not(test))]
+        pub fn revelar_calificacion(&mut self, id_orden: u32, calificacion: u8, sal: [u8; 32]) -> Result<(), String>{
+            let account_id = self.env().caller();
+            let ahora = self.env().block_timestamp();
+            self.priv_revelar_calificacion(account_id, id_orden, calificacion, sal, ahora)
+        }
+        fn priv_revelar_calificacion(&mut self, account_id: AccountId, id_orden: u32, calificacion: u8, sal: [u8; 32], ahora: Timestamp) -> Result<(), String>{
+            let usuario = self.buscar_usuario(account_id)?;
+            let mut orden = self.buscar_orden(id_orden)?;
+            let rol = usuario.comprobar_rol(orden.info_publicacion.3, orden.id_comprador)?;
+            let compromiso = if rol == Rol::Comp { orden.compromisos.0.take() } else { orden.compromisos.1.take() };
+            let compromiso = compromiso.ok_or("El usuario no envio su compromiso de calificacion.")?;
+            let contraparte_comprometida = if rol == Rol::Comp {
+                orden.compromisos.1.is_some() | orden.calificaciones.1
+            } else {
+                orden.compromisos.0.is_some() | orden.calificaciones.0
+            };
+            if !contraparte_comprometida & (ahora < self.limite_calificacion(&orden)?) {
+                return Err("La contraparte todavia no envio su compromiso de calificacion.".to_string())
+            }
+            if ahora >= self.limite_revelacion(&orden)? {
+                return Err("El plazo para revelar la calificacion ya vencio.".to_string())
+            }
+            if compromiso != self.calcular_compromiso(calificacion, sal) {
+                return Err("La calificacion no coincide con el compromiso.".to_string())
+            }
+            self.actualizar_ordenes(orden, id_orden)?;
+            self.priv_calificar(id_orden, calificacion, account_id, ahora)
+        }
+
+        /// La función "anular_compromisos_vencidos" descarta los compromisos de calificación de una orden que no se revelaron 
+        /// dentro del plazo para revelar. Puede ser llamada por cualquier usuario. Las calificaciones ya reveladas se mantienen. 
+        /// 
+        /// Errores posibles: la orden no existe o no fue recibida; el plazo para revelar todavía no venció; la orden no tiene compromisos pendientes. 
+        #[ink(message)]
+        #[cfg(not(test))]
+        pub fn anular_compromisos_vencidos(&mut self, id_orden: u32) -> Result<(), String>{
+            let ahora = self.env().block_timestamp();
+            self.priv_anular_compromisos_vencidos(id_orden, ahora)
+        }
+        fn priv_anular_compromisos_vencidos(&mut self, id_orden: u32, ahora: Timestamp) -> Result<(), String>{
+            let mut orden = self.buscar_orden(id_orden)?;
+            if ahora < self.limite_revelacion(&orden)? {
+                return Err("El plazo para revelar las calificaciones todavia no vencio.".to_string())
+            }
+            if orden.compromisos == (None, None) {
+                return Err("La orden no tiene compromisos pendientes.".to_string())
+            }
+            orden.compromisos = (None, None);
+            self.actualizar_ordenes(orden, id_orden)
+        }
+
+        /// La función se encarga de aplicar una calificación (ya revelada) a la contraparte de la orden. 
+        /// 
+        /// Errores posibles: cuando la calificación recibida por parametro se encuentra fuera de rango (rango = [1..5]).
+        fn priv_calificar(&mut self, id_orden:u32, calificacion: u8, account_id: AccountId, ahora: Timestamp) -> Result<(), String>{
+            if (calificacion < 1) | (calificacion > 5){ //Revisa que la calificacion este en rango
+                return Err("El valor de la calificacion no es valido (1..5).".to_string())
+            }
+            let mut usuario = self.buscar_usuario(account_id)?;
+            let mut orden_de_compra = self.buscar_orden(id_orden)?;
+            let id_comprador = orden_de_compra.id_comprador; //guarda la id del comprador y del vendedor
+            let id_vendedor = orden_de_compra.info_publicacion.3;
+            self.comprobar_estado_recibido(orden_de_compra.clone())?;
+            self.calificar_segun_rol(calificacion, orden_de_compra, id_vendedor, id_comprador, usuario, ahora) 
+        }
+
+        /// La función "calcular_precio_final" se encarga de calcular el precio final de una publicación.
+        /// Recibe los productos con sus cantidades de una publicación y las variantes elegidas, y retorna el precio final. 
+        /// El precio de un producto con variante elegida es el de la variante (precio del producto más su diferencia de precio). 
+        /// 
+        /// Errores posibles: overflow en la suma/multiplicación; la variante no existe. 
+        fn calcular_precio_final(&self, productos_publicados: Vec<(u32, u32)>, variantes: &[(u32, u32)]) -> Result<u32, String>{
+            let mut total: u32 = 0;
+            for (id, cantidad) in productos_publicados{
+                if let Some((producto, _stock)) = self.historial_productos.get(id){
+                    let precio = match Variante::elegida(variantes, id) {
+                        Some(id_variante) => self.buscar_variante(id, id_variante)?.precio(producto.precio)?,
+                        None => producto.precio,
+                    };
+                    total = total.checked_add(precio.checked_mul(cantidad)
+                    .ok_or("Overflow al multiplicar precio por cantidad.")?)
+                    .ok_or("Overflow al acumular el total.")?;
                 }
-                else {
-                    return Err("El comprador no desea cancelar la compra.".to_string());
+            }
+            Ok(total)
+        }
+
+      
+        /// La función "hay_stock_suficiente" se encarga de comprobar que el stock de mis productos almacenados sean suficientes y distintos de cero.
+        /// Recibe un Vector de tuplas donde la posición cero es el ID del producto y la posición uno la cantidad de ese producto a publicar, 
+        /// y las variantes elegidas (para esos productos se controla el stock de la variante). 
+        /// 
+        /// Errores posibles: si el stock es menor a la cantidad a publicar o el stock de mi producto es cero;
+        /// si el producto a publicar no se encuentra en mi sistema (historial_productos); la variante no existe. 
+        fn hay_stock_suficiente(&self, productos_cantidades: Vec<(u32, u32)>, variantes: &[(u32, u32)]) -> Result<(), String>{
+            for (id, cantidad) in productos_cantidades{
+                let stock = self.stock_disponible(id, Variante::elegida(variantes, id))?;
+                if (stock < cantidad) | (stock == 0){
+                    return Err("No hay stock suficiente.".to_string())
                 }
             }
+            Ok(())
         }
-    }
+        
+        /// Función para descontar el stock de un producto. Recibe los productos (ID) con las cantidades a descontar y las variantes elegidas. 
+        /// 
+        /// Errores posibles: cuando ocurre overflow/underflow al realizar la resta; el producto o la variante no existen. 
+        fn descontar_stock(&mut self, productos_cantidades: Vec<(u32, u32)>, variantes: &[(u32, u32)]) -> Result<(), String>{
+            for (id, cantidad) in productos_cantidades{
+                let variante = Variante::elegida(variantes, id);
+                let stock = self.stock_disponible(id, variante)?.checked_sub(cantidad).ok_or("Error al restar stock")?;
+                self.guardar_stock(id, variante, stock)?;
+            }
+            Ok(())
+        }
+
+        /// Devuelve el stock de un producto, o el de una de sus variantes si se indica. 
+        /// 
+        /// Errores posibles: el producto no se encuentra en mi sistema (historial_productos); la variante no existe. 
+        fn stock_disponible(&self, id_producto: u32, variante: Option<u32>) -> Result<u32, String>{
+            let (_producto, stock) = self.historial_productos.get(id_producto).ok_or("No se encontro el producto.")?;
+            match variante {
+                Some(id_variante) => Ok(self.buscar_variante(id_producto, id_variante)?.stock),
+                None => Ok(stock),
+            }
+        }
+
+        /// Reemplaza el stock de un producto, o el de una de sus variantes si se indica. 
+        /// 
+        /// Errores posibles: el producto no se encuentra en mi sistema (historial_productos); la variante no existe. 
+        fn guardar_stock(&mut self, id_producto: u32, variante: Option<u32>, stock: u32) -> Result<(), String>{
+            let (producto, _stock) = self.historial_productos.get(id_producto).ok_or("No se encontro el producto.")?;
+            match variante {
+                Some(id_variante) => {
+                    let mut variantes = self.variantes.get(id_producto).unwrap_or_default();
+                    let variante = variantes.iter_mut().find(|variante| variante.id == id_variante).ok_or("La variante no existe.")?;
+                    variante.stock = stock;
+                    self.variantes.insert(id_producto, &variantes);
+                },
+                None => {
+                    self.historial_productos.insert(id_producto, &(producto, stock));
+                },
+            }
+            Ok(())
+        }
+
+        /// La función se encarga de cargar los productos (ID, stock) en un Vector. 
+        /// Recibe un Vector con IDs de productos y retorna un Vector de tuplas con ID del producto, stock del producto.
+        fn cargar_productos_propios(&self, account_id: AccountId, lista_de_productos: Vec<u32>) -> Vec<(u32, u32)>{
+            let mut productos = Vec::new();
+            for id_producto in lista_de_productos {
+                let stock = self.historial_productos.get(id_producto).unwrap().1;
+                let producto = (id_producto, stock);
+                productos.push(producto);
+            }
+            productos
+        }
+
+        /// La función "puede_restockear" se encarga de comprobar si el stock de cada producto es mayor o igual al de una publicacion recibida por parametro.
+        /// 
+        /// Retornos posibles: falso en caso de que el stock sea insuficiente para reponer;
+        /// verdadero cuando es posible realizar la reposición. 
+        fn puede_restockear(&self, publicacion: Publicacion) -> bool{
+            for (id, cantidad) in publicacion.productos{
+                let stock = self.stock_disponible(id, Variante::elegida(&publicacion.variantes, id)).unwrap_or(0);
+                if stock < cantidad{
+                    return false;
+                }
+            }
+            return true;
+        }
+
+        /// La función se encarga de devolver la posición en la que se encuentra una publicación en base a un ID recibido por parametro. 
+        /// 
+        /// Errores posibles: cuando la publicación no se encuentra en mi sistema (historial_publicaciones).
+        fn devolver_posicion_publicacion(&self, id_publicacion: u32) -> Result<u32, String>{
+            for i in 0..self.historial_publicaciones.len() {
+                if let Some((id, publicacion)) = self.historial_publicaciones.get(i) {
+                    if id == id_publicacion { 
+                        return Ok(i);
+                    }
+                }
+            }
+            return Err("No se encontro publicacion".to_string());
+        }
+
+        /// La función se encarga de comprobar si es posible cancelar una compra. 
+        /// 
+        /// Errores posibles: cuando la compra no tiene estado Pendiente; cuando alguna línea de la compra ya fue enviada. 
+        fn se_puede_cancelar(&self, orden: &OrdenCompra)-> Result<(), String>{
+            if orden.estado != EstadoCompra::Pendiente{
+                return Err("Ya no se puede cancelar la compra.".to_string())
+            }
+            if orden.lineas.iter().any(|linea| linea.estado != EstadoLinea::Pendiente){
+                return Err("La compra tiene productos enviados.".to_string())
+            }
+            else {
+                return Ok(())
+            }
+        }
+
+        /// La función se encarga de reponer los productos en la publicación o en el stock del producto que corresponda.
+        /// En las subastas las unidades siempre vuelven al stock, ya que la subasta no se reabre. 
+        fn devolver_productos(&mut self, id_publicacion: u32,) -> Result<(), String>{
+            let mut publicacion = self.buscar_publicacion(id_publicacion)?;
+            if publicacion.disponible | publicacion.subasta.is_some() {
+                return self.aumentar_stock_productos(publicacion.productos, &publicacion.variantes);
+            }
+            else {
+                publicacion.disponible = true;
+                return self.actualizar_publicaciones(publicacion, id_publicacion);
+            }
+        }
+
+        /// La función se encarga de aumentar el stock de cada producto recibido por parametro. 
+        /// Recibe un Vector de tuplas donde la posición cero es el ID del producto y la posición uno es la cantidad a aumentar, 
+        /// y las variantes elegidas (para esos productos se aumenta el stock de la variante). 
+        /// 
+        /// Errores posibles: overflow en la suma;
+        /// un ID del Vector recibido por parametro no se encuentra en mi sistema (historial_productos); la variante no existe. 
+        fn aumentar_stock_productos(&mut self, productos_cantidades: Vec<(u32, u32)>, variantes: &[(u32, u32)]) -> Result<(), String>{
+            for (id, cantidad) in productos_cantidades{
+                let variante = Variante::elegida(variantes, id);
+                let stock = self.stock_disponible(id, variante)?.checked_add(cantidad).ok_or("Error al sumar stock")?;
+                self.guardar_stock(id, variante, stock)?;
+            }
+            Ok(())
+        }
+
+        /// La función se encarga de pisar un valor del StorageVec "historial_publicaciones" en base a un ID de una publicación recibida por parametro.
+        fn actualizar_publicaciones(&mut self, publicacion: Publicacion, id_publicacion: u32) -> Result<(), String>{
+            let pos = self.devolver_posicion_publicacion(id_publicacion)?;
+            self.historial_publicaciones.set(pos, &(id_publicacion, publicacion));
+            return Ok(());
+        }
+
+        /// La función se encarga de pisar un valor del StorageVec "historial_ordenes_de_compra" en base a un ID de una orden recibida por parametro.
+        fn actualizar_ordenes(&mut self, orden: OrdenCompra, id_orden: u32) -> Result<(), String>{
+            let pos = self.devolver_posicion_orden_de_compra(id_orden)?;
+            self.historial_ordenes_de_compra.set(pos, &(id_orden, orden));
+            return Ok(());
+        }
+
+        /// La función se encarga de devolver una OrdenCompra en base a un ID recibido por parametro.
+        /// 
+        /// Errores posibles: cuando no se encuentra el ID de la orden en mi sistema (historial_ordenes_de_compra). 
+        fn buscar_orden(&self, id_orden: u32) -> Result<OrdenCompra, String>{
+            for i in 0..self.historial_ordenes_de_compra.len(){
+                if let Some((id, ref orden)) = self.historial_ordenes_de_compra.get(i){
+                    if id == id_orden{
+                        return Ok(orden.clone());
+                    }
+                }
+            }
+            return Err("No se encontro la orden.".to_string());
+        }
+
+        /// La función se encarga de devolver una Publicación en base a un ID recibido por parametro. 
+        /// 
+        /// Errores posibles: cuando no se encuentra el ID de la publicación en mi sistema (historial_publicaciones).
+        fn buscar_publicacion(&self, id_publicacion: u32) -> Result<Publicacion, String>{
+            for i in 0..self.historial_publicaciones.len(){
+                if let Some((id, ref publicacion)) = self.historial_publicaciones.get(i){
+                    if id == id_publicacion{
+                        return Ok(publicacion.clone());
+                    }
+                }
+            }
+            return Err("No se encontro la publicacion.".to_string());
+        }
+
+        /// La función se encarga de devolver la categoria correspondiente al ID recibido por parametro. 
+        /// 
+        /// Errores posibles: la categoria no existe. 
+        fn buscar_categoria(&self, id_categoria: u32) -> Result<Categoria, String>{
+            self.categorias.get(id_categoria).ok_or("La categoria no existe.".to_string())
+        }
+
+        /// La función se encarga de validar los datos de la categoria "id" antes de crearla o modificarla. 
+        /// 
+        /// Errores posibles: el nombre está vacío o lo usa otra categoria; la categoria padre no existe o es la misma categoria. 
+        fn validar_categoria(&self, id: u32, nombre: &str, padre: Option<u32>) -> Result<(), String>{
+            if nombre.is_empty() {
+                return Err("El nombre de la categoria no es valido.".to_string())
+            }
+            if self.priv_get_categorias().iter().any(|categoria| (categoria.nombre == nombre) & (categoria.id != id)) {
+                return Err("Ya existe una categoria con ese nombre.".to_string())
+            }
+            if let Some(id_padre) = padre {
+                if id_padre == id {
+                    return Err("Una categoria no puede ser su propia categoria padre.".to_string())
+                }
+                self.buscar_categoria(id_padre)?;
+            }
+            Ok(())
+        }
+
+        /// La función se encarga de devolver la posición de una orden de compra en base a el ID recibido por parametro.
+        /// 
+        /// Errores posibles: cuando la orden con el ID recibido por parametro no se encuentra en mi sistema (historial_ordenes_de_compra).
+        fn devolver_posicion_orden_de_compra(&self, id_orden: u32) -> Result<u32, String>{
+            for i in 0..self.historial_ordenes_de_compra.len() {
+                if let Some((id, orden)) = self.historial_ordenes_de_compra.get(i) {
+                    if id == id_orden { 
+                        return Ok(i);
+                    }
+                }
+            }
+            return Err("No se encontro la orden de compra.".to_string());
+        }
+
+        /// La función se encarga de comprobar que quien responde una devolución sea el vendedor de la orden, y que la orden esté en el estado esperado. 
+        /// 
+        /// Errores posibles: el usuario no es el vendedor; la orden no está en el estado esperado. 
+        fn validar_devolucion(&self, orden: &OrdenCompra, account_id: AccountId, estado_esperado: EstadoCompra) -> Result<(), String>{
+            if orden.info_publicacion.3 != account_id {
+                return Err("Solo el vendedor puede responder la devolucion.".to_string())
+            }
+            if orden.estado != estado_esperado {
+                return Err("La orden no esta en el estado requerido para esta accion.".to_string())
+            }
+            Ok(())
+        }
+
+        /// La función se encarga de guardar una orden modificada en el historial y notificar el cambio de estado al contrato de reportes. 
+        /// 
+        /// Errores posibles: la orden no se encuentra en mi sistema (historial_ordenes_de_compra). 
+        fn actualizar_orden(&mut self, orden: OrdenCompra, estado_anterior: Option<EstadoCompra>) -> Result<(), String>{
+            let pos = self.devolver_posicion_orden_de_compra(orden.id)?;
+            self.historial_ordenes_de_compra.set(pos, &(orden.id, orden.clone()));
+            self.notificar_orden_actualizada(orden, estado_anterior);
+            Ok(())
+        }
+
+        /// La función se encarga de marcar una orden como recibida en el momento indicado y liberar su pago al vendedor. 
+        /// La emisión del recibo no bloquea la recepción: si falla, se emite el evento ReciboNoEmitido y el comprador puede 
+        /// reintentarla con "reintentar_recibo". 
+        /// 
+        /// Errores posibles: los de "liberar_pago". 
+        fn completar_recepcion(&mut self, orden: &mut OrdenCompra, ahora: Timestamp) -> Result<(), String>{
+            orden.estado = EstadoCompra::Recibido;
+            orden.recibido_en = Some(ahora);
+            let par = (orden.id_comprador, orden.info_publicacion.3);
+            let ordenes_entre = self.ordenes_entre_cuentas.get(par).unwrap_or(0).checked_add(1).ok_or("Error al sumar.")?;
+            self.ordenes_entre_cuentas.insert(par, &ordenes_entre);
+            let puntos = orden.puntos_ganados();
+            self.acreditar_puntos(orden.id_comprador, puntos)?;
+            self.acreditar_puntos(orden.info_publicacion.3, puntos)?;
+            if let Err(motivo) = self.emitir_recibo(orden, ahora) {
+                self.env().emit_event(ReciboNoEmitido{ comprador: orden.id_comprador, id_orden: orden.id, motivo });
+            }
+            self.liberar_pago(orden)
+        }
+
+        /// La función se encarga de pedir al contrato de recibos (si hay uno registrado) que acuñe el recibo de una orden para su comprador. 
+        /// El id del recibo es el id de la orden. 
+        /// 
+        /// Errores posibles: falla la llamada al contrato de recibos o éste rechaza el recibo. 
+        fn emitir_recibo(&self, orden: &OrdenCompra, ahora: Timestamp) -> Result<(), String>{
+            match self.contrato_recibos {
+                Some(direccion) => self.acunar_recibo(direccion, orden.id_comprador, Recibo::de_orden(orden, ahora)),
+                None => Ok(()),
+            }
+        }
+
+        /// La función "reintentar_recibo" permite al comprador pedir nuevamente el recibo de una orden recibida 
+        /// cuya emisión falló al recibirla (ver el evento ReciboNoEmitido). 
+        /// 
+        /// Errores posibles: no hay contrato de recibos registrado; la orden no existe; el usuario no es el comprador de la orden; 
+        /// la orden no fue recibida; el recibo ya fue emitido; falla la llamada al contrato de recibos o éste rechaza el recibo. 
+        #[ink(message)]
+        #[cfg(not(test))]
+        pub fn reintentar_recibo(&mut self, id_orden: u32) -> Result<(), String>{
+            let account_id = self.env().caller();
+            self.priv_reintentar_recibo(account_id, id_orden)
+        }
+        fn priv_reintentar_recibo(&mut self, account_id: AccountId, id_orden: u32) -> Result<(), String>{
+            let direccion = self.contrato_recibos.ok_or("No hay contrato de recibos registrado.")?;
+            let orden = self.buscar_orden(id_orden)?;
+            if orden.id_comprador != account_id {
+                return Err("Solo el comprador puede pedir el recibo.".to_string())
+            }
+            self.comprobar_estado_recibido(orden.clone())?;
+            let recibido_en = orden.recibido_en.ok_or("La compra aun no fue recibida.")?;
+            if self.psp34_owner_of(direccion, id_orden)?.is_some() {
+                return Err("El recibo ya fue emitido.".to_string())
+            }
+            self.acunar_recibo(direccion, orden.id_comprador, Recibo::de_orden(&orden, recibido_en))
+        }
+
+        /// La función se encarga comprobar el estado de la compra. 
+        /// 
+        /// Errores posibles: cuando el estado de la compra es distinto de Recibido. 
+        fn comprobar_estado_recibido(&self, orden: OrdenCompra) -> Result<(), String>{
+            if orden.estado == EstadoCompra::Recibido{
+                Ok(())
+            }
+            else{
+                Err("La compra aun no fue recibida.".to_string())
+            }
+        }
+
+        /// La función se encarga de calificar a un usuario según el rol que tuvo en la compra. 
+        fn calificar_segun_rol(&mut self, calificacion: u8, mut orden_de_compra: OrdenCompra, id_vendedor: AccountId, id_comprador: AccountId, usuario: Usuario, ahora: Timestamp) -> Result<(), String>{
+            let rol_del_usuario_en_compra = usuario.comprobar_rol(id_vendedor, id_comprador)?;
+            let _ = self.ya_califico(rol_del_usuario_en_compra.clone(), orden_de_compra.clone())?;
+            let (calificado, rol_calificado) = if rol_del_usuario_en_compra == Rol::Comp{
+                let categorias = self.categorias_de_la_orden(&orden_de_compra);
+                let calificacion_ponderada = CalificacionPonderada{
+                    puntuacion: calificacion,
+                    monto: orden_de_compra.info_publicacion.2,
+                    fecha: ahora,
+                    calificador: id_comprador,
+                    relacionada: self.son_cuentas_relacionadas(id_comprador, id_vendedor),
+                };
+                self.calificar_vendedor(id_vendedor, calificacion_ponderada, categorias)?;
+                orden_de_compra.calificaciones.0 = true;
+                orden_de_compra.puntuacion_del_comprador = Some(calificacion);
+                (id_vendedor, Rol::Vend)
+            }
+            else {
+                self.calificar_comprador(id_comprador, calificacion)?;
+                orden_de_compra.calificaciones.1 = true;
+                (id_comprador, Rol::Comp)
+            };
+            let id_orden = orden_de_compra.id;
+            self.actualizar_ordenes(orden_de_compra.clone(), id_orden)?;
+            self.notificar_calificacion(orden_de_compra, calificado, rol_calificado, calificacion);
+            Ok(())
+        }
+
+        /// La función se encarga de de comprobar si un usuario ya calificó una compra que realizó, o si ya envió el compromiso de su calificación. 
+        /// 
+        /// Errores posibles: cuando el usuario ya calificó la compra anteriormente; cuando ya envió su compromiso y todavía no lo reveló. 
+        fn ya_califico(&self, rol: Rol, orden: OrdenCompra) -> Result<(), String>{
+            if ((rol == Rol::Comp) & (orden.calificaciones.0)) | ((rol == Rol::Vend) & (orden.calificaciones.1)){
+                return Err("El usuario ya califico.".to_string())
+            }
+            if ((rol == Rol::Comp) & (orden.compromisos.0.is_some())) | ((rol == Rol::Vend) & (orden.compromisos.1.is_some())){
+                return Err("El usuario ya envio su compromiso de calificacion.".to_string())
+            }
+            Ok(())
+        }
+
+        /// La función se encarga de comprobar que la orden y la cuenta que califica cumplan los requisitos de calificación configurados por el owner. 
+        /// 
+        /// Errores posibles: la orden no alcanza el monto mínimo; la cuenta no tiene la antigüedad mínima. 
+        fn cumple_requisitos_calificacion(&self, usuario: &Usuario, orden: &OrdenCompra, ahora: Timestamp) -> Result<(), String>{
+            if orden.info_publicacion.2 < self.monto_minimo_calificacion {
+                return Err("La orden no alcanza el monto minimo para calificar.".to_string())
+            }
+            if ahora.saturating_sub(usuario.registrado_en) < self.antiguedad_minima_calificacion {
+                return Err("La cuenta no tiene la antiguedad minima para calificar.".to_string())
+            }
+            Ok(())
+        }
+
+        /// La función se encarga de determinar si dos cuentas están relacionadas: porque el owner las marcó, 
+        /// o porque ya completaron entre sí más de MAXIMO_ORDENES_ENTRE_CUENTAS ordenes. 
+        fn son_cuentas_relacionadas(&self, id_comprador: AccountId, id_vendedor: AccountId) -> bool{
+            self.cuentas_relacionadas.contains((id_comprador, id_vendedor)) 
+                | (self.ordenes_entre_cuentas.get((id_comprador, id_vendedor)).unwrap_or(0) > MAXIMO_ORDENES_ENTRE_CUENTAS)
+        }
+
+        /// La función se encarga de devolver el momento a partir del cual ya no se aceptan compromisos de calificación de una orden. 
+        /// 
+        /// Errores posibles: la orden no tiene fecha de recepción; overflow en la suma. 
+        fn limite_calificacion(&self, orden: &OrdenCompra) -> Result<Timestamp, String>{
+            let recibido_en = orden.recibido_en.ok_or("La compra aun no fue recibida.")?;
+            recibido_en.checked_add(self.plazo_calificacion).ok_or("Error al sumar.".to_string())
+        }
+
+        /// La función se encarga de devolver el momento a partir del cual ya no se pueden revelar los compromisos de calificación de una orden. 
+        /// 
+        /// Errores posibles: la orden no tiene fecha de recepción; overflow en la suma. 
+        fn limite_revelacion(&self, orden: &OrdenCompra) -> Result<Timestamp, String>{
+            self.limite_calificacion(orden)?.checked_add(PLAZO_REVELACION).ok_or("Error al sumar.".to_string())
+        }
+
+        /// La función se encarga de registrar la puntuación recibida por parametro a un usuario con rol Vend.
+        /// Además de la puntuación simple, guarda la calificación completa (monto, fecha, calificador) para la reputación ponderada, 
+        /// y suma la puntuación a la reputación del vendedor en cada una de las categorias recibidas. 
+        /// 
+        /// Errores posibles: cuando el usuario no tiene los datos correspondientes a un vendedor cargados. 
+        fn calificar_vendedor(&mut self, id_vendedor: AccountId, calificacion: CalificacionPonderada, categorias: Vec<u32>) -> Result<(), String>{
+            let mut vendedor = self.buscar_usuario(id_vendedor)?;
+            if let Some(ref mut datos_vendedor) = vendedor.datos_vendedor{
+                datos_vendedor.reputacion_como_vendedor.push(calificacion.puntuacion);
+                for categoria in categorias{
+                    datos_vendedor.calificar_en_categoria(categoria, calificacion.puntuacion);
+                }
+                datos_vendedor.calificaciones_ponderadas.push(calificacion);
+                self.actualizar_usuarios(vendedor);
+                Ok(())
+            }
+            else{
+                Err("El vendedor no tiene datos cargados.".to_string())
+            }
+        }
+
+        /// La función se encarga de registrar la puntuación recibida por parametro a un usuario con rol Comp. 
+        /// 
+        /// Errores posibles: cuando el usuario no tiene los datos correspondientes a un comprador cargados. 
+        fn calificar_comprador(&mut self, id_comprador: AccountId, calificacion: u8) -> Result<(), String>{
+            let mut comprador = self.buscar_usuario(id_comprador)?;
+            if let Some(ref mut datos_comprador) = comprador.datos_comprador{
+                datos_comprador.reputacion_como_comprador.push(calificacion);
+                self.actualizar_usuarios(comprador);
+                Ok(())
+            }
+            else{
+                Err("El comprador no tiene datos cargados.".to_string())
+            }
+        }
+
+        /// La función se encarga de comprobar que el usuario recibido por parametro sea un verificador habilitado por el owner. 
+        /// 
+        /// Errores posibles: cuando el usuario no es verificador. 
+        fn es_verificador(&self, account_id: AccountId) -> Result<(), String>{
+            if !self.verificadores.get(account_id).unwrap_or(false){
+                return Err("El usuario no es verificador.".to_string())
+            }
+            Ok(())
+        }
+
+        /// La función se encarga de devolver las verificaciones vigentes de un vendedor: las que no vencieron y cuyo verificador sigue habilitado. 
+        fn verificaciones_vigentes(&self, id_vendedor: AccountId, ahora: Timestamp) -> Vec<Verificacion>{
+            self.verificaciones.get(id_vendedor).unwrap_or_default()
+                .into_iter()
+                .filter(|verificacion| (verificacion.vencimiento > ahora) & self.verificadores.get(verificacion.verificador).unwrap_or(false))
+                .collect()
+        }
+
+        /// La función se encarga de comprobar que el usuario recibido por parametro sea un moderador habilitado por el owner. 
+        /// 
+        /// Errores posibles: cuando el usuario no es moderador. 
+        fn es_moderador(&self, account_id: AccountId) -> Result<(), String>{
+            if !self.moderadores.get(account_id).unwrap_or(false){
+                return Err("El usuario no es moderador.".to_string())
+            }
+            Ok(())
+        }
+
+        /// La función se encarga de comprobar que el usuario recibido por parametro no esté suspendido. 
+        /// 
+        /// Errores posibles: cuando el usuario está suspendido. 
+        fn no_esta_suspendido(&self, account_id: AccountId) -> Result<(), String>{
+            if self.sanciones.contains(ObjetivoModeracion::Usuario(account_id)){
+                return Err("El usuario esta suspendido.".to_string())
+            }
+            Ok(())
+        }
+
+        /// La función se encarga de comprobar que la moderación permita al comprador comprar u ofertar en la publicación. 
+        /// Es usada por las compras directas, las ofertas, las subastas y las suscripciones. 
+        /// 
+        /// Errores posibles: el comprador está suspendido; la publicación fue dada de baja; el vendedor está suspendido; la publicación está en revisión. 
+        fn validar_moderacion_de_compra(&self, account_id: AccountId, publicacion: &Publicacion) -> Result<(), String>{
+            self.no_esta_suspendido(account_id)?;
+            if self.sanciones.contains(ObjetivoModeracion::Publicacion(publicacion.id)){
+                return Err("La publicacion fue dada de baja.".to_string())
+            }
+            if self.sanciones.contains(ObjetivoModeracion::Usuario(publicacion.id_vendedor)){
+                return Err("El vendedor esta suspendido.".to_string())
+            }
+            if !self.esta_visible(publicacion){ //Descartadas las sanciones, solo puede estar oculta por estar en revisión
+                return Err("La publicacion esta en revision.".to_string())
+            }
+            Ok(())
+        }
+
+        /// La función se encarga de registrar la sanción de un moderador sobre un objetivo. 
+        /// 
+        /// Errores posibles: el usuario no es moderador; el objetivo ya tiene una sanción vigente. 
+        fn sancionar(&mut self, account_id: AccountId, objetivo: ObjetivoModeracion, motivo: MotivoModeracion, ahora: Timestamp) -> Result<(), String>{
+            self.es_moderador(account_id)?;
+            if self.sanciones.contains(&objetivo){
+                return Err("El objetivo ya tiene una sancion vigente.".to_string())
+            }
+            self.sanciones.insert(&objetivo, &Sancion{ moderador: account_id, motivo, fecha: ahora });
+            Ok(())
+        }
+
+        /// La función se encarga de devolver todas las apelaciones (pendientes o resueltas) de un objetivo. 
+        fn apelaciones_de(&self, objetivo: &ObjetivoModeracion) -> Vec<Apelacion>{
+            let mut apelaciones = Vec::new();
+            for i in 0..self.apelaciones.len() {
+                if let Some(apelacion) = self.apelaciones.get(i) {
+                    if apelacion.objetivo == *objetivo {
+                        apelaciones.push(apelacion);
+                    }
+                }
+            }
+            apelaciones
+        }
+
+        /// La función se encarga de determinar si un objetivo está oculto: porque tiene una sanción vigente o porque está en revisión por denuncias. 
+        fn esta_oculto(&self, objetivo: &ObjetivoModeracion) -> bool{
+            self.sanciones.contains(objetivo) | self.en_revision.contains(objetivo)
+        }
+
+        /// La función se encarga de determinar si una publicación puede mostrarse: ni ella ni su vendedor están ocultos. 
+        fn esta_visible(&self, publicacion: &Publicacion) -> bool{
+            !self.esta_oculto(&ObjetivoModeracion::Publicacion(publicacion.id)) 
+                & !self.esta_oculto(&ObjetivoModeracion::Usuario(publicacion.id_vendedor))
+        }
+
+        /// La función se encarga de determinar si una publicación puede comprarse: está disponible y es visible. 
+        fn esta_disponible(&self, publicacion: &Publicacion) -> bool{
+            publicacion.disponible & self.esta_visible(publicacion)
+        }
+
+        /// La función se encarga de comprobar si una publicación cumple con todos los criterios del filtro recibido. 
+        fn cumple_filtro(&self, publicacion: &Publicacion, filtro: &FiltroPublicaciones, ahora: Timestamp) -> bool{
+            if let Some(categoria) = filtro.categoria {
+                let categorias = self.priv_get_categorias();
+                let incluye_categoria = publicacion.productos.iter().any(|(id, _cantidad)| {
+                    self.historial_productos.get(id).is_some_and(|(producto, _stock)| Categoria::es_subcategoria(&categorias, producto.categoria, categoria))
+                });
+                if !incluye_categoria {
+                    return false
+                }
+            }
+            if let Some(precio_maximo) = filtro.precio_maximo {
+                if publicacion.precio_vigente(ahora) > precio_maximo {
+                    return false
+                }
+            }
+            if filtro.solo_vendedores_verificados & self.verificaciones_vigentes(publicacion.id_vendedor, ahora).is_empty() {
+                return false
+            }
+            true
+        }
+
+        /// La función se encarga de comprobar que el usuario recibido por parametro sea el owner del sistema. 
+        /// 
+        /// Errores posibles: cuando el usuario no es el owner. 
+        fn es_owner(&self, account_id: AccountId) -> Result<(), String>{
+            if account_id != self.owner{
+                return Err("Solo el owner puede realizar esta accion.".to_string())
+            }
+            Ok(())
+        }
+
+        /// La función se encarga de comprobar que el pago en moneda nativa recibido sea igual al precio de compra de la publicación 
+        /// (con la promoción vigente y el cupón aplicados). Si la publicación se paga con un token PSP22, no se debe recibir moneda nativa. 
+        /// 
+        /// Errores posibles: la publicación no se encuentra en mi sistema; el cupón no es válido; el pago no coincide con el precio de compra. 
+        fn verificar_pago(&self, id_publicacion: u32, pago: Balance, cupon: &Option<String>, puntos: u32, ahora: Timestamp) -> Result<(), String>{
+            let publicacion = self.buscar_publicacion(id_publicacion)?;
+            let precio = self.calcular_precio_de_compra(id_publicacion, cupon, puntos, ahora)?;
+            if publicacion.token_de_pago.is_some(){
+                if pago != 0 {
+                    return Err("La publicacion se paga con un token PSP22, no con moneda nativa.".to_string())
+                }
+            }
+            else if pago != Balance::from(precio){
+                return Err("El pago no coincide con el precio de la publicacion.".to_string())
+            }
+            Ok(())
+        }
+
+        /// La función se encarga de calcular el precio que paga un comprador por una publicación en un momento dado: 
+        /// el precio de la promoción vigente (o el precio final), menos el descuento del cupón del vendedor si se envía un código, 
+        /// menos el descuento de los puntos de fidelidad canjeados. 
+        /// 
+        /// Errores posibles: la publicación no se encuentra en mi sistema; el cupón no existe, está vencido, no tiene usos o su descuento supera el precio; 
+        /// se canjean puntos en una publicación en token PSP22; el descuento de los puntos no deja un precio mayor a 0. 
+        fn calcular_precio_de_compra(&self, id_publicacion: u32, cupon: &Option<String>, puntos: u32, ahora: Timestamp) -> Result<u32, String>{
+            let publicacion = self.buscar_publicacion(id_publicacion)?;
+            let precio = publicacion.precio_vigente(ahora);
+            let precio = match cupon {
+                Some(codigo) => {
+                    let cupon = self.cupones.get((publicacion.id_vendedor, codigo.clone())).ok_or("No se encontro el cupon.")?;
+                    cupon.aplicar(precio, ahora)?
+                }
+                None => precio,
+            };
+            if puntos > 0 && publicacion.token_de_pago.is_some() {
+                return Err("Los puntos solo se canjean en publicaciones en moneda nativa.".to_string())
+            }
+            let descuento = puntos.checked_mul(VALOR_DEL_PUNTO).ok_or("Error al multiplicar.")?;
+            match precio.checked_sub(descuento) {
+                Some(precio_final) if precio_final > 0 => Ok(precio_final),
+                _ => Err("Los puntos canjeados superan el precio de la compra.".to_string()),
+            }
+        }
+
+        /// La función se encarga de sumar puntos de fidelidad a un usuario. 
+        /// 
+        /// Errores posibles: overflow en la suma. 
+        fn acreditar_puntos(&mut self, account_id: AccountId, puntos: u32) -> Result<(), String>{
+            if puntos == 0 {
+                return Ok(());
+            }
+            let saldo = self.puntos_fidelidad.get(account_id).unwrap_or(0).checked_add(puntos).ok_or("Error al sumar.")?;
+            self.puntos_fidelidad.insert(account_id, &saldo);
+            Ok(())
+        }
+
+        /// La función se encarga de quitar puntos de fidelidad a un usuario, hasta dejar su saldo en cero. 
+        fn quitar_puntos(&mut self, account_id: AccountId, puntos: u32){
+            let saldo = self.puntos_fidelidad.get(account_id).unwrap_or(0).saturating_sub(puntos);
+            self.puntos_fidelidad.insert(account_id, &saldo);
+        }
+
+        /// La función se encarga de descontar un uso del cupón de un vendedor. 
+        /// 
+        /// Errores posibles: el cupón no existe; overflow en la resta. 
+        fn consumir_cupon(&mut self, id_vendedor: AccountId, codigo: String) -> Result<(), String>{
+            let mut cupon = self.cupones.get((id_vendedor, codigo.clone())).ok_or("No se encontro el cupon.")?;
+            cupon.usos_restantes = cupon.usos_restantes.checked_sub(1).ok_or("Error al restar.")?;
+            self.cupones.insert((id_vendedor, codigo), &cupon);
+            Ok(())
+        }
+
+        /// La función se encarga de cobrar al comprador un monto en el token PSP22 recibido por parametro, 
+        /// trayendo los tokens al contrato (transfer_from) donde quedan retenidos. Si el token es None (moneda nativa), no hace nada. 
+        /// 
+        /// Errores posibles: el comprador no tiene saldo o no autorizó al contrato; falla la llamada al token. 
+        fn cobrar_pago_token(&self, id_comprador: AccountId, token_de_pago: Option<AccountId>, monto: Balance) -> Result<(), String>{
+            if let Some(token) = token_de_pago{
+                let contrato = self.env().account_id();
+                self.psp22_transfer_from(token, id_comprador, contrato, monto)?;
+            }
+            Ok(())
+        }
+
+        /// La función se encarga de crear y registrar la orden de compra de una subasta adjudicada, al precio final de la publicación recibida. 
+        /// El pago ya se encuentra retenido y las unidades ya fueron descontadas del stock al crear la subasta. 
+        /// 
+        /// Errores posibles: el ganador no se encuentra en mi sistema o no tiene rol comprador. 
+        fn registrar_orden_de_compra(&mut self, id_comprador: AccountId, publicacion: Publicacion) -> Result<(), String>{
+            let mut usuario = self.buscar_usuario(id_comprador)?;
+            let id_orden = self.historial_ordenes_de_compra.len();
+            let orden_de_compra = usuario.crear_orden_de_compra(id_orden, publicacion, id_comprador)?;
+            self.historial_ordenes_de_compra.push(&(id_orden, orden_de_compra.clone()));
+            self.usuarios.insert(id_comprador, &usuario);
+            self.notificar_orden_actualizada(orden_de_compra, None);
+            Ok(())
+        }
+
+        /// La función se encarga de devolver una Suscripcion en base a un ID recibido por parametro. 
+        /// 
+        /// Errores posibles: cuando no se encuentra el ID de la suscripción en mi sistema (suscripciones). 
+        fn buscar_suscripcion(&self, id_suscripcion: u32) -> Result<Suscripcion, String>{
+            self.suscripciones.get(id_suscripcion).ok_or("No se encontro la suscripcion.".to_string())
+        }
+
+        /// La función se encarga de comprobar, con las mismas validaciones que "crear_orden", si ahora se puede crear la orden de una suscripción: 
+        /// la publicación sigue disponible (con stock), no es una subasta y su vendedor sigue siendo vendedor; 
+        /// la moderación lo permite (ni el comprador, ni la publicación, ni su vendedor están sancionados o en revisión); 
+        /// y el comprador sigue siendo comprador y no es el vendedor. 
+        fn se_puede_ejecutar(&self, suscripcion: &Suscripcion) -> bool{
+            let (Ok(publicacion), Ok(comprador)) = (self.buscar_publicacion(suscripcion.id_publicacion), self.buscar_usuario(suscripcion.comprador)) else {
+                return false;
+            };
+            let vendedor_habilitado = self.buscar_usuario(publicacion.id_vendedor).is_ok_and(|vendedor| vendedor.rol != Rol::Comp);
+            let comprador_habilitado = comprador.es_comprador_ambos().is_ok() & (suscripcion.comprador != publicacion.id_vendedor);
+            publicacion.disponible & publicacion.subasta.is_none() & vendedor_habilitado & comprador_habilitado
+                & self.validar_moderacion_de_compra(suscripcion.comprador, &publicacion).is_ok()
+        }
+
+        /// La función se encarga de devolver una Oferta en base a un ID recibido por parametro. 
+        /// 
+        /// Errores posibles: cuando no se encuentra el ID de la oferta en mi sistema (ofertas). 
+        fn buscar_oferta(&self, id_oferta: u32) -> Result<Oferta, String>{
+            self.ofertas.get(id_oferta).ok_or("No se encontro la oferta.".to_string())
+        }
+
+        /// La función se encarga de cerrar una oferta no aceptada con el estado recibido, acreditando al comprador el monto retenido. 
+        /// 
+        /// Errores posibles: la oferta ya fue resuelta; overflow en la suma. 
+        fn cerrar_oferta(&mut self, mut oferta: Oferta, estado: EstadoOferta) -> Result<(), String>{
+            if !oferta.esta_abierta() {
+                return Err("La oferta ya fue resuelta.".to_string())
+            }
+            self.acreditar_fondos(oferta.comprador, oferta.token_de_pago, Balance::from(oferta.precio))?;
+            oferta.estado = estado;
+            self.ofertas.set(oferta.id, &oferta);
+            Ok(())
+        }
+
+        /// La función se encarga de sumar un monto a los fondos que un usuario puede retirar (en el token indicado, o None para moneda nativa). 
+        /// 
+        /// Errores posibles: overflow en la suma. 
+        fn acreditar_fondos(&mut self, id_usuario: AccountId, token: Option<AccountId>, monto: Balance) -> Result<(), String>{
+            let acumulado = self.fondos_a_retirar.get((id_usuario, token)).unwrap_or(0);
+            self.fondos_a_retirar.insert((id_usuario, token), &acumulado.checked_add(monto).ok_or("Error al sumar.")?);
+            Ok(())
+        }
+
+        /// La función se encarga de liberar el pago retenido de una orden: descuenta la comisión vigente, la suma a la tesorería,
+        /// la registra en la orden y transfiere el resto al vendedor. 
+        /// La venta se liquida al precio pagado, por lo que el descuento de los puntos canjeados lo absorbe el vendedor. 
+        /// 
+        /// Errores posibles: overflow en las operaciones; falla la transferencia. 
+        fn liberar_pago(&mut self, orden: &mut OrdenCompra) -> Result<(), String>{
+            let precio = Balance::from(orden.info_publicacion.2);
+            let comision = self.calcular_comision(precio)?;
+            let pago_vendedor = precio.checked_sub(comision).ok_or("Error al restar.")?;
+            if let Some(token) = orden.token_de_pago{
+                let acumulado = self.tesoreria_tokens.get(token).unwrap_or(0);
+                self.tesoreria_tokens.insert(token, &acumulado.checked_add(comision).ok_or("Error al sumar.")?);
+            }
+            else {
+                self.tesoreria = self.tesoreria.checked_add(comision).ok_or("Error al sumar.")?;
+            }
+            orden.comision = comision;
+            self.transferir(orden.token_de_pago, orden.info_publicacion.3, pago_vendedor)
+        }
+
+        /// La función se encarga de quitar de la tesorería (nativa o del token de la orden) la comisión cobrada en una orden, 
+        /// que queda retenida en el contrato para reintegrarla al comprador. 
+        /// 
+        /// Errores posibles: la tesorería ya no tiene la comisión (el owner la retiró). 
+        fn descontar_comision_de_tesoreria(&mut self, orden: &OrdenCompra) -> Result<(), String>{
+            let error = "La tesoreria no alcanza para reintegrar la comision.";
+            if let Some(token) = orden.token_de_pago{
+                let acumulado = self.tesoreria_tokens.get(token).unwrap_or(0).checked_sub(orden.comision).ok_or(error)?;
+                self.tesoreria_tokens.insert(token, &acumulado);
+            }
+            else {
+                self.tesoreria = self.tesoreria.checked_sub(orden.comision).ok_or(error)?;
+            }
+            Ok(())
+        }
+
+        /// La función se encarga de calcular la comisión correspondiente a un monto, según los puntos básicos configurados. 
+        /// 
+        /// Errores posibles: overflow en la multiplicación. 
+        fn calcular_comision(&self, monto: Balance) -> Result<Balance, String>{
+            monto.checked_mul(Balance::from(self.comision_bps)).ok_or("Error al multiplicar.")?
+                .checked_div(10000).ok_or("Error al dividir.".to_string())
+        }
+
+        /// La función se encarga de transferir fondos del contrato a la cuenta recibida por parametro. 
+        /// Si token es None se transfiere moneda nativa, si no, se transfiere el token PSP22 indicado. 
+        /// 
+        /// Errores posibles: la transferencia falla (por ejemplo, por fondos insuficientes). 
+        fn transferir(&self, token: Option<AccountId>, destino: AccountId, monto: Balance) -> Result<(), String>{
+            if monto == 0 {
+                return Ok(());
+            }
+            match token {
+                Some(token) => self.psp22_transfer(token, destino, monto),
+                None => self.transferir_nativo(destino, monto),
+            }
+        }
+
+        fn transferir_nativo(&self, destino: AccountId, monto: Balance) -> Result<(), String>{
+            self.env().transfer(destino, monto).map_err(|_| "Error al transferir los fondos.".to_string())
+        }
+
+        /// La función se encarga de llamar al mensaje "PSP22::transfer" del token recibido por parametro. 
+        /// 
+        /// Errores posibles: falla la llamada; el token rechaza la transferencia. 
+        #[cfg(not(test))]
+        fn psp22_transfer(&self, token: AccountId, destino: AccountId, monto: Balance) -> Result<(), String>{
+            use ink::env::call::{build_call, ExecutionInput, Selector};
+            build_call::<Environment>()
+                .call(token)
+                .exec_input(ExecutionInput::new(Selector::new(ink::selector_bytes!("PSP22::transfer"))).push_arg(destino).push_arg(monto).push_arg(Vec::<u8>::new()))
+                .returns::<Result<(), Psp22Error>>()
+                .try_invoke()
+                .map_err(|_| "Error al llamar al token PSP22.".to_string())?
+                .map_err(|_| "Error al llamar al token PSP22.".to_string())?
+                .map_err(|error| error.a_mensaje())
+        }
+        #[cfg(test)]
+        fn psp22_transfer(&self, token: AccountId, destino: AccountId, monto: Balance) -> Result<(), String>{
+            mock_psp22::transfer(token, self.env().account_id(), destino, monto).map_err(|error| error.a_mensaje())
+        }
+
+        /// La función se encarga de llamar al mensaje "PSP22::transfer_from" del token recibido por parametro. 
+        /// El contrato debe estar autorizado (approve) por el dueño de los tokens. 
+        /// 
+        /// Errores posibles: falla la llamada; el token rechaza la transferencia. 
+        #[cfg(not(test))]
+        fn psp22_transfer_from(&self, token: AccountId, origen: AccountId, destino: AccountId, monto: Balance) -> Result<(), String>{
+            use ink::env::call::{build_call, ExecutionInput, Selector};
+            build_call::<Environment>()
+                .call(token)
+                .exec_input(ExecutionInput::new(Selector::new(ink::selector_bytes!("PSP22::transfer_from"))).push_arg(origen).push_arg(destino).push_arg(monto).push_arg(Vec::<u8>::new()))
+                .returns::<Result<(), Psp22Error>>()
+                .try_invoke()
+                .map_err(|_| "Error al llamar al token PSP22.".to_string())?
+                .map_err(|_| "Error al llamar al token PSP22.".to_string())?
+                .map_err(|error| error.a_mensaje())
+        }
+        #[cfg(test)]
+        fn psp22_transfer_from(&self, token: AccountId, origen: AccountId, destino: AccountId, monto: Balance) -> Result<(), String>{
+            mock_psp22::transfer_from(token, self.env().account_id(), origen, destino, monto).map_err(|error| error.a_mensaje())
+        }
+
+        /// La función se encarga de llamar al mensaje "acunar_recibo" del contrato de recibos, que acuña el recibo para el destinatario. 
+        /// 
+        /// Errores posibles: falla la llamada; el contrato de recibos rechaza el recibo. 
+        #[cfg(not(test))]
+        fn acunar_recibo(&self, contrato: AccountId, destinatario: AccountId, recibo: Recibo) -> Result<(), String>{
+            use ink::env::call::{build_call, ExecutionInput, Selector};
+            build_call::<Environment>()
+                .call(contrato)
+                .exec_input(ExecutionInput::new(Selector::new(ink::selector_bytes!("acunar_recibo"))).push_arg(destinatario).push_arg(recibo))
+                .returns::<Result<(), String>>()
+                .try_invoke()
+                .map_err(|_| "Error al llamar al contrato de recibos.".to_string())?
+                .map_err(|_| "Error al llamar al contrato de recibos.".to_string())?
+        }
+        #[cfg(test)]
+        fn acunar_recibo(&self, contrato: AccountId, destinatario: AccountId, recibo: Recibo) -> Result<(), String>{
+            mock_psp34::acunar(contrato, destinatario, recibo.id_orden).map_err(|_| "El contrato de recibos rechazo el recibo.".to_string())
+        }
+
+        /// La función se encarga de llamar al mensaje "PSP34::owner_of" del contrato de recibos. 
+        /// 
+        /// Errores posibles: falla la llamada. 
+        #[cfg(not(test))]
+        fn psp34_owner_of(&self, contrato: AccountId, id_token: u32) -> Result<Option<AccountId>, String>{
+            use ink::env::call::{build_call, ExecutionInput, Selector};
+            build_call::<Environment>()
+                .call(contrato)
+                .exec_input(ExecutionInput::new(Selector::new(ink::selector_bytes!("PSP34::owner_of"))).push_arg(Id::U32(id_token)))
+                .returns::<Option<AccountId>>()
+                .try_invoke()
+                .map_err(|_| "Error al llamar al contrato de recibos.".to_string())?
+                .map_err(|_| "Error al llamar al contrato de recibos.".to_string())
+        }
+        #[cfg(test)]
+        fn psp34_owner_of(&self, contrato: AccountId, id_token: u32) -> Result<Option<AccountId>, String>{
+            Ok(mock_psp34::owner_of(contrato, id_token))
+        }
+
+        /// La función se encarga de devolver los datos de los productos incluidos en una orden de compra. 
+        /// Los productos que no se encuentran en mi sistema (historial_productos) se omiten. 
+        fn productos_de_la_orden(&self, orden: &OrdenCompra) -> Vec<Producto>{
+            let mut productos = Vec::new();
+            for (id, _cantidad) in orden.info_publicacion.1.iter(){
+                if let Some((producto, _stock)) = self.historial_productos.get(id){
+                    productos.push(producto);
+                }
+            }
+            productos
+        }
+
+        /// La función se encarga de devolver los ids de las categorias (sin repetir) de los productos incluidos en una orden de compra. 
+        fn categorias_de_la_orden(&self, orden: &OrdenCompra) -> Vec<u32>{
+            let mut categorias: Vec<u32> = Vec::new();
+            for producto in self.productos_de_la_orden(orden){
+                if !categorias.contains(&producto.categoria){
+                    categorias.push(producto.categoria);
+                }
+            }
+            categorias
+        }
+
+        /// La función se encarga de notificar al contrato de reportes (si hay uno registrado) que una orden cambió de estado. 
+        /// estado_anterior es None cuando la orden se acaba de crear. 
+        fn notificar_orden_actualizada(&self, orden: OrdenCompra, estado_anterior: Option<EstadoCompra>){
+            if self.contrato_reportes.is_none(){
+                return;
+            }
+            let productos = self.productos_de_la_orden(&orden);
+            self.notificar_reportes(EventoMarketplace::OrdenActualizada{ orden: Box::new(orden), estado_anterior, productos })
+        }
+
+        /// La función se encarga de notificar al contrato de reportes (si hay uno registrado) una calificación realizada. 
+        /// calificado es el usuario que recibió la calificación, y rol_calificado el rol que tuvo en la orden. 
+        fn notificar_calificacion(&self, orden: OrdenCompra, calificado: AccountId, rol_calificado: Rol, puntuacion: u8){
+            if self.contrato_reportes.is_none(){
+                return;
+            }
+            let productos = self.productos_de_la_orden(&orden);
+            self.notificar_reportes(EventoMarketplace::Calificacion{ orden: Box::new(orden), calificado, rol_calificado, puntuacion, productos })
+        }
+
+        /// La función se encarga de enviar un evento al contrato de reportes. 
+        /// La notificación no bloquea la operación del marketplace que la originó: si falla, se emite el evento ReporteNoNotificado. 
+        fn notificar_reportes(&self, evento: EventoMarketplace){
+            let id_orden = match &evento {
+                EventoMarketplace::OrdenActualizada{ orden, .. } | EventoMarketplace::Calificacion{ orden, .. } => orden.id,
+            };
+            if let Err(motivo) = self.enviar_evento_a_reportes(evento) {
+                self.env().emit_event(ReporteNoNotificado{ id_orden, motivo });
+            }
+        }
+
+        /// La función se encarga de llamar al mensaje "registrar_evento" del contrato de reportes. 
+        /// 
+        /// Errores posibles: no hay contrato de reportes registrado; falla la llamada; el contrato de reportes rechaza el evento. 
+        #[cfg(not(test))]
+        fn enviar_evento_a_reportes(&self, evento: EventoMarketplace) -> Result<(), String>{
+            use ink::env::call::{build_call, ExecutionInput, Selector};
+            let direccion = self.contrato_reportes.ok_or("No hay contrato de reportes registrado.")?;
+            build_call::<Environment>()
+                .call(direccion)
+                .exec_input(ExecutionInput::new(Selector::new(ink::selector_bytes!("registrar_evento"))).push_arg(evento))
+                .returns::<Result<(), String>>()
+                .try_invoke()
+                .map_err(|_| "Error al notificar al contrato de reportes.".to_string())?
+                .map_err(|_| "El contrato de reportes no pudo procesar el evento.".to_string())?
+        }
+        /// En los tests no hay contratos desplegados, por lo que la llamada al contrato de reportes siempre falla. 
+        #[cfg(test)]
+        fn enviar_evento_a_reportes(&self, _evento: EventoMarketplace) -> Result<(), String>{
+            Err("Error al notificar al contrato de reportes.".to_string())
+        }
+
+        /// La función se encarga de pisar un valor de mi Mapping "usuarios".  
+        fn actualizar_usuarios(&mut self, usuario: Usuario){
+            self.usuarios.insert(usuario.id_usuario, &usuario);
+        }
+
+        /// La función se encarga de devolver el Usuario correspondiente al ID recibido por parametro. 
+        /// 
+        /// Errores posibles: no se halla el ID en mi sistema (usuarios). 
+        fn buscar_usuario(&self, id_usuario: AccountId) -> Result<Usuario, String>{
+            if let Some(ref usuario) = self.usuarios.get(id_usuario){
+                Ok(usuario.clone())
+            }
+            else {
+                return Err("No se encontro el usuario.".to_string())
+            }
+        }
+
+        /// Funcion que retorna el id del usuario en una posicion dada del vector vector_ids_usuarios
+        ///
+        /// Errores posibles: El id no se encuentra cargado en el sistema
+        fn buscar_id_usuario(&self, pos: u32) -> Result<AccountId, String>{
+            if let Some(id) = self.vector_ids_usuarios.get(pos){
+                return Ok(id)
+            } else{
+                return Err("No se encontro el id.".to_string())
+            }
+        }
+
+        //Getters para el segundo contrato
+        
+        #[ink(message)]
+        /// Funcion que devuelve un vector con las categorias del sistema, ordenadas por id
+        pub fn get_categorias(&self) -> Vec<Categoria>{
+            self.priv_get_categorias()
+        }
+        fn priv_get_categorias(&self) -> Vec<Categoria>{
+            let mut categorias = Vec::new();
+            for i in 0..self.categorias.len() {
+                if let Some(categoria) = self.categorias.get(i) {
+                    categorias.push(categoria);
+                }
+            }
+            categorias
+        }
+
+        #[ink(message)]
+        /// Funcion que devuelve un vector con los productos en sistema
+        pub fn get_productos(&self) -> Vec<Producto>{
+            self.priv_get_productos()
+        }
+        fn priv_get_productos(&self) -> Vec<Producto>{
+            let mut vec_productos = Vec::new();
+
+            for i in 0..=self.dimension_logica_productos {
+                if let Some((producto, _stock)) = self.historial_productos.get(i) {
+                    vec_productos.push(producto);
+                }
+            }
+            vec_productos
+        }
+
+        #[ink(message)]
+        /// Funcion que devuelve el perfil publico de un usuario: sus datos no sensibles, su reputacion como vendedor
+        /// y sus verificaciones vigentes. No incluye direccion ni email.
+        ///
+        /// Errores posibles: el usuario no existe; overflow en los calculos.
+        pub fn perfil_publico(&self, id_usuario: AccountId) -> Result<PerfilPublico, String>{
+            self.priv_perfil_publico(id_usuario, self.env().block_timestamp())
+        }
+        fn priv_perfil_publico(&self, id_usuario: AccountId, ahora: Timestamp) -> Result<PerfilPublico, String>{
+            let usuario = self.buscar_usuario(id_usuario)?;
+            let reputacion_como_vendedor = match usuario.datos_vendedor {
+                Some(ref datos_vendedor) => datos_vendedor.reputacion_promedio()?,
+                None => 0,
+            };
+            let verificaciones = self.verificaciones_vigentes(id_usuario, ahora);
+            Ok(PerfilPublico{
+                id_usuario,
+                nombre: usuario.nombre,
+                rol: usuario.rol,
+                registrado_en: usuario.registrado_en,
+                reputacion_como_vendedor,
+                verificado: !verificaciones.is_empty(),
+                verificaciones,
+            })
+        }
+
+        #[ink(message)]
+        /// Funcion que devuelve las publicaciones disponibles (y no moderadas) que cumplen con el filtro recibido
+        /// (categoria, precio maximo vigente y/o solo vendedores verificados).
+        pub fn buscar_publicaciones(&self, filtro: FiltroPublicaciones) -> Vec<Publicacion>{
+            self.priv_buscar_publicaciones(filtro, self.env().block_timestamp())
+        }
+        fn priv_buscar_publicaciones(&self, filtro: FiltroPublicaciones, ahora: Timestamp) -> Vec<Publicacion>{
+            let mut publicaciones = Vec::new();
+            for i in 0..self.historial_publicaciones.len() {
+                if let Some((_id, publicacion)) = self.historial_publicaciones.get(i) {
+                    if publicacion.disponible & self.esta_visible(&publicacion) & self.cumple_filtro(&publicacion, &filtro, ahora) {
+                        publicaciones.push(publicacion);
+                    }
+                }
+            }
+            publicaciones
+        }
+
+        #[ink(message)]
+        /// Funcion que devuelve la garantia de un recibo de compra (por su id de token, que es el id de la orden): 
+        /// el dueño actual del recibo segun el contrato de recibos, y los datos de la compra (orden, productos, precio, vendedor y fecha de recepcion).
+        ///
+        /// Errores posibles: no hay contrato de recibos registrado; la orden no existe o no fue recibida; la orden fue devuelta;
+        /// el recibo no existe en el contrato de recibos; falla la llamada al contrato de recibos.
+        pub fn garantia(&self, id_token: u32) -> Result<(AccountId, Recibo), String>{
+            let direccion = self.contrato_recibos.ok_or("No hay contrato de recibos registrado.")?;
+            let orden = self.buscar_orden(id_token)?;
+            if orden.estado == EstadoCompra::Devuelto {
+                return Err("La orden fue devuelta.".to_string())
+            }
+            let recibido_en = orden.recibido_en.ok_or("La compra aun no fue recibida.")?;
+            let dueno = self.psp34_owner_of(direccion, id_token)?.ok_or("El recibo no existe.")?;
+            Ok((dueno, Recibo::de_orden(&orden, recibido_en)))
+        }
+
+        #[ink(message)]
+        /// Funcion que devuelve el saldo de puntos de fidelidad de un usuario (cero si nunca gano puntos).
+        pub fn puntos_de_fidelidad(&self, id_usuario: AccountId) -> u32{
+            self.puntos_fidelidad.get(id_usuario).unwrap_or(0)
+        }
+
+        #[ink(message)]
+        /// Funcion que devuelve la sancion vigente sobre un objetivo (None si no tiene) y todas sus apelaciones, pendientes o resueltas.
+        pub fn estado_moderacion(&self, objetivo: ObjetivoModeracion) -> (Option<Sancion>, Vec<Apelacion>){
+            (self.sanciones.get(&objetivo), self.apelaciones_de(&objetivo))
+        }
+
+        #[ink(message)]
+        /// Funcion que devuelve la reputacion de un vendedor en centesimas (ej: 433 = 4,33), en formato (promedio simple, promedio ponderado).
+        /// El promedio ponderado pesa cada calificacion segun el monto de la orden y la antiguedad de la calificacion (ver reputacion_ponderada).
+        ///
+        /// Errores posibles: el usuario no existe o no tiene datos de vendedor; overflow en los calculos.
+        pub fn reputacion_vendedor(&self, id_vendedor: AccountId) -> Result<(u32, u32), String>{
+            self.priv_reputacion_vendedor(id_vendedor, self.env().block_timestamp())
+        }
+        fn priv_reputacion_vendedor(&self, id_vendedor: AccountId, ahora: Timestamp) -> Result<(u32, u32), String>{
+            let usuario = self.buscar_usuario(id_vendedor)?;
+            let datos_vendedor = usuario.datos_vendedor.ok_or("El usuario no tiene datos de vendedor.")?;
+            Ok((datos_vendedor.reputacion_promedio()?, datos_vendedor.reputacion_ponderada(ahora)?))
+        }
+
+        #[ink(message)]
+        /// Funcion que devuelve la reputacion promedio de un vendedor en una categoria, en centesimas (ej: 433 = 4,33).
+        /// Solo considera las calificaciones de ordenes que incluian productos de esa categoria. Sin calificaciones devuelve 0.
+        ///
+        /// Errores posibles: el usuario no existe o no tiene datos de vendedor.
+        pub fn reputacion_vendedor_en_categoria(&self, id_vendedor: AccountId, categoria: u32) -> Result<u32, String>{
+            let usuario = self.buscar_usuario(id_vendedor)?;
+            let datos_vendedor = usuario.datos_vendedor.ok_or("El usuario no tiene datos de vendedor.")?;
+            datos_vendedor.reputacion_en_categoria(&categoria)
+        }
+
+        #[ink(message)]
+        /// Funcion que devuelve el desglose de la reputacion ponderada de un vendedor, para poder auditarla.
+        /// Por cada calificacion recibida devuelve (calificacion, peso actual). Las calificaciones entre cuentas relacionadas
+        /// se incluyen igual, marcadas como relacionadas y con su peso reducido.
+        ///
+        /// Errores posibles: el usuario no existe o no tiene datos de vendedor; overflow en los calculos.
+        pub fn desglose_reputacion(&self, id_vendedor: AccountId) -> Result<Vec<(CalificacionPonderada, u128)>, String>{
+            self.priv_desglose_reputacion(id_vendedor, self.env().block_timestamp())
+        }
+        fn priv_desglose_reputacion(&self, id_vendedor: AccountId, ahora: Timestamp) -> Result<Vec<(CalificacionPonderada, u128)>, String>{
+            let usuario = self.buscar_usuario(id_vendedor)?;
+            let datos_vendedor = usuario.datos_vendedor.ok_or("El usuario no tiene datos de vendedor.")?;
+            let mut desglose = Vec::new();
+            for calificacion in datos_vendedor.calificaciones_ponderadas{
+                let peso = calificacion.peso(ahora)?;
+                desglose.push((calificacion, peso));
+            }
+            Ok(desglose)
+        }
+
+        #[ink(message)]
+        /// Funcion que devuelve un vector con los usuarios en sistema
+        pub fn get_usuarios(&self) -> Result<Vec<Usuario>, String>{
+            self.priv_get_usuarios()
+        }
+        fn priv_get_usuarios(&self) -> Result<Vec<Usuario>, String>{
+            let mut vec_usuarios= Vec::new();
+            
+            for i in 0..self.vector_ids_usuarios.len(){
+                vec_usuarios.push(self.buscar_usuario(self.buscar_id_usuario(i)?)?);
+            }
+            return Ok(vec_usuarios);
+        }
+
+        #[ink(message)]
+        ///Funcion que devuelve un vector con las ordenes de compra en sistema.
+        ///
+        /// Errores posibles: No hay ordenes de compra en el sistema.
+        pub fn get_ordenes(&self) -> Result<Vec<OrdenCompra>, String> {
+            self.priv_get_ordenes()
+        }
+        fn priv_get_ordenes(&self) -> Result<Vec<OrdenCompra>, String> {
+            let mut vec_ordenes = Vec::new();
+
+            for i in 0..self.historial_ordenes_de_compra.len() {
+                if let Some((_, orden)) = self.historial_ordenes_de_compra.get(i){
+                    vec_ordenes.push(orden)
+                }
+            }
+            if vec_ordenes.is_empty(){
+                return Err("No hay ordenes de compra cargadas.".to_string());
+            }
+            else {
+                return Ok(vec_ordenes);
+            }
+        }
+
+        #[ink(message)]
+        /// Funcion que devuelve un vector con las ordenes de compra a partir del id recibido (inclusive).
+        /// Permite al segundo contrato pedir solo las ordenes que todavia no proceso.
+        pub fn get_ordenes_desde(&self, desde: u32) -> Vec<OrdenCompra> {
+            self.priv_get_ordenes_desde(desde)
+        }
+        fn priv_get_ordenes_desde(&self, desde: u32) -> Vec<OrdenCompra> {
+            let mut vec_ordenes = Vec::new();
+
+            for i in desde..self.historial_ordenes_de_compra.len() {
+                if let Some((_, orden)) = self.historial_ordenes_de_compra.get(i){
+                    vec_ordenes.push(orden)
+                }
+            }
+            vec_ordenes
+        }
+
+    }
+       
+
+    #[ink(event)]
+    /// Evento emitido cuando no se pudo notificar al contrato de reportes un cambio en una orden (o su calificación). 
+    /// Los contadores del contrato de reportes no incluyen ese cambio hasta que se refresque su snapshot. 
+    pub struct ReporteNoNotificado{
+        #[ink(topic)]
+        pub id_orden: u32,
+        pub motivo: String,
+    }
+
+/////////////////////////// USUARIO ///////////////////////////
+
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std",derive(ink::storage::traits::StorageLayout))]
+    /// Struct que almacena la información de un usuario. 
+    /// id_usuario almacena el id.
+    /// nombre almacena su nombre.
+    /// apellido almacena su apellido.
+    /// dirección almacena su dirección.
+    /// email almacena su email.
+    /// rol almacena el rol que tiene el usuario. Éste puede ser: Comp (comprador), Vend (vendedor), Ambos. 
+    /// registrado_en almacena el timestamp en que el usuario se registró en el sistema (para calcular la antigüedad de la cuenta). 
+    /// datos_comprador almacena toda la información correspondiente al rol comprador. El Option será Some cuando éste posea el rol Comp u Ambos. Si en algún momento deja de serlo, el Option seguirá en Some con toda la información.  
+    /// datos_vendedor almacena toda la información correspondiente al rol vendedor. El Option será Some cuando éste posea el rol Vend u Ambos. Si en algún momento deja de serlo, el Option seguirá en Some con toda la información.  
+    #[derive(Clone)]
+    pub struct Usuario{
+        pub id_usuario: AccountId,
+        pub nombre: String,
+        pub apellido: String,
+        pub direccion: String,
+        pub email: String,
+        pub rol: Rol,
+        pub registrado_en: Timestamp,
+        pub datos_comprador: Option<Comprador>,
+        pub datos_vendedor: Option<Vendedor>,
+    }
+    impl Usuario {
+
+        fn es_vendedor_ambos(&self) -> Result<(), String>{
+            if self.rol == Rol::Comp{
+                return(Err("El usuario es comprador.".to_string()))
+            }
+            Ok(())
+        }
+
+        fn es_comprador_ambos(&self) -> Result<(), String>{
+            if self.rol == Rol::Vend{
+                return(Err("El usuario es vendedor.".to_string()))
+            }
+            Ok(())
+        }
+
+        fn lista_de_productos(&self) -> Result<Vec<u32>, String>{
+            if let Some(ref datos_vendedor) = self.datos_vendedor{
+                if datos_vendedor.productos.is_empty(){
+                    return Err("El vendedor no tiene productos.".to_string())
+                }
+                Ok(datos_vendedor.productos.clone())
+            }
+            else{
+                return Err("El vendedor no tiene datos.".to_string())
+            }
+        }
+
+        fn verificar_propiedad_producto(&self, id_producto: u32) -> Result<(), String>{
+            if let Some(ref datos_vendedor) = self.datos_vendedor{
+                if datos_vendedor.productos.contains(&id_producto){
+                    return Ok(())
+                }
+                return Err("El vendedor no posee ese producto.".to_string())
+            }
+            else{
+                return Err("El vendedor no tiene datos.".to_string())
+            }
+        }
+
+        fn cargar_producto(&mut self, id_producto: u32) -> Result<(), String>{
+            if let Some(ref mut datos_vendedor) = self.datos_vendedor{
+                datos_vendedor.cargar_producto(id_producto);
+                return Ok(())
+            }
+            else {
+                return Err("El vendedor no tiene datos.".to_string())
+            }
+        }
+        
+        pub fn nuevo(id: AccountId,nombre: String,apellido: String,direccion: String,email: String,rol: Rol,registrado_en: Timestamp) -> Usuario {
+            Usuario {
+                id_usuario: id,
+                nombre,
+                apellido,
+                direccion,
+                email,
+                rol: rol.clone(),
+                registrado_en,
+                datos_comprador: match rol {
+                    Rol::Comp | Rol::Ambos => Some(Comprador { 
+                        ordenes_de_compra: Vec::new(),
+                        reputacion_como_comprador: Vec::new(),
+                    }),
+                    _ => None,
+                },
+                datos_vendedor: match rol {
+                    Rol::Vend | Rol::Ambos => Some(Vendedor {
+                        productos: Vec::new(),
+                        publicaciones: Vec::new(),
+                        reputacion_como_vendedor: Vec::new(),
+                        calificaciones_ponderadas: Vec::new(),
+                        reputacion_por_categoria: Vec::new(),
+                    }),
+                    _ => None,
+                },
+            }
+        }
+
+        fn crear_publicacion(&mut self, productos_a_publicar: Vec<(u32, u32)>, precio_final: u32, id_publicacion: u32, id_vendedor: AccountId, token_de_pago: Option<AccountId>) -> Result<Publicacion, String>{  //productos_a_publicar = Vec<(id, cantidad)>
+            if self.rol == Rol::Comp {
+                Err("El usuario no es vendedor.".to_string())
+            }
+            else {
+                Ok(self.datos_vendedor.as_mut().expect("Error con los datos del vendedor.").crear_publicacion(productos_a_publicar, precio_final, id_publicacion, id_vendedor, token_de_pago))
+            } 
+        }
+
+        fn modificar_rol(&mut self, nuevo_rol: Rol) -> Result<(), String>{
+            if nuevo_rol == self.rol {
+                Err("El usuario ya posee ese rol.".to_string())
+            }
+            else {
+                self.rol = nuevo_rol.clone();
+                match nuevo_rol{
+                    Rol::Comp => {
+                        if self.datos_comprador.is_none(){
+                            let ordenes_de_compra = Vec::new();
+                            let reputacion_como_comprador = Vec::new();
+                            self.datos_comprador = Some(Comprador{
+                                ordenes_de_compra,
+                                reputacion_como_comprador,
+                            });
+                        }
+                    }
+
+                    Rol::Vend => {
+                        if self.datos_vendedor.is_none(){ 
+                            let productos = Vec::new();
+                            let publicaciones = Vec::new();
+                            let reputacion_como_vendedor = Vec::new();
+                            self.datos_vendedor = Some(Vendedor{
+                                productos,
+                                publicaciones,
+                                reputacion_como_vendedor,
+                                calificaciones_ponderadas: Vec::new(),
+                                reputacion_por_categoria: Vec::new(),
+                            });
+                        }
+                    }
+
+                    Rol::Ambos => {
+                        if self.datos_comprador.is_none(){
+                            let ordenes_de_compra = Vec::new();
+                            let reputacion_como_comprador = Vec::new();
+                            self.datos_comprador = Some(Comprador{
+                                ordenes_de_compra,
+                                reputacion_como_comprador,
+                            });
+                        }
+                        if self.datos_vendedor.is_none(){
+                            let productos = Vec::new();
+                            let publicaciones = Vec::new();
+                            let reputacion_como_vendedor = Vec::new();
+                            self.datos_vendedor = Some(Vendedor{
+                                productos,
+                                publicaciones,
+                                reputacion_como_vendedor,
+                                calificaciones_ponderadas: Vec::new(),
+                                reputacion_por_categoria: Vec::new(),
+                            });
+                        }
+                    }
+                }
+                Ok(())
+            }
+        }
+        
+        fn crear_orden_de_compra(&mut self, id_orden: u32, publicacion: Publicacion, id_comprador: AccountId) -> Result<OrdenCompra, String>{
+            if self.rol == Rol::Vend{
+                Err("El usuario no esta autorizado para realizar una compra. ERROR: No posee el rol comprador.".to_string())
+            }
+            else{
+                Ok(self.datos_comprador.as_mut().expect("No hay datos del comprador.").crear_orden_de_compra(id_orden, publicacion, id_comprador))
+            }
+        }
+
+        fn enviar_compra(&self, id_publicacion: u32) -> Result<(), String>{
+            if self.rol == Rol::Comp{
+                Err("El usuario no posee el rol de vendedor.".to_string())
+            }
+            else{
+                self.datos_vendedor.as_ref().expect("No hay datos del vendedor.").enviar_compra(id_publicacion)
+            }
+        }
+
+        fn recibir_compra(&self, id_orden: u32) -> Result<(), String>{
+            if self.rol == Rol::Vend{
+                Err("El usuario no posee el rol de comprador.".to_string())
+            }
+            else{
+                self.datos_comprador.as_ref().expect("No hay datos del comprador.").recibir_compra(id_orden)
+            }
+        }
+
+        fn comprobar_rol(&self, id_vendedor: AccountId, id_comprador: AccountId) -> Result<Rol, String>{
+            match self.rol{
+                Rol::Vend => {
+                    if let Some(ref _datos_vendedor) = self.datos_vendedor{
+                        if self.es_el_vendedor(id_vendedor){
+                            Ok(Rol::Vend)
+                        }
+                        else {
+                            Err("El vendedor no posee esta publicacion.".to_string())
+                        }
+                    }
+                    else {
+                        Err("El vendedor no tiene datos cargados.".to_string())
+                    }
+                },
+
+                Rol::Comp => {
+                    if let Some(ref _datos_comprador) = self.datos_comprador{
+                        if self.es_el_comprador(id_comprador){
+                            Ok(Rol::Comp)
+                        }
+                        else{
+                            Err("El usuario no posee esa orden de compra.".to_string())
+                        }
+                    }
+                    else {
+                        Err("El comprador no tiene datos cargados.".to_string())
+                    }
+                },
+
+                Rol::Ambos => {
+                    if self.es_el_vendedor(id_vendedor) {
+                        Ok(Rol::Vend)
+                    }
+                    else if self.es_el_comprador(id_comprador) {
+                        Ok(Rol::Comp)
+                    }
+                    else {
+                       Err("El usuario no participa de la compra.".to_string())
+                    }
+                },
+            }
+        }
+
+        fn es_el_vendedor(&self, id_vendedor: AccountId) -> bool{
+            id_vendedor == self.id_usuario
+        }
+
+        fn es_el_comprador(&self, id_comprador: AccountId) -> bool{
+            id_comprador == self.id_usuario
+        }
+    }
+
+
+
+/////////////////////////// COMPRADOR ///////////////////////////
+
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std",derive(ink::storage::traits::StorageLayout))]
+    /// Struct donde se encuentran los datos de aquel usuario con rol Comprador. 
+    /// ordenes_de_compra, es un Vec que almacena los IDs de cada orden realizada por el comprador. 
+    /// reputacion_como_comprador, es un Vec que almacena las califaciones recibidas por vendedores. 
+    #[derive(Clone)]
+    pub struct Comprador{
+        pub ordenes_de_compra: Vec<u32>,
+        pub reputacion_como_comprador: Vec<u8>, 
+    }
+    impl Comprador{
+
+        fn crear_orden_de_compra(&mut self, id_orden: u32, publicacion: Publicacion, id_comprador: AccountId) -> OrdenCompra{
+            self.ordenes_de_compra.push(id_orden);
+            OrdenCompra::crear_orden_de_compra(id_orden, publicacion, id_comprador)
+        }
+
+        fn recibir_compra(&self, id_orden: u32) -> Result<(), String>{
+            if self.ordenes_de_compra.contains(&id_orden){
+                Ok(())
+            }
+            else {
+                Err("No se encontro la orden de compra.".to_string())
+            }
+        }
+
+    }
+
+
+/////////////////////////// VENDEDOR ///////////////////////////
+
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std",derive(ink::storage::traits::StorageLayout))]
+    /// Struct que contiene la información del usuario con rol vendedor. 
+    /// productos, es un Vec con las ids de los productos de su propiedad. 
+    /// reputacion_como_vendedor, es un Vec que almacena las califaciones recibidas por compradores. 
+    /// calificaciones_ponderadas, almacena las mismas calificaciones junto al monto de la orden y el momento en que se calificó. 
+    /// reputacion_por_categoria, almacena las calificaciones recibidas agrupadas por la categoria de los productos de cada orden (id de la categoria, calificaciones). 
+    #[derive(Clone)]
+    pub struct Vendedor{
+        pub productos: Vec<u32>,
+        pub publicaciones: Vec<u32>,
+        pub reputacion_como_vendedor: Vec<u8>, 
+        pub calificaciones_ponderadas: Vec<CalificacionPonderada>,
+        pub reputacion_por_categoria: Vec<(u32, Vec<u8>)>,
+    }
+    impl Vendedor{
+
+        /// Devuelve el promedio simple de las calificaciones recibidas, en centésimas (ej: 433 = 4,33). Sin calificaciones devuelve 0. 
+        pub fn reputacion_promedio(&self) -> Result<u32, String>{
+            promedio_en_centesimas(&self.reputacion_como_vendedor)
+        }
+
+        /// Devuelve el promedio de las calificaciones recibidas en la categoria, en centésimas. Sin calificaciones en la categoria devuelve 0. 
+        pub fn reputacion_en_categoria(&self, categoria: &u32) -> Result<u32, String>{
+            match self.reputacion_por_categoria.iter().find(|(actual, _)| actual == categoria){
+                Some((_, puntuaciones)) => promedio_en_centesimas(puntuaciones),
+                None => Ok(0),
+            }
+        }
+
+        fn calificar_en_categoria(&mut self, categoria: u32, calificacion: u8){
+            if let Some((_, puntuaciones)) = self.reputacion_por_categoria.iter_mut().find(|(actual, _)| *actual == categoria){
+                puntuaciones.push(calificacion);
+            }
+            else {
+                self.reputacion_por_categoria.push((categoria, Vec::from([calificacion])));
+            }
+        }
+
+        /// Devuelve el promedio ponderado de las calificaciones recibidas, en centésimas (ej: 433 = 4,33). 
+        /// Cada calificación pesa el monto de su orden, y el peso se reduce a la mitad por cada VIDA_MEDIA_REPUTACION transcurrida desde la calificación. 
+        /// Si ninguna calificación tiene peso, devuelve 0. 
+        pub fn reputacion_ponderada(&self, ahora: Timestamp) -> Result<u32, String>{
+            let mut suma_puntos: u128 = 0;
+            let mut suma_pesos: u128 = 0;
+            for calificacion in self.calificaciones_ponderadas.iter(){
+                let peso = calificacion.peso(ahora)?;
+                suma_pesos = suma_pesos.checked_add(peso).ok_or("Error al sumar.")?;
+                suma_puntos = suma_puntos.checked_add(peso.checked_mul(u128::from(calificacion.puntuacion)).ok_or("Error al multiplicar.")?).ok_or("Error al sumar.")?;
+            }
+            if suma_pesos == 0 {
+                return Ok(0)
+            }
+            let puntos_en_centesimas = suma_puntos.checked_mul(100).ok_or("Error al multiplicar.")?;
+            let mitad = suma_pesos.checked_div(2).ok_or("Error al dividir.")?;
+            let promedio = puntos_en_centesimas.checked_add(mitad).ok_or("Error al sumar.")?.checked_div(suma_pesos).ok_or("Error al dividir.")?;
+            u32::try_from(promedio).map_err(|_| "Error al convertir.".to_string())
+        }
+
+        fn crear_publicacion(&mut self, productos_a_publicar: Vec<(u32, u32)>, precio_final: u32, id_publicacion: u32, id_vendedor: AccountId, token_de_pago: Option<AccountId>) -> Publicacion { 
+            let publicacion = Publicacion::crear_publicacion(productos_a_publicar, precio_final, id_publicacion, id_vendedor, token_de_pago);
+            self.publicaciones.push(id_publicacion);
+            publicacion
+        }
+
+        fn enviar_compra(&self, id_publicacion: u32) -> Result<(), String>{
+            if self.publicaciones.contains(&id_publicacion){
+                Ok(())
+            }
+            else {
+                Err("La publicacion buscada no pertenece a este vendedor.".to_string())
+            }
+        }
+
+        fn cargar_producto(& mut self, id_producto: u32){
+            self.productos.push(id_producto);
+        }
+    }
+
+
+
+/////////////////////////// CALIFICACION PONDERADA ///////////////////////////
+
+    /// Devuelve el promedio de las puntuaciones recibidas en centésimas (ej: 433 = 4,33), redondeado. Sin puntuaciones devuelve 0. 
+    fn promedio_en_centesimas(puntuaciones: &[u8]) -> Result<u32, String>{
+        let cantidad = u32::try_from(puntuaciones.len()).map_err(|_| "Error al convertir.")?;
+        if cantidad == 0 {
+            return Ok(0)
+        }
+        let mut suma: u32 = 0;
+        for puntuacion in puntuaciones.iter(){
+            suma = suma.checked_add(u32::from(*puntuacion)).ok_or("Error al sumar.")?;
+        }
+        let suma_en_centesimas = suma.checked_mul(100).ok_or("Error al multiplicar.")?;
+        let mitad = cantidad.checked_div(2).ok_or("Error al dividir.")?;
+        suma_en_centesimas.checked_add(mitad).ok_or("Error al sumar.")?.checked_div(cantidad).ok_or("Error al dividir.".to_string())
+    }
+
+    /// Tiempo (en milisegundos) tras el cual el peso de una calificación se reduce a la mitad: ciento ochenta días. 
+    const VIDA_MEDIA_REPUTACION: Timestamp = 180 * 24 * 60 * 60 * 1000;
+
+    /// Divisor que se aplica al peso de las calificaciones entre cuentas relacionadas. 
+    const DIVISOR_CUENTAS_RELACIONADAS: u128 = 10;
+
+    /// Cantidad de ordenes recibidas entre un mismo comprador y vendedor a partir de la cual (excluida) se consideran cuentas relacionadas. 
+    const MAXIMO_ORDENES_ENTRE_CUENTAS: u32 = 3;
+
+    #[derive(Clone, Debug, PartialEq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std",derive(ink::storage::traits::StorageLayout))]
+    /// Struct que contiene una calificación recibida por un vendedor, con los datos necesarios para ponderarla. 
+    /// puntuacion, es la calificación (1..5). 
+    /// monto, es el precio pagado en la orden calificada (info_publicacion.2). 
+    /// fecha, es el timestamp en que se realizó la calificación. 
+    /// calificador, es la cuenta del comprador que calificó. 
+    /// relacionada, indica si al calificar las cuentas se consideraban relacionadas. En ese caso la calificación pesa menos. 
+    pub struct CalificacionPonderada{
+        pub puntuacion: u8,
+        pub monto: u32,
+        pub fecha: Timestamp,
+        pub calificador: AccountId,
+        pub relacionada: bool,
+    }
+    impl CalificacionPonderada{
+
+        /// Devuelve el peso de la calificación en el momento recibido: el monto, dividido por dos por cada vida media transcurrida. 
+        /// Si la calificación es entre cuentas relacionadas, el peso además se divide por DIVISOR_CUENTAS_RELACIONADAS. 
+        fn peso(&self, ahora: Timestamp) -> Result<u128, String>{
+            let edad = ahora.saturating_sub(self.fecha);
+            let vidas_medias = edad.checked_div(VIDA_MEDIA_REPUTACION).ok_or("Error al dividir.")?;
+            let vidas_medias = u32::try_from(vidas_medias).unwrap_or(u32::MAX);
+            let peso = u128::from(self.monto).checked_shr(vidas_medias).unwrap_or(0);
+            if self.relacionada {
+                return peso.checked_div(DIVISOR_CUENTAS_RELACIONADAS).ok_or("Error al dividir.".to_string())
+            }
+            Ok(peso)
+        }
+    }
+
+
+/////////////////////////// VERIFICACION ///////////////////////////
+
+    #[derive(Clone, Debug, PartialEq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std",derive(ink::storage::traits::StorageLayout))]
+    /// Struct que contiene una verificación otorgada a un vendedor. 
+    /// verificador, es la cuenta verificadora que la otorgó. 
+    /// otorgada_en, es el timestamp en que se otorgó. 
+    /// vencimiento, es el timestamp a partir del cual deja de estar vigente. 
+    /// referencia, es el hash de la documentación fuera de la cadena que respalda la verificación. 
+    pub struct Verificacion{
+        pub verificador: AccountId,
+        pub otorgada_en: Timestamp,
+        pub vencimiento: Timestamp,
+        pub referencia: [u8; 32],
+    }
+
+    #[derive(Clone, Debug)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    /// Struct con el perfil público de un usuario. 
+    /// reputacion_como_vendedor, es el promedio simple de sus calificaciones como vendedor, en centésimas (0 si no es vendedor o no tiene calificaciones). 
+    /// verificado, indica si tiene al menos una verificación vigente, y verificaciones las contiene. 
+    pub struct PerfilPublico{
+        pub id_usuario: AccountId,
+        pub nombre: String,
+        pub rol: Rol,
+        pub registrado_en: Timestamp,
+        pub reputacion_como_vendedor: u32,
+        pub verificado: bool,
+        pub verificaciones: Vec<Verificacion>,
+    }
+
+
+/////////////////////////// MODERACION ///////////////////////////
+
+    #[derive(Clone, Debug, PartialEq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std",derive(ink::storage::traits::StorageLayout))]
+    /// Enum con los objetivos sobre los que puede actuar la moderación: un usuario (por su id) o una publicación (por su id). 
+    pub enum ObjetivoModeracion{
+        Usuario(AccountId),
+        Publicacion(u32),
+    }
+
+    #[derive(Clone, Debug, PartialEq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std",derive(ink::storage::traits::StorageLayout))]
+    /// Enum con los códigos de motivo de una sanción. 
+    pub enum MotivoModeracion{
+        Fraude,
+        ContenidoIlegal,
+        Spam,
+        Suplantacion,
+        Otro,
+    }
+
+    #[derive(Clone, Debug, PartialEq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std",derive(ink::storage::traits::StorageLayout))]
+    /// Struct que contiene una sanción vigente: el moderador que la aplicó, el motivo y el timestamp en que se aplicó. 
+    pub struct Sancion{
+        pub moderador: AccountId,
+        pub motivo: MotivoModeracion,
+        pub fecha: Timestamp,
+    }
+
+    #[derive(Clone, Debug, PartialEq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std",derive(ink::storage::traits::StorageLayout))]
+    /// Struct que contiene una apelación a una sanción. 
+    /// id, es la posición de la apelación en el registro. 
+    /// objetivo, es el usuario o la publicación sancionados. 
+    /// apelante, es quien apeló (el usuario suspendido o el vendedor de la publicación). 
+    /// argumento, es el descargo del apelante. 
+    /// fecha, es el timestamp en que se apeló. 
+    /// estado, indica si la apelación está pendiente, o si fue aceptada o rechazada. 
+    /// resuelta_por, es el moderador que la resolvió. None mientras está pendiente. 
+    pub struct Apelacion{
+        pub id: u32,
+        pub objetivo: ObjetivoModeracion,
+        pub apelante: AccountId,
+        pub argumento: String,
+        pub fecha: Timestamp,
+        pub estado: EstadoApelacion,
+        pub resuelta_por: Option<AccountId>,
+    }
+
+    #[derive(Clone, Debug, PartialEq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std",derive(ink::storage::traits::StorageLayout))]
+    pub enum EstadoApelacion{
+        Pendiente,
+        Aceptada,
+        Rechazada,
+    }
+
+    /// Tiempo mínimo (en milisegundos) entre dos denuncias de una misma cuenta: una hora. 
+    const INTERVALO_ENTRE_DENUNCIAS: Timestamp = 60 * 60 * 1000;
+
+    /// Cantidad de denunciantes distintos a partir de la cual un objetivo queda oculto hasta que un moderador lo revisa. 
+    const UMBRAL_DENUNCIANTES: usize = 3;
+
+    #[derive(Clone, Debug, PartialEq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std",derive(ink::storage::traits::StorageLayout))]
+    /// Struct que contiene una denuncia realizada por un usuario. 
+    /// id, es la posición de la denuncia en el registro. 
+    /// objetivo, es el usuario o la publicación denunciados. 
+    /// denunciante, es la cuenta que denunció. 
+    /// motivo, es el código de motivo de la denuncia. 
+    /// fecha, es el timestamp en que se denunció. 
+    /// abierta, indica si la denuncia todavía no fue revisada por un moderador. 
+    pub struct Denuncia{
+        pub id: u32,
+        pub objetivo: ObjetivoModeracion,
+        pub denunciante: AccountId,
+        pub motivo: MotivoModeracion,
+        pub fecha: Timestamp,
+        pub abierta: bool,
+    }
+
+
+/////////////////////////// ROL ///////////////////////////
+
+    #[derive(Debug, Clone, PartialEq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]                            
+    #[cfg_attr(feature = "std",derive(ink::storage::traits::StorageLayout))]
+    /// Enum utulizado para definir el rol de un usuario. 
+    /// Ambos (comprador y vendedor).
+    /// Comp (comprador).
+    /// Vend (vendedor).
+    pub enum Rol {
+        Ambos,
+        Comp, 
+        Vend,
+    }
+
+
+
+
+/////////////////////////// PUBLICACION ///////////////////////////
+
+    #[derive(Clone, Debug)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std",derive(ink::storage::traits::StorageLayout))]
+    /// Struct que contiene la información de una publicacion. 
+    /// id, guarda la id de la publicación. 
+    /// productos, es un Vec que contiene tuplas cuyos campos son, el id de cada producto publicado y la cantidad de unidades publicadas de ese mismo producto. (id producto, cantidad del producto)
+    /// precio_final, es la suma de, el precio de cada producto multiplicado por la cantidad de unidades del mismo. 
+    /// id_vendedor, es el id del vendedor que realizó la publicación. 
+    /// token_de_pago, es la dirección del contrato PSP22 con el que se paga la publicación. None si se paga con moneda nativa. 
+    /// subasta, contiene los datos de la subasta si la publicación se vende al mejor postor. None si es de precio fijo. 
+    /// promocion, es el precio de oferta programado por el vendedor para un período. None si no tiene promoción. 
+    /// variantes, son las variantes elegidas de los productos publicados. (id producto, id variante). Los productos sin variante se venden del stock del producto. 
+    /// periodo_suscripcion, es cada cuánto tiempo (en milisegundos) se repite la compra de una suscripción. None si no admite suscripciones. 
+    pub struct Publicacion{
+        id: u32,
+        productos: Vec<(u32, u32)>,
+        precio_final: u32,
+        id_vendedor:AccountId,
+        disponible: bool,
+        token_de_pago: Option<AccountId>,
+        subasta: Option<Subasta>,
+        promocion: Option<Promocion>,
+        variantes: Vec<(u32, u32)>,
+        periodo_suscripcion: Option<Timestamp>,
+    }
+
+    impl Publicacion {
+        fn crear_publicacion(productos_a_publicar: Vec<(u32, u32)>, precio_final: u32, id_publicacion: u32, id_vendedor: AccountId, token_de_pago: Option<AccountId>) -> Publicacion{
+            Publicacion{
+                id: id_publicacion,
+                productos: productos_a_publicar,
+                precio_final,
+                id_vendedor,
+                disponible: true,
+                token_de_pago,
+                subasta: None,
+                promocion: None,
+                variantes: Vec::new(),
+                periodo_suscripcion: None,
+            }
+        }
+
+        /// Devuelve el precio de la promoción si está vigente en el momento recibido, o el precio final en caso contrario. 
+        fn precio_vigente(&self, ahora: Timestamp) -> u32{
+            match self.promocion {
+                Some(ref promocion) if (promocion.inicio <= ahora) & (ahora < promocion.fin) => promocion.precio,
+                _ => self.precio_final,
+            }
+        }
+    }
+
+    #[derive(Clone, Debug, Default)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    /// Struct con los criterios de búsqueda de publicaciones. Los criterios en None (o false) no filtran. 
+    /// categoria, incluye solo las publicaciones con al menos un producto de esa categoria (o de alguna de sus subcategorias). 
+    /// precio_maximo, incluye solo las publicaciones cuyo precio vigente no lo supera. 
+    /// solo_vendedores_verificados, incluye solo las publicaciones de vendedores con alguna verificación vigente. 
+    pub struct FiltroPublicaciones{
+        pub categoria: Option<u32>,
+        pub precio_maximo: Option<u32>,
+        pub solo_vendedores_verificados: bool,
+    }
+
+    #[derive(Clone, Debug)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std",derive(ink::storage::traits::StorageLayout))]
+    /// Struct que contiene el precio de oferta de una publicación y el período (inicio inclusive, fin exclusive) en el que rige. 
+    pub struct Promocion{
+        pub precio: u32,
+        pub inicio: Timestamp,
+        pub fin: Timestamp,
+    }
+
+
+/////////////////////////// LISTA DE DESEOS ///////////////////////////
+
+    /// Cantidad máxima de publicaciones que puede tener la lista de deseos de un comprador. 
+    const MAXIMO_LISTA_DE_DESEOS: usize = 50;
+
+    /// Cantidad máxima de vendedores que puede seguir un comprador. 
+    const MAXIMO_VENDEDORES_SEGUIDOS: usize = 50;
+
+    #[derive(Clone, Debug, PartialEq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std",derive(ink::storage::traits::StorageLayout))]
+    /// Struct que contiene una publicación guardada en la lista de deseos de un comprador. 
+    /// precio_guardado, es el precio vigente de la publicación al momento de guardarla. 
+    /// disponible_al_guardar, indica si la publicación podía comprarse al momento de guardarla. 
+    pub struct Deseo{
+        pub id_publicacion: u32,
+        pub precio_guardado: u32,
+        pub disponible_al_guardar: bool,
+    }
+
+    #[derive(Clone, Debug, PartialEq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    /// Struct que informa una novedad de una publicación de la lista de deseos. 
+    /// precio_actual, es el precio vigente de la publicación. Si es menor al precio guardado, la publicación bajó de precio. 
+    /// volvio_a_estar_disponible, indica que la publicación no estaba disponible al guardarla y ahora sí. 
+    pub struct NovedadDeseo{
+        pub id_publicacion: u32,
+        pub precio_guardado: u32,
+        pub precio_actual: u32,
+        pub volvio_a_estar_disponible: bool,
+    }
+
+    #[ink(event)]
+    /// Evento emitido cuando un vendedor con seguidores crea una publicación (de precio fijo o subasta). 
+    /// Los seguidores pueden filtrar los eventos por el vendedor, que es un topic. 
+    pub struct PublicacionDeVendedorSeguido{
+        #[ink(topic)]
+        pub vendedor: AccountId,
+        pub id_publicacion: u32,
+    }
+
+    #[ink(event)]
+    /// Evento emitido cuando se recibe una orden pero el contrato de recibos no pudo acuñar su recibo. 
+    /// El comprador, que es un topic, puede pedirlo nuevamente con "reintentar_recibo". 
+    pub struct ReciboNoEmitido{
+        #[ink(topic)]
+        pub comprador: AccountId,
+        pub id_orden: u32,
+        pub motivo: String,
+    }
+
+
+/////////////////////////// CUPON ///////////////////////////
+
+    #[derive(Clone, Debug, PartialEq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std",derive(ink::storage::traits::StorageLayout))]
+    /// Enum utilizado para indicar el tipo de descuento de un cupón. 
+    /// Porcentaje (de 1 a 100 sobre el precio).
+    /// MontoFijo (monto que se resta al precio).
+    pub enum Descuento{
+        Porcentaje(u8),
+        MontoFijo(u32),
+    }
+    impl Descuento{
+
+        fn validar(&self) -> Result<(), String>{
+            match *self {
+                Descuento::Porcentaje(porcentaje) if (porcentaje == 0) | (porcentaje > 100) => Err("El descuento del cupon no es valido.".to_string()),
+                Descuento::MontoFijo(0) => Err("El descuento del cupon no es valido.".to_string()),
+                _ => Ok(()),
+            }
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std",derive(ink::storage::traits::StorageLayout))]
+    /// Struct que contiene la información de un cupón de descuento de un vendedor. 
+    /// descuento, indica si se descuenta un porcentaje o un monto fijo. 
+    /// usos_restantes, es la cantidad de compras en las que todavía se puede aplicar. 
+    /// vencimiento, es el timestamp a partir del cual el cupón ya no puede aplicarse. 
+    pub struct Cupon{
+        pub descuento: Descuento,
+        pub usos_restantes: u32,
+        pub vencimiento: Timestamp,
+    }
+    impl Cupon{
+
+        /// Devuelve el precio recibido con el descuento del cupón aplicado. 
+        fn aplicar(&self, precio: u32, ahora: Timestamp) -> Result<u32, String>{
+            if ahora >= self.vencimiento {
+                return Err("El cupon esta vencido.".to_string())
+            }
+            if self.usos_restantes == 0 {
+                return Err("El cupon no tiene usos restantes.".to_string())
+            }
+            let descuento = match self.descuento {
+                Descuento::Porcentaje(porcentaje) => {
+                    let descuento = u64::from(precio).checked_mul(u64::from(porcentaje)).ok_or("Error al multiplicar.")?;
+                    u32::try_from(descuento.checked_div(100).ok_or("Error al dividir.")?).map_err(|_| "Error al convertir.")?
+                }
+                Descuento::MontoFijo(monto) => monto,
+            };
+            if descuento >= precio {
+                return Err("El descuento del cupon supera el precio.".to_string())
+            }
+            precio.checked_sub(descuento).ok_or("Error al restar.".to_string())
+        }
+    }
+
+
+/////////////////////////// OFERTA ///////////////////////////
+
+    /// Tiempo (en milisegundos) que una oferta o contraoferta permanece vigente: tres días. 
+    const DURACION_OFERTA: Timestamp = 3 * 24 * 60 * 60 * 1000;
+
+    #[derive(Clone, Debug)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std",derive(ink::storage::traits::StorageLayout))]
+    /// Struct que contiene la información de una oferta de precio sobre una publicación. 
+    /// id, es la posición de la oferta en el StorageVec "ofertas". 
+    /// id_publicacion, es la publicación sobre la que se oferta. 
+    /// comprador, es quien realizó la oferta. 
+    /// precio, es el monto ofertado, que queda retenido en el contrato. 
+    /// contraoferta, es el precio propuesto por el vendedor (si contraofertó). 
+    /// token_de_pago, es el token PSP22 con el que se retuvo el monto (None para moneda nativa). 
+    /// vencimiento, es el timestamp a partir del cual la oferta (o contraoferta) ya no puede aceptarse. 
+    /// estado, indica si la oferta está pendiente, contraofertada, aceptada, rechazada o retirada. 
+    pub struct Oferta{
+        pub id: u32,
+        pub id_publicacion: u32,
+        pub comprador: AccountId,
+        pub precio: u32,
+        pub contraoferta: Option<u32>,
+        pub token_de_pago: Option<AccountId>,
+        pub vencimiento: Timestamp,
+        pub estado: EstadoOferta,
+    }
+    impl Oferta{
+
+        fn nueva(id: u32, id_publicacion: u32, comprador: AccountId, precio: u32, token_de_pago: Option<AccountId>, vencimiento: Timestamp) -> Oferta{
+            Oferta{
+                id,
+                id_publicacion,
+                comprador,
+                precio,
+                contraoferta: None,
+                token_de_pago,
+                vencimiento,
+                estado: EstadoOferta::Pendiente,
+            }
+        }
+
+        fn esta_abierta(&self) -> bool{
+            (self.estado == EstadoOferta::Pendiente) | (self.estado == EstadoOferta::Contraofertada)
+        }
+
+        /// Devuelve el precio que se acuerda si se acepta la oferta: la contraoferta si existe, o el precio ofertado. 
+        fn precio_vigente(&self) -> u32{
+            self.contraoferta.unwrap_or(self.precio)
+        }
+
+        /// Devuelve lo que el comprador debe pagar además de lo retenido para aceptar la contraoferta (cero si no hay que pagar nada). 
+        fn diferencia_a_pagar(&self) -> u32{
+            self.precio_vigente().saturating_sub(self.precio)
+        }
+
+        /// Comprueba que la oferta siga abierta y vigente, y que quien responde sea la contraparte: 
+        /// el vendedor si la oferta está pendiente, o el comprador si fue contraofertada. 
+        fn validar_respuesta(&self, account_id: AccountId, id_vendedor: AccountId, ahora: Timestamp) -> Result<(), String>{
+            if !self.esta_abierta() {
+                return Err("La oferta ya fue resuelta.".to_string())
+            }
+            if ahora >= self.vencimiento {
+                return Err("La oferta esta vencida.".to_string())
+            }
+            let responde = if self.estado == EstadoOferta::Pendiente { id_vendedor } else { self.comprador };
+            if account_id != responde {
+                return Err("El usuario no puede responder esta oferta.".to_string())
+            }
+            Ok(())
+        }
+    }
+
+    #[derive(Clone, PartialEq, Debug)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std",derive(ink::storage::traits::StorageLayout))]
+    /// Enum utilizado para indicar el estado de una oferta. 
+    /// Pendiente (esperando respuesta del vendedor).
+    /// Contraofertada (esperando respuesta del comprador).
+    /// Aceptada (se creó la orden de compra).
+    /// Rechazada (por el vendedor) y Retirada (por el comprador): el monto retenido se acreditó al comprador. 
+    pub enum EstadoOferta{
+        Pendiente,
+        Contraofertada,
+        Aceptada,
+        Rechazada,
+        Retirada,
+    }
+
+/////////////////////////// SUSCRIPCION ///////////////////////////
+
+    /// Período mínimo (en milisegundos) entre las compras de una suscripción: un día. 
+    const PERIODO_MINIMO_SUSCRIPCION: Timestamp = 24 * 60 * 60 * 1000;
+
+    /// Cantidad máxima de períodos que se pueden pagar por adelantado en una suscripción. 
+    const MAXIMO_PERIODOS_SUSCRIPCION: u32 = 12;
+
+    #[derive(Clone, Debug, PartialEq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std",derive(ink::storage::traits::StorageLayout))]
+    /// Struct que contiene la información de una suscripción a una publicación. 
+    /// id, es la posición de la suscripción en el StorageVec "suscripciones". 
+    /// id_publicacion, es la publicación que se compra en cada período. 
+    /// comprador, es quien se suscribió. 
+    /// precio, es el precio de cada período, fijado al suscribirse. 
+    /// token_de_pago, es el token PSP22 con el que se retuvo el total (None para moneda nativa). 
+    /// periodo, es el tiempo (en milisegundos) entre una compra y la siguiente. 
+    /// periodos_restantes, es la cantidad de compras pagadas que todavía no se crearon. Su precio sigue retenido en el contrato. 
+    /// proxima_ejecucion, es el timestamp a partir del cual se puede crear la próxima orden. 
+    /// estado, indica si la suscripción está activa, finalizada (se crearon todas las órdenes) o cancelada por el comprador. 
+    pub struct Suscripcion{
+        pub id: u32,
+        pub id_publicacion: u32,
+        pub comprador: AccountId,
+        pub precio: u32,
+        pub token_de_pago: Option<AccountId>,
+        pub periodo: Timestamp,
+        pub periodos_restantes: u32,
+        pub proxima_ejecucion: Timestamp,
+        pub estado: EstadoSuscripcion,
+    }
+
+    #[derive(Clone, PartialEq, Debug)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std",derive(ink::storage::traits::StorageLayout))]
+    /// Enum utilizado para indicar el estado de una suscripción. 
+    /// Activa (quedan períodos por ejecutar).
+    /// Finalizada (se crearon las órdenes de todos los períodos).
+    /// Cancelada (por el comprador): el monto de los períodos restantes se acreditó al comprador. 
+    pub enum EstadoSuscripcion{
+        Activa,
+        Finalizada,
+        Cancelada,
+    }
+
+    #[derive(Clone, Debug, PartialEq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    /// Struct que informa el resultado de una llamada a "ejecutar_suscripciones". 
+    /// creadas, es la cantidad de órdenes creadas. 
+    /// pendientes, son los ids de las suscripciones vencidas cuya orden no pudo crearse; se reintentan en la próxima ejecución. 
+    /// siguiente, es la posición desde la que continuar la ejecución (None si se revisaron todas las suscripciones activas). 
+    pub struct EjecucionSuscripciones{
+        pub creadas: u32,
+        pub pendientes: Vec<u32>,
+        pub siguiente: Option<u32>,
+    }
+
+/////////////////////////// SUBASTA ///////////////////////////
+
+    #[derive(Clone, Debug, PartialEq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std",derive(ink::storage::traits::StorageLayout))]
+    /// Struct que contiene la información de una subasta. 
+    /// precio_reserva, es el precio mínimo que debe alcanzar la mejor oferta para que la subasta se adjudique. 
+    /// fin, es el timestamp a partir del cual ya no se aceptan ofertas y la subasta puede finalizarse. 
+    /// mejor_oferta, es una tupla con el ofertante y el monto de la oferta más alta. (ofertante, monto)
+    /// finalizada, indica si la subasta ya fue finalizada. 
+    pub struct Subasta{
+        pub precio_reserva: u32,
+        pub fin: Timestamp,
+        pub mejor_oferta: Option<(AccountId, u32)>,
+        pub finalizada: bool,
+    }
+    impl Subasta{
+
+        fn nueva(precio_reserva: u32, fin: Timestamp) -> Subasta{
+            Subasta{
+                precio_reserva,
+                fin,
+                mejor_oferta: None,
+                finalizada: false,
+            }
+        }
+
+        /// Comprueba que la subasta siga abierta y que el monto supere a la mejor oferta actual. 
+        fn validar_oferta(&self, monto: u32, ahora: Timestamp) -> Result<(), String>{
+            if self.finalizada | (ahora >= self.fin) {
+                return Err("La subasta ya termino.".to_string())
+            }
+            if monto == 0 {
+                return Err("El monto ofertado no es valido.".to_string())
+            }
+            if let Some((_, mejor)) = self.mejor_oferta {
+                if monto <= mejor {
+                    return Err("La oferta debe superar a la mejor oferta actual.".to_string())
+                }
+            }
+            Ok(())
+        }
+    }
+
+
+/////////////////////////// PRODUCTO ///////////////////////////
+
+    /// Cantidad máxima de referencias a contenido (imágenes, fichas técnicas) que puede tener un producto. 
+    const MAXIMO_MEDIOS_POR_PRODUCTO: usize = 10;
+
+    /// Largo máximo (en bytes) de cada referencia a contenido. Alcanza para un CID de IPFS o un hash en hexadecimal. 
+    const LARGO_MAXIMO_MEDIO: usize = 128;
+
+    #[derive(Clone, Debug)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std",derive(ink::storage::traits::StorageLayout))]
+    /// Struct que contiene la información de un producto.
+    /// id, almacena el id del producto. Utilizado como clave en el "historial_productos" del sistema. 
+    /// nombre, almacena el nombre del producto.
+    /// descripcion, almacena la descripción de un producto.
+    /// precio, almacena el precio del producto.
+    /// categoria, almacena el id de la categoria. 
+    /// medios, almacena las referencias al contenido guardado fuera de la cadena (CIDs de IPFS o hashes) de las imágenes y fichas técnicas del producto. 
+    pub struct Producto{
+        pub id: u32,
+        pub nombre: String,
+        pub descripcion: String,
+        pub precio: u32,
+        pub categoria: u32,
+        pub medios: Vec<String>,
+    }
+    impl Producto{
+
+
+        fn cargar_producto(id: u32, nombre: String, descripcion: String, precio: u32, categoria: u32) -> Producto{
+            Producto{
+                id,
+                nombre,
+                descripcion,
+                precio,
+                categoria,
+                medios: Vec::new(),
+            }
+        }
+
+        /// Valida las referencias a contenido de un producto: a lo sumo MAXIMO_MEDIOS_POR_PRODUCTO, 
+        /// y cada una no vacía y de a lo sumo LARGO_MAXIMO_MEDIO bytes. 
+        fn validar_medios(medios: &[String]) -> Result<(), String>{
+            if medios.len() > MAXIMO_MEDIOS_POR_PRODUCTO {
+                return Err("El producto supera la cantidad maxima de referencias.".to_string())
+            }
+            if medios.iter().any(|medio| medio.is_empty() | (medio.len() > LARGO_MAXIMO_MEDIO)){
+                return Err("Una referencia no es valida.".to_string())
+            }
+            Ok(())
+        }
+    }
+
+    #[derive(Clone, Debug, PartialEq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std",derive(ink::storage::traits::StorageLayout))]
+    /// Struct que contiene una variante de un producto (por ejemplo, un talle y color de una prenda). 
+    /// id, es el id de la variante dentro del producto. 
+    /// atributos, son los pares (atributo, valor) que la describen. 
+    /// delta_precio, es la diferencia entre el precio de la variante y el precio del producto. Puede ser negativa. 
+    /// stock, es el stock propio de la variante, independiente del stock del producto. 
+    pub struct Variante{
+        pub id: u32,
+        pub atributos: Vec<(String, String)>,
+        pub delta_precio: i32,
+        pub stock: u32,
+    }
+    impl Variante{
+
+        /// Devuelve el precio de la variante a partir del precio del producto. 
+        /// 
+        /// Errores posibles: el precio resultante no es mayor a 0 o no entra en un u32. 
+        fn precio(&self, precio_producto: u32) -> Result<u32, String>{
+            let precio = i64::from(precio_producto).checked_add(i64::from(self.delta_precio)).ok_or("Error al sumar.")?;
+            u32::try_from(precio).ok().filter(|precio| *precio > 0).ok_or("Precio no valido".to_string())
+        }
+
+        /// Devuelve la variante elegida para un producto dentro de las variantes (id producto, id variante) de una publicación u orden. 
+        fn elegida(variantes: &[(u32, u32)], id_producto: u32) -> Option<u32>{
+            variantes.iter().find(|&&(id, _variante)| id == id_producto).map(|&(_id, variante)| variante)
+        }
+    }
+
+/////////////////////////// ORDEN DE COMPRA ///////////////////////////
+
+    /// Tiempo (en milisegundos) desde la recepción de una orden durante el cual el comprador puede solicitar la devolución: siete días. 
+    pub const PLAZO_DEVOLUCION: Timestamp = 7 * 24 * 60 * 60 * 1000;
+
+    /// Tiempo (en milisegundos) desde que el vendedor acepta una devolución durante el cual puede confirmarla. 
+    /// Vencido el plazo, el comprador puede reclamar el depósito: catorce días. 
+    const PLAZO_CONFIRMACION_DEVOLUCION: Timestamp = 14 * 24 * 60 * 60 * 1000;
+
+    /// Plazo de calificación inicial (en milisegundos desde la recepción de la orden): treinta días. 
+    const PLAZO_CALIFICACION_POR_DEFECTO: Timestamp = 30 * 24 * 60 * 60 * 1000;
+
+    /// Tiempo (en milisegundos) desde el fin del plazo de calificación durante el cual todavía se pueden revelar los compromisos: siete días. 
+    const PLAZO_REVELACION: Timestamp = 7 * 24 * 60 * 60 * 1000;
+
+    /// Precio pagado por cada punto de fidelidad que ganan el comprador y el vendedor al recibirse una orden. 
+    const PRECIO_POR_PUNTO: u32 = 100;
+
+    /// Descuento (en unidades del precio) que otorga cada punto de fidelidad canjeado. 
+    const VALOR_DEL_PUNTO: u32 = 1;
+
+    #[derive(Clone, Debug)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std",derive(ink::storage::traits::StorageLayout))]
+    /// Struct que contiene la información de una orden de compra.
+    /// id, almacena la id de la orden de compra. 
+    /// estado, almacena el estado de la compra. Éste puede ser: Pendiente, Enviado, Recibido, Cancelada. 
+    /// cancelacion, es una tupla que almacena el pedido de cancelación, tanto del vendedor como del comprador. (vendedor, comprador)
+    /// info_publicacion, tupla que almacena los datos de la publicación. (ID de la publicacion, Vec<(IDs de los productos, cantidades de ese producto)>, precio final de la publicacion, ID del Vendedor).
+    /// id_comprador, almacena el id del comprador de la orden de compra. 
+    /// calificaciones, es una tupla que indica si el vendor y/o comprador realizó la calificación a su contraparte. (vendedor, comprador)
+    /// puntuacion_del_comprador, almacena la calificacion dada por el comprador (una vez recibido)
+    /// comision, almacena la comisión cobrada por el sistema al liberar el pago al vendedor (cero mientras el pago siga retenido). 
+    /// token_de_pago, es el token PSP22 con el que se pagó la orden (copiado de la publicación). None si se pagó con moneda nativa. 
+    /// precio_original, es el precio final de la publicación al momento de la compra. El precio pagado (con promociones, cupones u ofertas) es info_publicacion.2. 
+    /// recibido_en, es el timestamp en que el comprador recibió la orden (desde allí corre el plazo de devolución). 
+    /// motivo_devolucion, es el motivo indicado por el comprador al solicitar la devolución. None si no la solicitó. 
+    /// lineas, contiene el estado de envío de cada producto de la orden, ya que el vendedor puede enviarlos por separado. 
+    /// compromisos, almacena los compromisos de calificación todavía no revelados. (del comprador, del vendedor)
+    /// variantes, son las variantes compradas de los productos de la orden (copiadas de la publicación). (id producto, id variante)
+    /// puntos_canjeados, son los puntos de fidelidad que el comprador usó como descuento en la orden. 
+    pub struct OrdenCompra{
+        pub id: u32,
+        pub estado: EstadoCompra,
+        pub cancelacion: (bool, bool), 
+        pub info_publicacion: (u32, Vec<(u32, u32)>, u32, AccountId),
+        pub id_comprador:AccountId,
+        pub calificaciones: (bool, bool),
+        pub puntuacion_del_comprador: Option<u8>,
+        pub comision: Balance,
+        pub token_de_pago: Option<AccountId>,
+        pub precio_original: u32,
+        pub recibido_en: Option<Timestamp>,
+        pub motivo_devolucion: Option<String>,
+        pub lineas: Vec<LineaEnvio>,
+        pub compromisos: (Option<[u8; 32]>, Option<[u8; 32]>),
+        pub variantes: Vec<(u32, u32)>,
+        pub puntos_canjeados: u32,
+    }
+    impl OrdenCompra{
+        
+        fn crear_orden_de_compra(id_orden: u32, publicacion: Publicacion, id_comprador: AccountId) -> OrdenCompra{
+            let id_publicacion = publicacion.id;
+            let productos = publicacion.productos;
+            let precio_final = publicacion.precio_final;
+            let id_vendedor = publicacion.id_vendedor;
+            let token_de_pago = publicacion.token_de_pago;
+            let variantes = publicacion.variantes;
+            let lineas = productos.iter().map(|&(id_producto, cantidad)| LineaEnvio::nueva(id_producto, cantidad)).collect();
+            let info_publicacion = (id_publicacion, productos, precio_final, id_vendedor);
+            let calificaciones = (false, false);
+
+            OrdenCompra{
+                id: id_orden,
+                estado: EstadoCompra::Pendiente,
+                cancelacion: (false, false),
+                info_publicacion,
+                id_comprador, 
+                calificaciones,
+                puntuacion_del_comprador: None,
+                comision: 0,
+                token_de_pago,
+                precio_original: precio_final,
+                recibido_en: None,
+                motivo_devolucion: None,
+                lineas,
+                compromisos: (None, None),
+                variantes,
+                puntos_canjeados: 0,
+            }
+        }
+
+        /// Devuelve la línea de la orden correspondiente al producto recibido por parametro. 
+        fn buscar_linea(&mut self, id_producto: u32) -> Result<&mut LineaEnvio, String>{
+            self.lineas.iter_mut().find(|linea| linea.id_producto == id_producto).ok_or("La orden no tiene ese producto.".to_string())
+        }
+
+        /// Pasa al estado nuevo todas las líneas que se encuentran en el estado actual. 
+        fn actualizar_lineas(&mut self, actual: EstadoLinea, nuevo: EstadoLinea){
+            for linea in self.lineas.iter_mut().filter(|linea| linea.estado == actual){
+                linea.estado = nuevo.clone();
+            }
+        }
+
+        /// Devuelve los puntos de fidelidad que ganan el comprador y el vendedor cuando la orden se recibe: uno cada PRECIO_POR_PUNTO del precio pagado. 
+        fn puntos_ganados(&self) -> u32{
+            self.info_publicacion.2 / PRECIO_POR_PUNTO
+        }
+
+        /// Devuelve el monto que deposita el vendedor al aceptar una devolución: lo que recibió por la orden (precio pagado menos la comisión). 
+        fn monto_a_reintegrar(&self) -> Result<Balance, String>{
+            Balance::from(self.info_publicacion.2).checked_sub(self.comision).ok_or("Error al restar.".to_string())
+        }
+        
+        fn cancelar_compra_comprador(&mut self) -> Result<(), String>{
+            if self.cancelacion.1 {
+                return Err("La compra ya fue cancelada por el comprador anteriormente.".to_string());
+            }
+            else{
+                self.cancelacion.1 = true;
+                return Ok(());
+            }
+        }
+        
+        fn cancelar_compra_vendedor(&mut self) -> Result<(), String>{
+            if self.cancelacion.0 {
+                return Err("La compra ya fue cancelada.".to_string());
+            }
+            else {
+                if self.cancelacion.1 {
+                    self.cancelacion.0 = true;
+                    return Ok(());
+                }
+                else {
+                    return Err("El comprador no desea cancelar la compra.".to_string());
+                }
+            }
+        }
+    }
+
+
+
+/////////////////////////// LINEA DE ENVIO ///////////////////////////
+
+    #[derive(Clone, Debug)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std",derive(ink::storage::traits::StorageLayout))]
+    /// Struct que contiene el estado de envío de un producto (línea) de una orden de compra. 
+    /// id_producto y cantidad, son los de la línea en la publicación. 
+    /// seguimiento, es el código de seguimiento informado por el vendedor al enviar la línea por separado. 
+    /// estado, indica si la línea está pendiente, enviada o recibida. 
+    pub struct LineaEnvio{
+        pub id_producto: u32,
+        pub cantidad: u32,
+        pub seguimiento: Option<String>,
+        pub estado: EstadoLinea,
+    }
+    impl LineaEnvio{
+
+        fn nueva(id_producto: u32, cantidad: u32) -> LineaEnvio{
+            LineaEnvio{ id_producto, cantidad, seguimiento: None, estado: EstadoLinea::Pendiente }
+        }
+    }
+
+    #[derive(Clone, PartialEq, Debug)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std",derive(ink::storage::traits::StorageLayout))]
+    /// Enum utilizado para indicar el estado de envío de una línea de una orden de compra. 
+    pub enum EstadoLinea{
+        Pendiente,
+        Enviada,
+        Recibida,
+    }
+
+/////////////////////////// MENSAJES ///////////////////////////
+
+    /// Cantidad máxima de mensajes que puede tener el registro de una orden. 
+    const MAXIMO_MENSAJES_POR_ORDEN: usize = 100;
+
+    /// Largo máximo (en bytes) del contenido de un mensaje, tanto en texto como cifrado. 
+    const LARGO_MAXIMO_MENSAJE: usize = 512;
+
+    #[derive(Clone, Debug, PartialEq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std",derive(ink::storage::traits::StorageLayout))]
+    /// Enum con el contenido de un mensaje. 
+    /// Texto (visible para cualquiera que lea el registro). 
+    /// Cifrado (bytes cifrados por las partes fuera de la cadena, por ejemplo con la clave pública de la contraparte). 
+    pub enum ContenidoMensaje{
+        Texto(String),
+        Cifrado(Vec<u8>),
+    }
+    impl ContenidoMensaje{
+
+        /// Errores posibles: el contenido está vacío o supera LARGO_MAXIMO_MENSAJE bytes. 
+        fn validar(&self) -> Result<(), String>{
+            let largo = match self {
+                ContenidoMensaje::Texto(texto) => texto.len(),
+                ContenidoMensaje::Cifrado(bytes) => bytes.len(),
+            };
+            if (largo == 0) | (largo > LARGO_MAXIMO_MENSAJE) {
+                return Err("El mensaje no es valido.".to_string())
+            }
+            Ok(())
+        }
+    }
+
+    #[derive(Clone, Debug, PartialEq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std",derive(ink::storage::traits::StorageLayout))]
+    /// Struct que contiene un mensaje del registro de una orden. 
+    /// autor, es quien envió el mensaje (el comprador o el vendedor de la orden). 
+    /// fecha, es el timestamp en que se envió. 
+    pub struct Mensaje{
+        pub autor: AccountId,
+        pub fecha: Timestamp,
+        pub contenido: ContenidoMensaje,
+    }
+
+
+/////////////////////////// ESTADO DE COMPRA ///////////////////////////
+
+    #[derive(Clone, PartialEq, Debug)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std",derive(ink::storage::traits::StorageLayout))]
+    /// Enum utilizado para indicar el estado de la orden de compra. 
+    /// Pendiente (cuando la orden se crea).
+    /// Enviado (cuando el vendedor envía los productos de la publicación).
+    /// Recibido (cuando el comprador recibe los productos de la orden). 
+    /// Cancelada (solo se asignará cuando ambas partes de la orden de compra cancelan la misma). 
+    /// DevolucionSolicitada (el comprador pidió devolver una orden recibida).
+    /// EnDevolucion (el vendedor aceptó la devolución y espera recibir los productos).
+    /// Devuelto (el vendedor recibió los productos y se reintegró el pago al comprador).
+    pub enum EstadoCompra{
+        Pendiente,
+        Enviado,
+        Recibido,
+        Cancelada,
+        DevolucionSolicitada,
+        EnDevolucion,
+        Devuelto,
+    }
+
+/////////////////////////// CATEGORIAS ///////////////////////////
+
+    /// Nombres de las categorias con las que se inicializa el sistema. Sus ids son sus posiciones. 
+    const CATEGORIAS_INICIALES: [&str; 8] = ["Electrodomesticos", "Limpieza", "Alimentos", "Hogar", "Ropa", "Mascotas", "Libreria", "Otro"];
+
+    #[derive(Clone, PartialEq, Debug)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std",derive(ink::storage::traits::StorageLayout))]
+    /// Struct que contiene una categoria de productos. 
+    /// id, es el id de la categoria (su posición en el StorageVec de categorias del sistema). 
+    /// nombre, es el nombre de la categoria. No se repite. 
+    /// padre, es el id de la categoria que la contiene. None si es una categoria de primer nivel. 
+    pub struct Categoria{
+        pub id: u32,
+        pub nombre: String,
+        pub padre: Option<u32>,
+    }
+    impl Categoria{
+
+        /// La función indica si la categoria "id" es "ancestro" o alguna de sus subcategorias (a cualquier profundidad), 
+        /// recorriendo las categorias padre en la lista recibida. 
+        pub fn es_subcategoria(categorias: &[Categoria], id: u32, ancestro: u32) -> bool{
+            let mut actual = Some(id);
+            for _ in 0..=categorias.len() {
+                match actual {
+                    Some(id_actual) if id_actual == ancestro => return true,
+                    Some(id_actual) => actual = categorias.iter().find(|categoria| categoria.id == id_actual).and_then(|categoria| categoria.padre),
+                    None => return false,
+                }
+            }
+            false
+        }
+    }
+
+/////////////////////////// EVENTOS PARA REPORTES ///////////////////////////
+
+    #[derive(Clone)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    /// Enum con los eventos que se notifican al contrato de reportes.
+    /// OrdenActualizada, se envía al crear una orden (estado_anterior = None), al enviarla, al recibirla y cuando ambas partes la cancelan. 
+    /// Calificacion, se envía cada vez que una de las partes califica a la otra. calificado es quien recibe la puntuación, y rol_calificado el rol que tuvo en la orden. 
+    /// En ambos casos, productos contiene los datos de los productos de la orden (para poder agrupar por categoría). 
+    /// La orden va en un Box para que el tamaño del evento no dependa del de la orden. 
+    pub enum EventoMarketplace{
+        OrdenActualizada{
+            orden: Box<OrdenCompra>,
+            estado_anterior: Option<EstadoCompra>,
+            productos: Vec<Producto>,
+        },
+        Calificacion{
+            orden: Box<OrdenCompra>,
+            calificado: AccountId,
+            rol_calificado: Rol,
+            puntuacion: u8,
+            productos: Vec<Producto>,
+        },
+    }
+
+/////////////////////////// PSP22 ///////////////////////////
+
+    #[derive(Debug, Clone, PartialEq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    /// Enum con los errores definidos por el estándar PSP22. Se utiliza para decodificar la respuesta de los tokens. 
+    pub enum Psp22Error{
+        Custom(String),
+        InsufficientBalance,
+        InsufficientAllowance,
+        ZeroRecipientAddress,
+        ZeroSenderAddress,
+        SafeTransferCheckFailed(String),
+    }
+    impl Psp22Error{
+
+        fn a_mensaje(&self) -> String{
+            match self{
+                Psp22Error::InsufficientBalance => "El saldo del token no es suficiente.".to_string(),
+                Psp22Error::InsufficientAllowance => "El contrato no esta autorizado a mover esa cantidad de tokens.".to_string(),
+                _ => "El token PSP22 rechazo la transferencia.".to_string(),
+            }
+        }
+    }
+
+    /// Token PSP22 simulado para los tests off-chain, donde no se pueden realizar llamadas a otros contratos.
+    /// Guarda los saldos y autorizaciones de cada token en memoria (una instancia por hilo, es decir, por test).
+    #[cfg(test)]
+    mod mock_psp22 {
+        use super::{AccountId, Balance, Psp22Error};
+        use std::cell::RefCell;
+        use std::collections::BTreeMap;
+
+        thread_local! {
+            static SALDOS: RefCell<BTreeMap<(AccountId, AccountId), Balance>> = RefCell::new(BTreeMap::new());
+            static AUTORIZACIONES: RefCell<BTreeMap<(AccountId, AccountId, AccountId), Balance>> = RefCell::new(BTreeMap::new());
+        }
+
+        pub fn mint(token: AccountId, cuenta: AccountId, monto: Balance) {
+            SALDOS.with(|saldos| *saldos.borrow_mut().entry((token, cuenta)).or_insert(0) += monto);
+        }
+
+        pub fn approve(token: AccountId, dueno: AccountId, autorizado: AccountId, monto: Balance) {
+            AUTORIZACIONES.with(|autorizaciones| autorizaciones.borrow_mut().insert((token, dueno, autorizado), monto));
+        }
+
+        pub fn balance_of(token: AccountId, cuenta: AccountId) -> Balance {
+            SALDOS.with(|saldos| *saldos.borrow().get(&(token, cuenta)).unwrap_or(&0))
+        }
+
+        pub fn transfer(token: AccountId, origen: AccountId, destino: AccountId, monto: Balance) -> Result<(), Psp22Error> {
+            let saldo_origen = balance_of(token, origen);
+            if saldo_origen < monto {
+                return Err(Psp22Error::InsufficientBalance);
+            }
+            SALDOS.with(|saldos| {
+                let mut saldos = saldos.borrow_mut();
+                saldos.insert((token, origen), saldo_origen - monto);
+                *saldos.entry((token, destino)).or_insert(0) += monto;
+            });
+            Ok(())
+        }
+
+        pub fn transfer_from(token: AccountId, autorizado: AccountId, origen: AccountId, destino: AccountId, monto: Balance) -> Result<(), Psp22Error> {
+            let permitido = AUTORIZACIONES.with(|autorizaciones| *autorizaciones.borrow().get(&(token, origen, autorizado)).unwrap_or(&0));
+            if permitido < monto {
+                return Err(Psp22Error::InsufficientAllowance);
+            }
+            transfer(token, origen, destino, monto)?;
+            approve(token, origen, autorizado, permitido - monto);
+            Ok(())
+        }
+    }
+
+/////////////////////////// PSP34 ///////////////////////////
+
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std",derive(ink::storage::traits::StorageLayout))]
+    /// Enum con los tipos de id de token definidos por el estándar PSP34. Los recibos de compra usan U32 (el id de la orden). 
+    pub enum Id{
+        U8(u8),
+        U16(u16),
+        U32(u32),
+        U64(u64),
+        U128(u128),
+        Bytes(Vec<u8>),
+    }
+
+    #[derive(Debug, Clone, PartialEq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    /// Enum con los errores definidos por el estándar PSP34. 
+    pub enum Psp34Error{
+        Custom(String),
+        SelfApprove,
+        NotApproved,
+        TokenExists,
+        TokenNotExists,
+        SafeTransferCheckFailed(String),
+    }
+
+    #[derive(Debug, Clone, PartialEq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std",derive(ink::storage::traits::StorageLayout))]
+    /// Struct con los datos de un recibo de compra, que se guardan como metadatos del token PSP34 del comprador. 
+    /// id_orden, es el id de la orden (y también el id del token). 
+    /// productos, son los productos comprados con sus cantidades. (id producto, cantidad)
+    /// precio, es el precio pagado por la orden. 
+    /// vendedor, es el vendedor de la orden, ante quien se reclama la garantía. 
+    /// recibido_en, es el timestamp en que el comprador recibió la orden (desde allí corre la garantía). 
+    pub struct Recibo{
+        pub id_orden: u32,
+        pub productos: Vec<(u32, u32)>,
+        pub precio: u32,
+        pub vendedor: AccountId,
+        pub recibido_en: Timestamp,
+    }
+    impl Recibo{
+
+        fn de_orden(orden: &OrdenCompra, recibido_en: Timestamp) -> Recibo{
+            Recibo{
+                id_orden: orden.id,
+                productos: orden.info_publicacion.1.clone(),
+                precio: orden.info_publicacion.2,
+                vendedor: orden.info_publicacion.3,
+                recibido_en,
+            }
+        }
+    }
+
+    /// Contrato de recibos PSP34 simulado para los tests off-chain, donde no se pueden realizar llamadas a otros contratos.
+    /// Guarda el dueño de cada recibo acuñado en memoria (una instancia por hilo, es decir, por test).
+    #[cfg(test)]
+    mod mock_psp34 {
+        use super::{AccountId, Psp34Error};
+        use std::cell::RefCell;
+        use std::collections::BTreeMap;
+
+        thread_local! {
+            static DUENOS: RefCell<BTreeMap<(AccountId, u32), AccountId>> = RefCell::new(BTreeMap::new());
+        }
+
+        pub fn acunar(contrato: AccountId, destinatario: AccountId, id_token: u32) -> Result<(), Psp34Error> {
+            if owner_of(contrato, id_token).is_some() {
+                return Err(Psp34Error::TokenExists);
+            }
+            DUENOS.with(|duenos| duenos.borrow_mut().insert((contrato, id_token), destinatario));
+            Ok(())
+        }
+
+        pub fn owner_of(contrato: AccountId, id_token: u32) -> Option<AccountId> {
+            DUENOS.with(|duenos| duenos.borrow().get(&(contrato, id_token)).copied())
+        }
+
+        pub fn transfer(contrato: AccountId, destino: AccountId, id_token: u32) {
+            DUENOS.with(|duenos| duenos.borrow_mut().insert((contrato, id_token), destino));
+        }
+    }
+
+//////////////////////////TEST/////////////////////////////////////
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Ids de las categorias iniciales (CATEGORIAS_INICIALES).
+    const ELECTRODOMESTICOS: u32 = 0;
+    const LIMPIEZA: u32 = 1;
+    const ALIMENTOS: u32 = 2;
+    const HOGAR: u32 = 3;
+    const ROPA: u32 = 4;
+    const MASCOTAS: u32 = 5;
+    const LIBRERIA: u32 = 6;
+    const OTRO: u32 = 7;
+
+    ///Función auxiliar para generar AccountID distintos en cada test 
+    fn account(n: u8) -> AccountId {
+        AccountId::from([n; 32])
+    }
+
+    #[ink::test]
+    fn agregar_usuario_comp_exitoso() {
+        let mut contrato = PrimerContrato::new();
+        let acc = account(1);
+
+        let res = contrato.priv_agregar_usuario_sistema(
+            acc,
+            "Juan".to_string(),
+            "Pérez".to_string(),
+            "Calle Falsa 123".to_string(),
+            "juan@mail.com".to_string(),
+            Rol::Comp,
+        );
+
+        assert!(res.is_ok(), "Debería registrar correctamente");
+        let u = contrato.usuarios.get(acc).expect("Usuario no almacenado");
+        assert!(u.datos_comprador.is_some(), "Para Rol::Comp debe inicializar datos_comprador");
+        assert!(u.datos_vendedor.is_none(), "Para Rol::Comp no debe inicializar datos_vendedor");
+    }
+
+    #[ink::test]
+    fn agregar_usuario_vend_exitoso() {
+        let mut contrato = PrimerContrato::new();
+        let acc = account(2);
+
+        let res = contrato.priv_agregar_usuario_sistema(
+            acc,
+            "Ana".to_string(),
+            "López".to_string(),
+            "Av. Central".to_string(),
+            "ana@mail.com".to_string(),
+            Rol::Vend,
+        );
+
+        assert!(res.is_ok(), "Debería registrar correctamente");
+        let u = contrato.usuarios.get(acc).expect("Usuario no almacenado");
+        assert!(u.datos_comprador.is_none(), "Para Rol::Vend no debe inicializar datos_comprador");
+        assert!(u.datos_vendedor.is_some(), "Para Rol::Vend debe inicializar datos_vendedor");
+    }
+
+    #[ink::test]
+    fn agregar_usuario_ambos_exitoso() {
+        let mut contrato = PrimerContrato::new();
+        let acc = account(3);
+
+        let res = contrato.priv_agregar_usuario_sistema(
+            acc,
+            "Luis".to_string(),
+            "Gómez".to_string(),
+            "Ruta 8".to_string(),
+            "luis@mail.com".to_string(),
+            Rol::Ambos,
+        );
+
+        assert!(res.is_ok(), "Debería registrar correctamente");
+        let u = contrato.usuarios.get(acc).expect("Usuario no almacenado");
+        assert!(u.datos_comprador.is_some(), "Para Rol::Ambos debe inicializar datos_comprador");
+        assert!(u.datos_vendedor.is_some(), "Para Rol::Ambos debe inicializar datos_vendedor");
+    }
+
+    #[ink::test]
+    fn agregar_usuario_duplicado_falla() {
+        let mut contrato = PrimerContrato::new();
+        let acc = account(4);
+
+        assert!(contrato.priv_agregar_usuario_sistema(
+            acc,
+            "Sofía".to_string(),
+            "Ibarra".to_string(),
+            "Mitre 100".to_string(),
+            "sofia@mail.com".to_string(),
+            Rol::Vend,
+        ).is_ok());
+
+        let res = contrato.priv_agregar_usuario_sistema(
+            acc,
+            "Sofía".to_string(),
+            "Ibarra".to_string(),
+            "Mitre 100".to_string(),
+            "sofia@mail.com".to_string(),
+            Rol::Vend,
+        );
+
+        assert!(res.is_err(), "Debe devolver error por usuario duplicado");
+        assert_eq!(res.unwrap_err(), "El usuario ya esta registrado.");
+    }
+
+    #[ink::test]
+    fn modificar_rol_usuario_inexistente_falla() {
+        let mut contrato = PrimerContrato::new();
+        let acc = account(10);
+
+        let res = contrato.priv_modificar_rol(acc, Rol::Vend);
+
+        assert!(res.is_err(), "Debe fallar porque el usuario no existe");
+        assert_eq!(res.unwrap_err(), "No se encontro el usuario.");
+    }
+
+    #[ink::test]
+    fn modificar_rol_exitoso() {
+        let mut contrato = PrimerContrato::new();
+        let acc = account(11);
+
+        assert!(contrato.priv_agregar_usuario_sistema(
+            acc,
+            "Mario".to_string(),
+            "Rossi".to_string(),
+            "Calle Uno".to_string(),
+            "mario@mail.com".to_string(),
+            Rol::Comp,
+        ).is_ok());
+
+        let res = contrato.priv_modificar_rol(acc, Rol::Vend);
+        assert!(res.is_ok(), "El cambio de rol debería ser exitoso");
+
+        let u = contrato.usuarios.get(acc).unwrap();
+        assert_eq!(u.rol, Rol::Vend, "El rol debe haberse actualizado a Vend");
+    }
+
+    #[ink::test]
+    fn modificar_rol_invalido_se_rechaza() {
+        let mut contrato = PrimerContrato::new();
+        let acc = account(12);
+
+        assert!(contrato.priv_agregar_usuario_sistema(
+            acc,
+            "Lucía".to_string(),
+            "Fernández".to_string(),
+            "Calle Dos".to_string(),
+            "lucia@mail.com".to_string(),
+            Rol::Comp,
+        ).is_ok());
+
+        let res = contrato.priv_modificar_rol(acc, Rol::Comp); 
+        if res.is_err() {
+            assert_eq!(res.unwrap_err(), "El usuario ya posee ese rol.");
+        } else {
+            let u = contrato.usuarios.get(acc).unwrap();
+            assert_eq!(u.rol, Rol::Comp);
+        }
+    }
+
+    #[ink::test]
+    fn cargar_producto_precio_cero_falla() {
+        let mut contrato = PrimerContrato::new();
+        let acc = account(20);
+
+        contrato.priv_agregar_usuario_sistema(
+            acc,
+            "Carlos".to_string(),
+            "García".to_string(),
+            "Dir".to_string(),
+            "carlos@mail.com".to_string(),
+            Rol::Vend,
+        ).unwrap();
+
+        let res = contrato.priv_cargar_producto(
+            acc,
+            "ProductoX".to_string(),
+            "Desc".to_string(),
+            0, 
+            OTRO,
+            10,
+        );
+
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err(), "Precio no valido");
+    }
+
+    #[ink::test]
+    fn cargar_producto_stock_cero_falla() {
+        let mut contrato = PrimerContrato::new();
+        let acc = account(21);
+
+        contrato.priv_agregar_usuario_sistema(
+            acc,
+            "Marta".to_string(),
+            "Lopez".to_string(),
+            "Dir".to_string(),
+            "marta@mail.com".to_string(),
+            Rol::Vend,
+        ).unwrap();
+
+        let res = contrato.priv_cargar_producto(
+            acc,
+            "ProductoY".to_string(),
+            "Desc".to_string(),
+            100,
+            OTRO,
+            0, 
+        );
+
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err(), "Stock no valido");
+    }
+
+    #[ink::test]
+    fn cargar_producto_usuario_inexistente_falla() {
+        let mut contrato = PrimerContrato::new();
+        let acc = account(22);
+
+        let res = contrato.priv_cargar_producto(
+            acc,
+            "Prod".to_string(),
+            "Desc".to_string(),
+            100,
+            OTRO,
+            5,
+        );
+
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err(), "No se encontro el usuario.");
+    }
+
+    #[ink::test]
+    fn cargar_producto_usuario_no_vendedor_falla() {
+        let mut contrato = PrimerContrato::new();
+        let acc = account(23);
+
+        contrato.priv_agregar_usuario_sistema(
+            acc,
+            "Pepe".to_string(),
+            "Suarez".to_string(),
+            "Dir".to_string(),
+            "pepe@mail.com".to_string(),
+            Rol::Comp, 
+        ).unwrap();
+
+        let res = contrato.priv_cargar_producto(
+            acc,
+            "Prod".to_string(),
+            "Desc".to_string(),
+            200,
+            OTRO,
+            5,
+        );
+
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err(), "El usuario no tiene permisos para cargar productos. No es vendedor.");
+    }
+
+    #[ink::test]
+    fn cargar_producto_exitoso_vendedor() {
+        let mut contrato = PrimerContrato::new();
+        let acc = account(24);
+
+        contrato.priv_agregar_usuario_sistema(
+            acc,
+            "Vero".to_string(),
+            "Alvarez".to_string(),
+            "Dir".to_string(),
+            "vero@mail.com".to_string(),
+            Rol::Vend,
+        ).unwrap();
+
+        let res = contrato.priv_cargar_producto(
+            acc,
+            "ProductoZ".to_string(),
+            "Descripcion Z".to_string(),
+            500,
+            OTRO,
+            15,
+        );
+
+        assert!(res.is_ok(), "El producto debería cargarse correctamente");
+
+        assert_eq!(contrato.dimension_logica_productos, 1);
+
+        let almacenado = contrato.historial_productos.get(1).expect("No se guardó el producto");
+        assert_eq!(almacenado.1, 15, "Stock debe coincidir con el cargado");
+    }
+
+    #[ink::test]
+    fn visualizar_productos_usuario_inexistente_falla() {
+        let contrato = PrimerContrato::new();
+        let acc = account(30);
+
+        let res = contrato.priv_visualizar_productos_propios(acc);
+
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err(), "No se encontro el usuario.");
+    }
+
+    #[ink::test]
+    fn visualizar_productos_usuario_comprador_falla() {
+        let mut contrato = PrimerContrato::new();
+        let acc = account(31);
+
+        contrato.priv_agregar_usuario_sistema(
+            acc,
+            "Juan".to_string(),
+            "Comprador".to_string(),
+            "Dir".to_string(),
+            "juan@mail.com".to_string(),
+            Rol::Comp,
+        ).unwrap();
+
+        let res = contrato.priv_visualizar_productos_propios(acc);
+
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err(), "El usuario es comprador.");
+    }
+
+    #[ink::test]
+    fn visualizar_productos_vendedor_sin_productos_falla() {
+        let mut contrato = PrimerContrato::new();
+        let acc = account(32);
+
+        contrato.priv_agregar_usuario_sistema(
+            acc,
+            "Ana".to_string(),
+            "Vendedora".to_string(),
+            "Dir".to_string(),
+            "ana@mail.com".to_string(),
+            Rol::Vend,
+        ).unwrap();
+
+        let res = contrato.priv_visualizar_productos_propios(acc);
+
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err(), "El vendedor no tiene productos.");
+    }
+
+    #[ink::test]
+    fn visualizar_productos_vendedor_con_productos() {
+        let mut contrato = PrimerContrato::new();
+        let acc = account(33);
+
+        contrato.priv_agregar_usuario_sistema(
+            acc,
+            "Pedro".to_string(),
+            "Vendedor".to_string(),
+            "Dir".to_string(),
+            "pedro@mail.com".to_string(),
+            Rol::Vend,
+        ).unwrap();
+
+        contrato.priv_cargar_producto(
+            acc,
+            "Producto1".to_string(),
+            "Desc".to_string(),
+            100,
+            OTRO,
+            5,
+        ).unwrap();
+
+        let res = contrato.priv_visualizar_productos_propios(acc);
+
+        assert!(res.is_ok());
+        let lista = res.unwrap();
+        assert_eq!(lista.len(), 1);
+        assert_eq!(lista[0].1, 5);
+    }
+
+    #[ink::test]
+    fn visualizar_productos_ambos_con_productos() {
+        let mut contrato = PrimerContrato::new();
+        let acc = account(34);
+
+        contrato.priv_agregar_usuario_sistema(
+            acc,
+            "Lucia".to_string(),
+            "Mixta".to_string(),
+            "Dir".to_string(),
+            "lucia@mail.com".to_string(),
+            Rol::Ambos,
+        ).unwrap();
+
+        contrato.priv_cargar_producto(
+            acc,
+            "Producto2".to_string(),
+            "Desc".to_string(),
+            150,
+            OTRO,
+            8,
+        ).unwrap();
+
+        let res = contrato.priv_visualizar_productos_propios(acc);
+
+        assert!(res.is_ok());
+        let lista = res.unwrap();
+        assert_eq!(lista.len(), 1);
+        assert_eq!(lista[0].1, 8);
+    }
+
+    #[ink::test]
+    fn crear_publicacion_usuario_inexistente_falla() {
+        let mut contrato = PrimerContrato::new();
+        let acc = account(40);
+
+        let res = contrato.priv_crear_publicacion(acc, vec![(1, 2)], None);
+
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err(), "No se encontro el usuario.");
+    }
+
+    #[ink::test]
+    fn crear_publicacion_producto_cantidad_cero_falla() {
+        let mut contrato = PrimerContrato::new();
+        let acc = account(41);
+
+        contrato.priv_agregar_usuario_sistema(
+            acc,
+            "Luis".to_string(),
+            "Vendedor".to_string(),
+            "Dir".to_string(),
+            "luis@mail.com".to_string(),
+            Rol::Vend,
+        ).unwrap();
+
+        contrato.priv_cargar_producto(
+            acc,
+            "Prod".to_string(),
+            "Desc".to_string(),
+            100,
+            OTRO,
+            10,
+        ).unwrap();
+
+        let res = contrato.priv_crear_publicacion(acc, vec![(1, 0)], None);
+
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err(), "Un producto tiene cantidades no validas.");
+    }
+
+    #[ink::test]
+    fn crear_publicacion_producto_no_pertenece_falla() {
+        let mut contrato = PrimerContrato::new();
+        let acc1 = account(42);
+        let acc2 = account(43);
+
+        contrato.priv_agregar_usuario_sistema(
+            acc1,
+            "Ana".to_string(),
+            "Vend1".to_string(),
+            "Dir".to_string(),
+            "ana@mail.com".to_string(),
+            Rol::Vend,
+        ).unwrap();
+
+        contrato.priv_cargar_producto(
+            acc1,
+            "Prod".to_string(),
+            "Desc".to_string(),
+            100,
+            OTRO,
+            5,
+        ).unwrap();
+
+        contrato.priv_agregar_usuario_sistema(
+            acc2,
+            "Beto".to_string(),
+            "Vend2".to_string(),
+            "Dir".to_string(),
+            "beto@mail.com".to_string(),
+            Rol::Vend,
+        ).unwrap();
+
+        let res = contrato.priv_crear_publicacion(acc2, vec![(1, 2)], None);
+
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err(), "El vendedor no posee ese producto.");
+    }
+
+    #[ink::test]
+    fn crear_publicacion_sin_stock_falla() {
+        let mut contrato = PrimerContrato::new();
+        let acc = account(44);
+
+        contrato.priv_agregar_usuario_sistema(
+            acc,
+            "Clara".to_string(),
+            "Vend".to_string(),
+            "Dir".to_string(),
+            "clara@mail.com".to_string(),
+            Rol::Vend,
+        ).unwrap();
+
+        contrato.priv_cargar_producto(
+            acc,
+            "Prod".to_string(),
+            "Desc".to_string(),
+            50,
+            OTRO,
+            3, 
+        ).unwrap();
+
+        let res = contrato.priv_crear_publicacion(acc, vec![(1, 10)], None);
+
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err(), "No hay stock suficiente.");
+    }
+
+    #[ink::test]
+    fn crear_publicacion_exitosa() {
+        let mut contrato = PrimerContrato::new();
+        let acc = account(45);
+
+        contrato.priv_agregar_usuario_sistema(
+            acc,
+            "Diego".to_string(),
+            "Vend".to_string(),
+            "Dir".to_string(),
+            "diego@mail.com".to_string(),
+            Rol::Vend,
+        ).unwrap();
+
+        contrato.priv_cargar_producto(
+            acc,
+            "Prod".to_string(),
+            "Desc".to_string(),
+            200,
+            OTRO,
+            5,
+        ).unwrap();
+
+        let res = contrato.priv_crear_publicacion(acc, vec![(1, 2)], None);
+        assert!(res.is_ok());
+
+        assert_eq!(contrato.historial_publicaciones.len(), 1);
+
+        let prod = contrato.historial_productos.get(1).unwrap();
+        assert_eq!(prod.1, 3); //Comprobar si se descuenta el stock del producto almacenado al crear la publicación
+    }
+
+    #[ink::test]
+    fn visualizar_publicacion_inexistente_falla() {
+        let contrato = PrimerContrato::new();
+
+        let res = contrato.priv_visualizar_productos_de_publicacion(1);
+
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err(), "No se encontro la publicacion.");
+    }
+
+    #[ink::test]
+    fn visualizar_publicacion_existente_ok() {
+        let mut contrato = PrimerContrato::new();
+        let acc = account(50);
+
+        contrato.priv_agregar_usuario_sistema(
+            acc,
+            "Laura".to_string(),
+            "Vendedora".to_string(),
+            "Dir".to_string(),
+            "laura@mail.com".to_string(),
+            Rol::Vend,
+        ).unwrap();
+
+        contrato.priv_cargar_producto(
+            acc,
+            "Prod".to_string(),
+            "Desc".to_string(),
+            100,
+            OTRO,
+            10,
+        ).unwrap();
+
+        contrato.priv_crear_publicacion(acc, vec![(1, 2)], None).unwrap();
+
+        let res = contrato.priv_visualizar_productos_de_publicacion(0);
+        assert!(res.is_ok());
+
+        let publicacion = res.unwrap();
+        assert_eq!(publicacion.id_vendedor, acc);
+        assert_eq!(publicacion.productos.len(), 1);
+        assert_eq!(publicacion.productos[0].1, 2); 
+    }
+
+    #[ink::test]
+    fn visualizar_publicacion_id_inexistente_falla() {
+        let mut contrato = PrimerContrato::new();
+        let acc = account(51);
+
+        contrato.priv_agregar_usuario_sistema(
+            acc,
+            "Carlos".to_string(),
+            "Vend".to_string(),
+            "Dir".to_string(),
+            "carlos@mail.com".to_string(),
+            Rol::Vend,
+        ).unwrap();
+
+        contrato.priv_cargar_producto(
+            acc,
+            "Prod".to_string(),
+            "Desc".to_string(),
+            50,
+            OTRO,
+            5,
+        ).unwrap();
+
+        contrato.priv_crear_publicacion(acc, vec![(1, 1)], None).unwrap();
+
+        let res = contrato.priv_visualizar_productos_de_publicacion(99);
+
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err(), "No se encontro la publicacion.");
+    }
+
+   #[ink::test]
+    fn crear_orden_exito() {
+        let mut contrato = PrimerContrato::new();
+
+        let vendedor = account(60);
+        let comprador = account(61);
+
+        contrato.priv_agregar_usuario_sistema(
+            vendedor,
+            "Vendedor".to_string(),
+            "V".to_string(),
+            "Dir".to_string(),
+            "v@mail.com".to_string(),
+            Rol::Vend,
+        ).unwrap();
+
+        contrato.priv_agregar_usuario_sistema(
+            comprador,
+            "Comprador".to_string(),
+            "C".to_string(),
+            "Dir".to_string(),
+            "c@mail.com".to_string(),
+            Rol::Comp,
+        ).unwrap();
+
+        contrato.priv_cargar_producto(
+            vendedor,
+            "Producto".to_string(),
+            "Desc".to_string(),
+            100,
+            OTRO,
+            10,
+        ).unwrap();
+
+        contrato.priv_crear_publicacion(vendedor, vec![(1, 2)], None).unwrap(); // ID de la publicación = 0;  
+
+        let resultado = contrato.priv_crear_orden_de_compra(comprador, 0, None, 0, 0);
+        assert!(resultado.is_ok());
+
+        let historial = contrato.historial_ordenes_de_compra.len();
+        assert_eq!(historial, 1);
+    }
+
+    #[ink::test]
+    fn crear_orden_propia_falla() {
+        let mut contrato = PrimerContrato::new();
+        let usuario = account(62);
+
+        contrato.priv_agregar_usuario_sistema(
+            usuario,
+            "Mixto".to_string(),
+            "U".to_string(),
+            "Dir".to_string(),
+            "m@mail.com".to_string(),
+            Rol::Ambos,
+        ).unwrap();
+
+        contrato.priv_cargar_producto(
+            usuario,
+            "Prod".to_string(),
+            "Desc".to_string(),
+            100,
+            OTRO,
+            5,
+        ).unwrap();
+
+        contrato.priv_crear_publicacion(usuario, vec![(1, 1)], None).unwrap(); 
+
+        let resultado = contrato.priv_crear_orden_de_compra(usuario, 0, None, 0, 0);
+        assert!(resultado.is_err());
+        assert_eq!(resultado.unwrap_err(), "El usuario no puede comprar sus propias publicaciones.");
+    }
+
+    #[ink::test]
+    fn crear_orden_publicacion_comp_falla() {
+        let mut contrato = PrimerContrato::new();
+        let vendedor = account(63);
+        let comprador = account(64);
+
+        contrato.priv_agregar_usuario_sistema(
+            vendedor,
+            "V".to_string(),
+            "V".to_string(),
+            "Dir".to_string(),
+            "v@mail.com".to_string(),
+            Rol::Vend,
+        ).unwrap();
+
+        contrato.priv_agregar_usuario_sistema(
+            comprador,
+            "C".to_string(),
+            "C".to_string(),
+            "Dir".to_string(),
+            "c@mail.com".to_string(),
+            Rol::Comp,
+        ).unwrap();
+
+        contrato.priv_cargar_producto(
+            vendedor,
+            "Prod".to_string(),
+            "Desc".to_string(),
+            30,
+            OTRO,
+            5,
+        ).unwrap();
+
+        contrato.priv_crear_publicacion(vendedor, vec![(1, 1)], None).unwrap(); 
+
+        contrato.priv_modificar_rol(vendedor, Rol::Comp).unwrap();
+
+        let resultado = contrato.priv_crear_orden_de_compra(comprador, 0, None, 0, 0);
+        assert!(resultado.is_err()); //El vendedor de la publicación cambió su rol a comprador. Debe anular sus publicaciones;
+        assert_eq!(resultado.unwrap_err(), "La publicacion ya no se encuentra disponible.");
+    }
+
+    #[ink::test]
+    fn crear_orden_sin_stock_falla() {
+        let mut contrato = PrimerContrato::new();
+        let vendedor = account(65);
+        let comprador = account(66);
+
+        contrato.priv_agregar_usuario_sistema(
+            vendedor,
+            "V".to_string(),
+            "V".to_string(),
+            "Dir".to_string(),
+            "v@mail.com".to_string(),
+            Rol::Vend,
+        ).unwrap();
+
+        contrato.priv_agregar_usuario_sistema(
+            comprador,
+            "C".to_string(),
+            "C".to_string(),
+            "Dir".to_string(),
+            "c@mail.com".to_string(),
+            Rol::Comp,
+        ).unwrap();
+
+        contrato.priv_cargar_producto(
+            vendedor,
+            "Prod".to_string(),
+            "Desc".to_string(),
+            20,
+            OTRO,
+            1,
+        ).unwrap();
+
+        contrato.priv_crear_publicacion(vendedor, vec![(1, 1)], None).unwrap(); 
+
+        let mut publicacion = contrato.priv_visualizar_productos_de_publicacion(0).unwrap();
+        publicacion.disponible = false; //Se fuerza la disponibilidad de la publicación en falso;
+        let pos = contrato.devolver_posicion_publicacion(0).unwrap();
+        contrato.historial_publicaciones.set(pos, &(0u32, publicacion)); //Se actualiza la publicación;
+
+        let resultado = contrato.priv_crear_orden_de_compra(comprador, 0, None, 0, 0);
+        assert!(resultado.is_err());
+        assert_eq!(resultado.unwrap_err(), "La publicacion ya no tiene stock");
+    }
+
+    #[ink::test]
+    fn crear_orden_usuario_inexistente_falla() {
+        let mut contrato = PrimerContrato::new();
+        let vendedor = account(67);
+
+        contrato.priv_agregar_usuario_sistema(
+            vendedor,
+            "V".to_string(),
+            "V".to_string(),
+            "Dir".to_string(),
+            "v@mail.com".to_string(),
+            Rol::Vend,
+        ).unwrap();
 
+        contrato.priv_cargar_producto(
+            vendedor,
+            "Prod".to_string(),
+            "Desc".to_string(),
+            15,
+            OTRO,
+            2,
+        ).unwrap();
 
+        contrato.priv_crear_publicacion(vendedor, vec![(1, 1)], None).unwrap();
 
-/////////////////////////// ESTADO DE COMPRA ///////////////////////////
+        let account_invalido: AccountId = [0x0; 32].into();
 
-    #[derive(Clone, PartialEq, Debug)]
-    #[ink::scale_derive(Encode, Decode, TypeInfo)]
-    #[cfg_attr(feature = "std",derive(ink::storage::traits::StorageLayout))]
-    /// Enum utilizado para indicar el estado de la orden de compra. 
-    /// Pendiente (cuando la orden se crea).
-    /// Enviado (cuando el vendedor envía los productos de la publicación).
-    /// Recibido (cuando el comprador recibe los productos de la orden). 
-    /// Cancelada (solo se asignará cuando ambas partes de la orden de compra cancelan la misma). 
-    pub enum EstadoCompra{
-        Pendiente,
-        Enviado,
-        Recibido,
-        Cancelada,
+        let resultado = contrato.priv_crear_orden_de_compra(account_invalido, 0, None, 0, 0);
+        assert!(resultado.is_err());
     }
 
-/////////////////////////// CATEGORIAS ///////////////////////////
-#[derive(Clone, PartialEq, Debug)]
-#[ink::scale_derive(Encode, Decode, TypeInfo)]
-#[cfg_attr(feature = "std",derive(ink::storage::traits::StorageLayout))]
-
-    pub enum Categoria{
-        Electrodomesticos,
-        Limpieza,
-        Alimentos,
-        Hogar,
-        Ropa,
-        Mascotas,
-        Libreria,
-        Otro,
-}
+    #[ink::test]
+    fn crear_orden_publicacion_inexistente_falla() {
+        let mut contrato = PrimerContrato::new();
+        let comprador = account(68);
 
-//////////////////////////TEST/////////////////////////////////////
-mod tests {
-    use super::*;
+        contrato.priv_agregar_usuario_sistema(
+            comprador,
+            "C".to_string(),
+            "C".to_string(),
+            "Dir".to_string(),
+            "c@mail.com".to_string(),
+            Rol::Comp,
+        ).unwrap();
 
-    ///Función auxiliar para generar AccountID distintos en cada test 
-    fn account(n: u8) -> AccountId {
-        AccountId::from([n; 32])
+        let id_pub_invalido = 999u32;
+        let resultado = contrato.priv_crear_orden_de_compra(comprador, id_pub_invalido, None, 0, 0);
+        assert!(resultado.is_err());
     }
 
+
     #[ink::test]
-    fn agregar_usuario_comp_exitoso() {
+    fn cancelar_compra_comprador_exito() {
         let mut contrato = PrimerContrato::new();
-        let acc = account(1);
+        let vendedor = account(100);
+        let comprador = account(101);
 
-        let res = contrato.priv_agregar_usuario_sistema(
-            acc,
-            "Juan".to_string(),
-            "Pérez".to_string(),
-            "Calle Falsa 123".to_string(),
-            "juan@mail.com".to_string(),
-            Rol::Comp,
-        );
+        contrato.priv_agregar_usuario_sistema(
+            vendedor, "V".into(), "V".into(), "Dir".into(), "v@mail".into(), Rol::Vend
+        ).unwrap();
+        contrato.priv_agregar_usuario_sistema(
+            comprador, "C".into(), "C".into(), "Dir".into(), "c@mail".into(), Rol::Comp
+        ).unwrap();
 
-        assert!(res.is_ok(), "Debería registrar correctamente");
-        let u = contrato.usuarios.get(acc).expect("Usuario no almacenado");
-        assert!(u.datos_comprador.is_some(), "Para Rol::Comp debe inicializar datos_comprador");
-        assert!(u.datos_vendedor.is_none(), "Para Rol::Comp no debe inicializar datos_vendedor");
+    contrato.priv_cargar_producto(vendedor, "P".into(), "D".into(), 10, OTRO, 5).unwrap();
+        contrato.priv_crear_publicacion(vendedor, vec![(1, 2)], None).unwrap(); // pub 0
+
+        contrato.priv_crear_orden_de_compra(comprador, 0, None, 0, 0).unwrap();
+
+        let res = contrato.priv_cancelar_compra(comprador, 0);
+        assert!(res.is_ok());
+
+        let (_, orden) = contrato.historial_ordenes_de_compra.get(0).unwrap();
+        assert!(orden.cancelacion.1, "El comprador debe haber marcado la cancelación");
+        assert!(!orden.cancelacion.0, "El vendedor aún no debe haber marcado la cancelación");
+        assert_eq!(orden.estado, EstadoCompra::Pendiente, "El estado permanece Pendiente hasta que ambas partes cancelen");
     }
 
     #[ink::test]
-    fn agregar_usuario_vend_exitoso() {
+    fn cancelar_compra_vendedor_exito() {
         let mut contrato = PrimerContrato::new();
-        let acc = account(2);
+        let vendedor = account(102);
+        let comprador = account(103);
 
-        let res = contrato.priv_agregar_usuario_sistema(
-            acc,
-            "Ana".to_string(),
-            "López".to_string(),
-            "Av. Central".to_string(),
-            "ana@mail.com".to_string(),
-            Rol::Vend,
-        );
+        contrato.priv_agregar_usuario_sistema(
+            vendedor, "V".into(), "V".into(), "Dir".into(), "v@mail".into(), Rol::Vend
+        ).unwrap();
+        contrato.priv_agregar_usuario_sistema(
+            comprador, "C".into(), "C".into(), "Dir".into(), "c@mail".into(), Rol::Comp
+        ).unwrap();
 
-        assert!(res.is_ok(), "Debería registrar correctamente");
-        let u = contrato.usuarios.get(acc).expect("Usuario no almacenado");
-        assert!(u.datos_comprador.is_none(), "Para Rol::Vend no debe inicializar datos_comprador");
-        assert!(u.datos_vendedor.is_some(), "Para Rol::Vend debe inicializar datos_vendedor");
+    contrato.priv_cargar_producto(vendedor, "P".into(), "D".into(), 10, OTRO, 5).unwrap(); //5 productos alcemanados;
+        contrato.priv_crear_publicacion(vendedor, vec![(1, 2)], None).unwrap(); //3 productos almcenados;
+
+        contrato.priv_crear_orden_de_compra(comprador, 0, None, 0, 0).unwrap(); //1 producto almacenado. (Restockeo automatico de la publicacion);
+        let escrow = fondear_escrow(20);
+
+        contrato.priv_cancelar_compra(comprador, 0).unwrap();
+
+        let res = contrato.priv_cancelar_compra(vendedor, 0); //3 productos almacenados;
+        assert!(res.is_ok());
+        assert_eq!(saldo_nativo(comprador), 20); //Se reintegra el pago retenido
+        assert_eq!(saldo_retenido(escrow), 0);
+
+        let (_, orden) = contrato.historial_ordenes_de_compra.get(0).unwrap();
+        assert!(orden.cancelacion.0 && orden.cancelacion.1, "Ambas partes deben haber marcado cancelación");
+
+        let (_, stock) = contrato.historial_productos.get(1).unwrap();
+        assert_eq!(stock, 3); //El stock que estaba en la publicación debe restaurse correctamente; (5 - 2 - 2 + 2) = 3; 
     }
 
     #[ink::test]
-    fn agregar_usuario_ambos_exitoso() {
+    fn cancelar_compra_orden_no_existe_falla() {
         let mut contrato = PrimerContrato::new();
-        let acc = account(3);
+        let acc = account(104);
 
-        let res = contrato.priv_agregar_usuario_sistema(
-            acc,
-            "Luis".to_string(),
-            "Gómez".to_string(),
-            "Ruta 8".to_string(),
-            "luis@mail.com".to_string(),
-            Rol::Ambos,
-        );
+        contrato.priv_agregar_usuario_sistema(
+            acc, "U".into(), "U".into(), "Dir".into(), "u@mail".into(), Rol::Vend
+        ).unwrap();
 
-        assert!(res.is_ok(), "Debería registrar correctamente");
-        let u = contrato.usuarios.get(acc).expect("Usuario no almacenado");
-        assert!(u.datos_comprador.is_some(), "Para Rol::Ambos debe inicializar datos_comprador");
-        assert!(u.datos_vendedor.is_some(), "Para Rol::Ambos debe inicializar datos_vendedor");
+        let id_orden_invalido = 999u32;
+        let res = contrato.priv_cancelar_compra(acc, id_orden_invalido);
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err(), "No se encontro la orden.");
     }
 
     #[ink::test]
-    fn agregar_usuario_duplicado_falla() {
+    fn cancelar_compra_estado_no_cancelable_falla() {
         let mut contrato = PrimerContrato::new();
-        let acc = account(4);
+        let vendedor = account(105);
+        let comprador = account(106);
 
-        assert!(contrato.priv_agregar_usuario_sistema(
-            acc,
-            "Sofía".to_string(),
-            "Ibarra".to_string(),
-            "Mitre 100".to_string(),
-            "sofia@mail.com".to_string(),
-            Rol::Vend,
-        ).is_ok());
+        contrato.priv_agregar_usuario_sistema(vendedor, "V".into(), "V".into(), "D".into(), "v@mail".into(), Rol::Vend).unwrap();
+        contrato.priv_agregar_usuario_sistema(comprador, "C".into(), "C".into(), "D".into(), "c@mail".into(), Rol::Comp).unwrap();
+    contrato.priv_cargar_producto(vendedor, "P".into(), "D".into(), 10, OTRO, 3).unwrap();
+        contrato.priv_crear_publicacion(vendedor, vec![(1,1)], None).unwrap();
+        contrato.priv_crear_orden_de_compra(comprador, 0, None, 0, 0).unwrap();
 
-        let res = contrato.priv_agregar_usuario_sistema(
-            acc,
-            "Sofía".to_string(),
-            "Ibarra".to_string(),
-            "Mitre 100".to_string(),
-            "sofia@mail.com".to_string(),
-            Rol::Vend,
-        );
+        let (pos_id, mut orden) = contrato.historial_ordenes_de_compra.get(0).unwrap();
+        orden.estado = EstadoCompra::Enviado; //Se fuerza el estado de la orden a Enviado;
+        contrato.historial_ordenes_de_compra.set(0, &(pos_id, orden));
 
-        assert!(res.is_err(), "Debe devolver error por usuario duplicado");
-        assert_eq!(res.unwrap_err(), "El usuario ya esta registrado.");
+        let res = contrato.priv_cancelar_compra(comprador, 0);
+        assert!(res.is_err()); //La orden tiene el estado "Enviado";
+        assert_eq!(res.unwrap_err(), "Ya no se puede cancelar la compra.");
     }
 
     #[ink::test]
-    fn modificar_rol_usuario_inexistente_falla() {
+    fn cancelar_compra_usuario_inexistente_falla() {
         let mut contrato = PrimerContrato::new();
-        let acc = account(10);
-
-        let res = contrato.priv_modificar_rol(acc, Rol::Vend);
+        let id_orden = 0u32;
+        let account_invalido: AccountId = [0x0; 32].into();
 
-        assert!(res.is_err(), "Debe fallar porque el usuario no existe");
+        let res = contrato.priv_cancelar_compra(account_invalido, id_orden);
+        assert!(res.is_err());
         assert_eq!(res.unwrap_err(), "No se encontro el usuario.");
     }
 
     #[ink::test]
-    fn modificar_rol_exitoso() {
+     fn priv_recibir_compra_exito() {
         let mut contrato = PrimerContrato::new();
-        let acc = account(11);
+        let vendedor = account(200);
+        let comprador = account(201);
 
-        assert!(contrato.priv_agregar_usuario_sistema(
-            acc,
-            "Mario".to_string(),
-            "Rossi".to_string(),
-            "Calle Uno".to_string(),
-            "mario@mail.com".to_string(),
-            Rol::Comp,
-        ).is_ok());
+        contrato.priv_agregar_usuario_sistema(
+            vendedor, "V".into(), "V".into(), "Dir".into(), "v@mail".into(), Rol::Vend
+        ).unwrap();
+        contrato.priv_agregar_usuario_sistema(
+            comprador, "C".into(), "C".into(), "Dir".into(), "c@mail".into(), Rol::Comp
+        ).unwrap();
 
-        let res = contrato.priv_modificar_rol(acc, Rol::Vend);
-        assert!(res.is_ok(), "El cambio de rol debería ser exitoso");
+    contrato.priv_cargar_producto(vendedor, "P".into(), "D".into(), 10, OTRO, 3).unwrap();
+        contrato.priv_crear_publicacion(vendedor, vec![(1,1)], None).unwrap();
+        contrato.priv_crear_orden_de_compra(comprador, 0, None, 0, 0).unwrap();
 
-        let u = contrato.usuarios.get(acc).unwrap();
-        assert_eq!(u.rol, Rol::Vend, "El rol debe haberse actualizado a Vend");
+        contrato.priv_enviar_compra(vendedor, 0).unwrap();
+
+        let res = contrato.priv_recibir_compra(comprador, 0, 0);
+        assert!(res.is_ok());
+
+        let (_, orden) = contrato.historial_ordenes_de_compra.get(0).unwrap();
+        assert_eq!(orden.estado, EstadoCompra::Recibido);
     }
 
     #[ink::test]
-    fn modificar_rol_invalido_se_rechaza() {
+    fn priv_recibir_compra_usuario_no_encontrado_falla() {
         let mut contrato = PrimerContrato::new();
-        let acc = account(12);
-
-        assert!(contrato.priv_agregar_usuario_sistema(
-            acc,
-            "Lucía".to_string(),
-            "Fernández".to_string(),
-            "Calle Dos".to_string(),
-            "lucia@mail.com".to_string(),
-            Rol::Comp,
-        ).is_ok());
-
-        let res = contrato.priv_modificar_rol(acc, Rol::Comp); 
-        if res.is_err() {
-            assert_eq!(res.unwrap_err(), "El usuario ya posee ese rol.");
-        } else {
-            let u = contrato.usuarios.get(acc).unwrap();
-            assert_eq!(u.rol, Rol::Comp);
-        }
+        let account_invalido: AccountId = [0x0; 32].into();
+        let res = contrato.priv_recibir_compra(account_invalido, 0, 0);
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err(), "No se encontro el usuario.");
     }
 
     #[ink::test]
-    fn cargar_producto_precio_cero_falla() {
+    fn priv_recibir_compra_orden_no_encontrada_falla() {
         let mut contrato = PrimerContrato::new();
-        let acc = account(20);
-
+        let comprador = account(210);
         contrato.priv_agregar_usuario_sistema(
-            acc,
-            "Carlos".to_string(),
-            "García".to_string(),
-            "Dir".to_string(),
-            "carlos@mail.com".to_string(),
-            Rol::Vend,
+            comprador, "C".into(), "C".into(), "Dir".into(), "c@mail".into(), Rol::Comp
         ).unwrap();
 
-        let res = contrato.priv_cargar_producto(
-            acc,
-            "ProductoX".to_string(),
-            "Desc".to_string(),
-            0, 
-            Categoria::Otro,
-            10,
-        );
-
+        let res = contrato.priv_recibir_compra(comprador, 999, 0);
         assert!(res.is_err());
-        assert_eq!(res.unwrap_err(), "Precio no valido");
+        assert_eq!(res.unwrap_err(), "No existe la orden buscada.");
     }
 
     #[ink::test]
-    fn cargar_producto_stock_cero_falla() {
+    fn priv_recibir_compra_no_enviado_falla() {
         let mut contrato = PrimerContrato::new();
-        let acc = account(21);
+        let vendedor = account(220);
+        let comprador = account(221);
 
         contrato.priv_agregar_usuario_sistema(
-            acc,
-            "Marta".to_string(),
-            "Lopez".to_string(),
-            "Dir".to_string(),
-            "marta@mail.com".to_string(),
-            Rol::Vend,
+            vendedor, "V".into(), "V".into(), "Dir".into(), "v@mail".into(), Rol::Vend
+        ).unwrap();
+        contrato.priv_agregar_usuario_sistema(
+            comprador, "C".into(), "C".into(), "Dir".into(), "c@mail".into(), Rol::Comp
         ).unwrap();
 
-        let res = contrato.priv_cargar_producto(
-            acc,
-            "ProductoY".to_string(),
-            "Desc".to_string(),
-            100,
-            Categoria::Otro,
-            0, 
-        );
+    contrato.priv_cargar_producto(vendedor, "P".into(), "D".into(), 10, OTRO, 3).unwrap();
+        contrato.priv_crear_publicacion(vendedor, vec![(1,1)], None).unwrap();
+        contrato.priv_crear_orden_de_compra(comprador, 0, None, 0, 0).unwrap();
 
+        let res = contrato.priv_recibir_compra(comprador, 0, 0);
         assert!(res.is_err());
-        assert_eq!(res.unwrap_err(), "Stock no valido");
+        assert_eq!(res.unwrap_err(), "El producto todavia no fue enviado.");
     }
 
     #[ink::test]
-    fn cargar_producto_usuario_inexistente_falla() {
+    fn priv_recibir_compra_usuario_no_comprador_falla() {
         let mut contrato = PrimerContrato::new();
-        let acc = account(22);
+        let vendedor = account(230);
+        let comprador = account(231);
+        let otro = account(232); 
 
-        let res = contrato.priv_cargar_producto(
-            acc,
-            "Prod".to_string(),
-            "Desc".to_string(),
-            100,
-            Categoria::Otro,
-            5,
-        );
+        contrato.priv_agregar_usuario_sistema(
+            vendedor, "V".into(), "V".into(), "Dir".into(), "v@mail".into(), Rol::Vend
+        ).unwrap();
+        contrato.priv_agregar_usuario_sistema(
+            comprador, "C".into(), "C".into(), "Dir".into(), "c@mail".into(), Rol::Comp
+        ).unwrap();
+        contrato.priv_agregar_usuario_sistema(
+            otro, "O".into(), "O".into(), "Dir".into(), "o@mail".into(), Rol::Comp
+        ).unwrap();
 
-        assert!(res.is_err());
-        assert_eq!(res.unwrap_err(), "No se encontro el usuario.");
+    contrato.priv_cargar_producto(vendedor, "P".into(), "D".into(), 10, OTRO, 3).unwrap();
+        contrato.priv_crear_publicacion(vendedor, vec![(1,1)], None).unwrap();
+        contrato.priv_crear_orden_de_compra(comprador, 0, None, 0, 0).unwrap();
+        contrato.priv_enviar_compra(vendedor, 0).unwrap();
+
+        let res = contrato.priv_recibir_compra(otro, 0, 0);
+        assert!(res.is_err()); //Otro usuario externo a la orden de la compra en cuestión no puede alterarla;
+        assert_eq!(res.unwrap_err(), "No se encontro la orden de compra.");
     }
 
     #[ink::test]
-    fn cargar_producto_usuario_no_vendedor_falla() {
+    fn priv_calificar_exito() {
         let mut contrato = PrimerContrato::new();
-        let acc = account(23);
+        let vendedor = account(150);
+        let comprador = account(151);
 
         contrato.priv_agregar_usuario_sistema(
-            acc,
-            "Pepe".to_string(),
-            "Suarez".to_string(),
-            "Dir".to_string(),
-            "pepe@mail.com".to_string(),
-            Rol::Comp, 
+            vendedor, "V".into(), "V".into(), "Dir".into(), "v@mail".into(), Rol::Vend
+        ).unwrap();
+        contrato.priv_agregar_usuario_sistema(
+            comprador, "C".into(), "C".into(), "Dir".into(), "c@mail".into(), Rol::Comp
         ).unwrap();
 
-        let res = contrato.priv_cargar_producto(
-            acc,
-            "Prod".to_string(),
-            "Desc".to_string(),
-            200,
-            Categoria::Otro,
-            5,
-        );
+    contrato.priv_cargar_producto(vendedor, "P".into(), "D".into(), 10, OTRO, 5).unwrap();
+        contrato.priv_crear_publicacion(vendedor, vec![(1,1)], None).unwrap();
+        contrato.priv_crear_orden_de_compra(comprador, 0, None, 0, 0).unwrap();
+        contrato.priv_enviar_compra(vendedor, 0).unwrap();
+        contrato.priv_recibir_compra(comprador, 0, 0).unwrap();
 
-        assert!(res.is_err());
-        assert_eq!(res.unwrap_err(), "El usuario no tiene permisos para cargar productos. No es vendedor.");
+        let res = contrato.priv_calificar(0, 5, comprador, 0);
+        assert!(res.is_ok());
+
+        let vend = contrato.buscar_usuario(vendedor).unwrap();
+        assert_eq!(vend.datos_vendedor.unwrap().reputacion_como_vendedor[0], 5);
     }
 
     #[ink::test]
-    fn cargar_producto_exitoso_vendedor() {
+    fn priv_calificar_valor_fuera_rango_falla() {
         let mut contrato = PrimerContrato::new();
-        let acc = account(24);
+        let vendedor = account(152);
+        let comprador = account(153);
 
         contrato.priv_agregar_usuario_sistema(
-            acc,
-            "Vero".to_string(),
-            "Alvarez".to_string(),
-            "Dir".to_string(),
-            "vero@mail.com".to_string(),
-            Rol::Vend,
+            vendedor, "V".into(), "V".into(), "Dir".into(), "v@mail".into(), Rol::Vend
+        ).unwrap();
+        contrato.priv_agregar_usuario_sistema(
+            comprador, "C".into(), "C".into(), "Dir".into(), "c@mail".into(), Rol::Comp
         ).unwrap();
 
-        let res = contrato.priv_cargar_producto(
-            acc,
-            "ProductoZ".to_string(),
-            "Descripcion Z".to_string(),
-            500,
-            Categoria::Otro,
-            15,
-        );
-
-        assert!(res.is_ok(), "El producto debería cargarse correctamente");
-
-        assert_eq!(contrato.dimension_logica_productos, 1);
+    contrato.priv_cargar_producto(vendedor, "P".into(), "D".into(), 10, OTRO, 5).unwrap();
+        contrato.priv_crear_publicacion(vendedor, vec![(1,1)], None).unwrap();
+        contrato.priv_crear_orden_de_compra(comprador, 0, None, 0, 0).unwrap();
+        contrato.priv_enviar_compra(vendedor, 0).unwrap();
+        contrato.priv_recibir_compra(comprador, 0, 0).unwrap();
 
-        let almacenado = contrato.historial_productos.get(1).expect("No se guardó el producto");
-        assert_eq!(almacenado.1, 15, "Stock debe coincidir con el cargado");
+        let res = contrato.priv_calificar(0, 0, comprador, 0);
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err(), "El valor de la calificacion no es valido (1..5).");
     }
 
     #[ink::test]
-    fn visualizar_productos_usuario_inexistente_falla() {
-        let contrato = PrimerContrato::new();
-        let acc = account(30);
+    fn priv_calificar_duplicada_falla() {
+        let mut contrato = PrimerContrato::new();
+        let vendedor = account(154);
+        let comprador = account(155);
 
-        let res = contrato.priv_visualizar_productos_propios(acc);
+        contrato.priv_agregar_usuario_sistema(
+            vendedor, "V".into(), "V".into(), "Dir".into(), "v@mail".into(), Rol::Vend
+        ).unwrap();
+        contrato.priv_agregar_usuario_sistema(
+            comprador, "C".into(), "C".into(), "Dir".into(), "c@mail".into(), Rol::Comp
+        ).unwrap();
 
-        assert!(res.is_err());
-        assert_eq!(res.unwrap_err(), "No se encontro el usuario.");
+        contrato.priv_cargar_producto(vendedor, "P".into(), "D".into(), 10, OTRO, 5).unwrap();
+        contrato.priv_crear_publicacion(vendedor, vec![(1,1)], None).unwrap();
+        contrato.priv_crear_orden_de_compra(comprador, 0, None, 0, 0).unwrap();
+        contrato.priv_enviar_compra(vendedor, 0).unwrap();
+        contrato.priv_recibir_compra(comprador, 0, 0).unwrap();
+
+        contrato.priv_calificar(0, 5, comprador, 0).unwrap();
+        let res2 = contrato.priv_calificar(0, 4, comprador, 0);
+        assert!(res2.is_err());
+        assert_eq!(res2.unwrap_err(), "El usuario ya califico.");
     }
 
     #[ink::test]
-    fn visualizar_productos_usuario_comprador_falla() {
+    fn priv_calificar_orden_no_recibida_falla() {
         let mut contrato = PrimerContrato::new();
-        let acc = account(31);
+        let vendedor = account(156);
+        let comprador = account(157);
 
         contrato.priv_agregar_usuario_sistema(
-            acc,
-            "Juan".to_string(),
-            "Comprador".to_string(),
-            "Dir".to_string(),
-            "juan@mail.com".to_string(),
-            Rol::Comp,
+            vendedor, "V".into(), "V".into(), "Dir".into(), "v@mail".into(), Rol::Vend
+        ).unwrap();
+        contrato.priv_agregar_usuario_sistema(
+            comprador, "C".into(), "C".into(), "Dir".into(), "c@mail".into(), Rol::Comp
         ).unwrap();
 
-        let res = contrato.priv_visualizar_productos_propios(acc);
+    contrato.priv_cargar_producto(vendedor, "P".into(), "D".into(), 10, OTRO, 5).unwrap();
+        contrato.priv_crear_publicacion(vendedor, vec![(1,1)], None).unwrap();
+        contrato.priv_crear_orden_de_compra(comprador, 0, None, 0, 0).unwrap();
 
+        let res = contrato.priv_calificar(0, 5, comprador, 0);
         assert!(res.is_err());
-        assert_eq!(res.unwrap_err(), "El usuario es comprador.");
     }
 
     #[ink::test]
-    fn visualizar_productos_vendedor_sin_productos_falla() {
+    fn priv_calificar_usuario_no_encontrado_falla() {
         let mut contrato = PrimerContrato::new();
-        let acc = account(32);
+        let vendedor = account(158);
+        let comprador = account(159);
 
         contrato.priv_agregar_usuario_sistema(
-            acc,
-            "Ana".to_string(),
-            "Vendedora".to_string(),
-            "Dir".to_string(),
-            "ana@mail.com".to_string(),
-            Rol::Vend,
+            vendedor, "V".into(), "V".into(), "Dir".into(), "v@mail".into(), Rol::Vend
         ).unwrap();
 
-        let res = contrato.priv_visualizar_productos_propios(acc);
+    contrato.priv_cargar_producto(vendedor, "P".into(), "D".into(), 10, OTRO, 5).unwrap();
+        contrato.priv_crear_publicacion(vendedor, vec![(1,1)], None).unwrap();
 
+        let account_invalido: AccountId = [0x0; 32].into();
+        let res = contrato.priv_calificar(0, 5, account_invalido, 0);
         assert!(res.is_err());
-        assert_eq!(res.unwrap_err(), "El vendedor no tiene productos.");
+        assert_eq!(res.unwrap_err(), "No se encontro el usuario.");
     }
 
     #[ink::test]
-    fn visualizar_productos_vendedor_con_productos() {
+    fn priv_calificar_usuario_no_participante_falla() {
         let mut contrato = PrimerContrato::new();
-        let acc = account(33);
+        let vendedor = account(160);
+        let comprador = account(161);
+        let otro = account(162);
 
         contrato.priv_agregar_usuario_sistema(
-            acc,
-            "Pedro".to_string(),
-            "Vendedor".to_string(),
-            "Dir".to_string(),
-            "pedro@mail.com".to_string(),
-            Rol::Vend,
+            vendedor, "V".into(), "V".into(), "Dir".into(), "v@mail".into(), Rol::Vend
         ).unwrap();
-
-        contrato.priv_cargar_producto(
-            acc,
-            "Producto1".to_string(),
-            "Desc".to_string(),
-            100,
-            Categoria::Otro,
-            5,
+        contrato.priv_agregar_usuario_sistema(
+            comprador, "C".into(), "C".into(), "Dir".into(), "c@mail".into(), Rol::Comp
+        ).unwrap();
+        contrato.priv_agregar_usuario_sistema(
+            otro, "O".into(), "O".into(), "Dir".into(), "o@mail".into(), Rol::Comp
         ).unwrap();
 
-        let res = contrato.priv_visualizar_productos_propios(acc);
+    contrato.priv_cargar_producto(vendedor, "P".into(), "D".into(), 10, OTRO, 5).unwrap();
+        contrato.priv_crear_publicacion(vendedor, vec![(1,1)], None).unwrap();
+        contrato.priv_crear_orden_de_compra(comprador, 0, None, 0, 0).unwrap();
+        contrato.priv_enviar_compra(vendedor, 0).unwrap();
+        contrato.priv_recibir_compra(comprador, 0, 0).unwrap();
 
-        assert!(res.is_ok());
-        let lista = res.unwrap();
-        assert_eq!(lista.len(), 1);
-        assert_eq!(lista[0].1, 5);
+        let res = contrato.priv_calificar(0, 4, otro, 0);
+        assert!(res.is_err());
     }
 
+
     #[ink::test]
-    fn visualizar_productos_ambos_con_productos() {
+    fn modificar_a_rol_ambos_sin_datos_previos() {
         let mut contrato = PrimerContrato::new();
-        let acc = account(34);
+        let usuario = account(200);
 
         contrato.priv_agregar_usuario_sistema(
-            acc,
-            "Lucia".to_string(),
-            "Mixta".to_string(),
+            usuario,
+            "Nombre".to_string(),
+            "Apellido".to_string(),
             "Dir".to_string(),
-            "lucia@mail.com".to_string(),
-            Rol::Ambos,
+            "u@mail.com".to_string(),
+            Rol::Comp,
         ).unwrap();
 
-        contrato.priv_cargar_producto(
-            acc,
-            "Producto2".to_string(),
-            "Desc".to_string(),
-            150,
-            Categoria::Otro,
-            8,
-        ).unwrap();
+        let res = contrato.priv_modificar_rol(usuario, Rol::Ambos);
+        assert!(res.is_ok());
 
-        let res = contrato.priv_visualizar_productos_propios(acc);
+        let u = contrato.buscar_usuario(usuario).unwrap();
+        assert!(u.datos_comprador.is_some());
+        assert!(u.datos_vendedor.is_some());
 
-        assert!(res.is_ok());
-        let lista = res.unwrap();
-        assert_eq!(lista.len(), 1);
-        assert_eq!(lista[0].1, 8);
+        assert_eq!(u.datos_comprador.as_ref().unwrap().ordenes_de_compra.len(), 0);
+        assert_eq!(u.datos_comprador.as_ref().unwrap().reputacion_como_comprador.len(), 0);
+        assert_eq!(u.datos_vendedor.as_ref().unwrap().productos.len(), 0);
+        assert_eq!(u.datos_vendedor.as_ref().unwrap().publicaciones.len(), 0);
+        assert_eq!(u.datos_vendedor.as_ref().unwrap().reputacion_como_vendedor.len(), 0);
     }
 
     #[ink::test]
-    fn crear_publicacion_usuario_inexistente_falla() {
+    fn modificar_a_rol_ambos_con_datos_comprador_previos() {
         let mut contrato = PrimerContrato::new();
-        let acc = account(40);
+        let usuario = account(201);
+
+        contrato.priv_agregar_usuario_sistema(
+            usuario,
+            "Comprador".to_string(),
+            "Uno".to_string(),
+            "Dir".to_string(),
+            "c@mail.com".to_string(),
+            Rol::Comp,
+        ).unwrap();
+
+        //Los datos del comprador no deben perderse si se modifica su rol;
+        {
+            let mut u = contrato.buscar_usuario(usuario).unwrap();
+            u.datos_comprador = Some(Comprador {
+                ordenes_de_compra: vec![1, 2],
+                reputacion_como_comprador: vec![5],
+            });
+            contrato.usuarios.insert(usuario, &u);
+        }
 
-        let res = contrato.priv_crear_publicacion(acc, vec![(1, 2)]);
+        let res = contrato.priv_modificar_rol(usuario, Rol::Ambos);
+        assert!(res.is_ok());
 
-        assert!(res.is_err());
-        assert_eq!(res.unwrap_err(), "No se encontro el usuario.");
+        let u = contrato.buscar_usuario(usuario).unwrap();
+        assert!(u.datos_comprador.is_some()); //Rol previo
+        assert!(u.datos_vendedor.is_some()); //Rol nuevo (ambos)
+
+        //Fijarse que efectivamente sus datos siguen estando
+        assert_eq!(u.datos_comprador.as_ref().unwrap().ordenes_de_compra.len(), 2);
+        assert_eq!(u.datos_comprador.as_ref().unwrap().reputacion_como_comprador.len(), 1);
     }
 
     #[ink::test]
-    fn crear_publicacion_producto_cantidad_cero_falla() {
+    fn modificar_a_rol_ambos_con_datos_vendedor_previos() {
         let mut contrato = PrimerContrato::new();
-        let acc = account(41);
+        let usuario = account(202);
 
         contrato.priv_agregar_usuario_sistema(
-            acc,
-            "Luis".to_string(),
+            usuario,
             "Vendedor".to_string(),
+            "Dos".to_string(),
             "Dir".to_string(),
-            "luis@mail.com".to_string(),
+            "v@mail.com".to_string(),
             Rol::Vend,
         ).unwrap();
 
-        contrato.priv_cargar_producto(
-            acc,
-            "Prod".to_string(),
-            "Desc".to_string(),
-            100,
-            Categoria::Otro,
-            10,
-        ).unwrap();
+        {
+            let mut u = contrato.buscar_usuario(usuario).unwrap();
+            u.datos_vendedor = Some(Vendedor {
+                productos: vec![10],
+                publicaciones: vec![5],
+                reputacion_como_vendedor: vec![3],
+                calificaciones_ponderadas: Vec::new(),
+                reputacion_por_categoria: Vec::new(),
+            });
+            contrato.usuarios.insert(usuario, &u);
+        }
+
+        let res = contrato.priv_modificar_rol(usuario, Rol::Ambos);
+        assert!(res.is_ok());
 
-        let res = contrato.priv_crear_publicacion(acc, vec![(1, 0)]);
+        let u = contrato.buscar_usuario(usuario).unwrap();
+        assert!(u.datos_comprador.is_some());
+        assert!(u.datos_vendedor.is_some());
 
-        assert!(res.is_err());
-        assert_eq!(res.unwrap_err(), "Un producto tiene cantidades no validas.");
+        assert_eq!(u.datos_vendedor.as_ref().unwrap().productos.len(), 1);
+        assert_eq!(u.datos_vendedor.as_ref().unwrap().publicaciones.len(), 1);
     }
 
     #[ink::test]
-    fn crear_publicacion_producto_no_pertenece_falla() {
+    fn modificar_a_rol_ambos_con_ambos_datos_existentes() {
         let mut contrato = PrimerContrato::new();
-        let acc1 = account(42);
-        let acc2 = account(43);
-
-        contrato.priv_agregar_usuario_sistema(
-            acc1,
-            "Ana".to_string(),
-            "Vend1".to_string(),
-            "Dir".to_string(),
-            "ana@mail.com".to_string(),
-            Rol::Vend,
-        ).unwrap();
-
-        contrato.priv_cargar_producto(
-            acc1,
-            "Prod".to_string(),
-            "Desc".to_string(),
-            100,
-            Categoria::Otro,
-            5,
-        ).unwrap();
+        let usuario = account(203);
 
         contrato.priv_agregar_usuario_sistema(
-            acc2,
-            "Beto".to_string(),
-            "Vend2".to_string(),
+            usuario,
+            "Ambos".to_string(),
+            "Tres".to_string(),
             "Dir".to_string(),
-            "beto@mail.com".to_string(),
-            Rol::Vend,
+            "a@mail.com".to_string(),
+            Rol::Ambos,
         ).unwrap();
 
-        let res = contrato.priv_crear_publicacion(acc2, vec![(1, 2)]);
+        assert!(contrato.priv_modificar_rol(usuario, Rol::Vend).is_ok());
+        let res = contrato.priv_modificar_rol(usuario, Rol::Ambos);
+        assert!(res.is_ok());
 
-        assert!(res.is_err());
-        assert_eq!(res.unwrap_err(), "El vendedor no posee ese producto.");
+        let u = contrato.buscar_usuario(usuario).unwrap();
+        assert!(u.datos_comprador.is_some());
+        assert!(u.datos_vendedor.is_some());
     }
 
     #[ink::test]
-    fn crear_publicacion_sin_stock_falla() {
-        let mut contrato = PrimerContrato::new();
-        let acc = account(44);
+    fn enviar_compra_fallo_estado() {
+        let mut instance = PrimerContrato::new();
+        let vendedor = account(240);
+        let comprador = account(241);
 
-        contrato.priv_agregar_usuario_sistema(
-            acc,
-            "Clara".to_string(),
-            "Vend".to_string(),
-            "Dir".to_string(),
-            "clara@mail.com".to_string(),
-            Rol::Vend,
+        instance.priv_agregar_usuario_sistema(
+            vendedor, "V".into(), "V".into(), "Dir".into(), "v@mail".into(), Rol::Vend
         ).unwrap();
-
-        contrato.priv_cargar_producto(
-            acc,
-            "Prod".to_string(),
-            "Desc".to_string(),
-            50,
-            Categoria::Otro,
-            3, 
+        instance.priv_agregar_usuario_sistema(
+            comprador, "C".into(), "C".into(), "Dir".into(), "c@mail".into(), Rol::Comp
         ).unwrap();
 
-        let res = contrato.priv_crear_publicacion(acc, vec![(1, 10)]);
+    instance.priv_cargar_producto(vendedor, "P".into(), "D".into(), 10, OTRO, 3).unwrap();
+        instance.priv_crear_publicacion(vendedor, vec![(1,1)], None).unwrap();
 
-        assert!(res.is_err());
-        assert_eq!(res.unwrap_err(), "No hay stock suficiente.");
+        instance.priv_crear_orden_de_compra(comprador, 0, None, 0, 0).unwrap();
+
+        let (pos_id, mut orden) = instance.historial_ordenes_de_compra.get(0).unwrap();
+        orden.estado = EstadoCompra::Enviado;
+        instance.historial_ordenes_de_compra.set(0, &(pos_id, orden));
+
+        let result = instance.priv_enviar_compra(vendedor, 0);
+        assert!(result.is_err());
+        assert_eq!(result.unwrap_err(), "El producto no puede ser enviado.".to_string());
     }
+    
+        #[ink::test]
+        fn enviar_compra_fallo_orden_no_existe() {
+            let mut instance = PrimerContrato::new();
+            let vendedor = account(240);
+            let comprador = account(241);
 
-    #[ink::test]
-    fn crear_publicacion_exitosa() {
-        let mut contrato = PrimerContrato::new();
-        let acc = account(45);
+            instance.priv_agregar_usuario_sistema(
+                vendedor, "V".into(), "V".into(), "Dir".into(), "v@mail".into(), Rol::Vend
+            ).unwrap();
+            instance.priv_agregar_usuario_sistema(
+                comprador, "C".into(), "C".into(), "Dir".into(), "c@mail".into(), Rol::Comp
+            ).unwrap();
 
-        contrato.priv_agregar_usuario_sistema(
-            acc,
-            "Diego".to_string(),
-            "Vend".to_string(),
-            "Dir".to_string(),
-            "diego@mail.com".to_string(),
-            Rol::Vend,
-        ).unwrap();
+            instance.priv_cargar_producto(vendedor, "P".into(), "D".into(), 10, OTRO, 3).unwrap();
+            instance.priv_crear_publicacion(vendedor, vec![(1,1)], None).unwrap();
 
-        contrato.priv_cargar_producto(
-            acc,
-            "Prod".to_string(),
-            "Desc".to_string(),
-            200,
-            Categoria::Otro,
-            5,
-        ).unwrap();
+            let result = instance.priv_enviar_compra(vendedor, 999); 
 
-        let res = contrato.priv_crear_publicacion(acc, vec![(1, 2)]);
-        assert!(res.is_ok());
+            assert!(result.is_err());
+            assert_eq!(result.unwrap_err(), "No existe la orden buscada.".to_string());
+        }
 
-        assert_eq!(contrato.historial_publicaciones.len(), 1);
+   
+    #[ink::test]
+    fn comprobar_rol_ambos_casos() {
+        let usuario_ambos = account(1);
+        let vendedor = account(2);
+        let comprador = account(3);
+        let _otro_usuario = account(4);
 
-        let prod = contrato.historial_productos.get(1).unwrap();
-        assert_eq!(prod.1, 3); //Comprobar si se descuenta el stock del producto almacenado al crear la publicación
+        let usuario = Usuario::nuevo(
+            usuario_ambos,
+            "Nombre".into(),
+            "Apellido".into(),
+            "Dir".into(),
+            "u@mail".into(),
+            Rol::Ambos,
+            0,
+        );
+
+        let resultado_vendedor = usuario.comprobar_rol(usuario_ambos, comprador);
+        assert_eq!(resultado_vendedor, Ok(Rol::Vend));
+
+        let resultado_comprador = usuario.comprobar_rol(vendedor, usuario_ambos);
+        assert_eq!(resultado_comprador, Ok(Rol::Comp));
+
+        let resultado_error = usuario.comprobar_rol(vendedor, comprador);
+        assert!(resultado_error.is_err());
+        assert_eq!(resultado_error.unwrap_err(), "El usuario no participa de la compra.".to_string());
     }
 
+
     #[ink::test]
-    fn visualizar_publicacion_inexistente_falla() {
-        let contrato = PrimerContrato::new();
+    fn comprobar_rol_errores() {
+        let mut instancia = PrimerContrato::new(); 
+        let vendedor_real = account(1);
+        let comprador_real = account(2);
+        let otro_vendedor = account(3);
+        let otro_comprador = account(4);
 
-        let res = contrato.priv_visualizar_productos_de_publicacion(1);
+        let mut usuario_vendedor = Usuario::nuevo(
+            vendedor_real,
+            "Vendedor".to_string(),
+            "Apellido".to_string(),
+            "Dir".to_string(),
+            "v@mail.com".to_string(),
+            Rol::Vend,
+            0,
+        );
 
-        assert!(res.is_err());
-        assert_eq!(res.unwrap_err(), "No se encontro la publicacion.");
+        let resultado_error_vendedor = usuario_vendedor.comprobar_rol(otro_vendedor, comprador_real);
+        assert!(resultado_error_vendedor.is_err());
+        assert_eq!(resultado_error_vendedor.unwrap_err(), "El vendedor no posee esta publicacion.".to_string());
+
+        let mut usuario_comprador = Usuario::nuevo(
+            comprador_real,
+            "Comprador".to_string(),
+            "Apellido".to_string(),
+            "Dir".to_string(),
+            "c@mail.com".to_string(),
+            Rol::Comp,
+            0,
+        );
+
+        let resultado_error_comprador = usuario_comprador.comprobar_rol(vendedor_real, otro_comprador);
+        assert!(resultado_error_comprador.is_err());
+        assert_eq!(resultado_error_comprador.unwrap_err(), "El usuario no posee esa orden de compra.".to_string());
     }
 
     #[ink::test]
-    fn visualizar_publicacion_existente_ok() {
+    fn actualizar_publicaciones_exito() {
         let mut contrato = PrimerContrato::new();
-        let acc = account(50);
+        let vendedor = account(210);
 
         contrato.priv_agregar_usuario_sistema(
-            acc,
-            "Laura".to_string(),
-            "Vendedora".to_string(),
-            "Dir".to_string(),
-            "laura@mail.com".to_string(),
-            Rol::Vend,
+            vendedor, "V".into(), "V".into(), "Dir".into(), "v@mail".into(), Rol::Vend
         ).unwrap();
 
         contrato.priv_cargar_producto(
-            acc,
-            "Prod".to_string(),
-            "Desc".to_string(),
-            100,
-            Categoria::Otro,
-            10,
+            vendedor, "Producto".into(), "Desc".into(), 100, OTRO, 5
         ).unwrap();
 
-        contrato.priv_crear_publicacion(acc, vec![(1, 2)]).unwrap();
+        contrato.priv_crear_publicacion(vendedor, vec![(1, 2)], None).unwrap(); 
 
-        let res = contrato.priv_visualizar_productos_de_publicacion(0);
-        assert!(res.is_ok());
+        let orig = contrato.priv_visualizar_productos_de_publicacion(0).unwrap();
 
-        let publicacion = res.unwrap();
-        assert_eq!(publicacion.id_vendedor, acc);
-        assert_eq!(publicacion.productos.len(), 1);
-        assert_eq!(publicacion.productos[0].1, 2); 
+        let mut nueva = Publicacion {
+            id: orig.id,
+            productos: orig.productos.clone(),
+            precio_final: orig.precio_final + 500,
+            id_vendedor: orig.id_vendedor,
+            disponible: false,
+            token_de_pago: None,
+            subasta: None,
+            promocion: None,
+            variantes: Vec::new(),
+            periodo_suscripcion: None,
+        };
+
+        assert!(contrato.actualizar_publicaciones(nueva.clone(), 0).is_ok());
+
+        let pos = contrato.devolver_posicion_publicacion(0).unwrap();
+        let (_id, guardada) = contrato.historial_publicaciones.get(pos).unwrap();
+        assert_eq!(guardada.precio_final, nueva.precio_final);
+        assert_eq!(guardada.disponible, nueva.disponible);
     }
 
     #[ink::test]
-    fn visualizar_publicacion_id_inexistente_falla() {
+    fn actualizar_publicaciones_id_inexistente_falla() {
         let mut contrato = PrimerContrato::new();
-        let acc = account(51);
+        let vendedor = account(211);
 
         contrato.priv_agregar_usuario_sistema(
-            acc,
-            "Carlos".to_string(),
-            "Vend".to_string(),
-            "Dir".to_string(),
-            "carlos@mail.com".to_string(),
-            Rol::Vend,
-        ).unwrap();
-
-        contrato.priv_cargar_producto(
-            acc,
-            "Prod".to_string(),
-            "Desc".to_string(),
-            50,
-            Categoria::Otro,
-            5,
+            vendedor, "V".into(), "V".into(), "Dir".into(), "v@mail".into(), Rol::Vend
         ).unwrap();
 
-        contrato.priv_crear_publicacion(acc, vec![(1, 1)]).unwrap();
-
-        let res = contrato.priv_visualizar_productos_de_publicacion(99);
+        let pub_falsa = Publicacion {
+            id: 999,
+            productos: vec![],
+            precio_final: 0,
+            id_vendedor: vendedor,
+            disponible: true,
+            token_de_pago: None,
+            subasta: None,
+            promocion: None,
+            variantes: Vec::new(),
+            periodo_suscripcion: None,
+        };
 
+        let res = contrato.actualizar_publicaciones(pub_falsa, 999);
         assert!(res.is_err());
-        assert_eq!(res.unwrap_err(), "No se encontro la publicacion.");
     }
 
-   #[ink::test]
-    fn crear_orden_exito() {
+    #[ink::test]
+    fn priv_calificar_comprador_exito() {
         let mut contrato = PrimerContrato::new();
-
-        let vendedor = account(60);
-        let comprador = account(61);
-
-        contrato.priv_agregar_usuario_sistema(
-            vendedor,
-            "Vendedor".to_string(),
-            "V".to_string(),
-            "Dir".to_string(),
-            "v@mail.com".to_string(),
-            Rol::Vend,
-        ).unwrap();
+        let comprador = account(250);
 
         contrato.priv_agregar_usuario_sistema(
-            comprador,
-            "Comprador".to_string(),
-            "C".to_string(),
-            "Dir".to_string(),
-            "c@mail.com".to_string(),
-            Rol::Comp,
-        ).unwrap();
-
-        contrato.priv_cargar_producto(
-            vendedor,
-            "Producto".to_string(),
-            "Desc".to_string(),
-            100,
-            Categoria::Otro,
-            10,
+            comprador, "Comprador".into(), "C".into(), "Dir".into(), "c@mail".into(), Rol::Comp
         ).unwrap();
 
-        contrato.priv_crear_publicacion(vendedor, vec![(1, 2)]).unwrap(); // ID de la publicación = 0;  
+        {
+            let mut u = contrato.buscar_usuario(comprador).unwrap();
+            u.datos_comprador = Some(Comprador {
+                ordenes_de_compra: vec![],
+                reputacion_como_comprador: vec![3],
+            });
+            contrato.usuarios.insert(comprador, &u);
+        }
 
-        let resultado = contrato.priv_crear_orden_de_compra(comprador, 0);
-        assert!(resultado.is_ok());
+        let res = contrato.calificar_comprador(comprador, 5);
+        assert!(res.is_ok());
 
-        let historial = contrato.historial_ordenes_de_compra.len();
-        assert_eq!(historial, 1);
+        let u = contrato.buscar_usuario(comprador).unwrap();
+        assert_eq!(u.datos_comprador.unwrap().reputacion_como_comprador.last().copied(), Some(5));
     }
 
     #[ink::test]
-    fn crear_orden_propia_falla() {
+    fn priv_calificar_comprador_sin_datos_falla() {
         let mut contrato = PrimerContrato::new();
-        let usuario = account(62);
+        let usuario = account(251);
 
         contrato.priv_agregar_usuario_sistema(
-            usuario,
-            "Mixto".to_string(),
-            "U".to_string(),
-            "Dir".to_string(),
-            "m@mail.com".to_string(),
-            Rol::Ambos,
+            usuario, "Usuario".into(), "U".into(), "Dir".into(), "u@mail".into(), Rol::Vend
         ).unwrap();
 
-        contrato.priv_cargar_producto(
-            usuario,
-            "Prod".to_string(),
-            "Desc".to_string(),
-            100,
-            Categoria::Otro,
-            5,
-        ).unwrap();
+        let res = contrato.calificar_comprador(usuario, 4);
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err(), "El comprador no tiene datos cargados.".to_string());
+    }
 
-        contrato.priv_crear_publicacion(usuario, vec![(1, 1)]).unwrap(); 
+    #[ink::test]
+    fn priv_calificar_comprador_usuario_no_encontrado_falla() {
+        let mut contrato = PrimerContrato::new();
+        let account_invalido: AccountId = [0x0; 32].into();
 
-        let resultado = contrato.priv_crear_orden_de_compra(usuario, 0);
-        assert!(resultado.is_err());
-        assert_eq!(resultado.unwrap_err(), "El usuario no puede comprar sus propias publicaciones.");
+        let res = contrato.calificar_comprador(account_invalido, 4);
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err(), "No se encontro el usuario.".to_string());
+    }
+
+
+     #[ink::test]
+    fn es_comprador_ambos_con_rol_comp_ok() {
+        let id = AccountId::from([1u8; 32]);
+        let usuario = Usuario::nuevo(
+            id,
+            "Nombre".into(),
+            "Apellido".into(),
+            "Dir".into(),
+            "u@mail".into(),
+            Rol::Comp,
+            0,
+        );
+        assert!(usuario.es_comprador_ambos().is_ok());
     }
 
     #[ink::test]
-    fn crear_orden_publicacion_comp_falla() {
+    fn es_comprador_ambos_con_rol_ambos_ok() {
+        let id = AccountId::from([2u8; 32]);
+        let usuario = Usuario::nuevo(
+            id,
+            "Nombre".into(),
+            "Apellido".into(),
+            "Dir".into(),
+            "u@mail".into(),
+            Rol::Ambos,
+            0,
+        );
+        assert!(usuario.es_comprador_ambos().is_ok());
+    }
+
+    #[ink::test]
+    fn es_comprador_ambos_con_rol_vend_err() {
+        let id = AccountId::from([3u8; 32]);
+        let usuario = Usuario::nuevo(
+            id,
+            "Nombre".into(),
+            "Apellido".into(),
+            "Dir".into(),
+            "u@mail".into(),
+            Rol::Vend,
+            0,
+        );
+        let res = usuario.es_comprador_ambos();
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err(), "El usuario es vendedor.".to_string());
+    }
+
+
+    #[ink::test]
+    fn calificar_segun_rol_else_califica_comprador_por_vendedor() {
         let mut contrato = PrimerContrato::new();
-        let vendedor = account(63);
-        let comprador = account(64);
+        let vendedor = account(240);
+        let comprador = account(241);
 
         contrato.priv_agregar_usuario_sistema(
-            vendedor,
-            "V".to_string(),
-            "V".to_string(),
-            "Dir".to_string(),
-            "v@mail.com".to_string(),
-            Rol::Vend,
+            vendedor, "V".into(), "V".into(), "Dir".into(), "v@mail".into(), Rol::Vend
         ).unwrap();
-
         contrato.priv_agregar_usuario_sistema(
-            comprador,
-            "C".to_string(),
-            "C".to_string(),
-            "Dir".to_string(),
-            "c@mail.com".to_string(),
-            Rol::Comp,
+            comprador, "C".into(), "C".into(), "Dir".into(), "c@mail".into(), Rol::Comp
         ).unwrap();
 
-        contrato.priv_cargar_producto(
-            vendedor,
-            "Prod".to_string(),
-            "Desc".to_string(),
-            30,
-            Categoria::Otro,
-            5,
-        ).unwrap();
+        contrato.priv_cargar_producto(vendedor, "P".into(), "D".into(), 10, OTRO, 3).unwrap();
+        contrato.priv_crear_publicacion(vendedor, vec![(1,1)], None).unwrap();
+        contrato.priv_crear_orden_de_compra(comprador, 0, None, 0, 0).unwrap();
 
-        contrato.priv_crear_publicacion(vendedor, vec![(1, 1)]).unwrap(); 
+        let usuario_vendedor = contrato.buscar_usuario(vendedor).unwrap();
+        let (_id, orden) = contrato.historial_ordenes_de_compra.get(0).unwrap();
 
-        contrato.priv_modificar_rol(vendedor, Rol::Comp).unwrap();
+        contrato.calificar_segun_rol(4u8, orden.clone(), vendedor, comprador, usuario_vendedor, 0).unwrap();
 
-        let resultado = contrato.priv_crear_orden_de_compra(comprador, 0);
-        assert!(resultado.is_err()); //El vendedor de la publicación cambió su rol a comprador. Debe anular sus publicaciones;
-        assert_eq!(resultado.unwrap_err(), "La publicacion ya no se encuentra disponible.");
+        let (_id2, orden_actualizada) = contrato.historial_ordenes_de_compra.get(0).unwrap();
+        assert!(orden_actualizada.calificaciones.1, "La orden debe marcar calificación del comprador por parte del vendedor");
+
+        let comprador_actual = contrato.buscar_usuario(comprador).unwrap();
+        assert_eq!(comprador_actual.datos_comprador.unwrap().reputacion_como_comprador.last().copied(), Some(4));
     }
 
     #[ink::test]
-    fn crear_orden_sin_stock_falla() {
+    fn get_productos_vacio() {
+        let contrato = PrimerContrato::new();
+        let productos = contrato.get_productos();
+        assert!(productos.is_empty());
+    }
+
+    #[ink::test]
+    fn get_productos_retorna_producto_cargado() {
         let mut contrato = PrimerContrato::new();
-        let vendedor = account(65);
-        let comprador = account(66);
+        let acc = account(80);
 
         contrato.priv_agregar_usuario_sistema(
-            vendedor,
-            "V".to_string(),
-            "V".to_string(),
+            acc,
+            "Vendedor".to_string(),
+            "Uno".to_string(),
             "Dir".to_string(),
-            "v@mail.com".to_string(),
+            "v@mail".to_string(),
             Rol::Vend,
         ).unwrap();
 
-        contrato.priv_agregar_usuario_sistema(
-            comprador,
-            "C".to_string(),
-            "C".to_string(),
-            "Dir".to_string(),
-            "c@mail.com".to_string(),
-            Rol::Comp,
-        ).unwrap();
-
         contrato.priv_cargar_producto(
-            vendedor,
-            "Prod".to_string(),
-            "Desc".to_string(),
-            20,
-            Categoria::Otro,
-            1,
+            acc,
+            "ProductoTest".to_string(),
+            "Descripcion".to_string(),
+            123,
+            ALIMENTOS,
+            10,
         ).unwrap();
 
-        contrato.priv_crear_publicacion(vendedor, vec![(1, 1)]).unwrap(); 
-
-        let mut publicacion = contrato.priv_visualizar_productos_de_publicacion(0).unwrap();
-        publicacion.disponible = false; //Se fuerza la disponibilidad de la publicación en falso;
-        let pos = contrato.devolver_posicion_publicacion(0).unwrap();
-        contrato.historial_publicaciones.set(pos, &(0u32, publicacion)); //Se actualiza la publicación;
-
-        let resultado = contrato.priv_crear_orden_de_compra(comprador, 0);
-        assert!(resultado.is_err());
-        assert_eq!(resultado.unwrap_err(), "La publicacion ya no tiene stock");
+        let productos = contrato.get_productos();
+        assert_eq!(productos.len(), 1);
+        let p = &productos[0];
+        assert_eq!(p.id, 1);
+        assert_eq!(p.nombre, "ProductoTest".to_string());
+        assert_eq!(p.precio, 123);
+        assert_eq!(p.categoria, ALIMENTOS);
     }
 
     #[ink::test]
-    fn crear_orden_usuario_inexistente_falla() {
+    fn get_productos_varios_productos() {
         let mut contrato = PrimerContrato::new();
-        let vendedor = account(67);
+        let acc = account(81);
 
         contrato.priv_agregar_usuario_sistema(
-            vendedor,
-            "V".to_string(),
-            "V".to_string(),
+            acc,
+            "Vendedor".to_string(),
+            "Dos".to_string(),
             "Dir".to_string(),
-            "v@mail.com".to_string(),
+            "v2@mail".to_string(),
             Rol::Vend,
         ).unwrap();
 
-        contrato.priv_cargar_producto(
-            vendedor,
-            "Prod".to_string(),
-            "Desc".to_string(),
-            15,
-            Categoria::Otro,
-            2,
-        ).unwrap();
+        contrato.priv_cargar_producto(acc, "P1".into(), "D1".into(), 10, HOGAR, 5).unwrap();
+        contrato.priv_cargar_producto(acc, "P2".into(), "D2".into(), 20, ROPA, 2).unwrap();
 
-        contrato.priv_crear_publicacion(vendedor, vec![(1, 1)]).unwrap();
+        let productos = contrato.get_productos();
+        assert_eq!(productos.len(), 2);
 
-        let account_invalido: AccountId = [0x0; 32].into();
+        let ids: Vec<u32> = productos.iter().map(|p| p.id).collect();
+        assert!(ids.contains(&1));
+        assert!(ids.contains(&2));
+    }
 
-        let resultado = contrato.priv_crear_orden_de_compra(account_invalido, 0);
-        assert!(resultado.is_err());
+    #[ink::test]
+    fn get_usuarios_vacio() {
+        let contrato = PrimerContrato::new();
+        let res = contrato.get_usuarios().unwrap();
+        assert!(res.is_empty());
     }
 
     #[ink::test]
-    fn crear_orden_publicacion_inexistente_falla() {
+    fn get_usuarios_dos_usuarios_devuelve_el_segundo() {
         let mut contrato = PrimerContrato::new();
-        let comprador = account(68);
+        let a1 = account(91);
+        let a2 = account(92);
 
         contrato.priv_agregar_usuario_sistema(
-            comprador,
-            "C".to_string(),
-            "C".to_string(),
-            "Dir".to_string(),
-            "c@mail.com".to_string(),
+            a1,
+            "Uno".into(),
+            "A".into(),
+            "Dir".into(),
+            "u1@mail".into(),
+            Rol::Vend,
+        ).unwrap();
+
+        contrato.priv_agregar_usuario_sistema(
+            a2,
+            "Dos".into(),
+            "B".into(),
+            "Dir2".into(),
+            "u2@mail".into(),
             Rol::Comp,
         ).unwrap();
 
-        let id_pub_invalido = 999u32;
-        let resultado = contrato.priv_crear_orden_de_compra(comprador, id_pub_invalido);
-        assert!(resultado.is_err());
+        let res = contrato.get_usuarios().unwrap();
+    
+        assert_eq!(res.len(), 2);
+        assert_eq!(res[1].id_usuario, a2);
     }
 
+    #[ink::test]
+    fn get_ordenes_vacio_err() {
+        let contrato = PrimerContrato::new();
+        let res = contrato.get_ordenes();
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err(), "No hay ordenes de compra cargadas.".to_string());
+    }
 
     #[ink::test]
-    fn cancelar_compra_comprador_exito() {
+    fn get_ordenes_retorna_ordenes_cargadas() {
         let mut contrato = PrimerContrato::new();
-        let vendedor = account(100);
-        let comprador = account(101);
+        let vendedor = account(60);
+        let comprador = account(61);
 
         contrato.priv_agregar_usuario_sistema(
             vendedor, "V".into(), "V".into(), "Dir".into(), "v@mail".into(), Rol::Vend
@@ -1998,25 +6509,22 @@ mod tests {
             comprador, "C".into(), "C".into(), "Dir".into(), "c@mail".into(), Rol::Comp
         ).unwrap();
 
-    contrato.priv_cargar_producto(vendedor, "P".into(), "D".into(), 10, Categoria::Otro, 5).unwrap();
-        contrato.priv_crear_publicacion(vendedor, vec![(1, 2)]).unwrap(); // pub 0
+        contrato.priv_cargar_producto(vendedor, "P".into(), "D".into(), 100, OTRO, 10).unwrap();
+        contrato.priv_crear_publicacion(vendedor, vec![(1, 2)], None).unwrap();
 
-        contrato.priv_crear_orden_de_compra(comprador, 0).unwrap();
+        contrato.priv_crear_orden_de_compra(comprador, 0, None, 0, 0).unwrap();
 
-        let res = contrato.priv_cancelar_compra(comprador, 0);
-        assert!(res.is_ok());
-
-        let (_, orden) = contrato.historial_ordenes_de_compra.get(0).unwrap();
-        assert!(orden.cancelacion.1, "El comprador debe haber marcado la cancelación");
-        assert!(!orden.cancelacion.0, "El vendedor aún no debe haber marcado la cancelación");
-        assert_eq!(orden.estado, EstadoCompra::Pendiente, "El estado permanece Pendiente hasta que ambas partes cancelen");
+        let ordenes = contrato.get_ordenes().expect("Debería devolver las ordenes");
+        assert_eq!(ordenes.len(), 1);
+        assert_eq!(ordenes[0].id, 0);
+        assert_eq!(ordenes[0].id_comprador, comprador);
     }
 
     #[ink::test]
-    fn cancelar_compra_vendedor_exito() {
+    fn get_ordenes_desde_saltea_las_anteriores() {
         let mut contrato = PrimerContrato::new();
-        let vendedor = account(102);
-        let comprador = account(103);
+        let vendedor = account(62);
+        let comprador = account(63);
 
         contrato.priv_agregar_usuario_sistema(
             vendedor, "V".into(), "V".into(), "Dir".into(), "v@mail".into(), Rol::Vend
@@ -2025,75 +6533,62 @@ mod tests {
             comprador, "C".into(), "C".into(), "Dir".into(), "c@mail".into(), Rol::Comp
         ).unwrap();
 
-    contrato.priv_cargar_producto(vendedor, "P".into(), "D".into(), 10, Categoria::Otro, 5).unwrap(); //5 productos alcemanados;
-        contrato.priv_crear_publicacion(vendedor, vec![(1, 2)]).unwrap(); //3 productos almcenados;
-
-        contrato.priv_crear_orden_de_compra(comprador, 0).unwrap(); //1 producto almacenado. (Restockeo automatico de la publicacion);
-
-        contrato.priv_cancelar_compra(comprador, 0).unwrap();
-
-        let res = contrato.priv_cancelar_compra(vendedor, 0); //3 productos almacenados;
-        assert!(res.is_ok());
-
-        let (_, orden) = contrato.historial_ordenes_de_compra.get(0).unwrap();
-        assert!(orden.cancelacion.0 && orden.cancelacion.1, "Ambas partes deben haber marcado cancelación");
-
-        let (_, stock) = contrato.historial_productos.get(1).unwrap();
-        assert_eq!(stock, 3); //El stock que estaba en la publicación debe restaurse correctamente; (5 - 2 - 2 + 2) = 3; 
-    }
-
-    #[ink::test]
-    fn cancelar_compra_orden_no_existe_falla() {
-        let mut contrato = PrimerContrato::new();
-        let acc = account(104);
+        contrato.priv_cargar_producto(vendedor, "P".into(), "D".into(), 100, OTRO, 10).unwrap();
+        contrato.priv_crear_publicacion(vendedor, vec![(1, 2)], None).unwrap();
 
-        contrato.priv_agregar_usuario_sistema(
-            acc, "U".into(), "U".into(), "Dir".into(), "u@mail".into(), Rol::Vend
-        ).unwrap();
+        contrato.priv_crear_orden_de_compra(comprador, 0, None, 0, 0).unwrap();
+        contrato.priv_crear_orden_de_compra(comprador, 0, None, 0, 0).unwrap();
 
-        let id_orden_invalido = 999u32;
-        let res = contrato.priv_cancelar_compra(acc, id_orden_invalido);
-        assert!(res.is_err());
-        assert_eq!(res.unwrap_err(), "No se encontro la orden.");
+        assert_eq!(contrato.get_ordenes_desde(0).len(), 2);
+        let ordenes = contrato.get_ordenes_desde(1);
+        assert_eq!(ordenes.len(), 1);
+        assert_eq!(ordenes[0].id, 1);
+        assert!(contrato.get_ordenes_desde(5).is_empty());
     }
 
     #[ink::test]
-    fn cancelar_compra_estado_no_cancelable_falla() {
+    fn registrar_contrato_reportes_solo_owner() {
         let mut contrato = PrimerContrato::new();
-        let vendedor = account(105);
-        let comprador = account(106);
+        let owner = contrato.owner;
 
-        contrato.priv_agregar_usuario_sistema(vendedor, "V".into(), "V".into(), "D".into(), "v@mail".into(), Rol::Vend).unwrap();
-        contrato.priv_agregar_usuario_sistema(comprador, "C".into(), "C".into(), "D".into(), "c@mail".into(), Rol::Comp).unwrap();
-    contrato.priv_cargar_producto(vendedor, "P".into(), "D".into(), 10, Categoria::Otro, 3).unwrap();
-        contrato.priv_crear_publicacion(vendedor, vec![(1,1)]).unwrap();
-        contrato.priv_crear_orden_de_compra(comprador, 0).unwrap();
+        let res = contrato.priv_registrar_contrato_reportes(account(70), Some(account(71)));
+        assert_eq!(res.unwrap_err(), "Solo el owner puede realizar esta accion.".to_string());
+        assert!(contrato.contrato_reportes.is_none());
 
-        let (pos_id, mut orden) = contrato.historial_ordenes_de_compra.get(0).unwrap();
-        orden.estado = EstadoCompra::Enviado; //Se fuerza el estado de la orden a Enviado;
-        contrato.historial_ordenes_de_compra.set(0, &(pos_id, orden));
+        contrato.priv_registrar_contrato_reportes(owner, Some(account(71))).unwrap();
+        assert_eq!(contrato.contrato_reportes, Some(account(71)));
 
-        let res = contrato.priv_cancelar_compra(comprador, 0);
-        assert!(res.is_err()); //La orden tiene el estado "Enviado";
-        assert_eq!(res.unwrap_err(), "Ya no se puede cancelar la compra.");
+        contrato.priv_registrar_contrato_reportes(owner, None).unwrap();
+        assert!(contrato.contrato_reportes.is_none());
     }
 
     #[ink::test]
-    fn cancelar_compra_usuario_inexistente_falla() {
+    fn falla_al_notificar_reportes_no_revierte_la_operacion() {
         let mut contrato = PrimerContrato::new();
-        let id_orden = 0u32;
-        let account_invalido: AccountId = [0x0; 32].into();
-
-        let res = contrato.priv_cancelar_compra(account_invalido, id_orden);
-        assert!(res.is_err());
-        assert_eq!(res.unwrap_err(), "No se encontro el usuario.");
+        let owner = contrato.owner;
+        let (vendedor, comprador, reportes) = (account(242), account(243), account(244));
+        preparar_publicacion_para_ofertas(&mut contrato, vendedor, comprador);
+
+        let antes = ink::env::test::recorded_events().count();
+        contrato.priv_crear_orden_de_compra(comprador, 0, None, 0, 0).unwrap();
+        let eventos_de_una_compra = ink::env::test::recorded_events().count() - antes;
+
+        //En los tests la llamada al contrato de reportes siempre falla
+        contrato.priv_registrar_contrato_reportes(owner, Some(reportes)).unwrap();
+        let antes = ink::env::test::recorded_events().count();
+        contrato.priv_crear_orden_de_compra(comprador, 0, None, 0, 0).unwrap();
+        assert_eq!(ink::env::test::recorded_events().count() - antes, eventos_de_una_compra + 1); //ReporteNoNotificado
+        contrato.priv_enviar_compra(vendedor, 1).unwrap();
+        assert_eq!(ink::env::test::recorded_events().count() - antes, eventos_de_una_compra + 2);
+
+        assert_eq!(contrato.buscar_orden(1).unwrap().estado, EstadoCompra::Enviado);
     }
 
     #[ink::test]
-     fn priv_recibir_compra_exito() {
+    fn productos_de_la_orden_devuelve_los_productos_publicados() {
         let mut contrato = PrimerContrato::new();
-        let vendedor = account(200);
-        let comprador = account(201);
+        let vendedor = account(72);
+        let comprador = account(73);
 
         contrato.priv_agregar_usuario_sistema(
             vendedor, "V".into(), "V".into(), "Dir".into(), "v@mail".into(), Rol::Vend
@@ -2102,69 +6597,77 @@ mod tests {
             comprador, "C".into(), "C".into(), "Dir".into(), "c@mail".into(), Rol::Comp
         ).unwrap();
 
-    contrato.priv_cargar_producto(vendedor, "P".into(), "D".into(), 10, Categoria::Otro, 3).unwrap();
-        contrato.priv_crear_publicacion(vendedor, vec![(1,1)]).unwrap();
-        contrato.priv_crear_orden_de_compra(comprador, 0).unwrap();
-
-        contrato.priv_enviar_compra(vendedor, 0).unwrap();
-
-        let res = contrato.priv_recibir_compra(comprador, 0);
-        assert!(res.is_ok());
+        contrato.priv_cargar_producto(vendedor, "P1".into(), "D".into(), 100, HOGAR, 10).unwrap();
+        contrato.priv_cargar_producto(vendedor, "P2".into(), "D".into(), 50, ROPA, 10).unwrap();
+        contrato.priv_crear_publicacion(vendedor, vec![(1, 1), (2, 3)], None).unwrap();
+        contrato.priv_crear_orden_de_compra(comprador, 0, None, 0, 0).unwrap();
 
-        let (_, orden) = contrato.historial_ordenes_de_compra.get(0).unwrap();
-        assert_eq!(orden.estado, EstadoCompra::Recibido);
+        let orden = contrato.buscar_orden(0).unwrap();
+        let productos = contrato.productos_de_la_orden(&orden);
+        assert_eq!(productos.len(), 2);
+        assert_eq!(productos[0].categoria, HOGAR);
+        assert_eq!(productos[1].categoria, ROPA);
     }
 
     #[ink::test]
-    fn priv_recibir_compra_usuario_no_encontrado_falla() {
+    fn configurar_comision_solo_owner_y_en_rango() {
         let mut contrato = PrimerContrato::new();
-        let account_invalido: AccountId = [0x0; 32].into();
-        let res = contrato.priv_recibir_compra(account_invalido, 0);
-        assert!(res.is_err());
-        assert_eq!(res.unwrap_err(), "No se encontro el usuario.");
-    }
+        let owner = contrato.owner;
 
-    #[ink::test]
-    fn priv_recibir_compra_orden_no_encontrada_falla() {
-        let mut contrato = PrimerContrato::new();
-        let comprador = account(210);
-        contrato.priv_agregar_usuario_sistema(
-            comprador, "C".into(), "C".into(), "Dir".into(), "c@mail".into(), Rol::Comp
-        ).unwrap();
+        let res = contrato.priv_configurar_comision(account(80), 100);
+        assert_eq!(res.unwrap_err(), "Solo el owner puede realizar esta accion.".to_string());
 
-        let res = contrato.priv_recibir_compra(comprador, 999);
-        assert!(res.is_err());
-        assert_eq!(res.unwrap_err(), "No existe la orden buscada.");
+        let res = contrato.priv_configurar_comision(owner, 10001);
+        assert_eq!(res.unwrap_err(), "La comision no puede superar los 10000 puntos basicos.".to_string());
+
+        contrato.priv_configurar_comision(owner, 250).unwrap();
+        assert_eq!(contrato.comision_bps, 250);
     }
 
     #[ink::test]
-    fn priv_recibir_compra_no_enviado_falla() {
+    fn verificar_pago_debe_coincidir_con_el_precio() {
         let mut contrato = PrimerContrato::new();
-        let vendedor = account(220);
-        let comprador = account(221);
+        let vendedor = account(81);
 
         contrato.priv_agregar_usuario_sistema(
             vendedor, "V".into(), "V".into(), "Dir".into(), "v@mail".into(), Rol::Vend
         ).unwrap();
-        contrato.priv_agregar_usuario_sistema(
-            comprador, "C".into(), "C".into(), "Dir".into(), "c@mail".into(), Rol::Comp
-        ).unwrap();
+        contrato.priv_cargar_producto(vendedor, "P".into(), "D".into(), 100, OTRO, 10).unwrap();
+        contrato.priv_crear_publicacion(vendedor, vec![(1, 2)], None).unwrap();
 
-    contrato.priv_cargar_producto(vendedor, "P".into(), "D".into(), 10, Categoria::Otro, 3).unwrap();
-        contrato.priv_crear_publicacion(vendedor, vec![(1,1)]).unwrap();
-        contrato.priv_crear_orden_de_compra(comprador, 0).unwrap();
+        assert!(contrato.verificar_pago(0, 200, &None, 0, 0).is_ok());
+        assert_eq!(contrato.verificar_pago(0, 199, &None, 0, 0).unwrap_err(), "El pago no coincide con el precio de la publicacion.".to_string());
+        assert!(contrato.verificar_pago(5, 200, &None, 0, 0).is_err());
+    }
 
-        let res = contrato.priv_recibir_compra(comprador, 0);
-        assert!(res.is_err());
-        assert_eq!(res.unwrap_err(), "El producto todavia no fue enviado.");
+    ///Saldo mínimo que el entorno de pruebas exige al cargar el saldo de una cuenta. El escrow lo conserva siempre. 
+    const SALDO_MINIMO: Balance = 1_000_000;
+
+    ///Función auxiliar que deja en la cuenta del contrato (el escrow) el saldo en moneda nativa recibido, y devuelve la cuenta del contrato. 
+    ///Los tests llaman a las funciones priv_, que no reciben el pago, así que el saldo retenido se carga a mano (sobre el saldo mínimo). 
+    fn fondear_escrow(monto: Balance) -> AccountId {
+        let escrow = ink::env::account_id::<ink::env::DefaultEnvironment>();
+        ink::env::test::set_account_balance::<ink::env::DefaultEnvironment>(escrow, SALDO_MINIMO + monto);
+        escrow
+    }
+
+    ///Función auxiliar que devuelve el saldo que sigue retenido en el escrow, sin contar su saldo mínimo. 
+    fn saldo_retenido(escrow: AccountId) -> Balance {
+        saldo_nativo(escrow) - SALDO_MINIMO
+    }
+
+    ///Función auxiliar que devuelve el saldo en moneda nativa de una cuenta (cero si nunca recibió fondos). 
+    fn saldo_nativo(cuenta: AccountId) -> Balance {
+        ink::env::test::get_account_balance::<ink::env::DefaultEnvironment>(cuenta).unwrap_or(0)
     }
 
     #[ink::test]
-    fn priv_recibir_compra_usuario_no_comprador_falla() {
+    fn recibir_compra_cobra_comision_y_owner_la_retira() {
         let mut contrato = PrimerContrato::new();
-        let vendedor = account(230);
-        let comprador = account(231);
-        let otro = account(232); 
+        let owner = account(84);
+        contrato.owner = owner; //El owner por defecto es la propia cuenta del contrato en el entorno de pruebas
+        let vendedor = account(82);
+        let comprador = account(83);
 
         contrato.priv_agregar_usuario_sistema(
             vendedor, "V".into(), "V".into(), "Dir".into(), "v@mail".into(), Rol::Vend
@@ -2172,708 +6675,1475 @@ mod tests {
         contrato.priv_agregar_usuario_sistema(
             comprador, "C".into(), "C".into(), "Dir".into(), "c@mail".into(), Rol::Comp
         ).unwrap();
-        contrato.priv_agregar_usuario_sistema(
-            otro, "O".into(), "O".into(), "Dir".into(), "o@mail".into(), Rol::Comp
-        ).unwrap();
+        contrato.priv_configurar_comision(owner, 250).unwrap();
 
-    contrato.priv_cargar_producto(vendedor, "P".into(), "D".into(), 10, Categoria::Otro, 3).unwrap();
-        contrato.priv_crear_publicacion(vendedor, vec![(1,1)]).unwrap();
-        contrato.priv_crear_orden_de_compra(comprador, 0).unwrap();
+        contrato.priv_cargar_producto(vendedor, "P".into(), "D".into(), 100, OTRO, 10).unwrap();
+        contrato.priv_crear_publicacion(vendedor, vec![(1, 2)], None).unwrap();
+        contrato.priv_crear_orden_de_compra(comprador, 0, None, 0, 0).unwrap();
+        let escrow = fondear_escrow(200);
         contrato.priv_enviar_compra(vendedor, 0).unwrap();
-
-        let res = contrato.priv_recibir_compra(otro, 0);
-        assert!(res.is_err()); //Otro usuario externo a la orden de la compra en cuestión no puede alterarla;
-        assert_eq!(res.unwrap_err(), "No se encontro la orden de compra.");
+        contrato.priv_recibir_compra(comprador, 0, 0).unwrap();
+
+        let orden = contrato.buscar_orden(0).unwrap();
+        assert_eq!(orden.comision, 5); // 2,5% de 200
+        assert_eq!(contrato.tesoreria, 5);
+        assert_eq!(saldo_nativo(vendedor), 195);
+        assert_eq!(saldo_retenido(escrow), 5);
+
+        let res = contrato.priv_retirar_comisiones(vendedor);
+        assert_eq!(res.unwrap_err(), "Solo el owner puede realizar esta accion.".to_string());
+
+        assert_eq!(contrato.priv_retirar_comisiones(owner), Ok(5));
+        assert_eq!(contrato.tesoreria, 0);
+        assert_eq!(saldo_nativo(owner), 5);
+        assert_eq!(saldo_retenido(escrow), 0);
+        assert_eq!(contrato.priv_retirar_comisiones(owner).unwrap_err(), "No hay comisiones para retirar.".to_string());
     }
 
-    #[ink::test]
-    fn priv_calificar_exito() {
-        let mut contrato = PrimerContrato::new();
-        let vendedor = account(150);
-        let comprador = account(151);
-
+    ///Función auxiliar que registra un vendedor y un comprador, y crea una publicación (id 0) de precio 200 que se paga con el token recibido
+    fn preparar_publicacion_con_token(contrato: &mut PrimerContrato, vendedor: AccountId, comprador: AccountId, token: AccountId) {
         contrato.priv_agregar_usuario_sistema(
             vendedor, "V".into(), "V".into(), "Dir".into(), "v@mail".into(), Rol::Vend
         ).unwrap();
         contrato.priv_agregar_usuario_sistema(
             comprador, "C".into(), "C".into(), "Dir".into(), "c@mail".into(), Rol::Comp
         ).unwrap();
+        contrato.priv_cargar_producto(vendedor, "P".into(), "D".into(), 100, OTRO, 10).unwrap();
+        contrato.priv_crear_publicacion(vendedor, vec![(1, 2)], Some(token)).unwrap();
+    }
 
-    contrato.priv_cargar_producto(vendedor, "P".into(), "D".into(), 10, Categoria::Otro, 5).unwrap();
-        contrato.priv_crear_publicacion(vendedor, vec![(1,1)]).unwrap();
-        contrato.priv_crear_orden_de_compra(comprador, 0).unwrap();
-        contrato.priv_enviar_compra(vendedor, 0).unwrap();
-        contrato.priv_recibir_compra(comprador, 0).unwrap();
+    #[ink::test]
+    fn orden_con_token_cobra_y_libera_con_psp22() {
+        let mut contrato = PrimerContrato::new();
+        let owner = account(89);
+        contrato.owner = owner; //El owner por defecto es la propia cuenta del contrato (el escrow) en el entorno de pruebas
+        let escrow = ink::env::account_id::<ink::env::DefaultEnvironment>();
+        let (vendedor, comprador, token) = (account(90), account(91), account(92));
+        preparar_publicacion_con_token(&mut contrato, vendedor, comprador, token);
+        contrato.priv_configurar_comision(owner, 250).unwrap();
 
-        let res = contrato.priv_calificar(0, 5, comprador);
-        assert!(res.is_ok());
+        mock_psp22::mint(token, comprador, 1000);
+        mock_psp22::approve(token, comprador, escrow, 200);
 
-        let vend = contrato.buscar_usuario(vendedor).unwrap();
-        assert_eq!(vend.datos_vendedor.unwrap().reputacion_como_vendedor[0], 5);
+        assert_eq!(contrato.verificar_pago(0, 200, &None, 0, 0).unwrap_err(), "La publicacion se paga con un token PSP22, no con moneda nativa.".to_string());
+        assert!(contrato.verificar_pago(0, 0, &None, 0, 0).is_ok());
+
+        contrato.priv_crear_orden_de_compra(comprador, 0, None, 0, 0).unwrap();
+        assert_eq!(mock_psp22::balance_of(token, comprador), 800);
+        assert_eq!(mock_psp22::balance_of(token, escrow), 200);
+        assert_eq!(contrato.buscar_orden(0).unwrap().token_de_pago, Some(token));
+
+        contrato.priv_enviar_compra(vendedor, 0).unwrap();
+        contrato.priv_recibir_compra(comprador, 0, 0).unwrap();
+        assert_eq!(mock_psp22::balance_of(token, vendedor), 195);
+        assert_eq!(mock_psp22::balance_of(token, escrow), 5);
+        assert_eq!(contrato.tesoreria, 0);
+
+        assert_eq!(contrato.priv_retirar_comisiones_token(owner, token), Ok(5));
+        assert_eq!(mock_psp22::balance_of(token, owner), 5);
+        assert_eq!(mock_psp22::balance_of(token, escrow), 0);
     }
 
     #[ink::test]
-    fn priv_calificar_valor_fuera_rango_falla() {
+    fn orden_con_token_sin_autorizacion_falla() {
         let mut contrato = PrimerContrato::new();
-        let vendedor = account(152);
-        let comprador = account(153);
-
-        contrato.priv_agregar_usuario_sistema(
-            vendedor, "V".into(), "V".into(), "Dir".into(), "v@mail".into(), Rol::Vend
-        ).unwrap();
-        contrato.priv_agregar_usuario_sistema(
-            comprador, "C".into(), "C".into(), "Dir".into(), "c@mail".into(), Rol::Comp
-        ).unwrap();
+        let (vendedor, comprador, token) = (account(93), account(94), account(95));
+        preparar_publicacion_con_token(&mut contrato, vendedor, comprador, token);
 
-    contrato.priv_cargar_producto(vendedor, "P".into(), "D".into(), 10, Categoria::Otro, 5).unwrap();
-        contrato.priv_crear_publicacion(vendedor, vec![(1,1)]).unwrap();
-        contrato.priv_crear_orden_de_compra(comprador, 0).unwrap();
-        contrato.priv_enviar_compra(vendedor, 0).unwrap();
-        contrato.priv_recibir_compra(comprador, 0).unwrap();
+        mock_psp22::mint(token, comprador, 1000);
 
-        let res = contrato.priv_calificar(0, 0, comprador);
-        assert!(res.is_err());
-        assert_eq!(res.unwrap_err(), "El valor de la calificacion no es valido (1..5).");
+        let res = contrato.priv_crear_orden_de_compra(comprador, 0, None, 0, 0);
+        assert_eq!(res.unwrap_err(), "El contrato no esta autorizado a mover esa cantidad de tokens.".to_string());
+        assert!(contrato.get_ordenes().is_err());
+        assert_eq!(mock_psp22::balance_of(token, comprador), 1000);
     }
 
     #[ink::test]
-    fn priv_calificar_duplicada_falla() {
+    fn cancelar_orden_con_token_reintegra_al_comprador() {
         let mut contrato = PrimerContrato::new();
-        let vendedor = account(154);
-        let comprador = account(155);
+        let escrow = ink::env::account_id::<ink::env::DefaultEnvironment>();
+        let (vendedor, comprador, token) = (account(96), account(97), account(98));
+        preparar_publicacion_con_token(&mut contrato, vendedor, comprador, token);
+
+        mock_psp22::mint(token, comprador, 200);
+        mock_psp22::approve(token, comprador, escrow, 200);
+        contrato.priv_crear_orden_de_compra(comprador, 0, None, 0, 0).unwrap();
+        assert_eq!(mock_psp22::balance_of(token, comprador), 0);
 
+        contrato.priv_cancelar_compra(comprador, 0).unwrap();
+        contrato.priv_cancelar_compra(vendedor, 0).unwrap();
+        assert_eq!(mock_psp22::balance_of(token, comprador), 200);
+        assert_eq!(mock_psp22::balance_of(token, escrow), 0);
+    }
+
+    ///Función auxiliar que registra un vendedor y dos compradores, y crea una subasta (id 0) de un producto con stock 5 y 2 unidades subastadas
+    fn preparar_subasta(contrato: &mut PrimerContrato, vendedor: AccountId, comprador1: AccountId, comprador2: AccountId, precio_reserva: u32) {
         contrato.priv_agregar_usuario_sistema(
             vendedor, "V".into(), "V".into(), "Dir".into(), "v@mail".into(), Rol::Vend
         ).unwrap();
         contrato.priv_agregar_usuario_sistema(
-            comprador, "C".into(), "C".into(), "Dir".into(), "c@mail".into(), Rol::Comp
+            comprador1, "C1".into(), "C1".into(), "Dir".into(), "c1@mail".into(), Rol::Comp
         ).unwrap();
-
-        contrato.priv_cargar_producto(vendedor, "P".into(), "D".into(), 10, Categoria::Otro, 5).unwrap();
-        contrato.priv_crear_publicacion(vendedor, vec![(1,1)]).unwrap();
-        contrato.priv_crear_orden_de_compra(comprador, 0).unwrap();
-        contrato.priv_enviar_compra(vendedor, 0).unwrap();
-        contrato.priv_recibir_compra(comprador, 0).unwrap();
-
-        contrato.priv_calificar(0, 5, comprador).unwrap();
-        let res2 = contrato.priv_calificar(0, 4, comprador);
-        assert!(res2.is_err());
-        assert_eq!(res2.unwrap_err(), "El usuario ya califico.");
+        contrato.priv_agregar_usuario_sistema(
+            comprador2, "C2".into(), "C2".into(), "Dir".into(), "c2@mail".into(), Rol::Comp
+        ).unwrap();
+        contrato.priv_cargar_producto(vendedor, "P".into(), "D".into(), 100, OTRO, 5).unwrap();
+        contrato.priv_crear_subasta(vendedor, vec![(1, 2)], precio_reserva, 1000, None, 0).unwrap();
     }
 
     #[ink::test]
-    fn priv_calificar_orden_no_recibida_falla() {
+    fn crear_subasta_con_fin_pasado_falla() {
         let mut contrato = PrimerContrato::new();
-        let vendedor = account(156);
-        let comprador = account(157);
-
+        let vendedor = account(100);
         contrato.priv_agregar_usuario_sistema(
             vendedor, "V".into(), "V".into(), "Dir".into(), "v@mail".into(), Rol::Vend
         ).unwrap();
-        contrato.priv_agregar_usuario_sistema(
-            comprador, "C".into(), "C".into(), "Dir".into(), "c@mail".into(), Rol::Comp
-        ).unwrap();
+        contrato.priv_cargar_producto(vendedor, "P".into(), "D".into(), 100, OTRO, 5).unwrap();
+
+        let res = contrato.priv_crear_subasta(vendedor, vec![(1, 2)], 50, 10, None, 10);
+        assert_eq!(res.unwrap_err(), "El fin de la subasta debe ser posterior al momento actual.".to_string());
+    }
 
-    contrato.priv_cargar_producto(vendedor, "P".into(), "D".into(), 10, Categoria::Otro, 5).unwrap();
-        contrato.priv_crear_publicacion(vendedor, vec![(1,1)]).unwrap();
-        contrato.priv_crear_orden_de_compra(comprador, 0).unwrap();
+    #[ink::test]
+    fn subasta_no_se_puede_comprar_a_precio_fijo() {
+        let mut contrato = PrimerContrato::new();
+        let (vendedor, c1, c2) = (account(101), account(102), account(103));
+        preparar_subasta(&mut contrato, vendedor, c1, c2, 150);
 
-        let res = contrato.priv_calificar(0, 5, comprador);
-        assert!(res.is_err());
+        let res = contrato.priv_crear_orden_de_compra(c1, 0, None, 0, 0);
+        assert_eq!(res.unwrap_err(), "La publicacion es una subasta. Solo se puede ofertar.".to_string());
     }
 
     #[ink::test]
-    fn priv_calificar_usuario_no_encontrado_falla() {
+    fn ofertar_valida_monto_y_acredita_oferta_superada() {
         let mut contrato = PrimerContrato::new();
-        let vendedor = account(158);
-        let comprador = account(159);
+        let (vendedor, c1, c2) = (account(104), account(105), account(106));
+        preparar_subasta(&mut contrato, vendedor, c1, c2, 150);
+        let escrow = fondear_escrow(120 + 160);
+
+        assert_eq!(contrato.priv_ofertar(vendedor, 0, 200, 10).unwrap_err(), "El usuario es vendedor.".to_string());
+        contrato.priv_ofertar(c1, 0, 120, 10).unwrap();
+        assert_eq!(contrato.priv_ofertar(c2, 0, 120, 20).unwrap_err(), "La oferta debe superar a la mejor oferta actual.".to_string());
+        contrato.priv_ofertar(c2, 0, 160, 20).unwrap();
+        assert_eq!(contrato.priv_ofertar(c1, 0, 300, 1000).unwrap_err(), "La subasta ya termino.".to_string());
+
+        let subasta = contrato.buscar_publicacion(0).unwrap().subasta.unwrap();
+        assert_eq!(subasta.mejor_oferta, Some((c2, 160)));
+
+        assert_eq!(contrato.priv_retirar_fondos(c1, None), Ok(120));
+        assert_eq!(saldo_nativo(c1), 120);
+        assert_eq!(saldo_retenido(escrow), 160); //La mejor oferta sigue retenida
+        assert_eq!(contrato.priv_retirar_fondos(c1, None).unwrap_err(), "No hay fondos para retirar.".to_string());
+        assert!(contrato.priv_retirar_fondos(c2, None).is_err());
+    }
 
-        contrato.priv_agregar_usuario_sistema(
-            vendedor, "V".into(), "V".into(), "Dir".into(), "v@mail".into(), Rol::Vend
-        ).unwrap();
+    #[ink::test]
+    fn finalizar_subasta_adjudica_al_ganador() {
+        let mut contrato = PrimerContrato::new();
+        let (vendedor, c1, c2) = (account(107), account(108), account(109));
+        preparar_subasta(&mut contrato, vendedor, c1, c2, 150);
+        contrato.priv_ofertar(c1, 0, 120, 10).unwrap();
+        contrato.priv_ofertar(c2, 0, 160, 20).unwrap();
+
+        assert_eq!(contrato.priv_finalizar_subasta(0, 999).unwrap_err(), "La subasta todavia no termino.".to_string());
+        contrato.priv_finalizar_subasta(0, 1000).unwrap();
+        assert_eq!(contrato.priv_finalizar_subasta(0, 1001).unwrap_err(), "La subasta ya fue finalizada.".to_string());
+
+        let orden = contrato.buscar_orden(0).unwrap();
+        assert_eq!(orden.id_comprador, c2);
+        assert_eq!(orden.info_publicacion.2, 160);
+        assert_eq!(orden.estado, EstadoCompra::Pendiente);
+        assert!(!contrato.buscar_publicacion(0).unwrap().disponible);
+        assert_eq!(contrato.historial_productos.get(1).unwrap().1, 3);
+    }
+
+    #[ink::test]
+    fn finalizar_subasta_sin_alcanzar_reserva_devuelve_stock() {
+        let mut contrato = PrimerContrato::new();
+        let (vendedor, c1, c2) = (account(110), account(111), account(112));
+        preparar_subasta(&mut contrato, vendedor, c1, c2, 500);
+        contrato.priv_ofertar(c1, 0, 120, 10).unwrap();
 
-    contrato.priv_cargar_producto(vendedor, "P".into(), "D".into(), 10, Categoria::Otro, 5).unwrap();
-        contrato.priv_crear_publicacion(vendedor, vec![(1,1)]).unwrap();
+        contrato.priv_finalizar_subasta(0, 1000).unwrap();
 
-        let account_invalido: AccountId = [0x0; 32].into();
-        let res = contrato.priv_calificar(0, 5, account_invalido);
-        assert!(res.is_err());
-        assert_eq!(res.unwrap_err(), "No se encontro el usuario.");
+        assert!(contrato.get_ordenes().is_err());
+        assert_eq!(contrato.historial_productos.get(1).unwrap().1, 5);
+        assert_eq!(contrato.priv_retirar_fondos(c1, None), Ok(120));
     }
 
     #[ink::test]
-    fn priv_calificar_usuario_no_participante_falla() {
+    fn finalizar_subasta_con_ganador_que_ya_no_es_comprador_devuelve_oferta_y_stock() {
         let mut contrato = PrimerContrato::new();
-        let vendedor = account(160);
-        let comprador = account(161);
-        let otro = account(162);
+        let (vendedor, c1, c2) = (account(237), account(238), account(239));
+        preparar_subasta(&mut contrato, vendedor, c1, c2, 150);
+        fondear_escrow(160);
+        contrato.priv_ofertar(c1, 0, 160, 10).unwrap();
+        contrato.priv_modificar_rol(c1, Rol::Vend).unwrap();
+
+        contrato.priv_finalizar_subasta(0, 1000).unwrap();
+
+        assert!(contrato.get_ordenes().is_err());
+        assert!(contrato.buscar_publicacion(0).unwrap().subasta.unwrap().finalizada);
+        assert_eq!(contrato.historial_productos.get(1).unwrap().1, 5);
+        assert_eq!(contrato.priv_retirar_fondos(c1, None), Ok(160));
+    }
 
+    ///Función auxiliar que registra un vendedor y un comprador, y crea una publicación (id 0) de precio 200 pagada en moneda nativa
+    fn preparar_publicacion_para_ofertas(contrato: &mut PrimerContrato, vendedor: AccountId, comprador: AccountId) {
         contrato.priv_agregar_usuario_sistema(
             vendedor, "V".into(), "V".into(), "Dir".into(), "v@mail".into(), Rol::Vend
         ).unwrap();
         contrato.priv_agregar_usuario_sistema(
-            comprador, "C".into(), "C".into(), "Dir".into(), "c@mail".into(), Rol::Comp
-        ).unwrap();
-        contrato.priv_agregar_usuario_sistema(
-            otro, "O".into(), "O".into(), "Dir".into(), "o@mail".into(), Rol::Comp
+            comprador, "C".into(), "C".into(), "Dir".into(), "c@mail".into(), Rol::Comp
         ).unwrap();
+        contrato.priv_cargar_producto(vendedor, "P".into(), "D".into(), 100, OTRO, 10).unwrap();
+        contrato.priv_crear_publicacion(vendedor, vec![(1, 2)], None).unwrap();
+    }
 
-    contrato.priv_cargar_producto(vendedor, "P".into(), "D".into(), 10, Categoria::Otro, 5).unwrap();
-        contrato.priv_crear_publicacion(vendedor, vec![(1,1)]).unwrap();
-        contrato.priv_crear_orden_de_compra(comprador, 0).unwrap();
-        contrato.priv_enviar_compra(vendedor, 0).unwrap();
-        contrato.priv_recibir_compra(comprador, 0).unwrap();
+    #[ink::test]
+    fn hacer_oferta_valida_usuario_y_publicacion() {
+        let mut contrato = PrimerContrato::new();
+        let (vendedor, comprador) = (account(113), account(114));
+        preparar_publicacion_para_ofertas(&mut contrato, vendedor, comprador);
 
-        let res = contrato.priv_calificar(0, 4, otro);
-        assert!(res.is_err());
-    }
+        assert_eq!(contrato.priv_hacer_oferta(vendedor, 0, 150, 0).unwrap_err(), "El usuario es vendedor.".to_string());
+        assert_eq!(contrato.priv_hacer_oferta(comprador, 0, 0, 0).unwrap_err(), "El precio ofertado no es valido.".to_string());
+        assert_eq!(contrato.priv_hacer_oferta(comprador, 5, 150, 0).unwrap_err(), "No se encontro la publicacion.".to_string());
+        assert_eq!(contrato.priv_hacer_oferta(comprador, 0, 150, 0), Ok(0));
 
+        let oferta = contrato.buscar_oferta(0).unwrap();
+        assert_eq!(oferta.estado, EstadoOferta::Pendiente);
+        assert_eq!(oferta.vencimiento, DURACION_OFERTA);
+    }
 
     #[ink::test]
-    fn modificar_a_rol_ambos_sin_datos_previos() {
+    fn aceptar_oferta_crea_orden_al_precio_negociado() {
         let mut contrato = PrimerContrato::new();
-        let usuario = account(200);
-
-        contrato.priv_agregar_usuario_sistema(
-            usuario,
-            "Nombre".to_string(),
-            "Apellido".to_string(),
-            "Dir".to_string(),
-            "u@mail.com".to_string(),
-            Rol::Comp,
-        ).unwrap();
+        let (vendedor, comprador) = (account(115), account(116));
+        preparar_publicacion_para_ofertas(&mut contrato, vendedor, comprador);
+        contrato.priv_hacer_oferta(comprador, 0, 150, 0).unwrap();
+
+        assert_eq!(contrato.priv_aceptar_oferta(comprador, 0, 10).unwrap_err(), "El usuario no puede responder esta oferta.".to_string());
+        contrato.priv_aceptar_oferta(vendedor, 0, 10).unwrap();
+        assert_eq!(contrato.priv_aceptar_oferta(vendedor, 0, 10).unwrap_err(), "La oferta ya fue resuelta.".to_string());
+
+        let orden = contrato.buscar_orden(0).unwrap();
+        assert_eq!(orden.id_comprador, comprador);
+        assert_eq!(orden.info_publicacion.2, 150);
+        assert_eq!(contrato.buscar_publicacion(0).unwrap().precio_final, 200);
+        assert_eq!(contrato.historial_productos.get(1).unwrap().1, 6);
+        assert_eq!(contrato.buscar_oferta(0).unwrap().estado, EstadoOferta::Aceptada);
+    }
 
-        let res = contrato.priv_modificar_rol(usuario, Rol::Ambos);
-        assert!(res.is_ok());
+    #[ink::test]
+    fn oferta_vencida_no_se_puede_aceptar() {
+        let mut contrato = PrimerContrato::new();
+        let (vendedor, comprador) = (account(117), account(118));
+        preparar_publicacion_para_ofertas(&mut contrato, vendedor, comprador);
+        contrato.priv_hacer_oferta(comprador, 0, 150, 0).unwrap();
 
-        let u = contrato.buscar_usuario(usuario).unwrap();
-        assert!(u.datos_comprador.is_some());
-        assert!(u.datos_vendedor.is_some());
+        let res = contrato.priv_aceptar_oferta(vendedor, 0, DURACION_OFERTA);
+        assert_eq!(res.unwrap_err(), "La oferta esta vencida.".to_string());
 
-        assert_eq!(u.datos_comprador.as_ref().unwrap().ordenes_de_compra.len(), 0);
-        assert_eq!(u.datos_comprador.as_ref().unwrap().reputacion_como_comprador.len(), 0);
-        assert_eq!(u.datos_vendedor.as_ref().unwrap().productos.len(), 0);
-        assert_eq!(u.datos_vendedor.as_ref().unwrap().publicaciones.len(), 0);
-        assert_eq!(u.datos_vendedor.as_ref().unwrap().reputacion_como_vendedor.len(), 0);
+        contrato.priv_retirar_oferta(comprador, 0).unwrap();
+        assert_eq!(contrato.buscar_oferta(0).unwrap().estado, EstadoOferta::Retirada);
+        assert_eq!(contrato.priv_retirar_fondos(comprador, None), Ok(150));
     }
 
     #[ink::test]
-    fn modificar_a_rol_ambos_con_datos_comprador_previos() {
+    fn rechazar_oferta_acredita_al_comprador() {
         let mut contrato = PrimerContrato::new();
-        let usuario = account(201);
-
-        contrato.priv_agregar_usuario_sistema(
-            usuario,
-            "Comprador".to_string(),
-            "Uno".to_string(),
-            "Dir".to_string(),
-            "c@mail.com".to_string(),
-            Rol::Comp,
-        ).unwrap();
+        let (vendedor, comprador) = (account(119), account(120));
+        preparar_publicacion_para_ofertas(&mut contrato, vendedor, comprador);
+        contrato.priv_hacer_oferta(comprador, 0, 150, 0).unwrap();
+        let escrow = fondear_escrow(150);
+
+        assert_eq!(contrato.priv_rechazar_oferta(comprador, 0).unwrap_err(), "Solo el vendedor puede rechazar la oferta.".to_string());
+        contrato.priv_rechazar_oferta(vendedor, 0).unwrap();
+        assert_eq!(contrato.priv_retirar_oferta(comprador, 0).unwrap_err(), "La oferta ya fue resuelta.".to_string());
+        assert_eq!(contrato.priv_retirar_fondos(comprador, None), Ok(150));
+        assert_eq!(saldo_nativo(comprador), 150);
+        assert_eq!(saldo_retenido(escrow), 0);
+        assert!(contrato.get_ordenes().is_err());
+    }
 
-        //Los datos del comprador no deben perderse si se modifica su rol;
-        {
-            let mut u = contrato.buscar_usuario(usuario).unwrap();
-            u.datos_comprador = Some(Comprador {
-                ordenes_de_compra: vec![1, 2],
-                reputacion_como_comprador: vec![5],
-            });
-            contrato.usuarios.insert(usuario, &u);
-        }
+    #[ink::test]
+    fn contraoferta_menor_acredita_la_diferencia() {
+        let mut contrato = PrimerContrato::new();
+        let (vendedor, comprador) = (account(121), account(122));
+        preparar_publicacion_para_ofertas(&mut contrato, vendedor, comprador);
+        contrato.priv_hacer_oferta(comprador, 0, 190, 0).unwrap();
+        let escrow = fondear_escrow(190);
+
+        contrato.priv_contraofertar(vendedor, 0, 170, 10).unwrap();
+        assert_eq!(contrato.priv_contraofertar(vendedor, 0, 160, 20).unwrap_err(), "Solo se puede contraofertar una oferta pendiente.".to_string());
+        assert_eq!(contrato.priv_aceptar_oferta(vendedor, 0, 20).unwrap_err(), "El usuario no puede responder esta oferta.".to_string());
+
+        contrato.priv_aceptar_oferta(comprador, 0, 20).unwrap();
+        assert_eq!(contrato.buscar_orden(0).unwrap().info_publicacion.2, 170);
+        assert_eq!(contrato.priv_retirar_fondos(comprador, None), Ok(20));
+        assert_eq!(saldo_nativo(comprador), 20);
+        assert_eq!(saldo_retenido(escrow), 170); //El precio acordado queda retenido en la orden
+    }
 
-        let res = contrato.priv_modificar_rol(usuario, Rol::Ambos);
-        assert!(res.is_ok());
+    #[ink::test]
+    fn contraoferta_mayor_con_token_cobra_la_diferencia() {
+        let mut contrato = PrimerContrato::new();
+        let escrow = ink::env::account_id::<ink::env::DefaultEnvironment>();
+        let (vendedor, comprador, token) = (account(123), account(124), account(125));
+        preparar_publicacion_con_token(&mut contrato, vendedor, comprador, token);
+        mock_psp22::mint(token, comprador, 1000);
+        mock_psp22::approve(token, comprador, escrow, 180);
+
+        contrato.priv_hacer_oferta(comprador, 0, 150, 0).unwrap();
+        assert_eq!(mock_psp22::balance_of(token, escrow), 150);
+
+        contrato.priv_contraofertar(vendedor, 0, 180, 10).unwrap();
+        contrato.priv_aceptar_oferta(comprador, 0, 20).unwrap();
+        assert_eq!(mock_psp22::balance_of(token, escrow), 180);
+        assert_eq!(mock_psp22::balance_of(token, comprador), 820);
+
+        let orden = contrato.buscar_orden(0).unwrap();
+        assert_eq!(orden.info_publicacion.2, 180);
+        assert_eq!(orden.token_de_pago, Some(token));
+    }
 
-        let u = contrato.buscar_usuario(usuario).unwrap();
-        assert!(u.datos_comprador.is_some()); //Rol previo
-        assert!(u.datos_vendedor.is_some()); //Rol nuevo (ambos)
+    #[ink::test]
+    fn crear_cupon_valida_datos() {
+        let mut contrato = PrimerContrato::new();
+        let (vendedor, comprador) = (account(126), account(127));
+        preparar_publicacion_para_ofertas(&mut contrato, vendedor, comprador);
+
+        assert_eq!(contrato.priv_crear_cupon(comprador, "C10".into(), Descuento::Porcentaje(10), 5, 100, 0).unwrap_err(), "El usuario es comprador.".to_string());
+        assert_eq!(contrato.priv_crear_cupon(vendedor, "C10".into(), Descuento::Porcentaje(101), 5, 100, 0).unwrap_err(), "El descuento del cupon no es valido.".to_string());
+        assert_eq!(contrato.priv_crear_cupon(vendedor, "C10".into(), Descuento::Porcentaje(10), 0, 100, 0).unwrap_err(), "La cantidad de usos del cupon no es valida.".to_string());
+        assert_eq!(contrato.priv_crear_cupon(vendedor, "C10".into(), Descuento::Porcentaje(10), 5, 100, 100).unwrap_err(), "El vencimiento del cupon debe ser posterior al momento actual.".to_string());
+        contrato.priv_crear_cupon(vendedor, "C10".into(), Descuento::Porcentaje(10), 5, 100, 0).unwrap();
+        assert_eq!(contrato.priv_crear_cupon(vendedor, "C10".into(), Descuento::MontoFijo(10), 5, 100, 0).unwrap_err(), "El cupon ya existe.".to_string());
+    }
 
-        //Fijarse que efectivamente sus datos siguen estando
-        assert_eq!(u.datos_comprador.as_ref().unwrap().ordenes_de_compra.len(), 2);
-        assert_eq!(u.datos_comprador.as_ref().unwrap().reputacion_como_comprador.len(), 1);
+    #[ink::test]
+    fn orden_con_cupon_registra_precio_original_y_descontado() {
+        let mut contrato = PrimerContrato::new();
+        let (vendedor, comprador) = (account(128), account(129));
+        preparar_publicacion_para_ofertas(&mut contrato, vendedor, comprador);
+        contrato.priv_crear_cupon(vendedor, "C10".into(), Descuento::Porcentaje(10), 1, 100, 0).unwrap();
+
+        let cupon = Some("C10".to_string());
+        assert_eq!(contrato.verificar_pago(0, 200, &cupon, 0, 10).unwrap_err(), "El pago no coincide con el precio de la publicacion.".to_string());
+        assert!(contrato.verificar_pago(0, 180, &cupon, 0, 10).is_ok());
+        contrato.priv_crear_orden_de_compra(comprador, 0, cupon.clone(), 0, 10).unwrap();
+
+        let orden = contrato.buscar_orden(0).unwrap();
+        assert_eq!(orden.precio_original, 200);
+        assert_eq!(orden.info_publicacion.2, 180);
+
+        let res = contrato.priv_crear_orden_de_compra(comprador, 0, cupon, 0, 20);
+        assert_eq!(res.unwrap_err(), "El cupon no tiene usos restantes.".to_string());
+        let res = contrato.priv_crear_orden_de_compra(comprador, 0, Some("OTRO".into()), 0, 20);
+        assert_eq!(res.unwrap_err(), "No se encontro el cupon.".to_string());
     }
 
     #[ink::test]
-    fn modificar_a_rol_ambos_con_datos_vendedor_previos() {
+    fn cupon_vencido_o_mayor_al_precio_falla() {
         let mut contrato = PrimerContrato::new();
-        let usuario = account(202);
+        let (vendedor, comprador) = (account(130), account(131));
+        preparar_publicacion_para_ofertas(&mut contrato, vendedor, comprador);
+        contrato.priv_crear_cupon(vendedor, "FIJO".into(), Descuento::MontoFijo(50), 5, 100, 0).unwrap();
+        contrato.priv_crear_cupon(vendedor, "TODO".into(), Descuento::MontoFijo(200), 5, 100, 0).unwrap();
+
+        let res = contrato.priv_crear_orden_de_compra(comprador, 0, Some("FIJO".into()), 0, 100);
+        assert_eq!(res.unwrap_err(), "El cupon esta vencido.".to_string());
+        let res = contrato.priv_crear_orden_de_compra(comprador, 0, Some("TODO".into()), 0, 10);
+        assert_eq!(res.unwrap_err(), "El descuento del cupon supera el precio.".to_string());
+
+        contrato.priv_crear_orden_de_compra(comprador, 0, Some("FIJO".into()), 0, 10).unwrap();
+        assert_eq!(contrato.buscar_orden(0).unwrap().info_publicacion.2, 150);
+    }
 
-        contrato.priv_agregar_usuario_sistema(
-            usuario,
-            "Vendedor".to_string(),
-            "Dos".to_string(),
-            "Dir".to_string(),
-            "v@mail.com".to_string(),
-            Rol::Vend,
-        ).unwrap();
+    #[ink::test]
+    fn promocion_rige_solo_en_su_periodo() {
+        let mut contrato = PrimerContrato::new();
+        let (vendedor, comprador) = (account(132), account(133));
+        preparar_publicacion_para_ofertas(&mut contrato, vendedor, comprador);
+
+        assert_eq!(contrato.priv_programar_promocion(comprador, 0, 150, 100, 200).unwrap_err(), "La publicacion no pertenece al usuario.".to_string());
+        assert_eq!(contrato.priv_programar_promocion(vendedor, 0, 200, 100, 200).unwrap_err(), "El precio de la promocion debe ser menor al precio de la publicacion.".to_string());
+        assert_eq!(contrato.priv_programar_promocion(vendedor, 0, 150, 200, 200).unwrap_err(), "El inicio de la promocion debe ser anterior a su fin.".to_string());
+        contrato.priv_programar_promocion(vendedor, 0, 150, 100, 200).unwrap();
+
+        assert_eq!(contrato.calcular_precio_de_compra(0, &None, 0, 99), Ok(200));
+        assert_eq!(contrato.calcular_precio_de_compra(0, &None, 0, 100), Ok(150));
+        assert_eq!(contrato.calcular_precio_de_compra(0, &None, 0, 200), Ok(200));
+
+        contrato.priv_crear_cupon(vendedor, "C10".into(), Descuento::Porcentaje(10), 5, 1000, 0).unwrap();
+        contrato.priv_crear_orden_de_compra(comprador, 0, Some("C10".into()), 0, 150).unwrap();
+        let orden = contrato.buscar_orden(0).unwrap();
+        assert_eq!(orden.precio_original, 200);
+        assert_eq!(orden.info_publicacion.2, 135);
+    }
 
-        {
-            let mut u = contrato.buscar_usuario(usuario).unwrap();
-            u.datos_vendedor = Some(Vendedor {
-                productos: vec![10],
-                publicaciones: vec![5],
-                reputacion_como_vendedor: vec![3],
-            });
-            contrato.usuarios.insert(usuario, &u);
-        }
+    #[ink::test]
+    fn solicitar_devolucion_valida_comprador_estado_y_plazo() {
+        let mut contrato = PrimerContrato::new();
+        let (vendedor, comprador) = (account(134), account(135));
+        preparar_publicacion_para_ofertas(&mut contrato, vendedor, comprador);
+        contrato.priv_crear_orden_de_compra(comprador, 0, None, 0, 0).unwrap();
 
-        let res = contrato.priv_modificar_rol(usuario, Rol::Ambos);
-        assert!(res.is_ok());
+        assert_eq!(contrato.priv_solicitar_devolucion(comprador, 0, "Roto".into(), 10).unwrap_err(), "La compra aun no fue recibida.".to_string());
+        contrato.priv_enviar_compra(vendedor, 0).unwrap();
+        contrato.priv_recibir_compra(comprador, 0, 100).unwrap();
 
-        let u = contrato.buscar_usuario(usuario).unwrap();
-        assert!(u.datos_comprador.is_some());
-        assert!(u.datos_vendedor.is_some());
+        assert_eq!(contrato.priv_solicitar_devolucion(vendedor, 0, "Roto".into(), 110).unwrap_err(), "Solo el comprador puede solicitar la devolucion.".to_string());
+        assert_eq!(contrato.priv_solicitar_devolucion(comprador, 0, "Roto".into(), 100 + PLAZO_DEVOLUCION).unwrap_err(), "El plazo de devolucion ya vencio.".to_string());
+        contrato.priv_solicitar_devolucion(comprador, 0, "Roto".into(), 110).unwrap();
 
-        assert_eq!(u.datos_vendedor.as_ref().unwrap().productos.len(), 1);
-        assert_eq!(u.datos_vendedor.as_ref().unwrap().publicaciones.len(), 1);
+        let orden = contrato.buscar_orden(0).unwrap();
+        assert_eq!(orden.estado, EstadoCompra::DevolucionSolicitada);
+        assert_eq!(orden.motivo_devolucion, Some("Roto".to_string()));
     }
 
     #[ink::test]
-    fn modificar_a_rol_ambos_con_ambos_datos_existentes() {
+    fn rechazar_devolucion_vuelve_a_recibido() {
         let mut contrato = PrimerContrato::new();
-        let usuario = account(203);
+        let (vendedor, comprador) = (account(136), account(137));
+        preparar_publicacion_para_ofertas(&mut contrato, vendedor, comprador);
+        contrato.priv_crear_orden_de_compra(comprador, 0, None, 0, 0).unwrap();
+        contrato.priv_enviar_compra(vendedor, 0).unwrap();
+        contrato.priv_recibir_compra(comprador, 0, 0).unwrap();
+        contrato.priv_solicitar_devolucion(comprador, 0, "No es lo pedido".into(), 10).unwrap();
 
-        contrato.priv_agregar_usuario_sistema(
-            usuario,
-            "Ambos".to_string(),
-            "Tres".to_string(),
-            "Dir".to_string(),
-            "a@mail.com".to_string(),
-            Rol::Ambos,
-        ).unwrap();
+        assert_eq!(contrato.priv_rechazar_devolucion(comprador, 0).unwrap_err(), "Solo el vendedor puede responder la devolucion.".to_string());
+        assert_eq!(contrato.priv_confirmar_devolucion(vendedor, 0).unwrap_err(), "La orden no esta en el estado requerido para esta accion.".to_string());
+        contrato.priv_rechazar_devolucion(vendedor, 0).unwrap();
 
-        assert!(contrato.priv_modificar_rol(usuario, Rol::Vend).is_ok());
-        let res = contrato.priv_modificar_rol(usuario, Rol::Ambos);
-        assert!(res.is_ok());
+        assert_eq!(contrato.buscar_orden(0).unwrap().estado, EstadoCompra::Recibido);
+        assert_eq!(contrato.priv_solicitar_devolucion(comprador, 0, "Otra vez".into(), 20).unwrap_err(), "La devolucion ya fue solicitada.".to_string());
+    }
 
-        let u = contrato.buscar_usuario(usuario).unwrap();
-        assert!(u.datos_comprador.is_some());
-        assert!(u.datos_vendedor.is_some());
+    #[ink::test]
+    fn devolucion_con_token_reintegra_al_comprador_y_repone_stock() {
+        let mut contrato = PrimerContrato::new();
+        let owner = contrato.owner;
+        let escrow = ink::env::account_id::<ink::env::DefaultEnvironment>();
+        let (vendedor, comprador, token) = (account(138), account(139), account(140));
+        preparar_publicacion_con_token(&mut contrato, vendedor, comprador, token);
+        contrato.priv_configurar_comision(owner, 250).unwrap();
+        mock_psp22::mint(token, comprador, 200);
+        mock_psp22::approve(token, comprador, escrow, 200);
+
+        contrato.priv_crear_orden_de_compra(comprador, 0, None, 0, 0).unwrap();
+        contrato.priv_enviar_compra(vendedor, 0).unwrap();
+        contrato.priv_recibir_compra(comprador, 0, 0).unwrap();
+        assert_eq!(mock_psp22::balance_of(token, vendedor), 195);
+        let stock_antes = contrato.historial_productos.get(1).unwrap().1;
+
+        contrato.priv_solicitar_devolucion(comprador, 0, "Roto".into(), 10).unwrap();
+        let res = contrato.priv_aceptar_devolucion(vendedor, 0, 20);
+        assert_eq!(res.unwrap_err(), "El contrato no esta autorizado a mover esa cantidad de tokens.".to_string());
+
+        mock_psp22::approve(token, vendedor, escrow, 195);
+        contrato.tesoreria_tokens.insert(token, &4); //Como si el owner hubiera retirado parte de la comision
+        assert_eq!(contrato.priv_aceptar_devolucion(vendedor, 0, 20).unwrap_err(), "La tesoreria no alcanza para reintegrar la comision.".to_string());
+        contrato.tesoreria_tokens.insert(token, &5);
+        contrato.priv_aceptar_devolucion(vendedor, 0, 20).unwrap();
+        assert_eq!(contrato.buscar_orden(0).unwrap().estado, EstadoCompra::EnDevolucion);
+        assert_eq!(mock_psp22::balance_of(token, vendedor), 0);
+        assert_eq!(contrato.tesoreria_tokens.get(token), Some(0));
+
+        contrato.priv_confirmar_devolucion(vendedor, 0).unwrap();
+        assert_eq!(contrato.buscar_orden(0).unwrap().estado, EstadoCompra::Devuelto);
+        assert_eq!(mock_psp22::balance_of(token, comprador), 200); //El total pagado, comision incluida
+        assert_eq!(mock_psp22::balance_of(token, escrow), 0);
+        assert_eq!(contrato.historial_productos.get(1).unwrap().1, stock_antes + 2);
     }
 
     #[ink::test]
-    fn enviar_compra_fallo_estado() {
-        let mut instance = PrimerContrato::new();
-        let vendedor = account(240);
-        let comprador = account(241);
+    fn comprador_reclama_devolucion_no_confirmada_al_vencer_el_plazo() {
+        let mut contrato = PrimerContrato::new();
+        let escrow = ink::env::account_id::<ink::env::DefaultEnvironment>();
+        let (vendedor, comprador, token) = (account(212), account(213), account(214));
+        preparar_publicacion_con_token(&mut contrato, vendedor, comprador, token);
+        mock_psp22::mint(token, comprador, 200);
+        mock_psp22::approve(token, comprador, escrow, 200);
+        contrato.priv_crear_orden_de_compra(comprador, 0, None, 0, 0).unwrap();
+        contrato.priv_enviar_compra(vendedor, 0).unwrap();
+        contrato.priv_recibir_compra(comprador, 0, 0).unwrap();
+        let stock_antes = contrato.historial_productos.get(1).unwrap().1;
+
+        contrato.priv_solicitar_devolucion(comprador, 0, "Roto".into(), 10).unwrap();
+        assert_eq!(contrato.priv_reclamar_devolucion(comprador, 0, 20).unwrap_err(), "La orden no esta en el estado requerido para esta accion.".to_string());
+        mock_psp22::approve(token, vendedor, escrow, 200);
+        contrato.priv_aceptar_devolucion(vendedor, 0, 20).unwrap();
+
+        let vencimiento = 20 + PLAZO_CONFIRMACION_DEVOLUCION;
+        assert_eq!(contrato.priv_reclamar_devolucion(vendedor, 0, vencimiento).unwrap_err(), "Solo el comprador puede reclamar la devolucion.".to_string());
+        assert_eq!(contrato.priv_reclamar_devolucion(comprador, 0, vencimiento - 1).unwrap_err(), "El vendedor todavia esta a tiempo de confirmar la devolucion.".to_string());
+        contrato.priv_reclamar_devolucion(comprador, 0, vencimiento).unwrap();
+
+        assert_eq!(contrato.buscar_orden(0).unwrap().estado, EstadoCompra::Devuelto);
+        assert_eq!(mock_psp22::balance_of(token, comprador), 200);
+        assert_eq!(contrato.historial_productos.get(1).unwrap().1, stock_antes); //Sin confirmacion del vendedor no se repone stock
+        assert_eq!(contrato.priv_confirmar_devolucion(vendedor, 0).unwrap_err(), "La orden no esta en el estado requerido para esta accion.".to_string());
+    }
 
-        instance.priv_agregar_usuario_sistema(
+    ///Función auxiliar que registra un vendedor y un comprador, y crea una orden (id 0) de una publicación con dos productos (ids 1 y 2)
+    fn preparar_orden_de_dos_lineas(contrato: &mut PrimerContrato, vendedor: AccountId, comprador: AccountId) {
+        contrato.priv_agregar_usuario_sistema(
             vendedor, "V".into(), "V".into(), "Dir".into(), "v@mail".into(), Rol::Vend
         ).unwrap();
-        instance.priv_agregar_usuario_sistema(
+        contrato.priv_agregar_usuario_sistema(
             comprador, "C".into(), "C".into(), "Dir".into(), "c@mail".into(), Rol::Comp
         ).unwrap();
+        contrato.priv_cargar_producto(vendedor, "P1".into(), "D".into(), 100, OTRO, 10).unwrap();
+        contrato.priv_cargar_producto(vendedor, "P2".into(), "D".into(), 50, OTRO, 10).unwrap();
+        contrato.priv_crear_publicacion(vendedor, vec![(1, 1), (2, 3)], None).unwrap();
+        contrato.priv_crear_orden_de_compra(comprador, 0, None, 0, 0).unwrap();
+    }
 
-    instance.priv_cargar_producto(vendedor, "P".into(), "D".into(), 10, Categoria::Otro, 3).unwrap();
-        instance.priv_crear_publicacion(vendedor, vec![(1,1)]).unwrap();
-
-        instance.priv_crear_orden_de_compra(comprador, 0).unwrap();
-
-        let (pos_id, mut orden) = instance.historial_ordenes_de_compra.get(0).unwrap();
-        orden.estado = EstadoCompra::Enviado;
-        instance.historial_ordenes_de_compra.set(0, &(pos_id, orden));
+    #[ink::test]
+    fn orden_se_crea_con_una_linea_por_producto() {
+        let mut contrato = PrimerContrato::new();
+        let (vendedor, comprador) = (account(141), account(142));
+        preparar_orden_de_dos_lineas(&mut contrato, vendedor, comprador);
 
-        let result = instance.priv_enviar_compra(vendedor, 0);
-        assert!(result.is_err());
-        assert_eq!(result.unwrap_err(), "El producto no puede ser enviado.".to_string());
+        let lineas = contrato.buscar_orden(0).unwrap().lineas;
+        assert_eq!(lineas.len(), 2);
+        assert_eq!((lineas[1].id_producto, lineas[1].cantidad), (2, 3));
+        assert!(lineas.iter().all(|linea| linea.estado == EstadoLinea::Pendiente));
     }
-    
-        #[ink::test]
-        fn enviar_compra_fallo_orden_no_existe() {
-            let mut instance = PrimerContrato::new();
-            let vendedor = account(240);
-            let comprador = account(241);
 
-            instance.priv_agregar_usuario_sistema(
-                vendedor, "V".into(), "V".into(), "Dir".into(), "v@mail".into(), Rol::Vend
-            ).unwrap();
-            instance.priv_agregar_usuario_sistema(
-                comprador, "C".into(), "C".into(), "Dir".into(), "c@mail".into(), Rol::Comp
-            ).unwrap();
+    #[ink::test]
+    fn envio_parcial_pasa_a_enviado_al_enviar_todas_las_lineas() {
+        let mut contrato = PrimerContrato::new();
+        let (vendedor, comprador) = (account(143), account(144));
+        preparar_orden_de_dos_lineas(&mut contrato, vendedor, comprador);
 
-            instance.priv_cargar_producto(vendedor, "P".into(), "D".into(), 10, Categoria::Otro, 3).unwrap();
-            instance.priv_crear_publicacion(vendedor, vec![(1,1)]).unwrap();
+        assert_eq!(contrato.priv_enviar_linea(vendedor, 0, 3, "T0".into()).unwrap_err(), "La orden no tiene ese producto.".to_string());
+        contrato.priv_enviar_linea(vendedor, 0, 1, "T1".into()).unwrap();
+        assert_eq!(contrato.priv_enviar_linea(vendedor, 0, 1, "T1".into()).unwrap_err(), "La linea ya fue enviada.".to_string());
 
-            let result = instance.priv_enviar_compra(vendedor, 999); 
+        let orden = contrato.buscar_orden(0).unwrap();
+        assert_eq!(orden.estado, EstadoCompra::Pendiente);
+        assert_eq!(orden.lineas[0].seguimiento, Some("T1".to_string()));
+        assert_eq!(contrato.priv_cancelar_compra(comprador, 0).unwrap_err(), "La compra tiene productos enviados.".to_string());
 
-            assert!(result.is_err());
-            assert_eq!(result.unwrap_err(), "No existe la orden buscada.".to_string());
-        }
+        contrato.priv_enviar_linea(vendedor, 0, 2, "T2".into()).unwrap();
+        assert_eq!(contrato.buscar_orden(0).unwrap().estado, EstadoCompra::Enviado);
+    }
 
-   
     #[ink::test]
-    fn comprobar_rol_ambos_casos() {
-        let usuario_ambos = account(1);
-        let vendedor = account(2);
-        let comprador = account(3);
-        let _otro_usuario = account(4);
-
-        let usuario = Usuario::nuevo(
-            usuario_ambos,
-            "Nombre".into(),
-            "Apellido".into(),
-            "Dir".into(),
-            "u@mail".into(),
-            Rol::Ambos,
-        );
+    fn recepcion_parcial_pasa_a_recibido_al_recibir_todas_las_lineas() {
+        let mut contrato = PrimerContrato::new();
+        let (vendedor, comprador) = (account(145), account(146));
+        preparar_orden_de_dos_lineas(&mut contrato, vendedor, comprador);
+        contrato.priv_enviar_linea(vendedor, 0, 1, "T1".into()).unwrap();
 
-        let resultado_vendedor = usuario.comprobar_rol(usuario_ambos, comprador);
-        assert_eq!(resultado_vendedor, Ok(Rol::Vend));
+        assert_eq!(contrato.priv_recibir_linea(comprador, 0, 2, 10).unwrap_err(), "La linea no esta en viaje.".to_string());
+        contrato.priv_recibir_linea(comprador, 0, 1, 10).unwrap();
+        assert_eq!(contrato.buscar_orden(0).unwrap().lineas[0].estado, EstadoLinea::Recibida);
 
-        let resultado_comprador = usuario.comprobar_rol(vendedor, usuario_ambos);
-        assert_eq!(resultado_comprador, Ok(Rol::Comp));
+        contrato.priv_enviar_linea(vendedor, 0, 2, "T2".into()).unwrap();
+        contrato.priv_recibir_linea(comprador, 0, 2, 20).unwrap();
 
-        let resultado_error = usuario.comprobar_rol(vendedor, comprador);
-        assert!(resultado_error.is_err());
-        assert_eq!(resultado_error.unwrap_err(), "El usuario no participa de la compra.".to_string());
+        let orden = contrato.buscar_orden(0).unwrap();
+        assert_eq!(orden.estado, EstadoCompra::Recibido);
+        assert_eq!(orden.recibido_en, Some(20));
     }
 
-
     #[ink::test]
-    fn comprobar_rol_errores() {
-        let mut instancia = PrimerContrato::new(); 
-        let vendedor_real = account(1);
-        let comprador_real = account(2);
-        let otro_vendedor = account(3);
-        let otro_comprador = account(4);
-
-        let mut usuario_vendedor = Usuario::nuevo(
-            vendedor_real,
-            "Vendedor".to_string(),
-            "Apellido".to_string(),
-            "Dir".to_string(),
-            "v@mail.com".to_string(),
-            Rol::Vend,
-        );
-
-        let resultado_error_vendedor = usuario_vendedor.comprobar_rol(otro_vendedor, comprador_real);
-        assert!(resultado_error_vendedor.is_err());
-        assert_eq!(resultado_error_vendedor.unwrap_err(), "El vendedor no posee esta publicacion.".to_string());
+    fn enviar_y_recibir_compra_completa_actualiza_las_lineas() {
+        let mut contrato = PrimerContrato::new();
+        let (vendedor, comprador) = (account(147), account(148));
+        preparar_orden_de_dos_lineas(&mut contrato, vendedor, comprador);
+        contrato.priv_enviar_linea(vendedor, 0, 1, "T1".into()).unwrap();
 
-        let mut usuario_comprador = Usuario::nuevo(
-            comprador_real,
-            "Comprador".to_string(),
-            "Apellido".to_string(),
-            "Dir".to_string(),
-            "c@mail.com".to_string(),
-            Rol::Comp,
-        );
+        contrato.priv_enviar_compra(vendedor, 0).unwrap();
+        assert!(contrato.buscar_orden(0).unwrap().lineas.iter().all(|linea| linea.estado == EstadoLinea::Enviada));
 
-        let resultado_error_comprador = usuario_comprador.comprobar_rol(vendedor_real, otro_comprador);
-        assert!(resultado_error_comprador.is_err());
-        assert_eq!(resultado_error_comprador.unwrap_err(), "El usuario no posee esa orden de compra.".to_string());
+        contrato.priv_recibir_compra(comprador, 0, 0).unwrap();
+        assert!(contrato.buscar_orden(0).unwrap().lineas.iter().all(|linea| linea.estado == EstadoLinea::Recibida));
     }
 
     #[ink::test]
-    fn actualizar_publicaciones_exito() {
+    fn calificar_registra_monto_y_fecha_para_la_reputacion_ponderada() {
         let mut contrato = PrimerContrato::new();
-        let vendedor = account(210);
+        let (vendedor, comprador) = (account(149), account(150));
+        preparar_publicacion_para_ofertas(&mut contrato, vendedor, comprador);
+        contrato.priv_crear_orden_de_compra(comprador, 0, None, 0, 0).unwrap();
+        contrato.priv_enviar_compra(vendedor, 0).unwrap();
+        contrato.priv_recibir_compra(comprador, 0, 0).unwrap();
+        contrato.priv_calificar(0, 4, comprador, 50).unwrap();
 
-        contrato.priv_agregar_usuario_sistema(
-            vendedor, "V".into(), "V".into(), "Dir".into(), "v@mail".into(), Rol::Vend
-        ).unwrap();
+        let datos_vendedor = contrato.buscar_usuario(vendedor).unwrap().datos_vendedor.unwrap();
+        assert_eq!(datos_vendedor.calificaciones_ponderadas, vec![CalificacionPonderada{ puntuacion: 4, monto: 200, fecha: 50, calificador: comprador, relacionada: false }]);
+        assert_eq!(contrato.priv_reputacion_vendedor(vendedor, 50), Ok((400, 400)));
+        assert_eq!(contrato.priv_reputacion_vendedor(comprador, 50).unwrap_err(), "El usuario no tiene datos de vendedor.".to_string());
+    }
 
-        contrato.priv_cargar_producto(
-            vendedor, "Producto".into(), "Desc".into(), 100, Categoria::Otro, 5
-        ).unwrap();
+    #[ink::test]
+    fn reputacion_ponderada_pesa_por_monto_y_decae_con_la_antiguedad() {
+        let vendedor = Vendedor{
+            productos: Vec::new(),
+            publicaciones: Vec::new(),
+            reputacion_como_vendedor: vec![5, 1],
+            calificaciones_ponderadas: vec![
+                CalificacionPonderada{ puntuacion: 5, monto: 300, fecha: 0, calificador: account(1), relacionada: false },
+                CalificacionPonderada{ puntuacion: 1, monto: 100, fecha: 0, calificador: account(1), relacionada: false },
+            ],
+            reputacion_por_categoria: Vec::new(),
+        };
+        assert_eq!(vendedor.reputacion_promedio(), Ok(300));
+        assert_eq!(vendedor.reputacion_ponderada(0), Ok(400));
 
-        contrato.priv_crear_publicacion(vendedor, vec![(1, 2)]).unwrap(); 
+        let mut decaido = vendedor.clone();
+        decaido.calificaciones_ponderadas[1].fecha = 2 * VIDA_MEDIA_REPUTACION;
+        // El 5 tiene dos vidas medias de antigüedad: pesa 300 / 4 = 75 contra 100 del 1 reciente.
+        assert_eq!(decaido.reputacion_ponderada(2 * VIDA_MEDIA_REPUTACION), Ok(271));
 
-        let orig = contrato.priv_visualizar_productos_de_publicacion(0).unwrap();
+        let sin_calificaciones = Vendedor{ productos: Vec::new(), publicaciones: Vec::new(), reputacion_como_vendedor: Vec::new(), calificaciones_ponderadas: Vec::new(), reputacion_por_categoria: Vec::new() };
+        assert_eq!(sin_calificaciones.reputacion_promedio(), Ok(0));
+        assert_eq!(sin_calificaciones.reputacion_ponderada(0), Ok(0));
+    }
 
-        let mut nueva = Publicacion {
-            id: orig.id,
-            productos: orig.productos.clone(),
-            precio_final: orig.precio_final + 500,
-            id_vendedor: orig.id_vendedor,
-            disponible: false,
-        };
+    ///Función auxiliar que crea una orden (id 0) recibida en el momento 100, lista para calificar
+    fn preparar_orden_recibida(contrato: &mut PrimerContrato, vendedor: AccountId, comprador: AccountId) {
+        preparar_publicacion_para_ofertas(contrato, vendedor, comprador);
+        contrato.priv_crear_orden_de_compra(comprador, 0, None, 0, 0).unwrap();
+        contrato.priv_enviar_compra(vendedor, 0).unwrap();
+        contrato.priv_recibir_compra(comprador, 0, 100).unwrap();
+    }
 
-        assert!(contrato.actualizar_publicaciones(nueva.clone(), 0).is_ok());
+    #[ink::test]
+    fn calificacion_ciega_se_revela_cuando_ambos_se_comprometen() {
+        let mut contrato = PrimerContrato::new();
+        let (vendedor, comprador) = (account(151), account(152));
+        preparar_orden_recibida(&mut contrato, vendedor, comprador);
+        let (sal_comprador, sal_vendedor) = ([1u8; 32], [2u8; 32]);
+
+        let compromiso = contrato.calcular_compromiso(5, sal_comprador);
+        contrato.priv_comprometer_calificacion(comprador, 0, compromiso, 110).unwrap();
+        assert_eq!(contrato.priv_comprometer_calificacion(comprador, 0, compromiso, 120).unwrap_err(), "El usuario ya envio su compromiso de calificacion.".to_string());
+        assert_eq!(contrato.priv_revelar_calificacion(comprador, 0, 5, sal_comprador, 120).unwrap_err(), "La contraparte todavia no envio su compromiso de calificacion.".to_string());
+
+        let compromiso = contrato.calcular_compromiso(3, sal_vendedor);
+        contrato.priv_comprometer_calificacion(vendedor, 0, compromiso, 130).unwrap();
+
+        assert_eq!(contrato.priv_revelar_calificacion(comprador, 0, 4, sal_comprador, 140).unwrap_err(), "La calificacion no coincide con el compromiso.".to_string());
+        contrato.priv_revelar_calificacion(comprador, 0, 5, sal_comprador, 140).unwrap();
+        contrato.priv_revelar_calificacion(vendedor, 0, 3, sal_vendedor, 150).unwrap();
+
+        let orden = contrato.buscar_orden(0).unwrap();
+        assert_eq!(orden.calificaciones, (true, true));
+        assert_eq!(orden.compromisos, (None, None));
+        assert_eq!(orden.puntuacion_del_comprador, Some(5));
+        assert_eq!(contrato.buscar_usuario(comprador).unwrap().datos_comprador.unwrap().reputacion_como_comprador, vec![3]);
+        assert_eq!(contrato.priv_comprometer_calificacion(comprador, 0, [0u8; 32], 160).unwrap_err(), "El usuario ya califico.".to_string());
+    }
 
-        let pos = contrato.devolver_posicion_publicacion(0).unwrap();
-        let (_id, guardada) = contrato.historial_publicaciones.get(pos).unwrap();
-        assert_eq!(guardada.precio_final, nueva.precio_final);
-        assert_eq!(guardada.disponible, nueva.disponible);
+    #[ink::test]
+    fn compromisos_no_revelados_se_anulan_al_vencer_el_plazo_para_revelar() {
+        let mut contrato = PrimerContrato::new();
+        let owner = contrato.owner;
+        let (vendedor, comprador) = (account(247), account(248));
+        preparar_orden_recibida(&mut contrato, vendedor, comprador);
+        contrato.priv_configurar_plazo_calificacion(owner, 1000).unwrap();
+        let limite_revelacion = 1100 + PLAZO_REVELACION;
+
+        let sal = [3u8; 32];
+        contrato.priv_comprometer_calificacion(comprador, 0, contrato.calcular_compromiso(5, sal), 200).unwrap();
+        contrato.priv_comprometer_calificacion(vendedor, 0, contrato.calcular_compromiso(4, sal), 300).unwrap();
+        contrato.priv_revelar_calificacion(vendedor, 0, 4, sal, 400).unwrap();
+
+        assert_eq!(contrato.priv_anular_compromisos_vencidos(0, limite_revelacion - 1).unwrap_err(), "El plazo para revelar las calificaciones todavia no vencio.".to_string());
+        assert_eq!(contrato.priv_revelar_calificacion(comprador, 0, 5, sal, limite_revelacion).unwrap_err(), "El plazo para revelar la calificacion ya vencio.".to_string());
+        contrato.priv_anular_compromisos_vencidos(0, limite_revelacion).unwrap();
+
+        let orden = contrato.buscar_orden(0).unwrap();
+        assert_eq!(orden.compromisos, (None, None));
+        assert_eq!(orden.calificaciones, (false, true)); //La calificacion revelada se mantiene
+        assert_eq!(contrato.priv_anular_compromisos_vencidos(0, limite_revelacion).unwrap_err(), "La orden no tiene compromisos pendientes.".to_string());
+        assert_eq!(contrato.priv_revelar_calificacion(comprador, 0, 5, sal, limite_revelacion).unwrap_err(), "El usuario no envio su compromiso de calificacion.".to_string());
     }
 
     #[ink::test]
-    fn actualizar_publicaciones_id_inexistente_falla() {
+    fn calificacion_ciega_respeta_el_plazo() {
         let mut contrato = PrimerContrato::new();
-        let vendedor = account(211);
+        let owner = contrato.owner;
+        let (vendedor, comprador) = (account(153), account(154));
+        preparar_orden_recibida(&mut contrato, vendedor, comprador);
+
+        assert_eq!(contrato.priv_configurar_plazo_calificacion(comprador, 1000).unwrap_err(), "Solo el owner puede realizar esta accion.".to_string());
+        assert_eq!(contrato.priv_configurar_plazo_calificacion(owner, 0).unwrap_err(), "El plazo de calificacion no es valido.".to_string());
+        contrato.priv_configurar_plazo_calificacion(owner, 1000).unwrap();
+
+        let sal = [7u8; 32];
+        let compromiso = contrato.calcular_compromiso(4, sal);
+        contrato.priv_comprometer_calificacion(comprador, 0, compromiso, 1099).unwrap();
+        assert_eq!(contrato.priv_comprometer_calificacion(vendedor, 0, compromiso, 1100).unwrap_err(), "El plazo de calificacion ya vencio.".to_string());
+
+        // Vencido el plazo, el comprador puede revelar aunque el vendedor nunca se haya comprometido.
+        contrato.priv_revelar_calificacion(comprador, 0, 4, sal, 1100).unwrap();
+        assert_eq!(contrato.buscar_orden(0).unwrap().puntuacion_del_comprador, Some(4));
+        assert_eq!(contrato.priv_revelar_calificacion(vendedor, 0, 4, sal, 1100).unwrap_err(), "El usuario no envio su compromiso de calificacion.".to_string());
+    }
 
+    #[ink::test]
+    fn calificacion_se_atribuye_a_cada_categoria_de_la_orden() {
+        let mut contrato = PrimerContrato::new();
+        let (vendedor, comprador) = (account(155), account(156));
         contrato.priv_agregar_usuario_sistema(
             vendedor, "V".into(), "V".into(), "Dir".into(), "v@mail".into(), Rol::Vend
         ).unwrap();
+        contrato.priv_agregar_usuario_sistema(
+            comprador, "C".into(), "C".into(), "Dir".into(), "c@mail".into(), Rol::Comp
+        ).unwrap();
+        contrato.priv_cargar_producto(vendedor, "Heladera".into(), "D".into(), 100, ELECTRODOMESTICOS, 10).unwrap();
+        contrato.priv_cargar_producto(vendedor, "Lavarropas".into(), "D".into(), 100, ELECTRODOMESTICOS, 10).unwrap();
+        contrato.priv_cargar_producto(vendedor, "Alimento".into(), "D".into(), 10, MASCOTAS, 10).unwrap();
+        contrato.priv_crear_publicacion(vendedor, vec![(1, 1), (2, 1)], None).unwrap();
+        contrato.priv_crear_publicacion(vendedor, vec![(3, 1)], None).unwrap();
+
+        for (id_orden, id_publicacion, calificacion) in [(0, 0, 5), (1, 1, 1)]{
+            contrato.priv_crear_orden_de_compra(comprador, id_publicacion, None, 0, 0).unwrap();
+            contrato.priv_enviar_compra(vendedor, id_orden).unwrap();
+            contrato.priv_recibir_compra(comprador, id_orden, 0).unwrap();
+            contrato.priv_calificar(id_orden, calificacion, comprador, 0).unwrap();
+        }
 
-        let pub_falsa = Publicacion {
-            id: 999,
-            productos: vec![],
-            precio_final: 0,
-            id_vendedor: vendedor,
-            disponible: true,
-        };
-
-        let res = contrato.actualizar_publicaciones(pub_falsa, 999);
-        assert!(res.is_err());
+        let datos_vendedor = contrato.buscar_usuario(vendedor).unwrap().datos_vendedor.unwrap();
+        assert_eq!(datos_vendedor.reputacion_por_categoria, vec![(ELECTRODOMESTICOS, vec![5]), (MASCOTAS, vec![1])]);
+        assert_eq!(contrato.reputacion_vendedor_en_categoria(vendedor, ELECTRODOMESTICOS), Ok(500));
+        assert_eq!(contrato.reputacion_vendedor_en_categoria(vendedor, MASCOTAS), Ok(100));
+        assert_eq!(contrato.reputacion_vendedor_en_categoria(vendedor, ROPA), Ok(0));
+        assert_eq!(contrato.priv_reputacion_vendedor(vendedor, 0).unwrap().0, 300);
     }
 
     #[ink::test]
-    fn priv_calificar_comprador_exito() {
+    fn calificar_requiere_monto_minimo_y_antiguedad_de_la_cuenta() {
         let mut contrato = PrimerContrato::new();
-        let comprador = account(250);
+        let owner = contrato.owner;
+        let (vendedor, comprador) = (account(157), account(158));
+        preparar_orden_recibida(&mut contrato, vendedor, comprador);
+        let compromiso = contrato.calcular_compromiso(5, [3u8; 32]);
+
+        assert_eq!(contrato.priv_configurar_requisitos_calificacion(comprador, 0, 0).unwrap_err(), "Solo el owner puede realizar esta accion.".to_string());
+        contrato.priv_configurar_requisitos_calificacion(owner, 201, 0).unwrap();
+        assert_eq!(contrato.priv_comprometer_calificacion(comprador, 0, compromiso, 110).unwrap_err(), "La orden no alcanza el monto minimo para calificar.".to_string());
+
+        contrato.priv_configurar_requisitos_calificacion(owner, 200, 1000).unwrap();
+        assert_eq!(contrato.priv_comprometer_calificacion(comprador, 0, compromiso, 999).unwrap_err(), "La cuenta no tiene la antiguedad minima para calificar.".to_string());
+        contrato.priv_comprometer_calificacion(comprador, 0, compromiso, 1000).unwrap();
+    }
 
-        contrato.priv_agregar_usuario_sistema(
-            comprador, "Comprador".into(), "C".into(), "Dir".into(), "c@mail".into(), Rol::Comp
-        ).unwrap();
+    #[ink::test]
+    fn calificaciones_entre_cuentas_relacionadas_pesan_menos_y_quedan_en_el_desglose() {
+        let mut contrato = PrimerContrato::new();
+        let owner = contrato.owner;
+        let (vendedor, comprador) = (account(159), account(160));
+        preparar_orden_recibida(&mut contrato, vendedor, comprador);
+        contrato.priv_calificar(0, 1, comprador, 100).unwrap();
+
+        assert_eq!(contrato.priv_marcar_cuentas_relacionadas(owner, comprador, comprador, true).unwrap_err(), "Las cuentas deben ser distintas.".to_string());
+        contrato.priv_marcar_cuentas_relacionadas(owner, vendedor, comprador, true).unwrap();
+        contrato.priv_crear_orden_de_compra(comprador, 0, None, 0, 0).unwrap();
+        contrato.priv_enviar_compra(vendedor, 1).unwrap();
+        contrato.priv_recibir_compra(comprador, 1, 100).unwrap();
+        contrato.priv_calificar(1, 5, comprador, 100).unwrap();
+
+        let desglose = contrato.priv_desglose_reputacion(vendedor, 100).unwrap();
+        assert_eq!(desglose.len(), 2);
+        assert_eq!((desglose[0].0.relacionada, desglose[0].1), (false, 200));
+        assert_eq!((desglose[1].0.relacionada, desglose[1].1), (true, 20));
+        assert_eq!(desglose[1].0.calificador, comprador);
+        // El 5 de la cuenta relacionada pesa 20 contra 200 del 1: (200 + 100) / 220 = 1,36.
+        assert_eq!(contrato.priv_reputacion_vendedor(vendedor, 100), Ok((300, 136)));
+    }
 
-        {
-            let mut u = contrato.buscar_usuario(comprador).unwrap();
-            u.datos_comprador = Some(Comprador {
-                ordenes_de_compra: vec![],
-                reputacion_como_comprador: vec![3],
-            });
-            contrato.usuarios.insert(comprador, &u);
+    #[ink::test]
+    fn contrapartes_repetidas_se_consideran_relacionadas() {
+        let mut contrato = PrimerContrato::new();
+        let (vendedor, comprador) = (account(161), account(162));
+        preparar_orden_recibida(&mut contrato, vendedor, comprador);
+        for id_orden in 1..4 {
+            contrato.priv_crear_orden_de_compra(comprador, 0, None, 0, 0).unwrap();
+            contrato.priv_enviar_compra(vendedor, id_orden).unwrap();
+            contrato.priv_recibir_compra(comprador, id_orden, 100).unwrap();
+            assert_eq!(contrato.son_cuentas_relacionadas(comprador, vendedor), id_orden == 3);
         }
-
-        let res = contrato.calificar_comprador(comprador, 5);
-        assert!(res.is_ok());
-
-        let u = contrato.buscar_usuario(comprador).unwrap();
-        assert_eq!(u.datos_comprador.unwrap().reputacion_como_comprador.last().copied(), Some(5));
+        assert!(!contrato.son_cuentas_relacionadas(vendedor, comprador));
     }
 
     #[ink::test]
-    fn priv_calificar_comprador_sin_datos_falla() {
+    fn verificador_otorga_y_revoca_verificaciones() {
         let mut contrato = PrimerContrato::new();
-        let usuario = account(251);
+        let owner = contrato.owner;
+        let (vendedor, comprador, verificador) = (account(163), account(164), account(165));
+        preparar_publicacion_para_ofertas(&mut contrato, vendedor, comprador);
+        let referencia = [9u8; 32];
+
+        assert_eq!(contrato.priv_otorgar_verificacion(verificador, vendedor, 1000, referencia, 0).unwrap_err(), "El usuario no es verificador.".to_string());
+        assert_eq!(contrato.priv_registrar_verificador(comprador, verificador, true).unwrap_err(), "Solo el owner puede realizar esta accion.".to_string());
+        contrato.priv_registrar_verificador(owner, verificador, true).unwrap();
+        assert_eq!(contrato.priv_otorgar_verificacion(verificador, comprador, 1000, referencia, 0).unwrap_err(), "El usuario es comprador.".to_string());
+        assert_eq!(contrato.priv_otorgar_verificacion(verificador, vendedor, 0, referencia, 0).unwrap_err(), "El vencimiento de la verificacion ya paso.".to_string());
+        contrato.priv_otorgar_verificacion(verificador, vendedor, 1000, referencia, 0).unwrap();
+        contrato.priv_otorgar_verificacion(verificador, vendedor, 2000, referencia, 10).unwrap();
+
+        let perfil = contrato.priv_perfil_publico(vendedor, 1500).unwrap();
+        assert!(perfil.verificado);
+        assert_eq!(perfil.verificaciones, vec![Verificacion{ verificador, otorgada_en: 10, vencimiento: 2000, referencia }]);
+        assert!(!contrato.priv_perfil_publico(vendedor, 2000).unwrap().verificado);
+
+        contrato.priv_revocar_verificacion(verificador, vendedor).unwrap();
+        assert!(!contrato.priv_perfil_publico(vendedor, 1500).unwrap().verificado);
+        assert_eq!(contrato.priv_revocar_verificacion(verificador, vendedor).unwrap_err(), "El vendedor no tiene una verificacion de este verificador.".to_string());
+    }
 
+    #[ink::test]
+    fn buscar_publicaciones_filtra_por_vendedores_verificados() {
+        let mut contrato = PrimerContrato::new();
+        let owner = contrato.owner;
+        let (vendedor, comprador, verificador, otro_vendedor) = (account(166), account(167), account(168), account(169));
+        preparar_publicacion_para_ofertas(&mut contrato, vendedor, comprador);
         contrato.priv_agregar_usuario_sistema(
-            usuario, "Usuario".into(), "U".into(), "Dir".into(), "u@mail".into(), Rol::Vend
+            otro_vendedor, "O".into(), "O".into(), "Dir".into(), "o@mail".into(), Rol::Vend
         ).unwrap();
-
-        let res = contrato.calificar_comprador(usuario, 4);
-        assert!(res.is_err());
-        assert_eq!(res.unwrap_err(), "El comprador no tiene datos cargados.".to_string());
+        contrato.priv_cargar_producto(otro_vendedor, "P".into(), "D".into(), 50, ROPA, 10).unwrap();
+        contrato.priv_crear_publicacion(otro_vendedor, vec![(2, 1)], None).unwrap();
+        contrato.priv_registrar_verificador(owner, verificador, true).unwrap();
+        contrato.priv_otorgar_verificacion(verificador, vendedor, 1000, [0u8; 32], 0).unwrap();
+
+        assert_eq!(contrato.priv_buscar_publicaciones(FiltroPublicaciones::default(), 0).len(), 2);
+        let verificados = FiltroPublicaciones{ solo_vendedores_verificados: true, ..Default::default() };
+        let resultado = contrato.priv_buscar_publicaciones(verificados.clone(), 0);
+        assert_eq!(resultado.len(), 1);
+        assert_eq!(resultado[0].id_vendedor, vendedor);
+        assert_eq!(contrato.priv_buscar_publicaciones(FiltroPublicaciones{ categoria: Some(ROPA), ..Default::default() }, 0)[0].id_vendedor, otro_vendedor);
+        assert_eq!(contrato.priv_buscar_publicaciones(FiltroPublicaciones{ precio_maximo: Some(100), ..Default::default() }, 0).len(), 1);
+
+        // Al deshabilitar al verificador, sus verificaciones dejan de estar vigentes.
+        contrato.priv_registrar_verificador(owner, verificador, false).unwrap();
+        assert!(contrato.priv_buscar_publicaciones(verificados, 0).is_empty());
     }
 
     #[ink::test]
-    fn priv_calificar_comprador_usuario_no_encontrado_falla() {
+    fn usuario_suspendido_no_opera_pero_completa_sus_ordenes() {
         let mut contrato = PrimerContrato::new();
-        let account_invalido: AccountId = [0x0; 32].into();
+        let owner = contrato.owner;
+        let (vendedor, comprador, moderador) = (account(170), account(171), account(172));
+        preparar_publicacion_para_ofertas(&mut contrato, vendedor, comprador);
+        contrato.priv_crear_orden_de_compra(comprador, 0, None, 0, 0).unwrap();
+
+        assert_eq!(contrato.priv_suspender_usuario(moderador, vendedor, MotivoModeracion::Fraude, 10).unwrap_err(), "El usuario no es moderador.".to_string());
+        contrato.priv_registrar_moderador(owner, moderador, true).unwrap();
+        contrato.priv_suspender_usuario(moderador, vendedor, MotivoModeracion::Fraude, 10).unwrap();
+        assert_eq!(contrato.priv_suspender_usuario(moderador, vendedor, MotivoModeracion::Spam, 10).unwrap_err(), "El objetivo ya tiene una sancion vigente.".to_string());
+
+        let suspendido = "El usuario esta suspendido.".to_string();
+        assert_eq!(contrato.priv_cargar_producto(vendedor, "P".into(), "D".into(), 100, OTRO, 10).unwrap_err(), suspendido);
+        assert_eq!(contrato.priv_crear_publicacion(vendedor, vec![(1, 1)], None).unwrap_err(), suspendido);
+        assert!(contrato.priv_buscar_publicaciones(FiltroPublicaciones::default(), 0).is_empty());
+        assert_eq!(contrato.priv_crear_orden_de_compra(comprador, 0, None, 0, 0).unwrap_err(), "El vendedor esta suspendido.".to_string());
+
+        // La orden en curso se completa igual.
+        contrato.priv_enviar_compra(vendedor, 0).unwrap();
+        contrato.priv_recibir_compra(comprador, 0, 20).unwrap();
+        assert_eq!(contrato.buscar_orden(0).unwrap().estado, EstadoCompra::Recibido);
 
-        let res = contrato.calificar_comprador(account_invalido, 4);
-        assert!(res.is_err());
-        assert_eq!(res.unwrap_err(), "No se encontro el usuario.".to_string());
+        contrato.priv_suspender_usuario(moderador, comprador, MotivoModeracion::Otro, 30).unwrap();
+        assert_eq!(contrato.priv_crear_orden_de_compra(comprador, 0, None, 0, 0).unwrap_err(), suspendido);
+        assert_eq!(contrato.priv_hacer_oferta(comprador, 0, 150, 30).unwrap_err(), suspendido);
     }
 
-
-     #[ink::test]
-    fn es_comprador_ambos_con_rol_comp_ok() {
-        let id = AccountId::from([1u8; 32]);
-        let usuario = Usuario::nuevo(
-            id,
-            "Nombre".into(),
-            "Apellido".into(),
-            "Dir".into(),
-            "u@mail".into(),
-            Rol::Comp,
-        );
-        assert!(usuario.es_comprador_ambos().is_ok());
+    #[ink::test]
+    fn no_se_puede_ofertar_en_subastas_sancionadas_ni_estando_suspendido() {
+        let mut contrato = PrimerContrato::new();
+        let owner = contrato.owner;
+        let (vendedor, c1, c2, moderador) = (account(233), account(234), account(235), account(236));
+        preparar_subasta(&mut contrato, vendedor, c1, c2, 150);
+        contrato.priv_registrar_moderador(owner, moderador, true).unwrap();
+        fondear_escrow(120);
+
+        contrato.priv_suspender_usuario(moderador, c1, MotivoModeracion::Fraude, 10).unwrap();
+        assert_eq!(contrato.priv_ofertar(c1, 0, 120, 10).unwrap_err(), "El usuario esta suspendido.".to_string());
+
+        contrato.priv_dar_de_baja_publicacion(moderador, 0, MotivoModeracion::ContenidoIlegal, 10).unwrap();
+        assert_eq!(contrato.priv_ofertar(c2, 0, 120, 10).unwrap_err(), "La publicacion fue dada de baja.".to_string());
+        assert!(contrato.buscar_publicacion(0).unwrap().subasta.unwrap().mejor_oferta.is_none());
     }
 
     #[ink::test]
-    fn es_comprador_ambos_con_rol_ambos_ok() {
-        let id = AccountId::from([2u8; 32]);
-        let usuario = Usuario::nuevo(
-            id,
-            "Nombre".into(),
-            "Apellido".into(),
-            "Dir".into(),
-            "u@mail".into(),
-            Rol::Ambos,
-        );
-        assert!(usuario.es_comprador_ambos().is_ok());
+    fn publicacion_dada_de_baja_se_restablece_al_aceptar_la_apelacion() {
+        let mut contrato = PrimerContrato::new();
+        let owner = contrato.owner;
+        let (vendedor, comprador, moderador) = (account(173), account(174), account(175));
+        preparar_publicacion_para_ofertas(&mut contrato, vendedor, comprador);
+        contrato.priv_registrar_moderador(owner, moderador, true).unwrap();
+        let objetivo = ObjetivoModeracion::Publicacion(0);
+
+        assert_eq!(contrato.priv_apelar(vendedor, objetivo.clone(), "Es legal".into(), 0).unwrap_err(), "El objetivo no tiene una sancion vigente.".to_string());
+        contrato.priv_dar_de_baja_publicacion(moderador, 0, MotivoModeracion::ContenidoIlegal, 10).unwrap();
+        assert_eq!(contrato.priv_crear_orden_de_compra(comprador, 0, None, 0, 0).unwrap_err(), "La publicacion fue dada de baja.".to_string());
+
+        assert_eq!(contrato.priv_apelar(comprador, objetivo.clone(), "Es legal".into(), 20).unwrap_err(), "Solo el afectado puede apelar la sancion.".to_string());
+        assert_eq!(contrato.priv_apelar(vendedor, objetivo.clone(), "Es legal".into(), 20), Ok(0));
+        assert_eq!(contrato.priv_apelar(vendedor, objetivo.clone(), "Es legal".into(), 20).unwrap_err(), "Ya hay una apelacion pendiente.".to_string());
+        contrato.priv_resolver_apelacion(moderador, 0, false).unwrap();
+        assert_eq!(contrato.priv_resolver_apelacion(moderador, 0, true).unwrap_err(), "La apelacion ya fue resuelta.".to_string());
+
+        assert_eq!(contrato.priv_apelar(vendedor, objetivo.clone(), "Adjunto factura".into(), 30), Ok(1));
+        contrato.priv_resolver_apelacion(moderador, 1, true).unwrap();
+
+        let (sancion, apelaciones) = contrato.estado_moderacion(objetivo);
+        assert!(sancion.is_none());
+        assert_eq!(apelaciones.iter().map(|apelacion| apelacion.estado.clone()).collect::<Vec<_>>(), vec![EstadoApelacion::Rechazada, EstadoApelacion::Aceptada]);
+        assert_eq!(apelaciones[1].resuelta_por, Some(moderador));
+        contrato.priv_crear_orden_de_compra(comprador, 0, None, 0, 0).unwrap();
     }
 
     #[ink::test]
-    fn es_comprador_ambos_con_rol_vend_err() {
-        let id = AccountId::from([3u8; 32]);
-        let usuario = Usuario::nuevo(
-            id,
-            "Nombre".into(),
-            "Apellido".into(),
-            "Dir".into(),
-            "u@mail".into(),
-            Rol::Vend,
-        );
-        let res = usuario.es_comprador_ambos();
-        assert!(res.is_err());
-        assert_eq!(res.unwrap_err(), "El usuario es vendedor.".to_string());
+    fn denuncias_ocultan_la_publicacion_al_superar_el_umbral() {
+        let mut contrato = PrimerContrato::new();
+        let owner = contrato.owner;
+        let (vendedor, comprador, moderador) = (account(176), account(177), account(178));
+        preparar_publicacion_para_ofertas(&mut contrato, vendedor, comprador);
+        contrato.priv_registrar_moderador(owner, moderador, true).unwrap();
+        for denunciante in [account(179), account(180)] {
+            contrato.priv_agregar_usuario_sistema(denunciante, "D".into(), "D".into(), "Dir".into(), "d@mail".into(), Rol::Comp).unwrap();
+        }
+        let objetivo = ObjetivoModeracion::Publicacion(0);
+
+        assert_eq!(contrato.priv_denunciar(comprador, ObjetivoModeracion::Publicacion(5), MotivoModeracion::Fraude, 0).unwrap_err(), "No se encontro la publicacion.".to_string());
+        assert_eq!(contrato.priv_denunciar(comprador, objetivo.clone(), MotivoModeracion::Fraude, 0), Ok(0));
+        assert_eq!(contrato.priv_denunciar(comprador, ObjetivoModeracion::Usuario(vendedor), MotivoModeracion::Fraude, 10).unwrap_err(), "Debe esperar para volver a denunciar.".to_string());
+        assert_eq!(contrato.priv_denunciar(comprador, objetivo.clone(), MotivoModeracion::Spam, INTERVALO_ENTRE_DENUNCIAS).unwrap_err(), "El usuario ya denuncio este objetivo.".to_string());
+        contrato.priv_denunciar(account(179), objetivo.clone(), MotivoModeracion::Fraude, 0).unwrap();
+        assert_eq!(contrato.priv_buscar_publicaciones(FiltroPublicaciones::default(), 0).len(), 1);
+        contrato.priv_denunciar(account(180), objetivo.clone(), MotivoModeracion::Spam, 0).unwrap();
+        assert!(contrato.priv_buscar_publicaciones(FiltroPublicaciones::default(), 0).is_empty());
+        assert_eq!(contrato.priv_crear_orden_de_compra(comprador, 0, None, 0, 0).unwrap_err(), "La publicacion esta en revision.".to_string());
+
+        assert_eq!(contrato.priv_denuncias_abiertas(comprador).unwrap_err(), "El usuario no es moderador.".to_string());
+        let abiertas = contrato.priv_denuncias_abiertas(moderador).unwrap();
+        assert_eq!(abiertas.len(), 1);
+        assert_eq!((abiertas[0].0.clone(), abiertas[0].1, abiertas[0].2.len()), (objetivo.clone(), true, 3));
+
+        // El moderador revisa y no encuentra motivo de sanción: la publicación vuelve a mostrarse.
+        contrato.priv_resolver_denuncias(moderador, objetivo.clone(), None, 20).unwrap();
+        assert!(contrato.priv_denuncias_abiertas(moderador).unwrap().is_empty());
+        assert_eq!(contrato.priv_buscar_publicaciones(FiltroPublicaciones::default(), 0).len(), 1);
+        assert_eq!(contrato.priv_resolver_denuncias(moderador, objetivo, None, 20).unwrap_err(), "El objetivo no tiene denuncias abiertas.".to_string());
+        contrato.priv_crear_orden_de_compra(comprador, 0, None, 0, 0).unwrap();
     }
 
+    #[ink::test]
+    fn resolver_denuncias_puede_sancionar_al_objetivo() {
+        let mut contrato = PrimerContrato::new();
+        let owner = contrato.owner;
+        let (vendedor, comprador, moderador) = (account(181), account(182), account(183));
+        preparar_publicacion_para_ofertas(&mut contrato, vendedor, comprador);
+        contrato.priv_registrar_moderador(owner, moderador, true).unwrap();
+        let objetivo = ObjetivoModeracion::Usuario(vendedor);
+
+        contrato.priv_denunciar(comprador, objetivo.clone(), MotivoModeracion::Suplantacion, 0).unwrap();
+        contrato.priv_resolver_denuncias(moderador, objetivo.clone(), Some(MotivoModeracion::Suplantacion), 10).unwrap();
+
+        let (sancion, _apelaciones) = contrato.estado_moderacion(objetivo);
+        assert_eq!(sancion, Some(Sancion{ moderador, motivo: MotivoModeracion::Suplantacion, fecha: 10 }));
+        assert_eq!(contrato.priv_crear_publicacion(vendedor, vec![(1, 1)], None).unwrap_err(), "El usuario esta suspendido.".to_string());
+    }
 
     #[ink::test]
-    fn calificar_segun_rol_else_califica_comprador_por_vendedor() {
+    fn owner_administra_categorias_jerarquicas() {
         let mut contrato = PrimerContrato::new();
-        let vendedor = account(240);
-        let comprador = account(241);
+        let owner = contrato.owner;
+        assert_eq!(contrato.get_categorias().len(), 8);
+        assert_eq!(contrato.get_categorias()[ROPA as usize].nombre, "Ropa".to_string());
+
+        assert_eq!(contrato.priv_crear_categoria(account(184), "Deportes".into(), None).unwrap_err(), "Solo el owner puede realizar esta accion.".to_string());
+        assert_eq!(contrato.priv_crear_categoria(owner, "".into(), None).unwrap_err(), "El nombre de la categoria no es valido.".to_string());
+        assert_eq!(contrato.priv_crear_categoria(owner, "Hogar".into(), None).unwrap_err(), "Ya existe una categoria con ese nombre.".to_string());
+        assert_eq!(contrato.priv_crear_categoria(owner, "Deportes".into(), Some(20)).unwrap_err(), "La categoria no existe.".to_string());
+        let deportes = contrato.priv_crear_categoria(owner, "Deportes".into(), None).unwrap();
+        let futbol = contrato.priv_crear_categoria(owner, "Futbol".into(), Some(deportes)).unwrap();
+        assert_eq!((deportes, futbol), (8, 9));
+
+        assert_eq!(contrato.priv_modificar_categoria(owner, deportes, "Deportes".into(), Some(deportes)).unwrap_err(), "Una categoria no puede ser su propia categoria padre.".to_string());
+        assert_eq!(contrato.priv_modificar_categoria(owner, deportes, "Deportes".into(), Some(futbol)).unwrap_err(), "La categoria padre no puede ser una subcategoria de la categoria.".to_string());
+        contrato.priv_modificar_categoria(owner, futbol, "Futbol 5".into(), Some(deportes)).unwrap();
+        assert_eq!(contrato.get_categorias()[futbol as usize], Categoria{ id: futbol, nombre: "Futbol 5".into(), padre: Some(deportes) });
+        assert!(Categoria::es_subcategoria(&contrato.get_categorias(), futbol, deportes));
+        assert!(!Categoria::es_subcategoria(&contrato.get_categorias(), deportes, futbol));
+    }
 
-        contrato.priv_agregar_usuario_sistema(
-            vendedor, "V".into(), "V".into(), "Dir".into(), "v@mail".into(), Rol::Vend
-        ).unwrap();
-        contrato.priv_agregar_usuario_sistema(
-            comprador, "C".into(), "C".into(), "Dir".into(), "c@mail".into(), Rol::Comp
-        ).unwrap();
+    #[ink::test]
+    fn cargar_producto_valida_la_categoria_y_la_busqueda_incluye_subcategorias() {
+        let mut contrato = PrimerContrato::new();
+        let owner = contrato.owner;
+        let vendedor = account(185);
+        contrato.priv_agregar_usuario_sistema(vendedor, "V".into(), "V".into(), "Dir".into(), "v@mail".into(), Rol::Vend).unwrap();
+        let deportes = contrato.priv_crear_categoria(owner, "Deportes".into(), None).unwrap();
+        let futbol = contrato.priv_crear_categoria(owner, "Futbol".into(), Some(deportes)).unwrap();
+
+        assert_eq!(contrato.priv_cargar_producto(vendedor, "P".into(), "D".into(), 100, 50, 10).unwrap_err(), "La categoria no existe.".to_string());
+        contrato.priv_cargar_producto(vendedor, "Pelota".into(), "D".into(), 100, futbol, 10).unwrap();
+        contrato.priv_crear_publicacion(vendedor, vec![(1, 1)], None).unwrap();
+
+        let filtro = |categoria| FiltroPublicaciones{ categoria: Some(categoria), ..Default::default() };
+        assert_eq!(contrato.priv_buscar_publicaciones(filtro(deportes), 0).len(), 1);
+        assert_eq!(contrato.priv_buscar_publicaciones(filtro(futbol), 0).len(), 1);
+        assert!(contrato.priv_buscar_publicaciones(filtro(HOGAR), 0).is_empty());
+    }
 
-        contrato.priv_cargar_producto(vendedor, "P".into(), "D".into(), 10, Categoria::Otro, 3).unwrap();
-        contrato.priv_crear_publicacion(vendedor, vec![(1,1)]).unwrap();
-        contrato.priv_crear_orden_de_compra(comprador, 0).unwrap();
+    #[ink::test]
+    fn agregar_variante_valida_propiedad_atributos_stock_y_precio() {
+        let mut contrato = PrimerContrato::new();
+        let (vendedor, otro_vendedor) = (account(186), account(187));
+        contrato.priv_agregar_usuario_sistema(vendedor, "V".into(), "V".into(), "Dir".into(), "v@mail".into(), Rol::Vend).unwrap();
+        contrato.priv_agregar_usuario_sistema(otro_vendedor, "O".into(), "O".into(), "Dir".into(), "o@mail".into(), Rol::Vend).unwrap();
+        contrato.priv_cargar_producto(vendedor, "Remera".into(), "D".into(), 100, ROPA, 10).unwrap();
+        let talle = |valor: &str| vec![("talle".to_string(), valor.to_string())];
+
+        assert_eq!(contrato.priv_agregar_variante(otro_vendedor, 1, talle("S"), 0, 3).unwrap_err(), "El vendedor no posee ese producto.".to_string());
+        assert_eq!(contrato.priv_agregar_variante(vendedor, 1, Vec::new(), 0, 3).unwrap_err(), "La variante debe tener al menos un atributo.".to_string());
+        assert_eq!(contrato.priv_agregar_variante(vendedor, 1, talle("S"), 0, 0).unwrap_err(), "Stock no valido".to_string());
+        assert_eq!(contrato.priv_agregar_variante(vendedor, 1, talle("S"), -100, 3).unwrap_err(), "Precio no valido".to_string());
+
+        assert_eq!(contrato.priv_agregar_variante(vendedor, 1, talle("S"), -10, 3), Ok(0));
+        assert_eq!(contrato.priv_agregar_variante(vendedor, 1, talle("M"), 20, 1), Ok(1));
+        let variantes = contrato.visualizar_variantes(1).unwrap();
+        assert_eq!(variantes.len(), 2);
+        assert_eq!(variantes[1], Variante{ id: 1, atributos: talle("M"), delta_precio: 20, stock: 1 });
+        assert_eq!(contrato.visualizar_variantes(2).unwrap_err(), "No se encontro el producto.".to_string());
+
+        assert_eq!(contrato.priv_reponer_stock_variante(otro_vendedor, 1, 1, 2).unwrap_err(), "El vendedor no posee ese producto.".to_string());
+        assert_eq!(contrato.priv_reponer_stock_variante(vendedor, 1, 5, 2).unwrap_err(), "La variante no existe.".to_string());
+        contrato.priv_reponer_stock_variante(vendedor, 1, 1, 2).unwrap();
+        assert_eq!(contrato.visualizar_variantes(1).unwrap()[1].stock, 3);
+        assert_eq!(contrato.historial_productos.get(1).unwrap().1, 10); //El stock del producto no cambia
+    }
 
-        let usuario_vendedor = contrato.buscar_usuario(vendedor).unwrap();
-        let (_id, orden) = contrato.historial_ordenes_de_compra.get(0).unwrap();
+    #[ink::test]
+    fn publicacion_con_variantes_usa_su_stock_y_precio_y_la_orden_registra_la_variante() {
+        let mut contrato = PrimerContrato::new();
+        let (vendedor, comprador) = (account(188), account(189));
+        contrato.priv_agregar_usuario_sistema(vendedor, "V".into(), "V".into(), "Dir".into(), "v@mail".into(), Rol::Vend).unwrap();
+        contrato.priv_agregar_usuario_sistema(comprador, "C".into(), "C".into(), "Dir".into(), "c@mail".into(), Rol::Comp).unwrap();
+        contrato.priv_cargar_producto(vendedor, "Remera".into(), "D".into(), 100, ROPA, 10).unwrap();
+        contrato.priv_cargar_producto(vendedor, "Gorra".into(), "D".into(), 50, ROPA, 10).unwrap();
+        contrato.priv_agregar_variante(vendedor, 1, vec![("talle".into(), "S".into())], 0, 3).unwrap();
+        contrato.priv_agregar_variante(vendedor, 1, vec![("talle".into(), "M".into())], 20, 1).unwrap();
+
+        assert_eq!(contrato.priv_crear_publicacion_con_variantes(vendedor, vec![(1, 2)], vec![(1, 1)], None).unwrap_err(), "No hay stock suficiente.".to_string());
+        assert_eq!(contrato.priv_crear_publicacion_con_variantes(vendedor, vec![(1, 1)], vec![(2, 0)], None).unwrap_err(), "Una variante no corresponde a un producto publicado.".to_string());
+        assert_eq!(contrato.priv_crear_publicacion_con_variantes(vendedor, vec![(1, 1)], vec![(1, 1), (1, 0)], None).unwrap_err(), "Un producto tiene mas de una variante elegida.".to_string());
+        assert_eq!(contrato.priv_crear_publicacion_con_variantes(vendedor, vec![(1, 1)], vec![(1, 5)], None).unwrap_err(), "La variante no existe.".to_string());
+
+        contrato.priv_crear_publicacion_con_variantes(vendedor, vec![(1, 1), (2, 1)], vec![(1, 1)], None).unwrap();
+        let publicacion = contrato.buscar_publicacion(0).unwrap();
+        assert_eq!(publicacion.precio_final, 170);
+        assert_eq!(publicacion.variantes, vec![(1, 1)]);
+        assert_eq!(contrato.visualizar_variantes(1).unwrap()[1].stock, 0);
+        assert_eq!(contrato.historial_productos.get(1).unwrap().1, 10);
+        assert_eq!(contrato.historial_productos.get(2).unwrap().1, 9);
+
+        contrato.priv_crear_orden_de_compra(comprador, 0, None, 0, 0).unwrap();
+        let orden = contrato.buscar_orden(0).unwrap();
+        assert_eq!(orden.variantes, vec![(1, 1)]);
+        assert_eq!(orden.info_publicacion.2, 170);
+        assert!(!contrato.buscar_publicacion(0).unwrap().disponible); //La variante M no tiene mas stock para reponer la publicacion
+    }
 
-        contrato.calificar_segun_rol(4u8, orden.clone(), vendedor, comprador, usuario_vendedor).unwrap();
+    #[ink::test]
+    fn actualizar_medios_valida_propiedad_y_limites() {
+        let mut contrato = PrimerContrato::new();
+        let (vendedor, otro_vendedor) = (account(190), account(191));
+        contrato.priv_agregar_usuario_sistema(vendedor, "V".into(), "V".into(), "Dir".into(), "v@mail".into(), Rol::Vend).unwrap();
+        contrato.priv_agregar_usuario_sistema(otro_vendedor, "O".into(), "O".into(), "Dir".into(), "o@mail".into(), Rol::Vend).unwrap();
+        contrato.priv_cargar_producto(vendedor, "P".into(), "D".into(), 100, OTRO, 10).unwrap();
+        let cid = "bafybeigdyrzt5sfp7udm7hu76uh7y26nf3efuylqabf3oclgtqy55fbzdi".to_string();
+
+        assert_eq!(contrato.priv_actualizar_medios(otro_vendedor, 1, vec![cid.clone()]).unwrap_err(), "El vendedor no posee ese producto.".to_string());
+        assert_eq!(contrato.priv_actualizar_medios(vendedor, 1, vec![cid.clone(); MAXIMO_MEDIOS_POR_PRODUCTO + 1]).unwrap_err(), "El producto supera la cantidad maxima de referencias.".to_string());
+        assert_eq!(contrato.priv_actualizar_medios(vendedor, 1, vec![String::new()]).unwrap_err(), "Una referencia no es valida.".to_string());
+        assert_eq!(contrato.priv_actualizar_medios(vendedor, 1, vec!["a".repeat(LARGO_MAXIMO_MEDIO + 1)]).unwrap_err(), "Una referencia no es valida.".to_string());
+
+        contrato.priv_actualizar_medios(vendedor, 1, vec![cid.clone(), "a".repeat(LARGO_MAXIMO_MEDIO)]).unwrap();
+        let (producto, stock) = contrato.visualizar_producto(1).unwrap();
+        assert_eq!(producto.medios, vec![cid.clone(), "a".repeat(LARGO_MAXIMO_MEDIO)]);
+        assert_eq!(stock, 10);
+        assert_eq!(contrato.get_productos()[0].medios.len(), 2);
+
+        contrato.priv_actualizar_medios(vendedor, 1, Vec::new()).unwrap();
+        assert!(contrato.visualizar_producto(1).unwrap().0.medios.is_empty());
+        assert_eq!(contrato.visualizar_producto(2).unwrap_err(), "No se encontro el producto.".to_string());
+    }
 
-        let (_id2, orden_actualizada) = contrato.historial_ordenes_de_compra.get(0).unwrap();
-        assert!(orden_actualizada.calificaciones.1, "La orden debe marcar calificación del comprador por parte del vendedor");
+    #[ink::test]
+    fn lista_de_deseos_informa_bajas_de_precio_y_reposiciones() {
+        let mut contrato = PrimerContrato::new();
+        let (vendedor, comprador, otro_comprador) = (account(192), account(193), account(194));
+        preparar_publicacion_para_ofertas(&mut contrato, vendedor, comprador);
+        contrato.priv_agregar_usuario_sistema(otro_comprador, "O".into(), "O".into(), "Dir".into(), "o@mail".into(), Rol::Comp).unwrap();
+        contrato.priv_crear_publicacion(vendedor, vec![(1, 8)], None).unwrap(); // pub 1, se agota el stock
+
+        assert_eq!(contrato.priv_guardar_en_lista_de_deseos(vendedor, 0, 0).unwrap_err(), "El usuario es vendedor.".to_string());
+        assert_eq!(contrato.priv_guardar_en_lista_de_deseos(comprador, 5, 0).unwrap_err(), "No se encontro la publicacion.".to_string());
+        contrato.priv_guardar_en_lista_de_deseos(comprador, 0, 0).unwrap();
+        contrato.priv_guardar_en_lista_de_deseos(comprador, 1, 0).unwrap();
+        assert!(contrato.priv_novedades_lista_de_deseos(comprador, 0).is_empty());
+
+        //Otro comprador compra la publicacion 1, que queda sin stock para reponerse
+        contrato.priv_crear_orden_de_compra(otro_comprador, 1, None, 0, 0).unwrap();
+        contrato.priv_guardar_en_lista_de_deseos(comprador, 1, 0).unwrap();
+        assert!(!contrato.listas_de_deseos.get(comprador).unwrap()[1].disponible_al_guardar);
+
+        //La publicacion 0 baja de precio con una promocion y la 1 vuelve a estar disponible al cancelarse la orden
+        contrato.priv_programar_promocion(vendedor, 0, 150, 10, 100).unwrap();
+        contrato.priv_cancelar_compra(otro_comprador, 0).unwrap();
+        contrato.priv_cancelar_compra(vendedor, 0).unwrap();
+
+        let novedades = contrato.priv_novedades_lista_de_deseos(comprador, 20);
+        assert_eq!(novedades, vec![
+            NovedadDeseo{ id_publicacion: 0, precio_guardado: 200, precio_actual: 150, volvio_a_estar_disponible: false },
+            NovedadDeseo{ id_publicacion: 1, precio_guardado: 800, precio_actual: 800, volvio_a_estar_disponible: true },
+        ]);
+        assert_eq!(contrato.priv_novedades_lista_de_deseos(comprador, 100).len(), 1); //Termino la promocion
+
+        contrato.priv_quitar_de_lista_de_deseos(comprador, 1).unwrap();
+        assert_eq!(contrato.priv_quitar_de_lista_de_deseos(comprador, 1).unwrap_err(), "La publicacion no esta en la lista de deseos.".to_string());
+        assert!(contrato.priv_novedades_lista_de_deseos(comprador, 100).is_empty());
+    }
 
-        let comprador_actual = contrato.buscar_usuario(comprador).unwrap();
-        assert_eq!(comprador_actual.datos_comprador.unwrap().reputacion_como_comprador.last().copied(), Some(4));
+    #[ink::test]
+    fn seguir_vendedor_emite_evento_al_publicar() {
+        let mut contrato = PrimerContrato::new();
+        let (vendedor, comprador) = (account(195), account(196));
+        preparar_publicacion_para_ofertas(&mut contrato, vendedor, comprador);
+        let eventos_iniciales = ink::env::test::recorded_events().count();
+
+        assert_eq!(contrato.priv_seguir_vendedor(comprador, comprador).unwrap_err(), "El usuario es comprador.".to_string());
+        contrato.priv_seguir_vendedor(comprador, vendedor).unwrap();
+        assert_eq!(contrato.priv_seguir_vendedor(comprador, vendedor).unwrap_err(), "El usuario ya sigue a ese vendedor.".to_string());
+        assert_eq!(contrato.seguidores.get(vendedor), Some(1));
+
+        contrato.priv_crear_publicacion(vendedor, vec![(1, 1)], None).unwrap();
+        assert_eq!(ink::env::test::recorded_events().count(), eventos_iniciales + 1);
+
+        contrato.priv_dejar_de_seguir_vendedor(comprador, vendedor).unwrap();
+        assert_eq!(contrato.priv_dejar_de_seguir_vendedor(comprador, vendedor).unwrap_err(), "El usuario no sigue a ese vendedor.".to_string());
+        contrato.priv_crear_publicacion(vendedor, vec![(1, 1)], None).unwrap();
+        assert_eq!(ink::env::test::recorded_events().count(), eventos_iniciales + 1);
     }
 
     #[ink::test]
-    fn get_productos_vacio() {
-        let contrato = PrimerContrato::new();
-        let productos = contrato.get_productos();
-        assert!(productos.is_empty());
+    fn mensajes_de_orden_solo_entre_las_partes_y_paginados() {
+        let mut contrato = PrimerContrato::new();
+        let owner = contrato.owner;
+        let (vendedor, comprador, ajeno, moderador) = (account(197), account(198), account(199), account(200));
+        preparar_publicacion_para_ofertas(&mut contrato, vendedor, comprador);
+        contrato.priv_crear_orden_de_compra(comprador, 0, None, 0, 0).unwrap();
+        contrato.priv_registrar_moderador(owner, moderador, true).unwrap();
+        let texto = |texto: &str| ContenidoMensaje::Texto(texto.to_string());
+
+        assert_eq!(contrato.priv_enviar_mensaje(ajeno, 0, texto("Hola"), 10).unwrap_err(), "El usuario no participa de la orden.".to_string());
+        assert_eq!(contrato.priv_enviar_mensaje(comprador, 0, texto(""), 10).unwrap_err(), "El mensaje no es valido.".to_string());
+        assert_eq!(contrato.priv_enviar_mensaje(comprador, 0, ContenidoMensaje::Cifrado(vec![1; LARGO_MAXIMO_MENSAJE + 1]), 10).unwrap_err(), "El mensaje no es valido.".to_string());
+        assert_eq!(contrato.priv_enviar_mensaje(comprador, 1, texto("Hola"), 10).unwrap_err(), contrato.buscar_orden(1).unwrap_err());
+
+        assert_eq!(contrato.priv_enviar_mensaje(comprador, 0, texto("Entregar por la tarde"), 10), Ok(0));
+        assert_eq!(contrato.priv_enviar_mensaje(vendedor, 0, ContenidoMensaje::Cifrado(vec![7; 32]), 20), Ok(1));
+        assert_eq!(contrato.priv_enviar_mensaje(comprador, 0, texto("Gracias"), 30), Ok(2));
+
+        let (pagina, total) = contrato.priv_mensajes_de_orden(vendedor, 0, 1, 5).unwrap();
+        assert_eq!(total, 3);
+        assert_eq!(pagina, vec![
+            Mensaje{ autor: vendedor, fecha: 20, contenido: ContenidoMensaje::Cifrado(vec![7; 32]) },
+            Mensaje{ autor: comprador, fecha: 30, contenido: texto("Gracias") },
+        ]);
+        assert_eq!(contrato.priv_mensajes_de_orden(moderador, 0, 0, 1).unwrap().0.len(), 1);
+        assert!(contrato.priv_mensajes_de_orden(comprador, 0, 3, 5).unwrap().0.is_empty());
+        assert_eq!(contrato.priv_mensajes_de_orden(ajeno, 0, 0, 5).unwrap_err(), "El usuario no participa de la orden.".to_string());
+
+        for i in 3..MAXIMO_MENSAJES_POR_ORDEN {
+            contrato.priv_enviar_mensaje(comprador, 0, texto("."), i as u64).unwrap();
+        }
+        assert_eq!(contrato.priv_enviar_mensaje(vendedor, 0, texto("."), 1000).unwrap_err(), "La orden alcanzo la cantidad maxima de mensajes.".to_string());
     }
 
     #[ink::test]
-    fn get_productos_retorna_producto_cargado() {
+    fn recibir_una_orden_otorga_puntos_que_se_canjean_como_descuento() {
         let mut contrato = PrimerContrato::new();
-        let acc = account(80);
+        let (vendedor, comprador) = (account(201), account(202));
+        preparar_orden_recibida(&mut contrato, vendedor, comprador); // orden 0 de 200
+        assert_eq!(contrato.puntos_de_fidelidad(comprador), 2);
+        assert_eq!(contrato.puntos_de_fidelidad(vendedor), 2);
+
+        assert_eq!(contrato.priv_crear_orden_de_compra(comprador, 0, None, 3, 0).unwrap_err(), "El usuario no tiene puntos suficientes.".to_string());
+        assert!(contrato.verificar_pago(0, 198, &None, 2, 0).is_ok());
+        contrato.priv_crear_orden_de_compra(comprador, 0, None, 2, 0).unwrap();
+        let orden = contrato.buscar_orden(1).unwrap();
+        assert_eq!(orden.puntos_canjeados, 2);
+        assert_eq!(orden.info_publicacion.2, 198);
+        assert_eq!(contrato.puntos_de_fidelidad(comprador), 0);
+
+        //Si la orden se cancela, el comprador recupera los puntos canjeados
+        contrato.priv_cancelar_compra(comprador, 1).unwrap();
+        contrato.priv_cancelar_compra(vendedor, 1).unwrap();
+        assert_eq!(contrato.puntos_de_fidelidad(comprador), 2);
+    }
 
-        contrato.priv_agregar_usuario_sistema(
-            acc,
-            "Vendedor".to_string(),
-            "Uno".to_string(),
-            "Dir".to_string(),
-            "v@mail".to_string(),
-            Rol::Vend,
-        ).unwrap();
+    #[ink::test]
+    fn el_descuento_por_puntos_lo_absorbe_el_vendedor_sin_tocar_la_tesoreria() {
+        let mut contrato = PrimerContrato::new();
+        let (vendedor, comprador) = (account(215), account(216));
+        preparar_orden_recibida(&mut contrato, vendedor, comprador); // orden 0 de 200, otorga 2 puntos
+        contrato.tesoreria = 10;
+        contrato.priv_crear_orden_de_compra(comprador, 0, None, 2, 0).unwrap();
+        let escrow = fondear_escrow(198);
+        let saldo_vendedor = saldo_nativo(vendedor);
+        contrato.priv_enviar_compra(vendedor, 1).unwrap();
+        contrato.priv_recibir_compra(comprador, 1, 0).unwrap();
+        assert_eq!(saldo_nativo(vendedor), saldo_vendedor + 198);
+        assert_eq!((contrato.tesoreria, saldo_retenido(escrow)), (10, 0));
+    }
 
-        contrato.priv_cargar_producto(
-            acc,
-            "ProductoTest".to_string(),
-            "Descripcion".to_string(),
-            123,
-            Categoria::Alimentos,
-            10,
-        ).unwrap();
+    #[ink::test]
+    fn los_puntos_solo_se_canjean_en_moneda_nativa() {
+        let mut contrato = PrimerContrato::new();
+        let (vendedor, comprador, token) = (account(217), account(218), account(219));
+        preparar_publicacion_con_token(&mut contrato, vendedor, comprador, token);
+        contrato.puntos_fidelidad.insert(comprador, &5);
+        assert_eq!(contrato.calcular_precio_de_compra(0, &None, 1, 0).unwrap_err(), "Los puntos solo se canjean en publicaciones en moneda nativa.".to_string());
+    }
 
-        let productos = contrato.get_productos();
-        assert_eq!(productos.len(), 1);
-        let p = &productos[0];
-        assert_eq!(p.id, 1);
-        assert_eq!(p.nombre, "ProductoTest".to_string());
-        assert_eq!(p.precio, 123);
-        assert_eq!(p.categoria, Categoria::Alimentos);
+    #[ink::test]
+    fn los_puntos_no_pueden_cubrir_todo_el_precio() {
+        let mut contrato = PrimerContrato::new();
+        let (vendedor, comprador) = (account(203), account(204));
+        preparar_publicacion_para_ofertas(&mut contrato, vendedor, comprador);
+        contrato.puntos_fidelidad.insert(comprador, &500);
+
+        assert_eq!(contrato.calcular_precio_de_compra(0, &None, 199, 0), Ok(1));
+        assert_eq!(contrato.calcular_precio_de_compra(0, &None, 200, 0).unwrap_err(), "Los puntos canjeados superan el precio de la compra.".to_string());
+        assert_eq!(contrato.priv_crear_orden_de_compra(comprador, 0, None, 500, 0).unwrap_err(), "Los puntos canjeados superan el precio de la compra.".to_string());
+        assert_eq!(contrato.puntos_de_fidelidad(comprador), 500);
     }
 
     #[ink::test]
-    fn get_productos_varios_productos() {
+    fn recibir_una_orden_emite_un_recibo_consultable_como_garantia() {
         let mut contrato = PrimerContrato::new();
-        let acc = account(81);
+        let owner = contrato.owner;
+        let (vendedor, comprador, nuevo_dueno, recibos) = (account(205), account(206), account(207), account(208));
+        assert_eq!(contrato.priv_registrar_contrato_recibos(vendedor, Some(recibos)).unwrap_err(), "Solo el owner puede realizar esta accion.".to_string());
+        assert_eq!(contrato.garantia(0).unwrap_err(), "No hay contrato de recibos registrado.".to_string());
+        contrato.priv_registrar_contrato_recibos(owner, Some(recibos)).unwrap();
+
+        preparar_publicacion_para_ofertas(&mut contrato, vendedor, comprador);
+        contrato.priv_crear_orden_de_compra(comprador, 0, None, 0, 0).unwrap();
+        assert_eq!(contrato.garantia(0).unwrap_err(), "La compra aun no fue recibida.".to_string());
+        contrato.priv_enviar_compra(vendedor, 0).unwrap();
+        contrato.priv_recibir_compra(comprador, 0, 100).unwrap();
 
-        contrato.priv_agregar_usuario_sistema(
-            acc,
-            "Vendedor".to_string(),
-            "Dos".to_string(),
-            "Dir".to_string(),
-            "v2@mail".to_string(),
-            Rol::Vend,
-        ).unwrap();
+        let recibo = Recibo{ id_orden: 0, productos: vec![(1, 2)], precio: 200, vendedor, recibido_en: 100 };
+        assert_eq!(contrato.garantia(0), Ok((comprador, recibo.clone())));
 
-        contrato.priv_cargar_producto(acc, "P1".into(), "D1".into(), 10, Categoria::Hogar, 5).unwrap();
-        contrato.priv_cargar_producto(acc, "P2".into(), "D2".into(), 20, Categoria::Ropa, 2).unwrap();
+        //Si el recibo se transfiere, la garantia informa al nuevo dueño
+        mock_psp34::transfer(recibos, nuevo_dueno, 0);
+        assert_eq!(contrato.garantia(0), Ok((nuevo_dueno, recibo)));
+        assert!(contrato.garantia(1).is_err());
+    }
 
-        let productos = contrato.get_productos();
-        assert_eq!(productos.len(), 2);
+    #[ink::test]
+    fn recibo_que_no_se_pudo_emitir_no_bloquea_la_recepcion_y_se_reintenta() {
+        let mut contrato = PrimerContrato::new();
+        let owner = contrato.owner;
+        let (vendedor, comprador, recibos_con_falla, recibos) = (account(222), account(223), account(224), account(225));
+        contrato.priv_registrar_contrato_recibos(owner, Some(recibos_con_falla)).unwrap();
+        mock_psp34::acunar(recibos_con_falla, account(226), 0).unwrap(); //El contrato rechaza el recibo 0 porque ya existe
 
-        let ids: Vec<u32> = productos.iter().map(|p| p.id).collect();
-        assert!(ids.contains(&1));
-        assert!(ids.contains(&2));
+        preparar_publicacion_para_ofertas(&mut contrato, vendedor, comprador);
+        contrato.priv_crear_orden_de_compra(comprador, 0, None, 0, 0).unwrap();
+        contrato.priv_enviar_compra(vendedor, 0).unwrap();
+        assert_eq!(contrato.priv_reintentar_recibo(comprador, 0).unwrap_err(), "La compra aun no fue recibida.".to_string());
+        let eventos_iniciales = ink::env::test::recorded_events().count();
+        contrato.priv_recibir_compra(comprador, 0, 100).unwrap();
+        assert_eq!(contrato.buscar_orden(0).unwrap().estado, EstadoCompra::Recibido);
+        assert_eq!(ink::env::test::recorded_events().count(), eventos_iniciales + 1);
+
+        //El owner registra un contrato de recibos que funciona y el comprador reintenta
+        contrato.priv_registrar_contrato_recibos(owner, Some(recibos)).unwrap();
+        assert_eq!(contrato.priv_reintentar_recibo(vendedor, 0).unwrap_err(), "Solo el comprador puede pedir el recibo.".to_string());
+        contrato.priv_reintentar_recibo(comprador, 0).unwrap();
+        assert_eq!(contrato.garantia(0).unwrap().0, comprador);
+        assert_eq!(contrato.priv_reintentar_recibo(comprador, 0).unwrap_err(), "El recibo ya fue emitido.".to_string());
     }
 
     #[ink::test]
-    fn get_usuarios_vacio() {
-        let contrato = PrimerContrato::new();
-        let res = contrato.get_usuarios().unwrap();
-        assert!(res.is_empty());
+    fn suscripcion_crea_ordenes_por_periodo_y_reintegra_al_cancelar() {
+        let mut contrato = PrimerContrato::new();
+        let (vendedor, comprador) = (account(209), account(210));
+        let dia = PERIODO_MINIMO_SUSCRIPCION;
+        preparar_publicacion_para_ofertas(&mut contrato, vendedor, comprador);
+
+        assert_eq!(contrato.priv_suscribirse(comprador, 0, 3, 0).unwrap_err(), "La publicacion no admite suscripciones.".to_string());
+        assert_eq!(contrato.priv_habilitar_suscripcion(comprador, 0, Some(dia)).unwrap_err(), "La publicacion no pertenece al usuario.".to_string());
+        assert_eq!(contrato.priv_habilitar_suscripcion(vendedor, 0, Some(dia - 1)).unwrap_err(), "El periodo de la suscripcion no es valido.".to_string());
+        contrato.priv_habilitar_suscripcion(vendedor, 0, Some(dia)).unwrap();
+
+        assert_eq!(contrato.priv_suscribirse(comprador, 0, 0, 0).unwrap_err(), "La cantidad de periodos no es valida.".to_string());
+        assert_eq!(contrato.priv_suscribirse(comprador, 0, MAXIMO_PERIODOS_SUSCRIPCION + 1, 0).unwrap_err(), "La cantidad de periodos no es valida.".to_string());
+        assert_eq!(contrato.priv_suscribirse(vendedor, 0, 3, 0).unwrap_err(), "El usuario es vendedor.".to_string());
+        assert_eq!(contrato.priv_suscribirse(comprador, 0, 3, 0), Ok(0));
+
+        //La primera orden se crea en la primera ejecucion, y las siguientes cada periodo
+        assert_eq!(contrato.priv_ejecutar_suscripciones(0, 10, 0).unwrap().creadas, 1);
+        assert_eq!(contrato.priv_ejecutar_suscripciones(0, 10, dia - 1).unwrap().creadas, 0);
+        assert_eq!(contrato.priv_ejecutar_suscripciones(0, 10, dia).unwrap().creadas, 1);
+        assert_eq!(contrato.historial_ordenes_de_compra.len(), 2);
+        assert_eq!(contrato.buscar_orden(1).unwrap().info_publicacion.2, 200);
+        assert_eq!(contrato.historial_productos.get(1).unwrap().1, 4);
+
+        let suscripcion = contrato.visualizar_suscripcion(0).unwrap();
+        assert_eq!(suscripcion.periodos_restantes, 1);
+        assert_eq!(suscripcion.proxima_ejecucion, 2 * dia);
+
+        //Al cancelar se reintegra el periodo que no se ejecuto
+        assert_eq!(contrato.priv_cancelar_suscripcion(vendedor, 0).unwrap_err(), "Solo el comprador puede cancelar la suscripcion.".to_string());
+        contrato.priv_cancelar_suscripcion(comprador, 0).unwrap();
+        assert_eq!(contrato.fondos_a_retirar.get((comprador, None::<AccountId>)), Some(200));
+        assert_eq!(contrato.visualizar_suscripcion(0).unwrap().estado, EstadoSuscripcion::Cancelada);
+        assert_eq!(contrato.priv_cancelar_suscripcion(comprador, 0).unwrap_err(), "La suscripcion ya no esta activa.".to_string());
+        assert_eq!(contrato.priv_ejecutar_suscripciones(0, 10, 5 * dia), Ok(EjecucionSuscripciones{ creadas: 0, pendientes: Vec::new(), siguiente: None }));
     }
 
     #[ink::test]
-    fn get_usuarios_dos_usuarios_devuelve_el_segundo() {
+    fn suscripcion_pendiente_si_no_se_puede_ejecutar_y_finaliza_con_el_ultimo_periodo() {
         let mut contrato = PrimerContrato::new();
-        let a1 = account(91);
-        let a2 = account(92);
-
-        contrato.priv_agregar_usuario_sistema(
-            a1,
-            "Uno".into(),
-            "A".into(),
-            "Dir".into(),
-            "u1@mail".into(),
-            Rol::Vend,
-        ).unwrap();
-
-        contrato.priv_agregar_usuario_sistema(
-            a2,
-            "Dos".into(),
-            "B".into(),
-            "Dir2".into(),
-            "u2@mail".into(),
-            Rol::Comp,
-        ).unwrap();
-
-        let res = contrato.get_usuarios().unwrap();
-    
-        assert_eq!(res.len(), 2);
-        assert_eq!(res[1].id_usuario, a2);
+        let (vendedor, comprador) = (account(209), account(210));
+        preparar_publicacion_para_ofertas(&mut contrato, vendedor, comprador);
+        contrato.priv_habilitar_suscripcion(vendedor, 0, Some(PERIODO_MINIMO_SUSCRIPCION)).unwrap();
+        contrato.priv_suscribirse(comprador, 0, 1, 0).unwrap();
+
+        //Con la publicacion no disponible, el periodo queda pendiente
+        let mut publicacion = contrato.buscar_publicacion(0).unwrap();
+        publicacion.disponible = false;
+        contrato.actualizar_publicaciones(publicacion.clone(), 0).unwrap();
+        assert_eq!(contrato.priv_ejecutar_suscripciones(0, 10, 0), Ok(EjecucionSuscripciones{ creadas: 0, pendientes: vec![0], siguiente: None }));
+        assert_eq!(contrato.visualizar_suscripcion(0).unwrap().periodos_restantes, 1);
+
+        publicacion.disponible = true;
+        contrato.actualizar_publicaciones(publicacion, 0).unwrap();
+        assert_eq!(contrato.priv_ejecutar_suscripciones(0, 10, 0).unwrap().creadas, 1);
+        assert_eq!(contrato.visualizar_suscripcion(0).unwrap().estado, EstadoSuscripcion::Finalizada);
+        assert!(contrato.suscripciones_activas.is_empty());
+        assert_eq!(contrato.priv_cancelar_suscripcion(comprador, 0).unwrap_err(), "La suscripcion ya no esta activa.".to_string());
     }
 
     #[ink::test]
-    fn get_ordenes_vacio_err() {
-        let contrato = PrimerContrato::new();
-        let res = contrato.get_ordenes();
-        assert!(res.is_err());
-        assert_eq!(res.unwrap_err(), "No hay ordenes de compra cargadas.".to_string());
+    fn suscripcion_de_publicacion_dada_de_baja_queda_pendiente() {
+        let mut contrato = PrimerContrato::new();
+        let (vendedor, comprador) = (account(245), account(246));
+        preparar_publicacion_para_ofertas(&mut contrato, vendedor, comprador);
+        contrato.priv_habilitar_suscripcion(vendedor, 0, Some(PERIODO_MINIMO_SUSCRIPCION)).unwrap();
+        contrato.priv_suscribirse(comprador, 0, 1, 0).unwrap();
+        contrato.sanciones.insert(ObjetivoModeracion::Publicacion(0), &Sancion{ moderador: vendedor, motivo: MotivoModeracion::Otro, fecha: 0 });
+
+        assert_eq!(contrato.priv_ejecutar_suscripciones(0, 10, 0), Ok(EjecucionSuscripciones{ creadas: 0, pendientes: vec![0], siguiente: None }));
+        assert!(contrato.historial_ordenes_de_compra.is_empty());
+
+        contrato.sanciones.remove(ObjetivoModeracion::Publicacion(0));
+        assert_eq!(contrato.priv_ejecutar_suscripciones(0, 10, 0).unwrap().creadas, 1);
     }
 
     #[ink::test]
-    fn get_ordenes_retorna_ordenes_cargadas() {
+    fn ejecutar_suscripciones_avanza_por_paginas_y_sigue_ante_pendientes() {
         let mut contrato = PrimerContrato::new();
-        let vendedor = account(60);
-        let comprador = account(61);
-
-        contrato.priv_agregar_usuario_sistema(
-            vendedor, "V".into(), "V".into(), "Dir".into(), "v@mail".into(), Rol::Vend
-        ).unwrap();
-        contrato.priv_agregar_usuario_sistema(
-            comprador, "C".into(), "C".into(), "Dir".into(), "c@mail".into(), Rol::Comp
-        ).unwrap();
+        let (vendedor, comprador, otro) = (account(227), account(228), account(229));
+        preparar_publicacion_para_ofertas(&mut contrato, vendedor, comprador);
+        contrato.priv_agregar_usuario_sistema(otro, "O".into(), "O".into(), "Dir".into(), "o@mail".into(), Rol::Comp).unwrap();
+        contrato.priv_habilitar_suscripcion(vendedor, 0, Some(PERIODO_MINIMO_SUSCRIPCION)).unwrap();
+        contrato.priv_suscribirse(comprador, 0, 1, 0).unwrap();
+        contrato.priv_suscribirse(otro, 0, 2, 0).unwrap();
+        contrato.priv_suscribirse(comprador, 0, 2, 0).unwrap();
+        contrato.sanciones.insert(ObjetivoModeracion::Usuario(otro), &Sancion{ moderador: vendedor, motivo: MotivoModeracion::Otro, fecha: 0 });
+
+        //La primera pagina crea la orden de la suscripcion 0, que finaliza y deja su lugar a la ultima activa
+        assert_eq!(contrato.priv_ejecutar_suscripciones(0, 1, 0), Ok(EjecucionSuscripciones{ creadas: 1, pendientes: Vec::new(), siguiente: Some(0) }));
+        assert_eq!(contrato.suscripciones_activas.get(0), Some(2));
+        //La suscripcion del comprador suspendido queda pendiente, pero no impide ejecutar las demas
+        assert_eq!(contrato.priv_ejecutar_suscripciones(0, 5, 0), Ok(EjecucionSuscripciones{ creadas: 1, pendientes: vec![1], siguiente: None }));
+        assert_eq!(contrato.historial_ordenes_de_compra.len(), 2);
+        assert_eq!(contrato.visualizar_suscripcion(1).unwrap().periodos_restantes, 2);
+
+        contrato.priv_cancelar_suscripcion(otro, 1).unwrap();
+        assert_eq!(contrato.suscripciones_activas.len(), 1);
+        assert_eq!(contrato.suscripciones_activas.get(0), Some(2));
+    }
+}
 
-        contrato.priv_cargar_producto(vendedor, "P".into(), "D".into(), 100, Categoria::Otro, 10).unwrap();
-        contrato.priv_crear_publicacion(vendedor, vec![(1, 2)]).unwrap();
+}
 
-        contrato.priv_crear_orden_de_compra(comprador, 0).unwrap();
+/// Tests e2e: despliegan el marketplace y un token PSP22 real (mock_psp22) en un nodo, para ejercitar las llamadas entre contratos 
+/// (build_call) que los tests off-chain reemplazan por mock_psp22 en memoria. 
+/// Los mensajes públicos del marketplace solo se compilan fuera de cfg(test), así que se llaman armando el mensaje con su selector. 
+/// Se ejecutan con "cargo test --features e2e-tests" (requiere cargo-contract y un nodo con pallet-contracts). 
+#[cfg(all(test, feature = "e2e-tests"))]
+mod e2e_tests {
+    use super::*;
+    use ink::env::call::{build_call, ExecutionInput, Selector};
+    use ink::env::DefaultEnvironment;
+    use ink::primitives::AccountId;
+    use ink_e2e::{account_id, AccountKeyring, ContractsBackend};
+    use mock_psp22::MockPsp22Ref;
+
+    type Balance = <DefaultEnvironment as ink::env::Environment>::Balance;
+    type E2EResult<T> = std::result::Result<T, Box<dyn std::error::Error>>;
+
+    /// Arma la llamada a un mensaje de un contrato desplegado a partir de su nombre (o el del estándar, como "PSP22::transfer"). 
+    macro_rules! mensaje {
+        ($contrato:expr, $nombre:literal, $retorno:ty $(, $argumento:expr)*) => {
+            build_call::<DefaultEnvironment>()
+                .call($contrato)
+                .exec_input(ExecutionInput::new(Selector::new(ink::selector_bytes!($nombre)))$(.push_arg($argumento))*)
+                .returns::<$retorno>()
+        };
+    }
 
-        let ordenes = contrato.get_ordenes().expect("Debería devolver las ordenes");
-        assert_eq!(ordenes.len(), 1);
-        assert_eq!(ordenes[0].id, 0);
-        assert_eq!(ordenes[0].id_comprador, comprador);
+    #[ink_e2e::test]
+    async fn compra_con_token_psp22_cobra_y_paga_mediante_el_token<Client: E2EBackend>(mut client: Client) -> E2EResult<()> {
+        let (owner, vendedor, comprador) = (account_id(AccountKeyring::Alice), account_id(AccountKeyring::Bob), account_id(AccountKeyring::Charlie));
+        let token = client.instantiate("mock_psp22", &ink_e2e::alice(), &mut MockPsp22Ref::new(1000)).submit().await.expect("falla el despliegue del token").account_id;
+        let contrato = client.instantiate("primer_contrato", &ink_e2e::alice(), &mut PrimerContratoRef::new()).submit().await.expect("falla el despliegue del marketplace").account_id;
+
+        client.call(&ink_e2e::alice(), &mensaje!(contrato, "configurar_comision", Result<(), String>, 250u16)).submit().await.expect("falla configurar_comision");
+        client.call(&ink_e2e::alice(), &mensaje!(token, "PSP22::transfer", Result<(), Psp22Error>, comprador, 500 as Balance, Vec::<u8>::new())).submit().await.expect("falla el fondeo del comprador");
+        client.call(&ink_e2e::bob(), &mensaje!(contrato, "agregar_usuario_sistema", Result<(), String>, String::from("V"), String::from("V"), String::from("Dir"), String::from("v@mail"), Rol::Vend)).submit().await.expect("falla el registro del vendedor");
+        client.call(&ink_e2e::charlie(), &mensaje!(contrato, "agregar_usuario_sistema", Result<(), String>, String::from("C"), String::from("C"), String::from("Dir"), String::from("c@mail"), Rol::Comp)).submit().await.expect("falla el registro del comprador");
+        client.call(&ink_e2e::bob(), &mensaje!(contrato, "cargar_producto", Result<(), String>, String::from("Mate"), String::from("Calabaza"), 200u32, 0u32, 5u32)).submit().await.expect("falla cargar_producto");
+        client.call(&ink_e2e::bob(), &mensaje!(contrato, "crear_publicacion", Result<(), String>, vec![(1u32, 1u32)], Some(token))).submit().await.expect("falla crear_publicacion");
+
+        //Sin autorizacion, el token rechaza el transfer_from y el marketplace traduce su error
+        let sin_autorizacion = client.call(&ink_e2e::charlie(), &mensaje!(contrato, "crear_orden_de_compra", Result<(), String>, 0u32, Option::<String>::None, 0u32)).dry_run().await?;
+        assert_eq!(sin_autorizacion.return_value(), Err("El contrato no esta autorizado a mover esa cantidad de tokens.".to_string()));
+
+        client.call(&ink_e2e::charlie(), &mensaje!(token, "PSP22::approve", Result<(), Psp22Error>, contrato, 200 as Balance)).submit().await.expect("falla approve");
+        client.call(&ink_e2e::charlie(), &mensaje!(contrato, "crear_orden_de_compra", Result<(), String>, 0u32, Option::<String>::None, 0u32)).submit().await.expect("falla crear_orden_de_compra");
+        let saldo = |cuenta: AccountId| mensaje!(token, "PSP22::balance_of", Balance, cuenta);
+        assert_eq!(client.call(&ink_e2e::alice(), &saldo(comprador)).dry_run().await?.return_value(), 300);
+        assert_eq!(client.call(&ink_e2e::alice(), &saldo(contrato)).dry_run().await?.return_value(), 200);
+
+        //Al recibir la compra, el pago al vendedor sale del contrato con PSP22::transfer
+        client.call(&ink_e2e::bob(), &mensaje!(contrato, "enviar_compra", Result<(), String>, 0u32)).submit().await.expect("falla enviar_compra");
+        client.call(&ink_e2e::charlie(), &mensaje!(contrato, "recibir_compra", Result<(), String>, 0u32)).submit().await.expect("falla recibir_compra");
+        assert_eq!(client.call(&ink_e2e::alice(), &saldo(vendedor)).dry_run().await?.return_value(), 195);
+        assert_eq!(client.call(&ink_e2e::alice(), &saldo(contrato)).dry_run().await?.return_value(), 5);
+
+        let retirado = client.call(&ink_e2e::alice(), &mensaje!(contrato, "retirar_comisiones_token", Result<Balance, String>, token)).submit().await.expect("falla retirar_comisiones_token").return_value();
+        assert_eq!(retirado, Ok(5));
+        assert_eq!(client.call(&ink_e2e::alice(), &saldo(owner)).dry_run().await?.return_value(), 505);
+        assert_eq!(client.call(&ink_e2e::alice(), &saldo(contrato)).dry_run().await?.return_value(), 0);
+        Ok(())
     }
 }
-
-}
\ No newline at end of file
@@ -0,0 +1,122 @@
+#![cfg_attr(not(feature = "std"), no_std, no_main)]
+
+pub use self::mock_psp22::{
+    MockPsp22Ref,
+    Psp22Error,
+};
+
+/// Token PSP22 mínimo, usado únicamente por los tests e2e de primer_contrato para ejercitar las llamadas reales (build_call) al token. 
+/// Implementa los mensajes del estándar que usa el marketplace, con los mismos selectores ("PSP22::transfer", etc.). 
+#[ink::contract]
+mod mock_psp22 {
+    use ink::storage::Mapping;
+    use ink::prelude::vec::Vec;
+    use ink::prelude::string::String;
+
+    #[derive(Debug, Clone, PartialEq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    /// Enum con los errores definidos por el estándar PSP22. 
+    pub enum Psp22Error{
+        Custom(String),
+        InsufficientBalance,
+        InsufficientAllowance,
+        ZeroRecipientAddress,
+        ZeroSenderAddress,
+        SafeTransferCheckFailed(String),
+    }
+
+    #[ink(storage)]
+    pub struct MockPsp22 {
+        saldos: Mapping<AccountId, Balance>,
+        autorizaciones: Mapping<(AccountId, AccountId), Balance>,
+        suministro: Balance,
+    }
+
+    impl MockPsp22 {
+        /// Constructor del token. Todo el suministro inicial queda en la cuenta de quien lo despliega. 
+        #[ink(constructor)]
+        pub fn new(suministro: Balance) -> Self {
+            let mut saldos = Mapping::default();
+            saldos.insert(Self::env().caller(), &suministro);
+            Self { saldos, autorizaciones: Mapping::default(), suministro }
+        }
+
+        #[ink(message, selector = 0x162df8c2)]
+        pub fn total_supply(&self) -> Balance {
+            self.suministro
+        }
+
+        #[ink(message, selector = 0x6568382f)]
+        pub fn balance_of(&self, owner: AccountId) -> Balance {
+            self.saldos.get(owner).unwrap_or(0)
+        }
+
+        #[ink(message, selector = 0x4d47d921)]
+        pub fn allowance(&self, owner: AccountId, spender: AccountId) -> Balance {
+            self.autorizaciones.get((owner, spender)).unwrap_or(0)
+        }
+
+        #[ink(message, selector = 0xdb20f9f5)]
+        pub fn transfer(&mut self, to: AccountId, value: Balance, _data: Vec<u8>) -> Result<(), Psp22Error> {
+            let from = self.env().caller();
+            self.mover(from, to, value)
+        }
+
+        #[ink(message, selector = 0x54b3c76e)]
+        pub fn transfer_from(&mut self, from: AccountId, to: AccountId, value: Balance, _data: Vec<u8>) -> Result<(), Psp22Error> {
+            let spender = self.env().caller();
+            let permitido = self.allowance(from, spender);
+            if permitido < value {
+                return Err(Psp22Error::InsufficientAllowance)
+            }
+            self.mover(from, to, value)?;
+            self.autorizaciones.insert((from, spender), &(permitido - value));
+            Ok(())
+        }
+
+        #[ink(message, selector = 0xb20f1bbd)]
+        pub fn approve(&mut self, spender: AccountId, value: Balance) -> Result<(), Psp22Error> {
+            let owner = self.env().caller();
+            self.autorizaciones.insert((owner, spender), &value);
+            Ok(())
+        }
+
+        fn mover(&mut self, from: AccountId, to: AccountId, value: Balance) -> Result<(), Psp22Error> {
+            let saldo_origen = self.balance_of(from);
+            if saldo_origen < value {
+                return Err(Psp22Error::InsufficientBalance)
+            }
+            self.saldos.insert(from, &(saldo_origen - value));
+            self.saldos.insert(to, &(self.balance_of(to) + value));
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::mock_psp22::*;
+
+    #[test]
+    fn los_selectores_coinciden_con_los_del_estandar() {
+        assert_eq!(ink::selector_bytes!("PSP22::total_supply"), [0x16, 0x2d, 0xf8, 0xc2]);
+        assert_eq!(ink::selector_bytes!("PSP22::balance_of"), [0x65, 0x68, 0x38, 0x2f]);
+        assert_eq!(ink::selector_bytes!("PSP22::allowance"), [0x4d, 0x47, 0xd9, 0x21]);
+        assert_eq!(ink::selector_bytes!("PSP22::transfer"), [0xdb, 0x20, 0xf9, 0xf5]);
+        assert_eq!(ink::selector_bytes!("PSP22::transfer_from"), [0x54, 0xb3, 0xc7, 0x6e]);
+        assert_eq!(ink::selector_bytes!("PSP22::approve"), [0xb2, 0x0f, 0x1b, 0xbd]);
+    }
+
+    #[ink::test]
+    fn transfer_from_descuenta_la_autorizacion() {
+        let cuentas = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+        let mut token = MockPsp22::new(100);
+        token.approve(cuentas.bob, 60).unwrap();
+        ink::env::test::set_caller::<ink::env::DefaultEnvironment>(cuentas.bob);
+        assert_eq!(token.transfer_from(cuentas.alice, cuentas.charlie, 70, Vec::new()), Err(Psp22Error::InsufficientAllowance));
+        token.transfer_from(cuentas.alice, cuentas.charlie, 40, Vec::new()).unwrap();
+        assert_eq!(token.balance_of(cuentas.charlie), 40);
+        assert_eq!(token.allowance(cuentas.alice, cuentas.bob), 20);
+        assert_eq!(token.transfer(cuentas.alice, 1, Vec::new()), Err(Psp22Error::InsufficientBalance));
+    }
+}
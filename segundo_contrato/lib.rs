@@ -3,7 +3,7 @@
 #[ink::contract]
 mod segundo_contrato {
 
-    use primer_contrato::{PrimerContratoRef, Usuario, Rol, EstadoCompra,Comprador,Vendedor, OrdenCompra, Categoria, Producto};
+    use primer_contrato::{PrimerContratoRef, EventoMarketplace, Usuario, Rol, EstadoCompra,Comprador,Vendedor, OrdenCompra, Categoria, Producto, PLAZO_DEVOLUCION};
     use ink::storage::Mapping;
     use ink::storage::StorageVec;
     use ink::prelude::vec::Vec;
@@ -13,9 +13,20 @@ mod segundo_contrato {
 /// Struct que representa el segundo contrato del sistema.  
 /// Este contrato se comunica con el primer contrato mediante su referencia
 /// para acceder a la información del marketplace y obtener estadísticas.  
+/// snapshot, almacena el ultimo reporte calculado. Las consultas de reportes se responden desde aca.
+/// acumulado, almacena los totales de las ordenes que ya no pueden cambiar, para no tener que volver a procesarlas en cada refresco.
+/// direccion_marketplace, es la unica cuenta autorizada a notificar eventos.
+/// contadores y contadores_usuarios, almacenan los totales que se actualizan con cada evento notificado por el marketplace (por producto, categoria y usuario).
+/// categorias, almacena las categorias del marketplace obtenidas en el ultimo refresco. Se usan para nombrar y agrupar las estadisticas por categoria.
     #[ink(storage)]
     pub struct SegundoContrato {
         marketplace: PrimerContratoRef,
+        snapshot: Option<Snapshot>,
+        acumulado: AcumuladoOrdenes,
+        direccion_marketplace: AccountId,
+        contadores: AcumuladoOrdenes,
+        contadores_usuarios: Mapping<AccountId, ContadoresUsuario>,
+        categorias: Vec<Categoria>,
     }
     
     impl SegundoContrato {
@@ -27,20 +38,76 @@ mod segundo_contrato {
         /// y la guarda como referencia (PrimerContratoRef) para poder interactuar con él.
         pub fn new(primer_contrato_addr: AccountId) -> Self {
             let marketplace = PrimerContratoRef::from_account_id(primer_contrato_addr);
-            Self { marketplace }
+            Self {
+                marketplace,
+                snapshot: None,
+                acumulado: AcumuladoOrdenes::nuevo(),
+                direccion_marketplace: primer_contrato_addr,
+                contadores: AcumuladoOrdenes::nuevo(),
+                contadores_usuarios: Mapping::default(),
+                categorias: Vec::new(),
+            }
         }
 
-
         #[ink(message)]
-        /// Funcion que retorna los cinco vendedores con mejor reputacion promedio.
+        /// Funcion que calcula todos los reportes y los guarda en el snapshot del contrato. Retorna el timestamp del snapshot generado.
+        /// Solo se piden al marketplace las ordenes posteriores a la ultima orden procesada.
+        ///
+        /// Errores posibles: Error al obtener los usuarios del marketplace; overflow al acumular los totales.
         #[cfg(not(test))]
-        pub fn vendedores_mejor_reputacion(&self) -> Result <Vec <AccountId>, String>{
-            self.priv_vendedores_mejor_reputacion()
+        pub fn refrescar_snapshot(&mut self) -> Result<Timestamp, String>{
+            self.priv_refrescar_snapshot()
         }
         #[cfg(not(test))]
-        fn priv_vendedores_mejor_reputacion(&self) -> Result<Vec<AccountId>, String>{
-            let vendedores = self.filtrar_vendedores()?;
-           self.procesar_vendedores_mejor_reputacion(vendedores)
+        fn priv_refrescar_snapshot(&mut self) -> Result<Timestamp, String>{
+            let desde = self.acumulado.proxima_orden()?;
+            let ordenes = self.marketplace.get_ordenes_desde(desde);
+            let productos = self.marketplace.get_productos();
+            let usuarios = self.marketplace.get_usuarios()?;
+            let categorias = self.marketplace.get_categorias();
+            let timestamp = self.env().block_timestamp();
+            self.procesar_snapshot(ordenes, productos, usuarios, categorias, timestamp)
+        }
+
+        /// Funcion que genera el snapshot a partir de las ordenes nuevas, los productos, los usuarios y las categorias recibidos.
+        /// Las ordenes cerradas (ver orden_cerrada) se consolidan en el acumulado y avanzan la ultima orden procesada;
+        /// el resto se suma solo al snapshot, ya que todavia pueden cambiar y se vuelven a pedir en el proximo refresco.
+        /// Las categorias reemplazan a las del refresco anterior.
+        fn procesar_snapshot(&mut self, ordenes: Vec<OrdenCompra>, productos: Vec<Producto>, usuarios: Vec<Usuario>, categorias: Vec<Categoria>, timestamp: Timestamp) -> Result<Timestamp, String>{
+            self.categorias = categorias;
+            let (cerradas, abiertas) = self.separar_ordenes_cerradas(ordenes, timestamp);
+
+            let mut acumulado = self.acumulado.clone();
+            self.acumular_ordenes(&mut acumulado, cerradas, &productos)?;
+            let mut parcial = acumulado.clone();
+            self.acumular_ordenes(&mut parcial, abiertas, &productos)?;
+
+            let snapshot = Snapshot{
+                timestamp,
+                ultima_orden_procesada: acumulado.ultima_orden_procesada,
+                ventas_por_producto: parcial.ventas_por_producto.clone(),
+                estadisticas_por_categoria: self.procesar_estadisticas_por_categoria(&parcial.totales_por_categoria)?,
+                vendedores_mejor_reputacion: self.procesar_vendedores_mejor_reputacion(self.filtrar_vendedores_interno(usuarios.clone()))?,
+                vendedores_mejor_reputacion_ponderada: self.procesar_vendedores_mejor_reputacion_ponderada(self.filtrar_vendedores_interno(usuarios.clone()), timestamp)?,
+                vendedores_mejor_reputacion_por_categoria: self.procesar_vendedores_mejor_reputacion_por_categoria(self.filtrar_vendedores_interno(usuarios.clone()))?,
+                compradores_mejor_reputacion: self.procesar_compradores_mejor_reputacion(self.filtrar_compradores_interno(usuarios.clone()))?,
+                ordenes_por_usuario: self.procesar_cantidad_ordenes_por_usuarios(usuarios)?,
+            };
+            self.snapshot = Some(snapshot);
+            self.acumulado = acumulado;
+            Ok(timestamp)
+        }
+
+        #[ink(message)]
+        /// Funcion que retorna los cinco vendedores con mejor reputacion promedio, junto al timestamp del snapshot.
+        ///
+        /// Errores posibles: No hay un snapshot generado.
+        pub fn vendedores_mejor_reputacion(&self) -> Result<(Vec<AccountId>, Timestamp), String>{
+            self.priv_vendedores_mejor_reputacion()
+        }
+        fn priv_vendedores_mejor_reputacion(&self) -> Result<(Vec<AccountId>, Timestamp), String>{
+            let snapshot = self.obtener_snapshot()?;
+            Ok((snapshot.vendedores_mejor_reputacion, snapshot.timestamp))
         }
         fn procesar_vendedores_mejor_reputacion(&self, vendedores: Vec<Usuario>) -> Result<Vec<AccountId>, String>{
             let vec_contador: Vec<(AccountId, u8)> = self.contar_promedios_vendedor(vendedores)?;
@@ -51,28 +118,78 @@ mod segundo_contrato {
         }
 
         #[ink(message)]
-        /// Funcion que retorna un vector con la longitud especificada en top, con los productos mas vendidos (id, cantidad).
+        /// Funcion que retorna los cinco vendedores con mejor reputacion ponderada (por monto de la orden y antiguedad de la calificacion),
+        /// calculada al momento del snapshot, junto al timestamp del snapshot.
         ///
-        /// Errores posibles: No hay productos vendidos para procesar.
-        #[cfg(not(test))]
-        pub fn productos_mas_vendidos(&self, top: Option<u32>) -> Result<Vec<(u32, u32)>, String>{
-            self.priv_productos_mas_vendidos(top)
+        /// Errores posibles: No hay un snapshot generado.
+        pub fn vendedores_mejor_reputacion_ponderada(&self) -> Result<(Vec<AccountId>, Timestamp), String>{
+            self.priv_vendedores_mejor_reputacion_ponderada()
         }
-        #[cfg(not(test))]
-        fn priv_productos_mas_vendidos(&self, top: Option<u32>) -> Result<Vec<(u32, u32)>, String>{
-            let ordenes = self.marketplace.get_ordenes()?;
-            self.procesar_productos_mas_vendidos(ordenes, top)
+        fn priv_vendedores_mejor_reputacion_ponderada(&self) -> Result<(Vec<AccountId>, Timestamp), String>{
+            let snapshot = self.obtener_snapshot()?;
+            Ok((snapshot.vendedores_mejor_reputacion_ponderada, snapshot.timestamp))
         }
-        fn procesar_productos_mas_vendidos(&self, ordenes: Vec<OrdenCompra>, top: Option<u32>) -> Result<Vec<(u32, u32)>, String>{
-            let ordenes = self.filtrar_validas(ordenes);
-            if ordenes.is_empty() {
-                return Err("No hay productos vendidos de ventas concretadas.".to_string())
+        fn procesar_vendedores_mejor_reputacion_ponderada(&self, vendedores: Vec<Usuario>, ahora: Timestamp) -> Result<Vec<AccountId>, String>{
+            let mut vec_contador: Vec<(AccountId, u32)> = Vec::new();
+            for vendedor in vendedores{
+                if let Some(datos_vendedor) = vendedor.datos_vendedor{
+                    vec_contador.push((vendedor.id_usuario, datos_vendedor.reputacion_ponderada(ahora)?));
+                }
             }
-            let mut vector_contador: Vec<(u32, u32)> = Vec::new();
+            self.calcular_5_mejores(vec_contador)
+        }
 
-            for orden in ordenes{
-                self.procesar_orden(&mut vector_contador, orden)?;
+        #[ink(message)]
+        /// Funcion que retorna los cinco vendedores con mejor reputacion en la categoria recibida (solo calificaciones de ordenes
+        /// con productos de esa categoria), junto al timestamp del snapshot.
+        ///
+        /// Errores posibles: No hay un snapshot generado.
+        pub fn vendedores_mejor_reputacion_en_categoria(&self, categoria: u32) -> Result<(Vec<AccountId>, Timestamp), String>{
+            self.priv_vendedores_mejor_reputacion_en_categoria(categoria)
+        }
+        fn priv_vendedores_mejor_reputacion_en_categoria(&self, categoria: u32) -> Result<(Vec<AccountId>, Timestamp), String>{
+            let snapshot = self.obtener_snapshot()?;
+            let vendedores = snapshot.vendedores_mejor_reputacion_por_categoria
+                .into_iter()
+                .find(|(actual, _)| *actual == categoria)
+                .map(|(_, vendedores)| vendedores)
+                .unwrap_or_default();
+            Ok((vendedores, snapshot.timestamp))
+        }
+        fn procesar_vendedores_mejor_reputacion_por_categoria(&self, vendedores: Vec<Usuario>) -> Result<Vec<(u32, Vec<AccountId>)>, String>{
+            let mut ranking = Vec::new();
+            for categoria in self.categorias.iter().map(|categoria| categoria.id){
+                let mut vec_contador: Vec<(AccountId, u32)> = Vec::new();
+                for vendedor in vendedores.iter(){
+                    if let Some(ref datos_vendedor) = vendedor.datos_vendedor{
+                        let reputacion = datos_vendedor.reputacion_en_categoria(&categoria)?;
+                        if reputacion > 0 {
+                            vec_contador.push((vendedor.id_usuario, reputacion));
+                        }
+                    }
+                }
+                ranking.push((categoria, self.calcular_5_mejores(vec_contador)?));
             }
+            Ok(ranking)
+        }
+
+        #[ink(message)]
+        /// Funcion que retorna un vector con la longitud especificada en top, con los productos mas vendidos (id, cantidad), junto al timestamp del snapshot.
+        ///
+        /// Errores posibles: No hay un snapshot generado; no hay productos vendidos para procesar.
+        pub fn productos_mas_vendidos(&self, top: Option<u32>) -> Result<(Vec<(u32, u32)>, Timestamp), String>{
+            self.priv_productos_mas_vendidos(top)
+        }
+        fn priv_productos_mas_vendidos(&self, top: Option<u32>) -> Result<(Vec<(u32, u32)>, Timestamp), String>{
+            let snapshot = self.obtener_snapshot()?;
+            let productos = self.procesar_productos_mas_vendidos(snapshot.ventas_por_producto, top)?;
+            Ok((productos, snapshot.timestamp))
+        }
+        fn procesar_productos_mas_vendidos(&self, ventas_por_producto: Vec<(u32, u32)>, top: Option<u32>) -> Result<Vec<(u32, u32)>, String>{
+            if ventas_por_producto.is_empty() {
+                return Err("No hay productos vendidos de ventas concretadas.".to_string())
+            }
+            let mut vector_contador = ventas_por_producto;
             
             vector_contador.sort_by(|a, b| b.1.cmp(&a.1));
 
@@ -85,15 +202,15 @@ mod segundo_contrato {
         }
 
         #[ink(message)]
-        /// Funcion que retorna los cinco compradores con mejor reputacion promedio.
-        #[cfg(not(test))]
-        pub fn compradores_mejor_reputacion(&self) -> Result<Vec<AccountId>, String>{
+        /// Funcion que retorna los cinco compradores con mejor reputacion promedio, junto al timestamp del snapshot.
+        ///
+        /// Errores posibles: No hay un snapshot generado.
+        pub fn compradores_mejor_reputacion(&self) -> Result<(Vec<AccountId>, Timestamp), String>{
             self.priv_compradores_mejor_reputacion()
         }
-        #[cfg(not(test))]
-        fn priv_compradores_mejor_reputacion(&self) -> Result<Vec<AccountId>, String>{
-            let compradores = self.filtrar_compradores()?;
-            self.procesar_compradores_mejor_reputacion(compradores)
+        fn priv_compradores_mejor_reputacion(&self) -> Result<(Vec<AccountId>, Timestamp), String>{
+            let snapshot = self.obtener_snapshot()?;
+            Ok((snapshot.compradores_mejor_reputacion, snapshot.timestamp))
         }
         fn procesar_compradores_mejor_reputacion(&self, compradores: Vec<Usuario>) -> Result<Vec<AccountId>, String>{
             let vec_contador: Vec<(AccountId, u8)> = self.contar_promedios_comprador(compradores)?;
@@ -104,128 +221,336 @@ mod segundo_contrato {
         }
 
         #[ink(message)]
-        /// Funcion que retorna un vector de tuplas con el id de los compradores y la cantidad de ordenes de compras realizadas por este. 
+        /// Funcion que retorna un vector de tuplas con el id de los compradores y la cantidad de ordenes de compras realizadas por este, junto al timestamp del snapshot. 
         /// En formato (id, cantidad)
-        /// Errores posibles: No hay usuarios con datos para procesar
-        #[cfg(not(test))]
-        pub fn cantidad_ordenes_por_usuarios(&self) -> Result<Vec<(AccountId, u32)>, String>{
+        /// Errores posibles: No hay un snapshot generado; no hay usuarios con datos para procesar
+        pub fn cantidad_ordenes_por_usuarios(&self) -> Result<(Vec<(AccountId, u32)>, Timestamp), String>{
             self.priv_cantidad_ordenes_por_usuarios()
         }
-        #[cfg(not(test))]
-        fn priv_cantidad_ordenes_por_usuarios(&self) -> Result<Vec<(AccountId, u32)>, String>{
-            let usuarios = self.marketplace.get_usuarios()?;
-            self.procesar_cantidad_ordenes_por_usuarios(usuarios)
+        fn priv_cantidad_ordenes_por_usuarios(&self) -> Result<(Vec<(AccountId, u32)>, Timestamp), String>{
+            let snapshot = self.obtener_snapshot()?;
+            if snapshot.ordenes_por_usuario.is_empty(){
+                return Err("No hay usuarios con datos de comprador cargados en sistema.".to_string());
+            }
+            Ok((snapshot.ordenes_por_usuario, snapshot.timestamp))
         }
         fn procesar_cantidad_ordenes_por_usuarios(&self, usuarios: Vec<Usuario>) -> Result<Vec<(AccountId, u32)>, String>{
             let usuarios = self.filtrar_con_datos_comprador(usuarios);
-
-            if usuarios.is_empty(){
-                return Err("No hay usuarios con datos de comprador cargados en sistema.".to_string());
-            }
-
-            let cantidades = self.contar_cantidades(usuarios);
-
-            return Ok(cantidades);
+            self.contar_cantidades(usuarios)
         }
 
         #[ink(message)]
-        /// Funcion que retorna un vector de tuplas, con cada categoria, su cantidad de ventas totales 
-        /// y el promedio de calificaciones de cada uno de sus productos vendidos
-        #[cfg(not(test))]
-        pub fn estadisticas_por_categoria(&self) -> Result< Vec< (Categoria, u32, u8)>, String>{
+        /// Funcion que retorna las estadisticas de cada una de las categorias del sistema (incluso las que no tienen ventas), junto al timestamp del snapshot.
+        /// Por cada categoria se informan las unidades vendidas, la cantidad de ordenes, la recaudacion,
+        /// la cantidad de ventas calificadas y la calificacion promedio (en centesimas, ej: 433 = 4,33).
+        /// Las estadisticas de una categoria incluyen las de todas sus subcategorias.
+        ///
+        /// Errores posibles: No hay un snapshot generado.
+        pub fn estadisticas_por_categoria(&self) -> Result<(Vec<EstadisticasCategoria>, Timestamp), String>{
             self.priv_estadisticas_por_categoria()
         }
+        fn priv_estadisticas_por_categoria(&self) -> Result<(Vec<EstadisticasCategoria>, Timestamp), String>{
+            let snapshot = self.obtener_snapshot()?;
+            Ok((snapshot.estadisticas_por_categoria, snapshot.timestamp))
+        }
+        /// Funcion que arma las estadisticas de cada categoria conocida a partir de los totales directos de cada categoria,
+        /// sumando en cada una los totales de sus subcategorias. Una orden con productos de dos subcategorias
+        /// se cuenta una vez en cada subcategoria, y por lo tanto dos veces en la categoria que las contiene.
+        ///
+        /// Errores posibles: Overflow al sumar; error al calcular los promedios.
+        fn procesar_estadisticas_por_categoria(&self, totales: &[TotalesCategoria]) -> Result<Vec<EstadisticasCategoria>, String> {
+            let mut estadisticas = Vec::new();
+            for categoria in self.categorias.iter(){
+                let mut total = TotalesCategoria::nuevo(categoria.id);
+                for parcial in totales.iter().filter(|parcial| Categoria::es_subcategoria(&self.categorias, parcial.categoria, categoria.id)){
+                    total.sumar(parcial)?;
+                }
+                estadisticas.push(EstadisticasCategoria::nueva(categoria.clone(), &total)?);
+            }
+            Ok(estadisticas)
+        }
+
+        #[ink(message)]
+        /// Funcion que recibe los eventos que notifica el marketplace y actualiza los contadores.
+        /// Al crearse una orden se cuenta para el comprador; al enviarse se suman las unidades, la recaudacion y la venta del vendedor;
+        /// al calificarse se suma la puntuacion al usuario calificado (y a las categorias de la orden, si califico el comprador).
+        ///
+        /// Errores posibles: Quien llama no es el marketplace configurado; overflow al sumar.
         #[cfg(not(test))]
-        fn priv_estadisticas_por_categoria(&self) -> Result<Vec<(Categoria, u32, u8)>, String>{
-            let ordenes = self.marketplace.get_ordenes()?;
-            let productos = self.marketplace.get_productos();
-            self.procesar_estadisticas_por_categoria(ordenes, productos)
+        pub fn registrar_evento(&mut self, evento: EventoMarketplace) -> Result<(), String>{
+            let caller = self.env().caller();
+            self.priv_registrar_evento(caller, evento)
         }
-        fn procesar_estadisticas_por_categoria(&self, ordenes: Vec<OrdenCompra>, productos: Vec<Producto>) -> Result<Vec<(Categoria, u32, u8)>, String> {
-            let ordenes = self.filtrar_validas(ordenes);
-            let mut vector_categorias: Vec<(Categoria, u32, u8)> = Vec::new();
-            let mut vector_puntuacion_total: Vec<(Categoria, u32)> = Vec::new();
-            
-            self.total_de_ventas_categorias(ordenes, productos, &mut vector_categorias, &mut vector_puntuacion_total)?;
-            self.calificacion_promedio_categorias(&mut vector_categorias, vector_puntuacion_total)?;
-            
-            Ok(vector_categorias)
+        fn priv_registrar_evento(&mut self, caller: AccountId, evento: EventoMarketplace) -> Result<(), String>{
+            if caller != self.direccion_marketplace{
+                return Err("Solo el marketplace puede notificar eventos.".to_string());
+            }
+            match evento{
+                EventoMarketplace::OrdenActualizada{ orden, estado_anterior, productos } => {
+                    self.registrar_orden_actualizada(*orden, estado_anterior, productos)
+                },
+                EventoMarketplace::Calificacion{ orden, calificado, rol_calificado, puntuacion, productos } => {
+                    self.registrar_calificacion(*orden, calificado, rol_calificado, puntuacion, productos)
+                },
+            }
         }
 
+        /// Funcion que actualiza los contadores segun el cambio de estado de una orden.
+        /// Una orden devuelta deja de contar como venta, por lo que se descuenta de los contadores del vendedor y de las categorias.
+        /// Los descuentos se saturan en cero, ya que la orden puede haberse enviado antes de registrar este contrato y no estar contada.
+        /// Las transiciones que no afectan a los contadores (recepcion y cancelacion) se ignoran.
+        ///
+        /// Errores posibles: Overflow al sumar.
+        fn registrar_orden_actualizada(&mut self, orden: OrdenCompra, estado_anterior: Option<EstadoCompra>, productos: Vec<Producto>) -> Result<(), String>{
+            match (estado_anterior, orden.estado.clone()){
+                (None, _) => {
+                    let mut comprador = self.contadores_usuarios.get(orden.id_comprador).unwrap_or_default();
+                    comprador.ordenes_como_comprador = comprador.ordenes_como_comprador.checked_add(1).ok_or("Error al sumar.")?;
+                    self.contadores_usuarios.insert(orden.id_comprador, &comprador);
+                },
+                (Some(EstadoCompra::Pendiente), EstadoCompra::Enviado) => {
+                    let id_vendedor = orden.info_publicacion.3;
+                    let mut vendedor = self.contadores_usuarios.get(id_vendedor).unwrap_or_default();
+                    vendedor.ventas_como_vendedor = vendedor.ventas_como_vendedor.checked_add(1).ok_or("Error al sumar.")?;
+                    self.contadores_usuarios.insert(id_vendedor, &vendedor);
+
+                    let mut contadores = self.contadores.clone();
+                    self.acumular_ordenes(&mut contadores, Vec::from([orden]), &productos)?;
+                    self.contadores = contadores;
+                },
+                (Some(EstadoCompra::EnDevolucion), EstadoCompra::Devuelto) => {
+                    let id_vendedor = orden.info_publicacion.3;
+                    let mut vendedor = self.contadores_usuarios.get(id_vendedor).unwrap_or_default();
+                    vendedor.ventas_como_vendedor = vendedor.ventas_como_vendedor.saturating_sub(1);
+                    self.contadores_usuarios.insert(id_vendedor, &vendedor);
+
+                    let mut contadores = self.contadores.clone();
+                    self.descontar_orden(&mut contadores, orden, &productos)?;
+                    self.contadores = contadores;
+                },
+                _ => {},
+            }
+            Ok(())
+        }
 
-        /// Funcion que calcula el promedio de calificacion de cada una de las categorias.
-        /// Divide la calificacion total por la cantidad total de ventas de la categoria.
-        /// Errores posibles: Error al dividir
-        fn calificacion_promedio_categorias(&self, vector_categorias: &mut Vec<(Categoria, u32, u8)>, vector_puntuacion_total: Vec<(Categoria, u32)>)-> Result<(), String>{
-            for i in 0..vector_categorias.len(){
-                let cantidad = vector_categorias[i].1;
-                let total = vector_puntuacion_total[i].1;
-                let promedio = total.checked_div(cantidad).ok_or("Error al dividir.")?;
-                vector_categorias[i].2 = promedio as u8;
+        /// Funcion que resta de los totales del acumulado las unidades, la orden y la recaudacion de una orden devuelta,
+        /// de forma inversa a acumular_ordenes. Las calificaciones ya registradas se mantienen.
+        /// Las restas se saturan en cero, para no fallar con ordenes que nunca se sumaron.
+        ///
+        /// Errores posibles: Error al calcular los importes de la orden.
+        fn descontar_orden(&self, acumulado: &mut AcumuladoOrdenes, orden: OrdenCompra, productos: &[Producto]) -> Result<(), String>{
+            for (id_producto, cantidad) in orden.info_publicacion.1.iter(){
+                if let Some(dato) = acumulado.ventas_por_producto.iter_mut().find(|(id, _)| id == id_producto){
+                    dato.1 = dato.1.saturating_sub(*cantidad);
+                }
+            }
+            let mut categorias_de_la_orden: Vec<usize> = Vec::new();
+            let importes = self.importes_de_las_lineas(productos, &orden)?;
+            for ((id, cantidad), importe) in orden.info_publicacion.1.into_iter().zip(importes){
+                if let Some(producto) = productos.iter().find(|producto| producto.id == id){
+                    let pos = self.posicion_categoria(&mut acumulado.totales_por_categoria, producto.categoria);
+                    let total = &mut acumulado.totales_por_categoria[pos];
+                    total.unidades_vendidas = total.unidades_vendidas.saturating_sub(cantidad);
+                    total.recaudacion = total.recaudacion.saturating_sub(importe);
+                    if !categorias_de_la_orden.contains(&pos){
+                        categorias_de_la_orden.push(pos);
+                    }
+                }
+            }
+            for pos in categorias_de_la_orden{
+                let total = &mut acumulado.totales_por_categoria[pos];
+                total.ordenes = total.ordenes.saturating_sub(1);
             }
             Ok(())
         }
 
-        /// Funcion que procesa cada categoria de todas las ordenes de compra.
-        fn total_de_ventas_categorias(&self, ordenes: Vec<OrdenCompra>, productos: Vec<Producto>, vector_categorias: &mut Vec<(Categoria, u32, u8)>, vector_puntuacion_total: &mut Vec<(Categoria, u32)>)->Result<(), String>{
-            for orden in ordenes{
-                let _ = self.procesar_categorias(&productos, vector_categorias, orden, vector_puntuacion_total)?;
+        /// Funcion que suma una calificacion a los contadores del usuario calificado.
+        /// Si el calificado es el vendedor, la puntuacion tambien se suma a cada categoria de la orden (una vez por categoria).
+        ///
+        /// Errores posibles: Overflow al sumar.
+        fn registrar_calificacion(&mut self, orden: OrdenCompra, calificado: AccountId, rol_calificado: Rol, puntuacion: u8, productos: Vec<Producto>) -> Result<(), String>{
+            let mut contador = self.contadores_usuarios.get(calificado).unwrap_or_default();
+            if rol_calificado == Rol::Comp{
+                contador.calificaciones_como_comprador = contador.calificaciones_como_comprador.checked_add(1).ok_or("Error al sumar.")?;
+                contador.puntos_como_comprador = contador.puntos_como_comprador.checked_add(u32::from(puntuacion)).ok_or("Error al sumar.")?;
             }
-            return Ok(());
-        }
-
-        /// Funcion que se encarga de contar la cantidad de ventas de cada categoria y sus calificaciones totales,
-        /// modificando el vector vector_categorias recibido por referencia mutable.
-        /// El formato de la tupla es (categoria, total de ventas, total de calificacion).
-        fn procesar_categorias(&self, productos: &Vec<Producto>, vector_categorias: &mut Vec<(Categoria, u32, u8)>, orden: OrdenCompra, vector_puntuacion_total: &mut Vec<(Categoria, u32)>)-> Result<(), String> {
-            for (id, _) in orden.info_publicacion.1{
-                if let Some(pos) = productos.iter().position(|producto| producto.id == id){
-                    let categoria = productos.get(pos).unwrap().categoria.clone();
-                    let _ = self.contar_categoria(categoria.clone(), vector_categorias, vector_puntuacion_total)?;
-                    let _ = self.contar_puntuacion(categoria, vector_puntuacion_total, orden.puntuacion_del_comprador)?;
+            else {
+                contador.calificaciones_como_vendedor = contador.calificaciones_como_vendedor.checked_add(1).ok_or("Error al sumar.")?;
+                contador.puntos_como_vendedor = contador.puntos_como_vendedor.checked_add(u32::from(puntuacion)).ok_or("Error al sumar.")?;
+
+                let mut contadores = self.contadores.clone();
+                let mut categorias_de_la_orden: Vec<usize> = Vec::new();
+                for (id, _) in orden.info_publicacion.1{
+                    if let Some(producto) = productos.iter().find(|producto| producto.id == id){
+                        let pos = self.posicion_categoria(&mut contadores.totales_por_categoria, producto.categoria);
+                        if !categorias_de_la_orden.contains(&pos){
+                            categorias_de_la_orden.push(pos);
+                        }
+                    }
                 }
+                for pos in categorias_de_la_orden{
+                    let total = &mut contadores.totales_por_categoria[pos];
+                    total.ventas_calificadas = total.ventas_calificadas.checked_add(1).ok_or("Error al sumar.")?;
+                    total.puntuaciones = total.puntuaciones.checked_add(u32::from(puntuacion)).ok_or("Error al sumar.")?;
+                }
+                self.contadores = contadores;
             }
-            return Ok(())
+            self.contadores_usuarios.insert(calificado, &contador);
+            Ok(())
+        }
+
+        #[ink(message)]
+        /// Funcion que retorna la cantidad de unidades vendidas de un producto, segun los eventos notificados por el marketplace.
+        pub fn contador_producto(&self, id_producto: u32) -> u32{
+            self.contadores.ventas_por_producto.iter()
+            .find(|(id, _)| *id == id_producto)
+            .map(|(_, cantidad)| *cantidad)
+            .unwrap_or(0)
         }
 
-        /// Funcion que suma la calificacion de una venta a la calificacion total de su categoria
+        #[ink(message)]
+        /// Funcion que retorna las estadisticas de cada categoria, segun los eventos notificados por el marketplace.
+        /// Las categorias (y su jerarquia) son las obtenidas en el ultimo refresco del snapshot.
         ///
-        /// Errores posibles: Error al sumar
-        fn contar_puntuacion(&self, categoria: Categoria, vector_puntuacion_total: &mut Vec<(Categoria, u32)>, puntuacion: Option<u8>) -> Result<(), String>{
-            if let Some(nota) = puntuacion{
-                let pos = vector_puntuacion_total.iter().position(|(categoria_del_vector,_)| *categoria_del_vector == categoria).unwrap();
-                let mut nodo_vector = vector_puntuacion_total.get_mut(pos).unwrap();
-                nodo_vector.1 = nodo_vector.1.checked_add(nota as u32).ok_or("Error al sumar.")?;
-                Ok(())
-            } else{
-                Ok(())
+        /// Errores posibles: Error al calcular los promedios.
+        pub fn contadores_por_categoria(&self) -> Result<Vec<EstadisticasCategoria>, String>{
+            self.procesar_estadisticas_por_categoria(&self.contadores.totales_por_categoria)
+        }
+
+        #[ink(message)]
+        /// Funcion que retorna los contadores de un usuario, segun los eventos notificados por el marketplace.
+        /// Si el usuario no participo de ninguna orden, todos los contadores son cero.
+        pub fn contador_usuario(&self, id_usuario: AccountId) -> ContadoresUsuario{
+            self.contadores_usuarios.get(id_usuario).unwrap_or_default()
+        }
+
+        /// Funcion que devuelve una copia del snapshot almacenado.
+        ///
+        /// Errores posibles: Todavia no se genero ningun snapshot.
+        fn obtener_snapshot(&self) -> Result<Snapshot, String>{
+            self.snapshot.clone().ok_or("No hay un snapshot generado. Ejecute refrescar_snapshot.".to_string())
+        }
+
+        /// Funcion que separa las ordenes en dos vectores: (cerradas, abiertas).
+        /// Las cerradas son las del comienzo del vector hasta la primera orden que todavia puede cambiar;
+        /// a partir de esa orden todas se consideran abiertas, para que la ultima orden procesada no saltee ninguna.
+        fn separar_ordenes_cerradas(&self, ordenes: Vec<OrdenCompra>, timestamp: Timestamp) -> (Vec<OrdenCompra>, Vec<OrdenCompra>){
+            let corte = ordenes.iter().position(|orden| !self.orden_cerrada(orden, timestamp)).unwrap_or(ordenes.len());
+            let mut cerradas = ordenes;
+            let abiertas = cerradas.split_off(corte);
+            (cerradas, abiertas)
+        }
+
+        /// Funcion que indica si una orden ya no puede modificarse: fue cancelada (por estado o por ambas partes),
+        /// fue devuelta, o fue recibida, el comprador ya la califico y vencio el plazo para solicitar su devolucion.
+        fn orden_cerrada(&self, orden: &OrdenCompra, timestamp: Timestamp) -> bool{
+            let vencio_devolucion = orden.recibido_en.is_some_and(|recibido_en| recibido_en.saturating_add(PLAZO_DEVOLUCION) <= timestamp);
+            (orden.estado == EstadoCompra::Cancelada)
+            || (orden.estado == EstadoCompra::Devuelto)
+            || (orden.cancelacion.0 && orden.cancelacion.1)
+            || (orden.estado == EstadoCompra::Recibido && orden.calificaciones.0 && vencio_devolucion)
+        }
+
+        /// Funcion que suma las ordenes validas recibidas a los totales del acumulado,
+        /// y registra la ultima de ellas como la ultima orden procesada.
+        ///
+        /// Errores posibles: Overflow al acumular los totales.
+        fn acumular_ordenes(&self, acumulado: &mut AcumuladoOrdenes, ordenes: Vec<OrdenCompra>, productos: &[Producto]) -> Result<(), String>{
+            if let Some(ultima) = ordenes.last(){
+                acumulado.ultima_orden_procesada = Some(ultima.id);
             }
+            for orden in self.filtrar_validas(ordenes){
+                self.procesar_orden(&mut acumulado.ventas_por_producto, orden.clone())?;
+                self.procesar_categorias(productos, &mut acumulado.totales_por_categoria, orden)?;
+            }
+            Ok(())
         }
 
-        /// Funcion que incrementa en uno las ventas totales de una categoria
+        /// Funcion que se encarga de sumar los datos de una orden de compra a los totales directos de cada categoria.
+        /// Las unidades y la recaudacion se suman por cada linea de la orden (ver importes_de_las_lineas),
+        /// mientras que la orden y su calificacion se cuentan una unica vez por categoria.
         ///
-        /// Errores posibles: Error al incrementar
-        fn contar_categoria(&self, categoria: Categoria, vector_categorias: &mut Vec<(Categoria, u32, u8)>, vector_puntuacion_total: &mut Vec<(Categoria, u32)>)-> Result<(), String>{
-            if let Some(pos) = vector_categorias.iter().position(|(categoria_del_vector,_,_)| *categoria_del_vector == categoria){
-                let mut nodo_vector = vector_categorias.get_mut(pos).unwrap();
-                nodo_vector.1 = nodo_vector.1.checked_add(1).ok_or("Error al sumar.")?;
+        /// Errores posibles: Overflow en las sumas.
+        fn procesar_categorias(&self, productos: &[Producto], totales: &mut Vec<TotalesCategoria>, orden: OrdenCompra)-> Result<(), String> {
+            let mut categorias_de_la_orden: Vec<usize> = Vec::new();
+            let importes = self.importes_de_las_lineas(productos, &orden)?;
+            for ((id, cantidad), importe) in orden.info_publicacion.1.into_iter().zip(importes){
+                if let Some(producto) = productos.iter().find(|producto| producto.id == id){
+                    let pos = self.posicion_categoria(totales, producto.categoria);
+                    let total = &mut totales[pos];
+                    total.unidades_vendidas = total.unidades_vendidas.checked_add(cantidad).ok_or("Error al sumar.")?;
+                    total.recaudacion = total.recaudacion.checked_add(importe).ok_or("Error al sumar.")?;
+                    if !categorias_de_la_orden.contains(&pos){
+                        categorias_de_la_orden.push(pos);
+                    }
+                }
             }
-            else {
-                vector_categorias.push((categoria.clone(), 1, 0));
-                vector_puntuacion_total.push((categoria, 0))
+            for pos in categorias_de_la_orden{
+                let total = &mut totales[pos];
+                total.ordenes = total.ordenes.checked_add(1).ok_or("Error al sumar.")?;
+                if let Some(nota) = orden.puntuacion_del_comprador{
+                    total.ventas_calificadas = total.ventas_calificadas.checked_add(1).ok_or("Error al sumar.")?;
+                    total.puntuaciones = total.puntuaciones.checked_add(u32::from(nota)).ok_or("Error al sumar.")?;
+                }
+            }
+            Ok(())
+        }
+
+        /// Funcion que reparte el monto efectivamente pagado por la orden (info_publicacion.2, con los descuentos y ajustes de precio ya aplicados)
+        /// entre sus lineas, en proporcion al precio de catalogo por la cantidad de cada linea (o a la cantidad, si ningun producto tiene precio).
+        /// El resto de la division se suma a la ultima linea, para que los importes sumen exactamente lo pagado.
+        ///
+        /// Errores posibles: Overflow en las operaciones.
+        fn importes_de_las_lineas(&self, productos: &[Producto], orden: &OrdenCompra) -> Result<Vec<Balance>, String>{
+            let mut pesos: Vec<Balance> = Vec::new();
+            for (id, cantidad) in orden.info_publicacion.1.iter(){
+                let precio = productos.iter().find(|producto| producto.id == *id).map(|producto| producto.precio).unwrap_or(0);
+                pesos.push(Balance::from(precio).checked_mul(Balance::from(*cantidad)).ok_or("Error al multiplicar.")?);
+            }
+            if pesos.iter().all(|peso| *peso == 0){
+                pesos = orden.info_publicacion.1.iter().map(|(_, cantidad)| Balance::from(*cantidad)).collect();
+            }
+            let total_pesos = pesos.iter().try_fold(0, |acumulado: Balance, peso| acumulado.checked_add(*peso)).ok_or("Error al sumar.")?;
+            let pagado = Balance::from(orden.info_publicacion.2);
+            let mut importes: Vec<Balance> = Vec::new();
+            let mut repartido: Balance = 0;
+            for peso in pesos{
+                let importe = if total_pesos == 0 { 0 } else {
+                    pagado.checked_mul(peso).ok_or("Error al multiplicar.")?.checked_div(total_pesos).ok_or("Error al dividir.")?
+                };
+                repartido = repartido.checked_add(importe).ok_or("Error al sumar.")?;
+                importes.push(importe);
+            }
+            if let Some(ultimo) = importes.last_mut(){
+                *ultimo = ultimo.checked_add(pagado.checked_sub(repartido).ok_or("Error al restar.")?).ok_or("Error al sumar.")?;
+            }
+            Ok(importes)
+        }
+
+        /// Funcion que devuelve la posicion de los totales de una categoria dentro del vector.
+        /// Si la categoria todavia no tiene totales, los agrega en cero al final.
+        fn posicion_categoria(&self, totales: &mut Vec<TotalesCategoria>, categoria: u32) -> usize{
+            match totales.iter().position(|total| total.categoria == categoria){
+                Some(pos) => pos,
+                None => {
+                    totales.push(TotalesCategoria::nuevo(categoria));
+                    totales.len() - 1
+                },
             }
-            return Ok(());
         }
 
         /// Funcion que cuenta la cantidad de ordenes de compra realizada por cada uno de los compradores
-        fn contar_cantidades(&self, usuarios: Vec<Usuario>) -> Vec<(AccountId, u32)>{
+        ///
+        /// Errores posibles: La cantidad de ordenes no entra en un u32.
+        fn contar_cantidades(&self, usuarios: Vec<Usuario>) -> Result<Vec<(AccountId, u32)>, String>{
             let mut cantidades = Vec::new();
 
             for usuario in usuarios {
-                cantidades.push((usuario.id_usuario, usuario.datos_comprador.unwrap().ordenes_de_compra.len() as u32))
+                let ordenes = usuario.datos_comprador.map(|comprador| comprador.ordenes_de_compra.len()).unwrap_or(0);
+                cantidades.push((usuario.id_usuario, u32::try_from(ordenes).map_err(|_| "Error al convertir.")?))
             }
 
-            cantidades
+            Ok(cantidades)
         }
 
         /// Funcion que filtra y descarta los usario que no poseen datos de comprador cargados
@@ -234,7 +559,7 @@ mod segundo_contrato {
         }
         
         /// Funcion que devuelve el id de los usuarios con mejor reputacion promedio del vector pasado por parametro
-        fn calcular_5_mejores(&self, vec_contador: Vec<(AccountId, u8)>) -> Result<Vec<AccountId>, String>{
+        fn calcular_5_mejores<T: Ord>(&self, vec_contador: Vec<(AccountId, T)>) -> Result<Vec<AccountId>, String>{
             let mut v = vec_contador;
             v.sort_by(|a, b| b.1.cmp(&a.1));
             let top: Vec<AccountId> = v.into_iter().take(5).map(|(acct, _score)| acct).collect();
@@ -274,10 +599,6 @@ mod segundo_contrato {
         }
         
         /// Funcion que filtra un listado de usuario, dejando solo aquellos que tengan el rol "Comp" o "Ambos" 
-        fn filtrar_compradores(&self) -> Result<Vec<Usuario>, String> {
-            let usuarios = self.marketplace.get_usuarios()?;
-            Ok(self.filtrar_compradores_interno(usuarios))
-        }
         fn filtrar_compradores_interno(&self, usuarios: Vec<Usuario>) -> Vec<Usuario> {
             usuarios
                 .into_iter()
@@ -286,10 +607,6 @@ mod segundo_contrato {
         }
 
         /// Funcion que filtra un listado de usuario, dejando solo aquellos que tengan el rol "Vend" o "Ambos"
-        fn filtrar_vendedores(&self) -> Result<Vec<Usuario>, String> {
-            let usuarios = self.marketplace.get_usuarios()?;
-            Ok(self.filtrar_vendedores_interno(usuarios))
-        }
         fn filtrar_vendedores_interno(&self, usuarios: Vec<Usuario>) -> Vec<Usuario> {
             usuarios
                 .into_iter()
@@ -317,10 +634,8 @@ mod segundo_contrato {
         /// Errores posibles: Overflow en la suma de las cantidades
         fn procesar_orden(&self, vector_contador: &mut Vec<(u32, u32)>, orden: OrdenCompra) -> Result<(), String> {
             for (id_producto, cantidad_producto) in orden.info_publicacion.1{
-                if let Some(pos) = vector_contador.iter().position(|(id, _)| *id == id_producto){
-                    let mut dato= *vector_contador.get_mut(pos).unwrap();
+                if let Some(dato) = vector_contador.iter_mut().find(|(id, _)| *id == id_producto){
                     dato.1 = dato.1.checked_add(cantidad_producto).ok_or("Error al sumar.")?;
-                    vector_contador.insert(pos, dato); 
                 }
                 else {
                     vector_contador.push((id_producto, cantidad_producto))
@@ -329,21 +644,193 @@ mod segundo_contrato {
             return Ok(())
         }
 
-        /// Funcion que filtra y descarta las ordenes de compra que esten en estado "Pendiente" o "Cancelada"
+        /// Funcion que filtra y descarta las ordenes de compra que esten en estado "Pendiente", "Cancelada" o "Devuelto"
         fn filtrar_validas(&self, ordenes: Vec<OrdenCompra>) -> Vec<OrdenCompra> {
             ordenes.into_iter()
-            .filter(|orden| orden.estado != EstadoCompra::Pendiente && orden.estado != EstadoCompra::Cancelada)
+            .filter(|orden| orden.estado != EstadoCompra::Pendiente && orden.estado != EstadoCompra::Cancelada && orden.estado != EstadoCompra::Devuelto)
             .collect()
         }
     }
 
+/////////////////////////// ESTADISTICAS POR CATEGORIA ///////////////////////////
+
+    #[derive(Clone, Debug, PartialEq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std",derive(ink::storage::traits::StorageLayout))]
+    /// Struct que contiene las estadisticas de ventas de una categoria, incluidas las de sus subcategorias.
+    /// categoria, la categoria a la que corresponden los datos (id, nombre y categoria padre).
+    /// unidades_vendidas, suma de las cantidades vendidas de los productos de la categoria.
+    /// ordenes, cantidad de ordenes que incluyen al menos un producto de la categoria (cada orden se cuenta una sola vez por subcategoria).
+    /// recaudacion, suma de lo efectivamente pagado por los productos vendidos de la categoria (el monto de cada orden repartido entre sus lineas).
+    /// ventas_calificadas, cantidad de ordenes de la categoria que fueron calificadas por el comprador.
+    /// calificacion_promedio, promedio de las calificaciones expresado en centesimas (ej: 433 = 4,33). Solo considera las ventas calificadas.
+    pub struct EstadisticasCategoria{
+        pub categoria: Categoria,
+        pub unidades_vendidas: u32,
+        pub ordenes: u32,
+        pub recaudacion: Balance,
+        pub ventas_calificadas: u32,
+        pub calificacion_promedio: u32,
+    }
+    impl EstadisticasCategoria{
+
+        /// Arma las estadisticas de la categoria a partir de sus totales, calculando el promedio de calificacion.
+        ///
+        /// Errores posibles: Error al calcular el promedio.
+        fn nueva(categoria: Categoria, total: &TotalesCategoria) -> Result<EstadisticasCategoria, String>{
+            Ok(EstadisticasCategoria{
+                categoria,
+                unidades_vendidas: total.unidades_vendidas,
+                ordenes: total.ordenes,
+                recaudacion: total.recaudacion,
+                ventas_calificadas: total.ventas_calificadas,
+                calificacion_promedio: total.calificacion_promedio()?,
+            })
+        }
+    }
+
+    #[derive(Clone, Debug, PartialEq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std",derive(ink::storage::traits::StorageLayout))]
+    /// Struct que contiene los totales directos de una categoria (sin sus subcategorias), tal como se acumulan con cada orden.
+    /// categoria, el id de la categoria.
+    /// puntuaciones, la suma de las calificaciones de las ventas calificadas.
+    /// El resto de los campos tienen el mismo significado que en EstadisticasCategoria.
+    struct TotalesCategoria{
+        categoria: u32,
+        unidades_vendidas: u32,
+        ordenes: u32,
+        recaudacion: Balance,
+        ventas_calificadas: u32,
+        puntuaciones: u32,
+    }
+    impl TotalesCategoria{
+
+        fn nuevo(categoria: u32) -> TotalesCategoria{
+            TotalesCategoria{
+                categoria,
+                unidades_vendidas: 0,
+                ordenes: 0,
+                recaudacion: 0,
+                ventas_calificadas: 0,
+                puntuaciones: 0,
+            }
+        }
+
+        /// Suma a estos totales los totales recibidos (de una subcategoria).
+        ///
+        /// Errores posibles: Overflow al sumar.
+        fn sumar(&mut self, otro: &TotalesCategoria) -> Result<(), String>{
+            self.unidades_vendidas = self.unidades_vendidas.checked_add(otro.unidades_vendidas).ok_or("Error al sumar.")?;
+            self.ordenes = self.ordenes.checked_add(otro.ordenes).ok_or("Error al sumar.")?;
+            self.recaudacion = self.recaudacion.checked_add(otro.recaudacion).ok_or("Error al sumar.")?;
+            self.ventas_calificadas = self.ventas_calificadas.checked_add(otro.ventas_calificadas).ok_or("Error al sumar.")?;
+            self.puntuaciones = self.puntuaciones.checked_add(otro.puntuaciones).ok_or("Error al sumar.")?;
+            Ok(())
+        }
+
+        /// Devuelve el promedio de calificacion con dos decimales (en centesimas), redondeando al valor mas cercano.
+        /// Sin ventas calificadas devuelve 0.
+        ///
+        /// Errores posibles: Error al multiplicar o dividir.
+        fn calificacion_promedio(&self) -> Result<u32, String>{
+            let cantidad = self.ventas_calificadas;
+            if cantidad == 0 {
+                return Ok(0);
+            }
+            let total_en_centesimas = self.puntuaciones.checked_mul(100).ok_or("Error al multiplicar.")?;
+            let mitad = cantidad / 2;
+            total_en_centesimas.checked_add(mitad).ok_or("Error al sumar.")?
+                .checked_div(cantidad).ok_or("Error al dividir.".to_string())
+        }
+    }
+
+/////////////////////////// CONTADORES DE USUARIO ///////////////////////////
+
+    #[derive(Clone, Debug, PartialEq, Default)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std",derive(ink::storage::traits::StorageLayout))]
+    /// Struct que contiene los contadores de un usuario, actualizados con los eventos del marketplace.
+    /// ordenes_como_comprador, cantidad de ordenes creadas por el usuario.
+    /// ventas_como_vendedor, cantidad de ordenes del usuario que fueron enviadas.
+    /// calificaciones_como_comprador y puntos_como_comprador, cantidad y suma de las calificaciones recibidas como comprador.
+    /// calificaciones_como_vendedor y puntos_como_vendedor, cantidad y suma de las calificaciones recibidas como vendedor.
+    pub struct ContadoresUsuario{
+        pub ordenes_como_comprador: u32,
+        pub ventas_como_vendedor: u32,
+        pub calificaciones_como_comprador: u32,
+        pub puntos_como_comprador: u32,
+        pub calificaciones_como_vendedor: u32,
+        pub puntos_como_vendedor: u32,
+    }
+
+/////////////////////////// SNAPSHOT ///////////////////////////
+
+    #[derive(Clone, Debug, PartialEq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std",derive(ink::storage::traits::StorageLayout))]
+    /// Struct que contiene los reportes calculados en el ultimo refresco.
+    /// timestamp, momento (del bloque) en el que se genero el snapshot.
+    /// ultima_orden_procesada, id de la ultima orden consolidada en el acumulado. None si todavia no se consolido ninguna.
+    /// ventas_por_producto, cantidades vendidas de cada producto (id, cantidad), sin ordenar.
+    /// estadisticas_por_categoria, estadisticas de cada una de las categorias.
+    /// vendedores_mejor_reputacion y compradores_mejor_reputacion, los cinco usuarios con mejor reputacion promedio de cada rol.
+    /// vendedores_mejor_reputacion_ponderada, los cinco vendedores con mejor reputacion ponderada al momento del snapshot.
+    /// vendedores_mejor_reputacion_por_categoria, los cinco vendedores con mejor reputacion en cada categoria (id de la categoria, vendedores).
+    ///   Solo incluye vendedores con calificaciones en la categoria.
+    /// ordenes_por_usuario, cantidad de ordenes realizadas por cada comprador (id, cantidad).
+    pub struct Snapshot{
+        pub timestamp: Timestamp,
+        pub ultima_orden_procesada: Option<u32>,
+        pub ventas_por_producto: Vec<(u32, u32)>,
+        pub estadisticas_por_categoria: Vec<EstadisticasCategoria>,
+        pub vendedores_mejor_reputacion: Vec<AccountId>,
+        pub vendedores_mejor_reputacion_ponderada: Vec<AccountId>,
+        pub vendedores_mejor_reputacion_por_categoria: Vec<(u32, Vec<AccountId>)>,
+        pub compradores_mejor_reputacion: Vec<AccountId>,
+        pub ordenes_por_usuario: Vec<(AccountId, u32)>,
+    }
+
+    #[derive(Clone, Debug, PartialEq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std",derive(ink::storage::traits::StorageLayout))]
+    /// Struct que contiene los totales de las ordenes ya consolidadas.
+    /// ultima_orden_procesada, id de la ultima orden sumada. El proximo refresco pide las ordenes a partir de la siguiente.
+    /// ventas_por_producto, cantidades vendidas de cada producto (id, cantidad).
+    /// totales_por_categoria, totales directos de cada categoria con ventas (sin sumar las subcategorias).
+    struct AcumuladoOrdenes{
+        ultima_orden_procesada: Option<u32>,
+        ventas_por_producto: Vec<(u32, u32)>,
+        totales_por_categoria: Vec<TotalesCategoria>,
+    }
+    impl AcumuladoOrdenes{
+
+        fn nuevo() -> AcumuladoOrdenes{
+            AcumuladoOrdenes{
+                ultima_orden_procesada: None,
+                ventas_por_producto: Vec::new(),
+                totales_por_categoria: Vec::new(),
+            }
+        }
+
+        /// Devuelve el id de la primera orden que todavia no fue consolidada.
+        ///
+        /// Errores posibles: Error al sumar
+        fn proxima_orden(&self) -> Result<u32, String>{
+            match self.ultima_orden_procesada{
+                Some(id) => Ok(id.checked_add(1).ok_or("Error al sumar.")?),
+                None => Ok(0),
+            }
+        }
+    }
+
     //-------------Testing-------------//
 
     #[cfg(test)]
     mod tests {
         use super::*;
         use ink::prelude::string::String;
-        use primer_contrato::{Categoria, OrdenCompra, Producto, EstadoCompra,Comprador,Vendedor};
+        use primer_contrato::{Categoria, OrdenCompra, Producto, EstadoCompra,Comprador,Vendedor, EventoMarketplace, CalificacionPonderada};
         use ink::prelude::vec::Vec;
         use ink::env::account_id;
         use crate::segundo_contrato::AccountId;
@@ -351,6 +838,20 @@ mod segundo_contrato {
         fn account(n: u8) -> AccountId {
             AccountId::from([n; 32])
         }
+
+        const ELECTRODOMESTICOS: u32 = 0;
+        const ALIMENTOS: u32 = 2;
+        const HOGAR: u32 = 3;
+        const ROPA: u32 = 4;
+        const MASCOTAS: u32 = 5;
+        const OTRO: u32 = 7;
+
+        /// Devuelve las categorias con las que arranca el marketplace.
+        fn categorias_de_prueba() -> Vec<Categoria> {
+            ["Electrodomesticos", "Limpieza", "Alimentos", "Hogar", "Ropa", "Mascotas", "Libreria", "Otro"].iter().enumerate()
+                .map(|(id, nombre)| Categoria{ id: id as u32, nombre: String::from(*nombre), padre: None })
+                .collect()
+        }
     
         #[ink::test]
         fn promedio_reputacion_vacio() {
@@ -372,11 +873,12 @@ mod segundo_contrato {
         #[ink::test]
         fn filtrar_validas_pendientes_canceladas() {
             let contrato = SegundoContrato::new(account(0));
-            let o1 = OrdenCompra { id: 1, estado: EstadoCompra::Pendiente, cancelacion: (false, false), info_publicacion: (0, Vec::new(), 0, account(1)), id_comprador: account(2), calificaciones: (false, false), puntuacion_del_comprador: None };
-            let o2 = OrdenCompra { id: 2, estado: EstadoCompra::Enviado, cancelacion: (false, false), info_publicacion: (0, Vec::new(), 0, account(1)), id_comprador: account(2), calificaciones: (false, false), puntuacion_del_comprador: None };
-            let o3 = OrdenCompra { id: 3, estado: EstadoCompra::Cancelada, cancelacion: (false, false), info_publicacion: (0, Vec::new(), 0, account(1)), id_comprador: account(2), calificaciones: (false, false), puntuacion_del_comprador: None };
+            let o1 = OrdenCompra { id: 1, estado: EstadoCompra::Pendiente, cancelacion: (false, false), info_publicacion: (0, Vec::new(), 0, account(1)), id_comprador: account(2), calificaciones: (false, false), puntuacion_del_comprador: None, comision: 0, token_de_pago: None, precio_original: 0, recibido_en: None, motivo_devolucion: None, lineas: Vec::new(), compromisos: (None, None), variantes: Vec::new(), puntos_canjeados: 0 };
+            let o2 = OrdenCompra { id: 2, estado: EstadoCompra::Enviado, cancelacion: (false, false), info_publicacion: (0, Vec::new(), 0, account(1)), id_comprador: account(2), calificaciones: (false, false), puntuacion_del_comprador: None, comision: 0, token_de_pago: None, precio_original: 0, recibido_en: None, motivo_devolucion: None, lineas: Vec::new(), compromisos: (None, None), variantes: Vec::new(), puntos_canjeados: 0 };
+            let o3 = OrdenCompra { id: 3, estado: EstadoCompra::Cancelada, cancelacion: (false, false), info_publicacion: (0, Vec::new(), 0, account(1)), id_comprador: account(2), calificaciones: (false, false), puntuacion_del_comprador: None, comision: 0, token_de_pago: None, precio_original: 0, recibido_en: None, motivo_devolucion: None, lineas: Vec::new(), compromisos: (None, None), variantes: Vec::new(), puntos_canjeados: 0 };
+            let o4 = OrdenCompra { id: 4, estado: EstadoCompra::Devuelto, cancelacion: (false, false), info_publicacion: (0, Vec::new(), 0, account(1)), id_comprador: account(2), calificaciones: (false, false), puntuacion_del_comprador: None, comision: 0, token_de_pago: None, precio_original: 0, recibido_en: Some(0), motivo_devolucion: Some("Roto".to_string()), lineas: Vec::new(), compromisos: (None, None), variantes: Vec::new(), puntos_canjeados: 0 };
 
-            let in_vec = vec![o1.clone(), o2.clone(), o3.clone()];
+            let in_vec = vec![o1.clone(), o2.clone(), o3.clone(), o4.clone()];
             let out = contrato.filtrar_validas(in_vec);
             assert_eq!(out.len(), 1);
             assert_eq!(out[0].id, 2);
@@ -387,7 +889,7 @@ mod segundo_contrato {
             let contrato = SegundoContrato::new(account(0));
             let mut counter: Vec<(u32, u32)> = Vec::new();
             let pub_info = (1u32, vec![(10u32, 2u32), (20u32, 3u32)], 0u32, account(1));
-            let orden = OrdenCompra { id: 1, estado: EstadoCompra::Enviado, cancelacion: (false, false), info_publicacion: pub_info, id_comprador: account(2), calificaciones: (false, false), puntuacion_del_comprador: None };
+            let orden = OrdenCompra { id: 1, estado: EstadoCompra::Enviado, cancelacion: (false, false), info_publicacion: pub_info, id_comprador: account(2), calificaciones: (false, false), puntuacion_del_comprador: None, comision: 0, token_de_pago: None, precio_original: 0, recibido_en: None, motivo_devolucion: None, lineas: Vec::new(), compromisos: (None, None), variantes: Vec::new(), puntos_canjeados: 0 };
             contrato.procesar_orden(&mut counter, orden).expect("procesar_orden falla");
             assert_eq!(counter.len(), 2);
             assert!(counter.iter().any(|(id, qty)| *id == 10 && *qty == 2));
@@ -397,9 +899,9 @@ mod segundo_contrato {
         #[ink::test]
         fn contar_cantidades_por_usuario() {
             let contrato = SegundoContrato::new(account(0));
-            let u1 = Usuario { id_usuario: account(1), nombre: String::from("a"), apellido: String::from("b"), direccion: String::from("c"), email: String::from("e"), rol: Rol::Comp, datos_comprador: Some(Comprador { ordenes_de_compra: vec![1,2,3], reputacion_como_comprador: vec![] }), datos_vendedor: None };
-            let u2 = Usuario { id_usuario: account(2), nombre: String::from("x"), apellido: String::from("y"), direccion: String::from("z"), email: String::from("e2"), rol: Rol::Comp, datos_comprador: Some(Comprador { ordenes_de_compra: vec![10], reputacion_como_comprador: vec![] }), datos_vendedor: None };
-            let res = contrato.contar_cantidades(vec![u1, u2]);
+            let u1 = Usuario { id_usuario: account(1), nombre: String::from("a"), apellido: String::from("b"), direccion: String::from("c"), email: String::from("e"), rol: Rol::Comp, registrado_en: 0, datos_comprador: Some(Comprador { ordenes_de_compra: vec![1,2,3], reputacion_como_comprador: vec![] }), datos_vendedor: None };
+            let u2 = Usuario { id_usuario: account(2), nombre: String::from("x"), apellido: String::from("y"), direccion: String::from("z"), email: String::from("e2"), rol: Rol::Comp, registrado_en: 0, datos_comprador: Some(Comprador { ordenes_de_compra: vec![10], reputacion_como_comprador: vec![] }), datos_vendedor: None };
+            let res = contrato.contar_cantidades(vec![u1, u2]).unwrap();
             assert_eq!(res.len(), 2);
             assert!(res.iter().any(|(id, qty)| *id == account(1) && *qty == 3));
             assert!(res.iter().any(|(id, qty)| *id == account(2) && *qty == 1));
@@ -422,36 +924,35 @@ mod segundo_contrato {
         fn contar_categoria_puntuacion_promedio() {
             let contrato = SegundoContrato::new(account(0));
 
-            let p1 = Producto { id: 1, nombre: String::from("p1"), descripcion: String::from("d"), precio: 10, categoria: Categoria::Alimentos };
-            let p2 = Producto { id: 2, nombre: String::from("p2"), descripcion: String::from("d2"), precio: 20, categoria: Categoria::Electrodomesticos };
+            let p1 = Producto { id: 1, nombre: String::from("p1"), descripcion: String::from("d"), precio: 10, categoria: ALIMENTOS, medios: Vec::new() };
+            let p2 = Producto { id: 2, nombre: String::from("p2"), descripcion: String::from("d2"), precio: 20, categoria: ELECTRODOMESTICOS, medios: Vec::new() };
             let productos = vec![p1.clone(), p2.clone()];
 
             let pub_info = (0u32, vec![(1u32, 2u32)], 0u32, account(3));
-            let orden1 = OrdenCompra { id: 1, estado: EstadoCompra::Recibido, cancelacion: (false,false), info_publicacion: pub_info, id_comprador: account(4), calificaciones: (false,false), puntuacion_del_comprador: Some(4) };
+            let orden1 = OrdenCompra { id: 1, estado: EstadoCompra::Recibido, cancelacion: (false,false), info_publicacion: pub_info, id_comprador: account(4), calificaciones: (false,false), puntuacion_del_comprador: Some(4), comision: 0, token_de_pago: None, precio_original: 0, recibido_en: None, motivo_devolucion: None, lineas: Vec::new(), compromisos: (None, None), variantes: Vec::new(), puntos_canjeados: 0 };
 
-            let mut vector_categorias: Vec<(Categoria, u32, u8)> = Vec::new();
-            let mut vector_puntuacion_total: Vec<(Categoria, u32)> = Vec::new();
+            let mut totales: Vec<TotalesCategoria> = Vec::new();
 
-            contrato.total_de_ventas_categorias(vec![orden1.clone()], productos.clone(), &mut vector_categorias, &mut vector_puntuacion_total).expect("total falla");
-            assert_eq!(vector_categorias.len(), 1);
-            assert_eq!(vector_puntuacion_total.len(), 1);
-            
-            contrato.calificacion_promedio_categorias(&mut vector_categorias, vector_puntuacion_total).expect("promedio falla");
-            assert_eq!(vector_categorias[0].2, 4u8);
+            contrato.procesar_categorias(&productos, &mut totales, orden1).expect("procesar falla");
+            let pos = contrato.posicion_categoria(&mut totales, ALIMENTOS);
+            assert_eq!(totales.len(), 1); //Electrodomesticos no aparece en la orden
+            assert_eq!(totales[pos].ordenes, 1);
+            assert_eq!(totales[pos].puntuaciones, 4);
+            assert_eq!(totales[pos].calificacion_promedio(), Ok(400));
         }
 
         #[ink::test]
         fn contar_promedios_vendedor_comprador() {
             let contrato = SegundoContrato::new(account(0));
 
-            let vdata = Vendedor { productos: vec![], publicaciones: vec![], reputacion_como_vendedor: vec![5,4,3] };
-            let vendedor = Usuario { id_usuario: account(10), nombre: String::from("v"), apellido: String::from("v"), direccion: String::from("d"), email: String::from("e"), rol: Rol::Vend, datos_comprador: None, datos_vendedor: Some(vdata) };
+            let vdata = Vendedor { productos: vec![], publicaciones: vec![], reputacion_como_vendedor: vec![5,4,3], calificaciones_ponderadas: vec![], reputacion_por_categoria: vec![] };
+            let vendedor = Usuario { id_usuario: account(10), nombre: String::from("v"), apellido: String::from("v"), direccion: String::from("d"), email: String::from("e"), rol: Rol::Vend, registrado_en: 0, datos_comprador: None, datos_vendedor: Some(vdata) };
             let res_v = contrato.contar_promedios_vendedor(vec![vendedor]).expect("vendedor falla");
             assert_eq!(res_v.len(), 1);
             assert_eq!(res_v[0].0, account(10));
 
             let cdata = Comprador { ordenes_de_compra: vec![1], reputacion_como_comprador: vec![2,4] };
-            let comprador = Usuario { id_usuario: account(11), nombre: String::from("c"), apellido: String::from("c"), direccion: String::from("d"), email: String::from("e"), rol: Rol::Comp, datos_comprador: Some(cdata), datos_vendedor: None };
+            let comprador = Usuario { id_usuario: account(11), nombre: String::from("c"), apellido: String::from("c"), direccion: String::from("d"), email: String::from("e"), rol: Rol::Comp, registrado_en: 0, datos_comprador: Some(cdata), datos_vendedor: None };
             let res_c = contrato.contar_promedios_comprador(vec![comprador]).expect("comprador falla");
             assert_eq!(res_c.len(), 1);
             assert_eq!(res_c[0].0, account(11));
@@ -465,11 +966,14 @@ mod segundo_contrato {
             direccion: "Dir".to_string(),
             email: format!("vend{}@test.com", id),
             rol: Rol::Vend,
+            registrado_en: 0,
             datos_comprador: None,
             datos_vendedor: Some(Vendedor {
                 productos: vec![],
                 publicaciones: vec![],
                 reputacion_como_vendedor: reputacion,
+                calificaciones_ponderadas: vec![],
+                reputacion_por_categoria: vec![],
             }),
         }
     }
@@ -482,6 +986,7 @@ mod segundo_contrato {
             direccion: "Dir".to_string(),
             email: format!("ambos{}@test.com", id),
             rol: Rol::Ambos,
+            registrado_en: 0,
             datos_comprador: Some(Comprador {
                 ordenes_de_compra: vec![],
                 reputacion_como_comprador: vec![],
@@ -490,6 +995,8 @@ mod segundo_contrato {
                 productos: vec![],
                 publicaciones: vec![],
                 reputacion_como_vendedor: reputacion,
+                calificaciones_ponderadas: vec![],
+                reputacion_por_categoria: vec![],
             }),
         }
     }
@@ -502,6 +1009,7 @@ mod segundo_contrato {
             direccion: "Dir".to_string(),
             email: format!("comp{}@test.com", id),
             rol: Rol::Comp,
+            registrado_en: 0,
             datos_comprador: Some(Comprador {
                 ordenes_de_compra: vec![],
                 reputacion_como_comprador: vec![],
@@ -691,6 +1199,15 @@ mod segundo_contrato {
             id_comprador: account(2),
             calificaciones: (false, false),
             puntuacion_del_comprador: None,
+            comision: 0,
+            token_de_pago: None,
+            precio_original: 100,
+            recibido_en: None,
+            motivo_devolucion: None,
+            lineas: Vec::new(),
+            compromisos: (None, None),
+            variantes: Vec::new(),
+            puntos_canjeados: 0,
         }
     }
 
@@ -703,6 +1220,15 @@ mod segundo_contrato {
             id_comprador: account(2),
             calificaciones: (false, false),
             puntuacion_del_comprador: None,
+            comision: 0,
+            token_de_pago: None,
+            precio_original: 100,
+            recibido_en: None,
+            motivo_devolucion: None,
+            lineas: Vec::new(),
+            compromisos: (None, None),
+            variantes: Vec::new(),
+            puntos_canjeados: 0,
         }
     }
 
@@ -715,12 +1241,21 @@ mod segundo_contrato {
             id_comprador: account(2),
             calificaciones: (false, false),
             puntuacion_del_comprador: None,
+            comision: 0,
+            token_de_pago: None,
+            precio_original: 100,
+            recibido_en: None,
+            motivo_devolucion: None,
+            lineas: Vec::new(),
+            compromisos: (None, None),
+            variantes: Vec::new(),
+            puntos_canjeados: 0,
         }
     }
 
     #[ink::test]
     fn test_procesar_productos_mas_vendidos_sin_top() {
-        let contrato = SegundoContrato::new(account(0));
+        let mut contrato = SegundoContrato::new(account(0));
         
         let ordenes = vec![
             crear_orden_valida(1, vec![(10, 2), (20, 3)]),
@@ -728,9 +1263,10 @@ mod segundo_contrato {
             crear_orden_valida(3, vec![(20, 2)]),      
         ];
         
-        let result = contrato.procesar_productos_mas_vendidos(ordenes, None).unwrap();
+        refrescar_con_ordenes(&mut contrato, ordenes, vec![]);
+        let result = contrato.priv_productos_mas_vendidos(None).unwrap().0;
         
-        assert_eq!(result.len(), 5);
+        assert_eq!(result.len(), 3);
         assert_eq!(result[0], (20, 5));
         assert_eq!(result[1], (30, 5)); 
         assert_eq!(result[2], (10, 3)); 
@@ -738,7 +1274,7 @@ mod segundo_contrato {
 
     #[ink::test]
     fn test_procesar_productos_mas_vendidos_con_top() {
-        let contrato = SegundoContrato::new(account(0));
+        let mut contrato = SegundoContrato::new(account(0));
         
         let ordenes = vec![
             crear_orden_valida(1, vec![(10, 5)]),
@@ -748,7 +1284,8 @@ mod segundo_contrato {
             crear_orden_valida(5, vec![(50, 1)]),
         ];
         
-        let result = contrato.procesar_productos_mas_vendidos(ordenes, Some(3)).unwrap();
+        refrescar_con_ordenes(&mut contrato, ordenes, vec![]);
+        let result = contrato.priv_productos_mas_vendidos(Some(3)).unwrap().0;
         
         assert_eq!(result.len(), 3);
         assert_eq!(result[0], (10, 5));
@@ -758,7 +1295,7 @@ mod segundo_contrato {
 
     #[ink::test]
     fn test_procesar_productos_mas_vendidos_filtra_ordenes_invalidas() {
-        let contrato = SegundoContrato::new(account(0));
+        let mut contrato = SegundoContrato::new(account(0));
         
         let ordenes = vec![
             crear_orden_valida(1, vec![(10, 5)]),       
@@ -767,7 +1304,8 @@ mod segundo_contrato {
             crear_orden_cancelada(4, vec![(40, 4)]),    
         ];
         
-        let result = contrato.procesar_productos_mas_vendidos(ordenes, None).unwrap();
+        refrescar_con_ordenes(&mut contrato, ordenes, vec![]);
+        let result = contrato.priv_productos_mas_vendidos(None).unwrap().0;
         
         assert_eq!(result.len(), 2);
         assert_eq!(result[0], (10, 5));
@@ -777,7 +1315,7 @@ mod segundo_contrato {
 
     #[ink::test]
     fn test_procesar_productos_mas_vendidos_agrupa_multiples_ordenes() {
-        let contrato = SegundoContrato::new(account(0));
+        let mut contrato = SegundoContrato::new(account(0));
         
         let ordenes = vec![
             crear_orden_valida(1, vec![(10, 2)]),  
@@ -786,20 +1324,22 @@ mod segundo_contrato {
             crear_orden_valida(4, vec![(20, 4)]), 
         ];
         
-        let result = contrato.procesar_productos_mas_vendidos(ordenes, None).unwrap();
+        refrescar_con_ordenes(&mut contrato, ordenes, vec![]);
+        let result = contrato.priv_productos_mas_vendidos(None).unwrap().0;
         
-        assert_eq!(result.len(), 4);
+        assert_eq!(result.len(), 2);
         assert_eq!(result[0], (10, 6)); 
-        assert_eq!(result[1], (10, 5));
+        assert_eq!(result[1], (20, 4));
     }
 
     #[ink::test]
     fn test_procesar_productos_mas_vendidos_vacio() {
-        let contrato = SegundoContrato::new(account(0));
+        let mut contrato = SegundoContrato::new(account(0));
         
         let ordenes = vec![];
         
-        let result = contrato.procesar_productos_mas_vendidos(ordenes, None);
+        refrescar_con_ordenes(&mut contrato, ordenes, vec![]);
+        let result = contrato.priv_productos_mas_vendidos(None);
         
         assert!(result.is_err());
         assert_eq!(result.unwrap_err(), "No hay productos vendidos de ventas concretadas.");
@@ -807,7 +1347,7 @@ mod segundo_contrato {
 
     #[ink::test]
     fn test_procesar_productos_mas_vendidos_solo_ordenes_invalidas() {
-        let contrato = SegundoContrato::new(account(0));
+        let mut contrato = SegundoContrato::new(account(0));
         
         let ordenes = vec![
             crear_orden_pendiente(1, vec![(10, 5)]),
@@ -815,7 +1355,8 @@ mod segundo_contrato {
             crear_orden_pendiente(3, vec![(30, 2)]),
         ];
         
-        let result = contrato.procesar_productos_mas_vendidos(ordenes, None);
+        refrescar_con_ordenes(&mut contrato, ordenes, vec![]);
+        let result = contrato.priv_productos_mas_vendidos(None);
         
         assert!(result.is_err());
         assert_eq!(result.unwrap_err(), "No hay productos vendidos de ventas concretadas.");
@@ -823,14 +1364,15 @@ mod segundo_contrato {
 
     #[ink::test]
     fn test_procesar_productos_mas_vendidos_top_mayor_a_cantidad() {
-        let contrato = SegundoContrato::new(account(0));
+        let mut contrato = SegundoContrato::new(account(0));
         
         let ordenes = vec![
             crear_orden_valida(1, vec![(10, 2)]),
             crear_orden_valida(2, vec![(20, 1)]),
         ];
         
-        let result = contrato.procesar_productos_mas_vendidos(ordenes, Some(5)).unwrap();
+        refrescar_con_ordenes(&mut contrato, ordenes, vec![]);
+        let result = contrato.priv_productos_mas_vendidos(Some(5)).unwrap().0;
         
         assert_eq!(result.len(), 2);
         assert_eq!(result[0], (10, 2));
@@ -839,7 +1381,7 @@ mod segundo_contrato {
 
     #[ink::test]
     fn test_procesar_productos_mas_vendidos_empate_cantidades() {
-        let contrato = SegundoContrato::new(account(0));
+        let mut contrato = SegundoContrato::new(account(0));
         
         let ordenes = vec![
             crear_orden_valida(1, vec![(10, 5)]),
@@ -847,7 +1389,8 @@ mod segundo_contrato {
             crear_orden_valida(3, vec![(30, 3)]),
         ];
         
-        let result = contrato.procesar_productos_mas_vendidos(ordenes, None).unwrap();
+        refrescar_con_ordenes(&mut contrato, ordenes, vec![]);
+        let result = contrato.priv_productos_mas_vendidos(None).unwrap().0;
         
         assert_eq!(result.len(), 3);
         assert!(result[0].1 == 5);
@@ -857,14 +1400,15 @@ mod segundo_contrato {
 
     #[ink::test]
     fn test_procesar_productos_mas_vendidos_top_cero() {
-        let contrato = SegundoContrato::new(account(0));
+        let mut contrato = SegundoContrato::new(account(0));
         
         let ordenes = vec![
             crear_orden_valida(1, vec![(10, 5)]),
             crear_orden_valida(2, vec![(20, 3)]),
         ];
         
-        let result = contrato.procesar_productos_mas_vendidos(ordenes, Some(0)).unwrap();
+        refrescar_con_ordenes(&mut contrato, ordenes, vec![]);
+        let result = contrato.priv_productos_mas_vendidos(Some(0)).unwrap().0;
         
         assert!(result.is_empty());
     }
@@ -877,6 +1421,7 @@ mod segundo_contrato {
             direccion: "Dir".to_string(),
             email: "test@test.com".to_string(),
             rol: Rol::Comp,
+            registrado_en: 0,
             datos_comprador: Some(Comprador { 
                 ordenes_de_compra: vec![], 
                 reputacion_como_comprador: reputacion 
@@ -897,8 +1442,9 @@ mod segundo_contrato {
             direccion: "Dir".to_string(),
             email: "test@test.com".to_string(),
             rol: Rol::Vend,
+            registrado_en: 0,
             datos_comprador: None,
-            datos_vendedor: Some(Vendedor { productos: vec![], publicaciones: vec![], reputacion_como_vendedor: vec![] }),
+            datos_vendedor: Some(Vendedor { productos: vec![], publicaciones: vec![], reputacion_como_vendedor: vec![], calificaciones_ponderadas: vec![], reputacion_por_categoria: vec![] }),
         }
     }
 
@@ -975,6 +1521,7 @@ mod segundo_contrato {
             direccion: "Dir".to_string(),
             email: "test@test.com".to_string(),
             rol: Rol::Comp,
+            registrado_en: 0,
             datos_comprador: Some(Comprador { 
                 ordenes_de_compra: vec![], 
                 reputacion_como_comprador: vec![] 
@@ -991,11 +1538,14 @@ mod segundo_contrato {
             direccion: "Dir".to_string(),
             email: "test@test.com".to_string(),
             rol: Rol::Vend,
+            registrado_en: 0,
             datos_comprador: None,
             datos_vendedor: Some(Vendedor { 
                 productos: vec![], 
                 publicaciones: vec![], 
-                reputacion_como_vendedor: vec![] 
+                reputacion_como_vendedor: vec![],
+                calificaciones_ponderadas: vec![],
+                reputacion_por_categoria: vec![],
             }),
         }
     }
@@ -1022,37 +1572,52 @@ mod segundo_contrato {
 
 
 
-    fn crear_producto(id: u32, categoria: Categoria) -> Producto {
+    fn crear_producto(id: u32, categoria: u32) -> Producto {
         Producto {
             id,
             nombre: "Test".to_string(),
             descripcion: "Test".to_string(),
             precio: 10,
             categoria,
+            medios: Vec::new(),
         }
     }
 
+    fn refrescar_con_ordenes(contrato: &mut SegundoContrato, ordenes: Vec<OrdenCompra>, productos: Vec<Producto>) {
+        contrato.procesar_snapshot(ordenes, productos, vec![], categorias_de_prueba(), 0).expect("snapshot falla");
+    }
+
+    fn buscar_estadistica(estadisticas: &[EstadisticasCategoria], categoria: u32) -> EstadisticasCategoria {
+        estadisticas.iter().find(|estadistica| estadistica.categoria.id == categoria).unwrap().clone()
+    }
+
     #[ink::test]
     fn test_procesar_estadisticas_por_categoria_basico() {
-        let contrato = SegundoContrato::new(account(0));
+        let mut contrato = SegundoContrato::new(account(0));
         
         let ordenes = vec![
             crear_orden_valida(1, vec![(1, 2)]),
         ];
         
         let productos = vec![
-            crear_producto(1, Categoria::Alimentos),
+            crear_producto(1, ALIMENTOS),
         ];
         
-        let result = contrato.procesar_estadisticas_por_categoria(ordenes, productos).unwrap();
+        refrescar_con_ordenes(&mut contrato, ordenes, productos);
+        let result = contrato.priv_estadisticas_por_categoria().unwrap().0;
         
-        assert_eq!(result.len(), 1);
-        assert_eq!(result[0], (Categoria::Alimentos, 1, 0)); 
+        assert_eq!(result.len(), categorias_de_prueba().len());
+        let alimentos = buscar_estadistica(&result, ALIMENTOS);
+        assert_eq!(alimentos.unidades_vendidas, 2);
+        assert_eq!(alimentos.ordenes, 1);
+        assert_eq!(alimentos.recaudacion, 100); //Lo pagado por la orden, no el precio de catalogo
+        assert_eq!(alimentos.ventas_calificadas, 0);
+        assert_eq!(alimentos.calificacion_promedio, 0);
     }
 
     #[ink::test]
     fn test_procesar_estadisticas_por_categoria_filtra_ordenes_invalidas() {
-        let contrato = SegundoContrato::new(account(0));
+        let mut contrato = SegundoContrato::new(account(0));
         
         let ordenes = vec![
             crear_orden_valida(1, vec![(1, 2)]),
@@ -1064,33 +1629,351 @@ mod segundo_contrato {
         ];
         
         let productos = vec![
-            crear_producto(1, Categoria::Alimentos),
-            crear_producto(2, Categoria::Electrodomesticos),
+            crear_producto(1, ALIMENTOS),
+            crear_producto(2, ELECTRODOMESTICOS),
         ];
         
-        let result = contrato.procesar_estadisticas_por_categoria(ordenes, productos).unwrap();
+        refrescar_con_ordenes(&mut contrato, ordenes, productos);
+        let result = contrato.priv_estadisticas_por_categoria().unwrap().0;
         
-        assert_eq!(result.len(), 1);
-        assert_eq!(result[0], (Categoria::Alimentos, 1, 0));
+        assert_eq!(buscar_estadistica(&result, ALIMENTOS).ordenes, 1);
+        assert_eq!(buscar_estadistica(&result, ELECTRODOMESTICOS).ordenes, 0);
     }
 
     #[ink::test]
     fn test_procesar_estadisticas_por_categoria_promedio() {
-        let contrato = SegundoContrato::new(account(0));
+        let mut contrato = SegundoContrato::new(account(0));
         
         let ordenes = vec![
-            crear_orden_valida(1, vec![(1, 1)],),
-            crear_orden_valida(2, vec![(1, 1)],),
+            {
+                let mut orden = crear_orden_valida(1, vec![(1, 1)]);
+                orden.puntuacion_del_comprador = Some(5);
+                orden
+            },
+            {
+                let mut orden = crear_orden_valida(2, vec![(1, 1)]);
+                orden.puntuacion_del_comprador = Some(4);
+                orden
+            },
+            {
+                let mut orden = crear_orden_valida(3, vec![(1, 1)]);
+                orden.puntuacion_del_comprador = Some(4);
+                orden
+            },
+            crear_orden_valida(4, vec![(1, 1)]), //Venta sin calificar, no debe afectar el promedio
         ];
         
         let productos = vec![
-            crear_producto(1, Categoria::Alimentos),
+            crear_producto(1, ALIMENTOS),
         ];
         
-        let result = contrato.procesar_estadisticas_por_categoria(ordenes, productos).unwrap();
+        refrescar_con_ordenes(&mut contrato, ordenes, productos);
+        let result = contrato.priv_estadisticas_por_categoria().unwrap().0;
         
-        assert_eq!(result.len(), 1);
-        assert_eq!(result[0], (Categoria::Alimentos, 2, 0));
+        let alimentos = buscar_estadistica(&result, ALIMENTOS);
+        assert_eq!(alimentos.ordenes, 4);
+        assert_eq!(alimentos.ventas_calificadas, 3);
+        assert_eq!(alimentos.calificacion_promedio, 433); // (5 + 4 + 4) / 3 = 4,33
+    }
+
+    #[ink::test]
+    fn test_procesar_estadisticas_por_categoria_orden_con_varios_productos() {
+        let mut contrato = SegundoContrato::new(account(0));
+
+        let mut orden = crear_orden_valida(1, vec![(1, 2), (2, 3), (3, 1)]);
+        orden.puntuacion_del_comprador = Some(3);
+
+        let productos = vec![
+            crear_producto(1, ALIMENTOS),
+            crear_producto(2, ALIMENTOS),
+            crear_producto(3, HOGAR),
+        ];
+
+        refrescar_con_ordenes(&mut contrato, vec![orden], productos);
+        let result = contrato.priv_estadisticas_por_categoria().unwrap().0;
+
+        let alimentos = buscar_estadistica(&result, ALIMENTOS);
+        assert_eq!(alimentos.unidades_vendidas, 5);
+        assert_eq!(alimentos.ordenes, 1); //La orden se cuenta una sola vez aunque tenga dos productos de la categoria
+        assert_eq!(alimentos.recaudacion, 83); //Se pagaron 100 por lineas de catalogo 20, 30 y 10
+        assert_eq!(alimentos.ventas_calificadas, 1);
+        assert_eq!(alimentos.calificacion_promedio, 300);
+
+        let hogar = buscar_estadistica(&result, HOGAR);
+        assert_eq!(hogar.unidades_vendidas, 1);
+        assert_eq!(hogar.ordenes, 1);
+        assert_eq!(hogar.recaudacion, 17);
+    }
+
+    #[ink::test]
+    fn test_procesar_estadisticas_por_categoria_sin_ventas() {
+        let mut contrato = SegundoContrato::new(account(0));
+
+        refrescar_con_ordenes(&mut contrato, vec![], vec![]);
+        let result = contrato.priv_estadisticas_por_categoria().unwrap().0;
+
+        assert_eq!(result.len(), categorias_de_prueba().len());
+        assert!(result.iter().all(|estadistica| estadistica.ordenes == 0 && estadistica.calificacion_promedio == 0));
+    }
+
+    fn crear_orden_calificada(id: u32, productos: Vec<(u32, u32)>, puntuacion: u8) -> OrdenCompra {
+        let mut orden = crear_orden_valida(id, productos);
+        orden.calificaciones.0 = true;
+        orden.puntuacion_del_comprador = Some(puntuacion);
+        orden.recibido_en = Some(0);
+        orden
+    }
+
+    #[ink::test]
+    fn test_consultas_sin_snapshot() {
+        let contrato = SegundoContrato::new(account(0));
+
+        let error = "No hay un snapshot generado. Ejecute refrescar_snapshot.".to_string();
+        assert_eq!(contrato.priv_productos_mas_vendidos(None).unwrap_err(), error);
+        assert_eq!(contrato.priv_estadisticas_por_categoria().unwrap_err(), error);
+        assert_eq!(contrato.priv_vendedores_mejor_reputacion().unwrap_err(), error);
+        assert_eq!(contrato.priv_compradores_mejor_reputacion().unwrap_err(), error);
+        assert_eq!(contrato.priv_cantidad_ordenes_por_usuarios().unwrap_err(), error);
+        assert_eq!(contrato.priv_vendedores_mejor_reputacion_ponderada().unwrap_err(), error);
+        assert_eq!(contrato.priv_vendedores_mejor_reputacion_en_categoria(OTRO).unwrap_err(), error);
+    }
+
+    #[ink::test]
+    fn test_vendedores_mejor_reputacion_en_categoria() {
+        let mut contrato = SegundoContrato::new(account(0));
+
+        let mut vendedor1 = crear_vendedor_con_reputacion(1, vec![5, 2]);
+        vendedor1.datos_vendedor.as_mut().unwrap().reputacion_por_categoria = vec![(MASCOTAS, vec![5]), (ELECTRODOMESTICOS, vec![2])];
+        let mut vendedor2 = crear_vendedor_con_reputacion(2, vec![4]);
+        vendedor2.datos_vendedor.as_mut().unwrap().reputacion_por_categoria = vec![(ELECTRODOMESTICOS, vec![4])];
+
+        contrato.procesar_snapshot(vec![], vec![], vec![vendedor1, vendedor2], categorias_de_prueba(), 0).unwrap();
+
+        assert_eq!(contrato.priv_vendedores_mejor_reputacion_en_categoria(ELECTRODOMESTICOS).unwrap().0, vec![account(2), account(1)]);
+        assert_eq!(contrato.priv_vendedores_mejor_reputacion_en_categoria(MASCOTAS).unwrap().0, vec![account(1)]);
+        assert!(contrato.priv_vendedores_mejor_reputacion_en_categoria(ROPA).unwrap().0.is_empty());
+    }
+
+    #[ink::test]
+    fn test_vendedores_mejor_reputacion_ponderada_prioriza_montos_y_calificaciones_recientes() {
+        let mut contrato = SegundoContrato::new(account(0));
+        let un_anio: u64 = 365 * 24 * 60 * 60 * 1000;
+
+        // Vendedor 1: un 5 viejo en una orden grande y un 1 reciente. Promedio simple 3, ponderado cercano a 1.
+        let mut vendedor1 = crear_vendedor_con_reputacion(1, vec![5, 1]);
+        vendedor1.datos_vendedor.as_mut().unwrap().calificaciones_ponderadas = vec![
+            CalificacionPonderada{ puntuacion: 5, monto: 1000, fecha: 0, calificador: account(9), relacionada: false },
+            CalificacionPonderada{ puntuacion: 1, monto: 1000, fecha: 2 * un_anio, calificador: account(9), relacionada: false },
+        ];
+        // Vendedor 2: un 3 reciente en una orden grande y un 1 reciente en una orden chica. Promedio simple 2, ponderado 2,8.
+        let mut vendedor2 = crear_vendedor_con_reputacion(2, vec![3, 1]);
+        vendedor2.datos_vendedor.as_mut().unwrap().calificaciones_ponderadas = vec![
+            CalificacionPonderada{ puntuacion: 3, monto: 900, fecha: 2 * un_anio, calificador: account(9), relacionada: false },
+            CalificacionPonderada{ puntuacion: 1, monto: 100, fecha: 2 * un_anio, calificador: account(9), relacionada: false },
+        ];
+
+        contrato.procesar_snapshot(vec![], vec![], vec![vendedor1, vendedor2], categorias_de_prueba(), 2 * un_anio).unwrap();
+
+        assert_eq!(contrato.priv_vendedores_mejor_reputacion().unwrap().0, vec![account(1), account(2)]);
+        assert_eq!(contrato.priv_vendedores_mejor_reputacion_ponderada().unwrap().0, vec![account(2), account(1)]);
+    }
+
+    #[ink::test]
+    fn test_procesar_snapshot_guarda_reportes_y_timestamp() {
+        let mut contrato = SegundoContrato::new(account(0));
+
+        let usuarios = vec![
+            crear_vendedor_con_reputacion(1, vec![5, 4]),
+            {
+                let mut comp = crear_comprador_simple(2);
+                comp.datos_comprador.as_mut().unwrap().ordenes_de_compra = vec![0];
+                comp
+            },
+        ];
+        let ordenes = vec![crear_orden_calificada(0, vec![(1, 2)], 5)];
+        let productos = vec![crear_producto(1, HOGAR)];
+
+        let timestamp = contrato.procesar_snapshot(ordenes, productos, usuarios, categorias_de_prueba(), 1234).unwrap();
+        assert_eq!(timestamp, 1234);
+
+        assert_eq!(contrato.priv_productos_mas_vendidos(None).unwrap(), (vec![(1, 2)], 1234));
+        assert_eq!(contrato.priv_vendedores_mejor_reputacion().unwrap(), (vec![account(1)], 1234));
+        assert_eq!(contrato.priv_compradores_mejor_reputacion().unwrap(), (vec![account(2)], 1234));
+        assert_eq!(contrato.priv_cantidad_ordenes_por_usuarios().unwrap(), (vec![(account(2), 1)], 1234));
+        let (estadisticas, ts) = contrato.priv_estadisticas_por_categoria().unwrap();
+        assert_eq!(ts, 1234);
+        assert_eq!(buscar_estadistica(&estadisticas, HOGAR).calificacion_promedio, 500);
+    }
+
+    #[ink::test]
+    fn test_procesar_snapshot_incremental() {
+        let mut contrato = SegundoContrato::new(account(0));
+        let productos = vec![crear_producto(1, HOGAR)];
+
+        //La orden 1 todavia no fue calificada, por lo que no se consolida
+        let ordenes = vec![
+            crear_orden_calificada(0, vec![(1, 2)], 4),
+            crear_orden_valida(1, vec![(1, 1)]),
+        ];
+        contrato.procesar_snapshot(ordenes, productos.clone(), vec![], categorias_de_prueba(), PLAZO_DEVOLUCION).unwrap();
+        assert_eq!(contrato.acumulado.ultima_orden_procesada, Some(0));
+        assert_eq!(contrato.acumulado.proxima_orden(), Ok(1));
+        assert_eq!(contrato.priv_productos_mas_vendidos(None).unwrap().0, vec![(1, 3)]);
+
+        //En el siguiente refresco solo llegan las ordenes desde la 1
+        let ordenes = vec![
+            crear_orden_calificada(1, vec![(1, 1)], 2),
+            crear_orden_calificada(2, vec![(1, 4)], 3),
+        ];
+        contrato.procesar_snapshot(ordenes, productos, vec![], categorias_de_prueba(), PLAZO_DEVOLUCION + 10).unwrap();
+        assert_eq!(contrato.acumulado.ultima_orden_procesada, Some(2));
+
+        let snapshot = contrato.obtener_snapshot().unwrap();
+        assert_eq!(snapshot.timestamp, PLAZO_DEVOLUCION + 10);
+        assert_eq!(snapshot.ultima_orden_procesada, Some(2));
+        assert_eq!(snapshot.ventas_por_producto, vec![(1, 7)]);
+        let hogar = buscar_estadistica(&snapshot.estadisticas_por_categoria, HOGAR);
+        assert_eq!(hogar.ordenes, 3);
+        assert_eq!(hogar.calificacion_promedio, 300); // (4 + 2 + 3) / 3
+    }
+
+    #[ink::test]
+    fn test_separar_ordenes_cerradas_corta_en_la_primera_abierta() {
+        let contrato = SegundoContrato::new(account(0));
+
+        let mut cancelada_por_ambos = crear_orden_pendiente(2, vec![(1, 1)]);
+        cancelada_por_ambos.cancelacion = (true, true);
+        let ordenes = vec![
+            crear_orden_cancelada(0, vec![(1, 1)]),
+            crear_orden_calificada(1, vec![(1, 1)], 5),
+            cancelada_por_ambos,
+            crear_orden_pendiente(3, vec![(1, 1)]),
+            crear_orden_calificada(4, vec![(1, 1)], 5),
+        ];
+
+        let (cerradas, abiertas) = contrato.separar_ordenes_cerradas(ordenes.clone(), PLAZO_DEVOLUCION);
+        assert_eq!(cerradas.len(), 3);
+        assert_eq!(abiertas.len(), 2);
+        assert_eq!(abiertas[0].id, 3);
+
+        //Mientras el comprador pueda pedir la devolucion, la orden calificada sigue abierta
+        let (cerradas, abiertas) = contrato.separar_ordenes_cerradas(ordenes, PLAZO_DEVOLUCION - 1);
+        assert_eq!(cerradas.len(), 1);
+        assert_eq!(abiertas[0].id, 1);
+    }
+
+    #[ink::test]
+    fn test_registrar_evento_solo_marketplace() {
+        let mut contrato = SegundoContrato::new(account(0));
+        let evento = EventoMarketplace::OrdenActualizada{ orden: Box::new(crear_orden_pendiente(0, vec![(1, 1)])), estado_anterior: None, productos: vec![] };
+
+        let result = contrato.priv_registrar_evento(account(9), evento);
+
+        assert_eq!(result.unwrap_err(), "Solo el marketplace puede notificar eventos.");
+        assert_eq!(contrato.contador_usuario(account(2)), ContadoresUsuario::default());
+    }
+
+    #[ink::test]
+    fn test_registrar_evento_flujo_de_una_orden() {
+        let mut contrato = SegundoContrato::new(account(0));
+        contrato.categorias = categorias_de_prueba();
+        let productos = vec![crear_producto(1, HOGAR), crear_producto(2, HOGAR)];
+        let mut orden = crear_orden_pendiente(0, vec![(1, 2), (2, 1)]);
+
+        contrato.priv_registrar_evento(account(0), EventoMarketplace::OrdenActualizada{ orden: Box::new(orden.clone()), estado_anterior: None, productos: productos.clone() }).unwrap();
+        assert_eq!(contrato.contador_usuario(account(2)).ordenes_como_comprador, 1);
+        assert_eq!(contrato.contador_producto(1), 0); //Una orden pendiente todavia no es una venta
+
+        orden.estado = EstadoCompra::Enviado;
+        contrato.priv_registrar_evento(account(0), EventoMarketplace::OrdenActualizada{ orden: Box::new(orden.clone()), estado_anterior: Some(EstadoCompra::Pendiente), productos: productos.clone() }).unwrap();
+        assert_eq!(contrato.contador_producto(1), 2);
+        assert_eq!(contrato.contador_producto(2), 1);
+        assert_eq!(contrato.contador_usuario(account(1)).ventas_como_vendedor, 1);
+
+        orden.estado = EstadoCompra::Recibido;
+        contrato.priv_registrar_evento(account(0), EventoMarketplace::OrdenActualizada{ orden: Box::new(orden.clone()), estado_anterior: Some(EstadoCompra::Enviado), productos: productos.clone() }).unwrap();
+        assert_eq!(contrato.contador_producto(1), 2); //Recibir la orden no vuelve a contar la venta
+
+        contrato.priv_registrar_evento(account(0), EventoMarketplace::Calificacion{ orden: Box::new(orden.clone()), calificado: account(1), rol_calificado: Rol::Vend, puntuacion: 4, productos: productos.clone() }).unwrap();
+        contrato.priv_registrar_evento(account(0), EventoMarketplace::Calificacion{ orden: Box::new(orden), calificado: account(2), rol_calificado: Rol::Comp, puntuacion: 5, productos }).unwrap();
+
+        let vendedor = contrato.contador_usuario(account(1));
+        assert_eq!((vendedor.calificaciones_como_vendedor, vendedor.puntos_como_vendedor), (1, 4));
+        let comprador = contrato.contador_usuario(account(2));
+        assert_eq!((comprador.calificaciones_como_comprador, comprador.puntos_como_comprador), (1, 5));
+
+        let hogar = buscar_estadistica(&contrato.contadores_por_categoria().unwrap(), HOGAR);
+        assert_eq!(hogar.unidades_vendidas, 3);
+        assert_eq!(hogar.ordenes, 1);
+        assert_eq!(hogar.recaudacion, 100);
+        assert_eq!(hogar.ventas_calificadas, 1); //La orden tiene dos productos de la categoria pero se califica una sola vez
+        assert_eq!(hogar.calificacion_promedio, 400);
+    }
+
+    #[ink::test]
+    fn test_registrar_evento_orden_devuelta_descuenta_la_venta() {
+        let mut contrato = SegundoContrato::new(account(0));
+        contrato.categorias = categorias_de_prueba();
+        let productos = vec![crear_producto(1, HOGAR), crear_producto(2, HOGAR)];
+        let mut orden = crear_orden_pendiente(0, vec![(1, 2), (2, 1)]);
+
+        orden.estado = EstadoCompra::Enviado;
+        contrato.priv_registrar_evento(account(0), EventoMarketplace::OrdenActualizada{ orden: Box::new(orden.clone()), estado_anterior: Some(EstadoCompra::Pendiente), productos: productos.clone() }).unwrap();
+        orden.estado = EstadoCompra::EnDevolucion;
+        contrato.priv_registrar_evento(account(0), EventoMarketplace::OrdenActualizada{ orden: Box::new(orden.clone()), estado_anterior: Some(EstadoCompra::DevolucionSolicitada), productos: productos.clone() }).unwrap();
+        assert_eq!(contrato.contador_producto(1), 2); //Aceptar la devolucion todavia no descuenta la venta
+
+        orden.estado = EstadoCompra::Devuelto;
+        contrato.priv_registrar_evento(account(0), EventoMarketplace::OrdenActualizada{ orden: Box::new(orden), estado_anterior: Some(EstadoCompra::EnDevolucion), productos }).unwrap();
+        assert_eq!(contrato.contador_producto(1), 0);
+        assert_eq!(contrato.contador_producto(2), 0);
+        assert_eq!(contrato.contador_usuario(account(1)).ventas_como_vendedor, 0);
+        let hogar = buscar_estadistica(&contrato.contadores_por_categoria().unwrap(), HOGAR);
+        assert_eq!((hogar.unidades_vendidas, hogar.ordenes, hogar.recaudacion), (0, 0, 0));
+    }
+
+    #[ink::test]
+    fn test_registrar_evento_orden_devuelta_sin_envio_registrado_no_falla() {
+        let mut contrato = SegundoContrato::new(account(0));
+        contrato.categorias = categorias_de_prueba();
+        let productos = vec![crear_producto(1, HOGAR)];
+        let mut orden = crear_orden_pendiente(0, vec![(1, 2)]);
+
+        //La orden se envio antes de registrar el contrato de reportes, por lo que nunca se sumo
+        orden.estado = EstadoCompra::Devuelto;
+        contrato.priv_registrar_evento(account(0), EventoMarketplace::OrdenActualizada{ orden: Box::new(orden), estado_anterior: Some(EstadoCompra::EnDevolucion), productos }).unwrap();
+        assert_eq!(contrato.contador_producto(1), 0);
+        assert_eq!(contrato.contador_usuario(account(1)).ventas_como_vendedor, 0);
+    }
+
+    #[ink::test]
+    fn test_estadisticas_por_categoria_incluyen_subcategorias() {
+        let mut contrato = SegundoContrato::new(account(0));
+        let mut categorias = categorias_de_prueba();
+        categorias.push(Categoria{ id: 8, nombre: String::from("Deportes"), padre: None });
+        categorias.push(Categoria{ id: 9, nombre: String::from("Futbol"), padre: Some(8) });
+        categorias.push(Categoria{ id: 10, nombre: String::from("Pelotas"), padre: Some(9) });
+        let productos = vec![crear_producto(1, 10), crear_producto(2, 8), crear_producto(3, 9)];
+        let ordenes = vec![
+            crear_orden_calificada(0, vec![(1, 2)], 4),
+            crear_orden_calificada(1, vec![(2, 1)], 2),
+            crear_orden_calificada(2, vec![(3, 1)], 3),
+        ];
+
+        contrato.procesar_snapshot(ordenes, productos, vec![], categorias.clone(), 0).unwrap();
+        let estadisticas = contrato.priv_estadisticas_por_categoria().unwrap().0;
+        assert_eq!(estadisticas.len(), categorias.len());
+
+        let deportes = buscar_estadistica(&estadisticas, 8);
+        assert_eq!((deportes.unidades_vendidas, deportes.ordenes, deportes.recaudacion), (4, 3, 300));
+        assert_eq!(deportes.calificacion_promedio, 300);
+        let futbol = buscar_estadistica(&estadisticas, 9);
+        assert_eq!(futbol.categoria.nombre, "Futbol");
+        assert_eq!((futbol.unidades_vendidas, futbol.ordenes), (3, 2));
+        assert_eq!(futbol.calificacion_promedio, 350);
+        let pelotas = buscar_estadistica(&estadisticas, 10);
+        assert_eq!((pelotas.unidades_vendidas, pelotas.ordenes), (2, 1));
+        assert_eq!(buscar_estadistica(&estadisticas, HOGAR).ordenes, 0);
     }
 
 }
@@ -0,0 +1,354 @@
+#![cfg_attr(not(feature = "std"), no_std, no_main)]
+
+use ink::primitives::AccountId;
+use ink::prelude::vec::Vec;
+use primer_contrato::{Id, Psp34Error};
+
+/// Trait con los mensajes del estándar PSP34 (tokens no fungibles).
+/// Los selectores quedan con el prefijo "PSP34::", que es el que usa el marketplace para consultar los recibos.
+#[ink::trait_definition]
+pub trait PSP34 {
+    /// Devuelve el id de la colección de tokens.
+    #[ink(message)]
+    fn collection_id(&self) -> Id;
+
+    /// Devuelve la cantidad de tokens de una cuenta.
+    #[ink(message)]
+    fn balance_of(&self, owner: AccountId) -> u32;
+
+    /// Devuelve el dueño de un token, si existe.
+    #[ink(message)]
+    fn owner_of(&self, id: Id) -> Option<AccountId>;
+
+    /// Devuelve si el operador puede transferir el token (o todos los tokens, con None) del dueño.
+    #[ink(message)]
+    fn allowance(&self, owner: AccountId, operator: AccountId, id: Option<Id>) -> bool;
+
+    /// Aprueba (o revoca) al operador para transferir el token (o todos los tokens, con None) del llamador.
+    #[ink(message)]
+    fn approve(&mut self, operator: AccountId, id: Option<Id>, approved: bool) -> Result<(), Psp34Error>;
+
+    /// Transfiere un token a otra cuenta.
+    #[ink(message)]
+    fn transfer(&mut self, to: AccountId, id: Id, data: Vec<u8>) -> Result<(), Psp34Error>;
+
+    /// Devuelve la cantidad total de tokens acuñados.
+    #[ink(message)]
+    fn total_supply(&self) -> u128;
+}
+
+#[ink::contract]
+mod tercer_contrato {
+
+    use super::PSP34;
+    use primer_contrato::{Recibo, Id, Psp34Error};
+    use ink::storage::Mapping;
+    use ink::prelude::vec::Vec;
+    use ink::prelude::string::{String, ToString};
+
+/// Struct que representa el tercer contrato del sistema: la colección PSP34 de recibos de compra.
+/// Cada vez que una orden del marketplace pasa a Recibido, el marketplace acuña un recibo para el comprador,
+/// que sirve como prueba de compra para reclamar la garantía.
+/// marketplace, es la unica cuenta autorizada a acuñar recibos.
+/// transferibles, indica si los dueños pueden transferir sus recibos (por ejemplo, al revender el producto).
+/// duenos y recibos, almacenan el dueño y los datos de cada recibo por su id (el id de la orden).
+/// saldos, almacena la cantidad de recibos de cada cuenta.
+/// aprobaciones, almacena los operadores aprobados por cada dueño para todos sus recibos.
+/// aprobaciones_por_recibo, almacena los operadores aprobados por el dueño actual de cada recibo. Se borran al transferirlo.
+/// total, es la cantidad de recibos acuñados.
+    #[ink(storage)]
+    pub struct TercerContrato {
+        marketplace: AccountId,
+        transferibles: bool,
+        duenos: Mapping<u32, AccountId>,
+        recibos: Mapping<u32, Recibo>,
+        saldos: Mapping<AccountId, u32>,
+        aprobaciones: Mapping<(AccountId, AccountId), bool>,
+        aprobaciones_por_recibo: Mapping<u32, Vec<AccountId>>,
+        total: u32,
+    }
+
+    #[ink(event)]
+    /// Evento PSP34 emitido cuando un recibo se acuña (from es None) o se transfiere.
+    pub struct Transfer {
+        #[ink(topic)]
+        from: Option<AccountId>,
+        #[ink(topic)]
+        to: Option<AccountId>,
+        #[ink(topic)]
+        id: Id,
+    }
+
+    #[ink(event)]
+    /// Evento PSP34 emitido cuando un dueño aprueba o revoca a un operador, para un recibo o para todos (id es None).
+    pub struct Approval {
+        #[ink(topic)]
+        owner: AccountId,
+        #[ink(topic)]
+        operator: AccountId,
+        #[ink(topic)]
+        id: Option<Id>,
+        approved: bool,
+    }
+
+    impl TercerContrato {
+
+        #[ink(constructor)]
+        /// Constructor del contrato.
+        ///
+        /// Recibe la dirección del contrato principal (PrimerContrato), unico autorizado a acuñar recibos,
+        /// y si los recibos se pueden transferir entre cuentas.
+        pub fn new(marketplace: AccountId, transferibles: bool) -> Self {
+            Self {
+                marketplace,
+                transferibles,
+                duenos: Mapping::default(),
+                recibos: Mapping::default(),
+                saldos: Mapping::default(),
+                aprobaciones: Mapping::default(),
+                aprobaciones_por_recibo: Mapping::default(),
+                total: 0,
+            }
+        }
+
+        #[ink(message)]
+        /// Funcion que acuña el recibo de una orden recibida para el destinatario (el comprador). El id del token es el id de la orden.
+        ///
+        /// Errores posibles: el llamador no es el marketplace; ya existe un recibo para la orden; overflow al sumar.
+        #[cfg(not(test))]
+        pub fn acunar_recibo(&mut self, destinatario: AccountId, recibo: Recibo) -> Result<(), String>{
+            let caller = self.env().caller();
+            self.priv_acunar_recibo(caller, destinatario, recibo)
+        }
+        fn priv_acunar_recibo(&mut self, caller: AccountId, destinatario: AccountId, recibo: Recibo) -> Result<(), String>{
+            if caller != self.marketplace{
+                return Err("Solo el marketplace puede acuñar recibos.".to_string());
+            }
+            if self.duenos.contains(recibo.id_orden){
+                return Err("Ya existe un recibo para la orden.".to_string());
+            }
+            let saldo = self.saldos.get(destinatario).unwrap_or(0).checked_add(1).ok_or("Error al sumar.")?;
+            self.total = self.total.checked_add(1).ok_or("Error al sumar.")?;
+            self.saldos.insert(destinatario, &saldo);
+            self.duenos.insert(recibo.id_orden, &destinatario);
+            self.recibos.insert(recibo.id_orden, &recibo);
+            self.env().emit_event(Transfer{ from: None, to: Some(destinatario), id: Id::U32(recibo.id_orden) });
+            Ok(())
+        }
+
+        #[ink(message)]
+        /// Funcion que devuelve los datos de compra de un recibo.
+        ///
+        /// Errores posibles: el recibo no existe.
+        pub fn recibo(&self, id: u32) -> Result<Recibo, String>{
+            self.recibos.get(id).ok_or("El recibo no existe.".to_string())
+        }
+
+        #[ink(message)]
+        /// Funcion que devuelve si los recibos se pueden transferir.
+        pub fn son_transferibles(&self) -> bool{
+            self.transferibles
+        }
+
+        fn priv_approve(&mut self, caller: AccountId, operator: AccountId, id: Option<Id>, approved: bool) -> Result<(), Psp34Error>{
+            if operator == caller{
+                return Err(Psp34Error::SelfApprove);
+            }
+            match id.clone(){
+                Some(id) => {
+                    let id = Self::id_recibo(&id).ok_or(Psp34Error::TokenNotExists)?;
+                    if self.duenos.get(id) != Some(caller){
+                        return Err(Psp34Error::NotApproved);
+                    }
+                    let mut operadores = self.aprobaciones_por_recibo.get(id).unwrap_or_default();
+                    operadores.retain(|operador| *operador != operator);
+                    if approved{
+                        operadores.push(operator);
+                    }
+                    self.aprobaciones_por_recibo.insert(id, &operadores);
+                },
+                None => {
+                    if approved{
+                        self.aprobaciones.insert((caller, operator), &true);
+                    } else {
+                        self.aprobaciones.remove((caller, operator));
+                    }
+                },
+            }
+            self.env().emit_event(Approval{ owner: caller, operator, id, approved });
+            Ok(())
+        }
+
+        fn priv_transfer(&mut self, caller: AccountId, to: AccountId, id: Id) -> Result<(), Psp34Error>{
+            if !self.transferibles{
+                return Err(Psp34Error::Custom("Los recibos no son transferibles.".to_string()));
+            }
+            let id = Self::id_recibo(&id).ok_or(Psp34Error::TokenNotExists)?;
+            let dueno = self.duenos.get(id).ok_or(Psp34Error::TokenNotExists)?;
+            if caller != dueno && !self.esta_aprobado(dueno, caller, id){
+                return Err(Psp34Error::NotApproved);
+            }
+            if to == dueno{
+                return Ok(());
+            }
+            let saldo_dueno = self.saldos.get(dueno).unwrap_or(0).checked_sub(1).ok_or(Psp34Error::Custom("Error al restar.".to_string()))?;
+            let saldo_destino = self.saldos.get(to).unwrap_or(0).checked_add(1).ok_or(Psp34Error::Custom("Error al sumar.".to_string()))?;
+            self.saldos.insert(dueno, &saldo_dueno);
+            self.saldos.insert(to, &saldo_destino);
+            self.duenos.insert(id, &to);
+            self.aprobaciones_por_recibo.remove(id);
+            self.env().emit_event(Transfer{ from: Some(dueno), to: Some(to), id: Id::U32(id) });
+            Ok(())
+        }
+
+        /// Funcion que indica si el operador puede transferir el recibo del dueño, ya sea por una aprobación del recibo o de todos sus recibos.
+        fn esta_aprobado(&self, dueno: AccountId, operador: AccountId, id: u32) -> bool{
+            let aprobado_en_el_recibo = self.duenos.get(id) == Some(dueno)
+                && self.aprobaciones_por_recibo.get(id).is_some_and(|operadores| operadores.contains(&operador));
+            aprobado_en_el_recibo || self.aprobaciones.contains((dueno, operador))
+        }
+
+        /// Funcion que convierte un id PSP34 al id de recibo. Los recibos solo usan ids U32.
+        fn id_recibo(id: &Id) -> Option<u32>{
+            match id{
+                Id::U32(id) => Some(*id),
+                _ => None,
+            }
+        }
+    }
+
+    impl PSP34 for TercerContrato {
+
+        #[ink(message)]
+        fn collection_id(&self) -> Id {
+            let cuenta = self.env().account_id();
+            Id::Bytes(AsRef::<[u8]>::as_ref(&cuenta).to_vec())
+        }
+
+        #[ink(message)]
+        fn balance_of(&self, owner: AccountId) -> u32 {
+            self.saldos.get(owner).unwrap_or(0)
+        }
+
+        #[ink(message)]
+        fn owner_of(&self, id: Id) -> Option<AccountId> {
+            self.duenos.get(Self::id_recibo(&id)?)
+        }
+
+        #[ink(message)]
+        fn allowance(&self, owner: AccountId, operator: AccountId, id: Option<Id>) -> bool {
+            match id{
+                Some(id) => Self::id_recibo(&id).is_some_and(|id| self.esta_aprobado(owner, operator, id)),
+                None => self.aprobaciones.contains((owner, operator)),
+            }
+        }
+
+        #[ink(message)]
+        fn approve(&mut self, operator: AccountId, id: Option<Id>, approved: bool) -> Result<(), Psp34Error> {
+            let caller = self.env().caller();
+            self.priv_approve(caller, operator, id, approved)
+        }
+
+        #[ink(message)]
+        fn transfer(&mut self, to: AccountId, id: Id, _data: Vec<u8>) -> Result<(), Psp34Error> {
+            let caller = self.env().caller();
+            self.priv_transfer(caller, to, id)
+        }
+
+        #[ink(message)]
+        fn total_supply(&self) -> u128 {
+            u128::from(self.total)
+        }
+    }
+
+    //-------------Testing-------------//
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        fn account(n: u8) -> AccountId {
+            AccountId::from([n; 32])
+        }
+
+        fn recibo(id_orden: u32) -> Recibo {
+            Recibo{ id_orden, productos: vec![(1, 2)], precio: 200, vendedor: account(2), recibido_en: 100 }
+        }
+
+        #[ink::test]
+        fn acunar_recibo_solo_desde_el_marketplace() {
+            let (marketplace, comprador) = (account(1), account(3));
+            let mut contrato = TercerContrato::new(marketplace, false);
+
+            assert_eq!(contrato.priv_acunar_recibo(comprador, comprador, recibo(0)), Err("Solo el marketplace puede acuñar recibos.".to_string()));
+            assert!(contrato.priv_acunar_recibo(marketplace, comprador, recibo(0)).is_ok());
+            assert_eq!(contrato.priv_acunar_recibo(marketplace, comprador, recibo(0)), Err("Ya existe un recibo para la orden.".to_string()));
+
+            assert_eq!(contrato.owner_of(Id::U32(0)), Some(comprador));
+            assert_eq!(contrato.owner_of(Id::U8(0)), None);
+            assert_eq!(contrato.balance_of(comprador), 1);
+            assert_eq!(contrato.total_supply(), 1);
+            assert_eq!(contrato.recibo(0), Ok(recibo(0)));
+            assert_eq!(contrato.recibo(1), Err("El recibo no existe.".to_string()));
+        }
+
+        #[ink::test]
+        fn recibos_no_transferibles() {
+            let (marketplace, comprador, otro) = (account(1), account(3), account(4));
+            let mut contrato = TercerContrato::new(marketplace, false);
+            contrato.priv_acunar_recibo(marketplace, comprador, recibo(0)).unwrap();
+
+            assert_eq!(contrato.priv_transfer(comprador, otro, Id::U32(0)), Err(Psp34Error::Custom("Los recibos no son transferibles.".to_string())));
+            assert_eq!(contrato.owner_of(Id::U32(0)), Some(comprador));
+        }
+
+        #[ink::test]
+        fn transferir_recibo_por_el_dueno_o_un_operador_aprobado() {
+            let (marketplace, comprador, operador, destino) = (account(1), account(3), account(4), account(5));
+            let mut contrato = TercerContrato::new(marketplace, true);
+            contrato.priv_acunar_recibo(marketplace, comprador, recibo(0)).unwrap();
+
+            assert_eq!(contrato.priv_transfer(operador, destino, Id::U32(0)), Err(Psp34Error::NotApproved));
+            assert_eq!(contrato.priv_transfer(comprador, destino, Id::U32(1)), Err(Psp34Error::TokenNotExists));
+            assert_eq!(contrato.priv_approve(comprador, comprador, None, true), Err(Psp34Error::SelfApprove));
+            assert_eq!(contrato.priv_approve(operador, destino, Some(Id::U32(0)), true), Err(Psp34Error::NotApproved));
+
+            contrato.priv_approve(comprador, operador, Some(Id::U32(0)), true).unwrap();
+            assert!(contrato.allowance(comprador, operador, Some(Id::U32(0))));
+            assert!(!contrato.allowance(comprador, operador, None));
+            contrato.priv_transfer(operador, destino, Id::U32(0)).unwrap();
+
+            assert_eq!(contrato.owner_of(Id::U32(0)), Some(destino));
+            assert_eq!(contrato.balance_of(comprador), 0);
+            assert_eq!(contrato.balance_of(destino), 1);
+            //La aprobacion del recibo no sobrevive a la transferencia
+            assert!(!contrato.allowance(comprador, operador, Some(Id::U32(0))));
+
+            contrato.priv_transfer(destino, comprador, Id::U32(0)).unwrap();
+            assert_eq!(contrato.owner_of(Id::U32(0)), Some(comprador));
+        }
+
+        #[ink::test]
+        fn transferir_borra_las_aprobaciones_del_recibo_y_emite_eventos() {
+            let (marketplace, comprador, operador, otro_operador, destino) = (account(1), account(3), account(4), account(6), account(5));
+            let mut contrato = TercerContrato::new(marketplace, true);
+            contrato.priv_acunar_recibo(marketplace, comprador, recibo(0)).unwrap();
+            contrato.priv_approve(comprador, operador, Some(Id::U32(0)), true).unwrap();
+            contrato.priv_approve(comprador, otro_operador, Some(Id::U32(0)), true).unwrap();
+            assert_eq!(ink::env::test::recorded_events().count(), 3); //El acuñado y las dos aprobaciones
+
+            contrato.priv_transfer(operador, destino, Id::U32(0)).unwrap();
+            contrato.priv_transfer(destino, comprador, Id::U32(0)).unwrap();
+            assert_eq!(ink::env::test::recorded_events().count(), 5);
+
+            //Al volver al dueño anterior, ninguna de sus aprobaciones del recibo sigue vigente
+            assert!(!contrato.allowance(comprador, operador, Some(Id::U32(0))));
+            assert!(!contrato.allowance(comprador, otro_operador, Some(Id::U32(0))));
+            assert_eq!(contrato.priv_transfer(otro_operador, destino, Id::U32(0)), Err(Psp34Error::NotApproved));
+
+            contrato.priv_approve(comprador, operador, None, true).unwrap();
+            assert!(contrato.allowance(comprador, operador, None));
+            assert!(contrato.allowance(comprador, operador, Some(Id::U32(0))));
+        }
+    }
+}